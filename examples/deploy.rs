@@ -125,16 +125,15 @@ async fn main() -> anyhow::Result<()> {
 
     debug!("getting reveal transaction...");
     let reveal_transaction = builder
-        .build_reveal_transaction(RevealTransactionArgs {
-            input: ord_rs::wallet::Utxo {
+        .build_reveal_transaction(RevealTransactionArgs::new(
+            ord_rs::wallet::Utxo {
                 id: commit_txid,
                 index: 0,
                 amount: commit_tx.reveal_balance,
             },
-            recipient_address: sender_address,
-            redeem_script: commit_tx.redeem_script,
-            derivation_path: None,
-        })
+            sender_address,
+            commit_tx.redeem_script,
+        ))
         .await?;
     debug!("reveal transaction: {reveal_transaction:?}");
 
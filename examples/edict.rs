@@ -66,6 +66,8 @@ async fn main() -> anyhow::Result<()> {
                 script_pubkey: sender_address.script_pubkey(),
             },
             derivation_path: DerivationPath::default(),
+            key_spend: Default::default(),
+            rune_balances: None,
         })
         .collect();
 
@@ -172,6 +172,9 @@ async fn main() -> anyhow::Result<()> {
             redeem_script: commit_tx.redeem_script,
             runestone,
             derivation_path: None,
+            min_commit_confirmations: None,
+            funding_inputs: vec![],
+            change_output: None,
         })
         .await?;
     debug!("reveal transaction: {reveal_transaction:?}");
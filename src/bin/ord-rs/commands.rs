@@ -0,0 +1,429 @@
+use std::str::FromStr as _;
+
+use anyhow::Context as _;
+use bitcoin::absolute::LockTime;
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Address, Amount, Network, OutPoint, PrivateKey, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+    Txid, Witness,
+};
+use ord_rs::client::EsploraClient;
+use ord_rs::constants::dust_limit;
+use ord_rs::provider::{submit_package, Broadcaster as _, PackageBroadcast};
+use ord_rs::wallet::{
+    CreateCommitTransactionArgs, LocalSigner, ParseMode, RevealTransactionArgs, ScriptType,
+    SignCommitTransactionArgs, TxInputInfo, Utxo,
+};
+use ord_rs::{
+    estimate_inscription_cost, estimate_transaction_fees_for_prevouts, Inscription, InscriptionId,
+    OrdError, OrdParser, OrdTransactionBuilder, Wallet,
+};
+
+use crate::{
+    parse_output, CliInscription, EstimateFeeCommand, InscribeCommand, ParseTxCommand,
+    SendInscriptionCommand,
+};
+
+/// Transactions of an inscription, as printed by `inscribe`.
+#[derive(Serialize)]
+struct InscribeOutput {
+    inscription_id: InscriptionId,
+    commit_fee: Amount,
+    reveal_fee: Amount,
+    commit: String,
+    reveal: String,
+    broadcast: Option<PackageBroadcast>,
+}
+
+/// Transaction sending an inscription, as printed by `send-inscription`.
+#[derive(Serialize)]
+struct SendInscriptionOutput {
+    txid: Txid,
+    fee: Amount,
+    tx: String,
+    broadcast: bool,
+}
+
+/// The key signing the transactions and its P2WPKH address, holding the funding outputs.
+struct Sender {
+    private_key: PrivateKey,
+    address: Address,
+}
+
+impl Sender {
+    fn from_wif(wif: &str, network: Network) -> anyhow::Result<Self> {
+        let private_key = PrivateKey::from_wif(wif).context("invalid private key")?;
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, network)?;
+        debug!("sender address: {address}");
+
+        Ok(Self {
+            private_key,
+            address,
+        })
+    }
+
+    fn builder(&self, script_type: ScriptType) -> OrdTransactionBuilder {
+        let public_key = self.private_key.public_key(&Secp256k1::new());
+        OrdTransactionBuilder::new(public_key, script_type, self.wallet())
+    }
+
+    fn wallet(&self) -> Wallet {
+        Wallet::new_with_signer(LocalSigner::new(self.private_key))
+    }
+}
+
+/// Resolves the outputs given on the command line, looking up the values which aren't given.
+async fn resolve_outputs<'a>(
+    client: &EsploraClient,
+    outputs: impl IntoIterator<Item = &'a String>,
+) -> anyhow::Result<Vec<Utxo>> {
+    let mut utxos = Vec::new();
+    for output in outputs {
+        let (outpoint, amount) = parse_output(output)?;
+        let amount = match amount {
+            Some(amount) => amount,
+            None => client.output_value(outpoint).await?,
+        };
+        utxos.push(Utxo {
+            id: outpoint.txid,
+            index: outpoint.vout,
+            amount,
+        });
+    }
+
+    if utxos.is_empty() {
+        anyhow::bail!("no inputs given");
+    }
+    Ok(utxos)
+}
+
+fn parse_address(address: &str, network: Network) -> anyhow::Result<Address> {
+    Ok(Address::from_str(address)
+        .with_context(|| format!("invalid address {address}"))?
+        .require_network(network)?)
+}
+
+fn print_json(value: &impl serde::Serialize) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Inscribes the inscription, submitting the commit and reveal transactions as a package.
+pub async fn inscribe(command: InscribeCommand) -> anyhow::Result<()> {
+    match command.inscription.to_inscription()? {
+        CliInscription::Brc20(inscription) => inscribe_with(&command, inscription).await,
+        CliInscription::Nft(inscription) => inscribe_with(&command, inscription).await,
+    }
+}
+
+async fn inscribe_with<T>(command: &InscribeCommand, inscription: T) -> anyhow::Result<()>
+where
+    T: Inscription,
+{
+    let sender = Sender::from_wif(&command.private_key, command.network)?;
+    let recipient = match &command.recipient {
+        Some(recipient) => parse_address(recipient, command.network)?,
+        None => sender.address.clone(),
+    };
+    let client = EsploraClient::new(command.esplora.clone(), command.network);
+    let inputs = resolve_outputs(&client, &command.input).await?;
+
+    let mut builder = sender.builder(command.script_type);
+    let commit = builder
+        .build_commit_transaction(
+            command.network,
+            recipient.clone(),
+            CreateCommitTransactionArgs::new(
+                inputs.clone(),
+                inscription,
+                sender.address.clone(),
+                sender.address.script_pubkey(),
+                command.fee_rate,
+            ),
+        )
+        .await?;
+    let signed_commit = builder
+        .sign_commit_transaction(
+            commit.unsigned_tx.clone(),
+            SignCommitTransactionArgs {
+                inputs,
+                txin_script_pubkey: sender.address.script_pubkey(),
+                derivation_path: None,
+            },
+        )
+        .await?;
+    let commit_txid = commit.txid_after_signing(&signed_commit)?;
+    info!("commit transaction: {commit_txid}");
+
+    let reveal = builder
+        .build_reveal_transaction(RevealTransactionArgs {
+            postage: Some(commit.postage),
+            ..RevealTransactionArgs::new(
+                commit.reveal_input(commit_txid),
+                recipient,
+                commit.redeem_script.clone(),
+            )
+        })
+        .await?;
+    info!("reveal transaction: {}", reveal.txid());
+
+    let broadcast = if command.dry_run {
+        None
+    } else {
+        Some(submit_package(&signed_commit, &reveal, &client).await?)
+    };
+
+    print_json(&InscribeOutput {
+        inscription_id: commit.inscription_id(reveal.txid()),
+        commit_fee: commit.commit_fee,
+        reveal_fee: commit.reveal_fee,
+        commit: serialize_hex(&signed_commit),
+        reveal: serialize_hex(&reveal),
+        broadcast,
+    })
+}
+
+/// Sends the output holding an inscription to the recipient, paying the fee with the other
+/// inputs so the inscription keeps its postage.
+pub async fn send_inscription(command: SendInscriptionCommand) -> anyhow::Result<()> {
+    let sender = Sender::from_wif(&command.private_key, command.network)?;
+    let recipient = parse_address(&command.to, command.network)?;
+    let client = EsploraClient::new(command.esplora.clone(), command.network);
+    let utxos = resolve_outputs(
+        &client,
+        std::iter::once(&command.utxo).chain(&command.input),
+    )
+    .await?;
+
+    let inputs = utxos
+        .iter()
+        .map(|utxo| TxInputInfo {
+            outpoint: OutPoint {
+                txid: utxo.id,
+                vout: utxo.index,
+            },
+            tx_out: TxOut {
+                value: utxo.amount,
+                script_pubkey: sender.address.script_pubkey(),
+            },
+            derivation_path: Default::default(),
+            key_spend: Default::default(),
+            rune_balances: None,
+        })
+        .collect::<Vec<_>>();
+    let prevouts = inputs
+        .iter()
+        .map(|input| input.tx_out.clone())
+        .collect::<Vec<_>>();
+
+    // the inscription is on the first sat of the first input, so it's sent with the first output
+    let mut unsigned_tx = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: inputs
+            .iter()
+            .map(|input| TxIn {
+                previous_output: input.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output: vec![
+            TxOut {
+                value: utxos[0].amount,
+                script_pubkey: recipient.script_pubkey(),
+            },
+            TxOut {
+                value: Amount::ZERO,
+                script_pubkey: sender.address.script_pubkey(),
+            },
+        ],
+    };
+
+    let funding = utxos[1..].iter().map(|utxo| utxo.amount).sum::<Amount>();
+    let mut fee = estimate_transaction_fees_for_prevouts(
+        &prevouts,
+        command.fee_rate,
+        unsigned_tx.output.clone(),
+    )?;
+    let change = funding
+        .checked_sub(fee)
+        .ok_or(OrdError::InsufficientBalance {
+            required: fee,
+            available: funding,
+        })?;
+    if change < dust_limit(&sender.address.script_pubkey()) {
+        // dust change is left to the miners
+        unsigned_tx.output.pop();
+        fee = funding;
+    } else {
+        unsigned_tx.output[1].value = change;
+    }
+
+    let tx = sender
+        .wallet()
+        .sign_transaction(&unsigned_tx, &inputs)
+        .await?;
+    let txid = if command.dry_run {
+        tx.txid()
+    } else {
+        client.broadcast(&tx).await?
+    };
+
+    print_json(&SendInscriptionOutput {
+        txid,
+        fee,
+        tx: serialize_hex(&tx),
+        broadcast: !command.dry_run,
+    })
+}
+
+/// Etches a rune, waiting for the commit transaction to mature before revealing it.
+#[cfg(feature = "rune")]
+pub async fn etch(command: crate::EtchCommand) -> anyhow::Result<()> {
+    use ord_rs::provider::await_confirmations;
+    use ord_rs::wallet::{validate_etching, EtchingTransactionArgs, Runestone};
+    use ord_rs::Nft;
+    use ordinals::{Etching, SpacedRune, Terms};
+
+    let spaced_rune = SpacedRune::from_str(&command.rune)
+        .map_err(|err| anyhow::anyhow!("invalid rune {}: {err}", command.rune))?;
+    let etching = Etching {
+        rune: Some(spaced_rune.rune),
+        divisibility: Some(command.divisibility),
+        premine: Some(command.premine),
+        spacers: Some(spaced_rune.spacers).filter(|spacers| *spacers != 0),
+        symbol: command.symbol,
+        terms: (command.amount.is_some() || command.cap.is_some()).then_some(Terms {
+            amount: command.amount,
+            cap: command.cap,
+            height: (None, None),
+            offset: (None, None),
+        }),
+        turbo: false,
+    };
+    validate_etching(&etching, command.network, None)?;
+
+    let sender = Sender::from_wif(&command.private_key, command.network)?;
+    let client = EsploraClient::new(command.esplora.clone(), command.network);
+    let inputs = resolve_outputs(&client, &command.input).await?;
+
+    let inscription = Nft::new(
+        Some(b"text/plain;charset=utf-8".to_vec()),
+        Some(spaced_rune.to_string().into_bytes()),
+    )
+    .with_rune(spaced_rune.rune);
+
+    let mut builder = sender.builder(ScriptType::P2TR);
+    let commit = builder
+        .build_commit_transaction(
+            command.network,
+            sender.address.clone(),
+            CreateCommitTransactionArgs::new(
+                inputs.clone(),
+                inscription,
+                sender.address.clone(),
+                sender.address.script_pubkey(),
+                command.fee_rate,
+            ),
+        )
+        .await?;
+    let signed_commit = builder
+        .sign_commit_transaction(
+            commit.unsigned_tx.clone(),
+            SignCommitTransactionArgs {
+                inputs,
+                txin_script_pubkey: sender.address.script_pubkey(),
+                derivation_path: None,
+            },
+        )
+        .await?;
+    let commit_txid = commit.txid_after_signing(&signed_commit)?;
+
+    if !command.dry_run {
+        client.broadcast(&signed_commit).await?;
+        info!("commit transaction {commit_txid} broadcast, waiting for it to mature");
+        await_confirmations(
+            commit_txid,
+            ordinals::Runestone::COMMIT_CONFIRMATIONS.into(),
+            &client,
+        )
+        .await?;
+    }
+
+    let args = EtchingTransactionArgs {
+        input: commit.reveal_input(commit_txid),
+        recipient_address: sender.address.clone(),
+        redeem_script: commit.redeem_script.clone(),
+        runestone: Runestone {
+            etching: Some(etching),
+            edicts: vec![],
+            mint: None,
+            pointer: Some(1),
+        },
+        derivation_path: None,
+        min_commit_confirmations: None,
+        funding_inputs: vec![],
+        change_output: None,
+    };
+    // a dry run can't wait for the commit to mature
+    let reveal = if command.dry_run {
+        builder.build_etching_transaction(args).await?
+    } else {
+        let reveal = builder
+            .build_etching_transaction_with_provider(args, &client)
+            .await?;
+        client.broadcast(&reveal).await?;
+        reveal
+    };
+
+    print_json(&InscribeOutput {
+        inscription_id: commit.inscription_id(reveal.txid()),
+        commit_fee: commit.commit_fee,
+        reveal_fee: commit.reveal_fee,
+        commit: serialize_hex(&signed_commit),
+        reveal: serialize_hex(&reveal),
+        broadcast: (!command.dry_run).then_some(PackageBroadcast::Sequential),
+    })
+}
+
+/// Prints the inscriptions of the raw transaction.
+pub fn parse_tx(command: ParseTxCommand) -> anyhow::Result<()> {
+    let bytes = hex::decode(command.tx.trim()).context("invalid raw transaction")?;
+    let tx: Transaction =
+        bitcoin::consensus::deserialize(&bytes).context("invalid raw transaction")?;
+    let mode = if command.strict {
+        ParseMode::Strict
+    } else {
+        ParseMode::Lenient
+    };
+
+    print_json(&OrdParser::parse_all_with_mode(&tx, mode)?)
+}
+
+/// Prints the expected cost of the inscription.
+pub fn estimate_fee(command: EstimateFeeCommand) -> anyhow::Result<()> {
+    let postage = Amount::from_sat(command.postage);
+    let cost = match command.inscription.to_inscription()? {
+        CliInscription::Brc20(inscription) => estimate_inscription_cost(
+            &inscription,
+            command.fee_rate,
+            command.script_type,
+            postage,
+            command.network,
+        ),
+        CliInscription::Nft(inscription) => estimate_inscription_cost(
+            &inscription,
+            command.fee_rate,
+            command.script_type,
+            postage,
+            command.network,
+        ),
+    }?;
+
+    print_json(&cost)
+}
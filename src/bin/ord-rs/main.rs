@@ -0,0 +1,403 @@
+//! `ord-rs` command line interface, built with the `cli` feature.
+//!
+//! Inscribes BRC-20 operations and NFTs, sends inscriptions, etches runes, parses the
+//! inscriptions of raw transactions and quotes the cost of inscriptions. Transactions are funded
+//! by the P2WPKH outputs of a WIF private key, and broadcast to an esplora instance through the
+//! [`ord_rs::provider`] traits, unless `--dry-run` is given.
+//!
+//! ```text
+//! ord-rs inscribe -p <wif> -i <txid:vout> brc20 --op mint --tick ordi --amount 1000
+//! ord-rs estimate-fee --fee-rate 10 nft --content-type text/plain --file hello.txt
+//! ord-rs parse-tx <raw transaction hex>
+//! ```
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde;
+
+mod commands;
+
+use std::path::PathBuf;
+use std::str::FromStr as _;
+
+use anyhow::Context as _;
+use argh::FromArgs;
+use bitcoin::{Amount, FeeRate, Network, OutPoint, Txid};
+use ord_rs::wallet::ScriptType;
+use ord_rs::{Brc20, Nft};
+
+#[derive(FromArgs, Debug)]
+/// Inscribe, send and parse ordinals.
+struct Cli {
+    #[argh(subcommand)]
+    command: Command,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+enum Command {
+    Inscribe(InscribeCommand),
+    SendInscription(SendInscriptionCommand),
+    #[cfg(feature = "rune")]
+    Etch(EtchCommand),
+    ParseTx(ParseTxCommand),
+    EstimateFee(EstimateFeeCommand),
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "inscribe")]
+/// Inscribe a BRC-20 operation or an NFT with a commit and a reveal transaction.
+struct InscribeCommand {
+    #[argh(option, short = 'p')]
+    /// WIF private key of the P2WPKH outputs funding the inscription
+    private_key: String,
+
+    #[argh(option, short = 'i')]
+    /// funding output, as txid:vout or txid:vout:sats, can be repeated
+    input: Vec<String>,
+
+    #[argh(option, short = 'r')]
+    /// recipient of the inscription, the P2WPKH address of the key by default
+    recipient: Option<String>,
+
+    #[argh(
+        option,
+        short = 's',
+        default = "ScriptType::P2TR",
+        from_str_fn(parse_script_type)
+    )]
+    /// script type of the commit output (p2tr, p2wsh, p2sh-p2wsh)
+    script_type: ScriptType,
+
+    #[argh(
+        option,
+        short = 'f',
+        default = "FeeRate::from_sat_per_vb_unchecked(1)",
+        from_str_fn(parse_fee_rate)
+    )]
+    /// fee rate in sat/vB
+    fee_rate: FeeRate,
+
+    #[argh(
+        option,
+        short = 'n',
+        default = "Network::Testnet",
+        from_str_fn(parse_network)
+    )]
+    /// network (mainnet, testnet, signet, regtest)
+    network: Network,
+
+    #[argh(option)]
+    /// URL of the esplora API, blockstream.info by default
+    esplora: Option<String>,
+
+    #[argh(switch, short = 'd')]
+    /// print the signed transactions without broadcasting them
+    dry_run: bool,
+
+    #[argh(subcommand)]
+    inscription: InscriptionKind,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "send-inscription")]
+/// Send the output holding an inscription to an address.
+struct SendInscriptionCommand {
+    #[argh(option, short = 'p')]
+    /// WIF private key of the P2WPKH outputs
+    private_key: String,
+
+    #[argh(option, short = 'u')]
+    /// output holding the inscription, as txid:vout or txid:vout:sats
+    utxo: String,
+
+    #[argh(option, short = 'i')]
+    /// output funding the fee, as txid:vout or txid:vout:sats, can be repeated
+    input: Vec<String>,
+
+    #[argh(option, short = 't')]
+    /// recipient of the inscription
+    to: String,
+
+    #[argh(
+        option,
+        short = 'f',
+        default = "FeeRate::from_sat_per_vb_unchecked(1)",
+        from_str_fn(parse_fee_rate)
+    )]
+    /// fee rate in sat/vB
+    fee_rate: FeeRate,
+
+    #[argh(
+        option,
+        short = 'n',
+        default = "Network::Testnet",
+        from_str_fn(parse_network)
+    )]
+    /// network (mainnet, testnet, signet, regtest)
+    network: Network,
+
+    #[argh(option)]
+    /// URL of the esplora API, blockstream.info by default
+    esplora: Option<String>,
+
+    #[argh(switch, short = 'd')]
+    /// print the signed transaction without broadcasting it
+    dry_run: bool,
+}
+
+#[cfg(feature = "rune")]
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "etch")]
+/// Etch a rune, committing to its name and revealing it once the commit is mature.
+struct EtchCommand {
+    #[argh(option, short = 'p')]
+    /// WIF private key of the P2WPKH outputs funding the etching
+    private_key: String,
+
+    #[argh(option, short = 'i')]
+    /// funding output, as txid:vout or txid:vout:sats, can be repeated
+    input: Vec<String>,
+
+    #[argh(option, short = 'N')]
+    /// name of the rune, e.g. UNCOMMON•GOODS
+    rune: String,
+
+    #[argh(option)]
+    /// currency symbol of the rune
+    symbol: Option<char>,
+
+    #[argh(option, default = "0")]
+    /// number of decimals of the rune
+    divisibility: u8,
+
+    #[argh(option, default = "0")]
+    /// amount of the rune sent to the etcher
+    premine: u128,
+
+    #[argh(option, short = 'a')]
+    /// amount of the rune per mint
+    amount: Option<u128>,
+
+    #[argh(option, short = 'c')]
+    /// maximum number of mints
+    cap: Option<u128>,
+
+    #[argh(
+        option,
+        short = 'f',
+        default = "FeeRate::from_sat_per_vb_unchecked(1)",
+        from_str_fn(parse_fee_rate)
+    )]
+    /// fee rate in sat/vB
+    fee_rate: FeeRate,
+
+    #[argh(
+        option,
+        short = 'n',
+        default = "Network::Testnet",
+        from_str_fn(parse_network)
+    )]
+    /// network (mainnet, testnet, signet, regtest)
+    network: Network,
+
+    #[argh(option)]
+    /// URL of the esplora API, blockstream.info by default
+    esplora: Option<String>,
+
+    #[argh(switch, short = 'd')]
+    /// print the signed transactions without broadcasting them
+    dry_run: bool,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "parse-tx")]
+/// Print the inscriptions of a raw transaction as JSON.
+struct ParseTxCommand {
+    #[argh(positional)]
+    /// raw transaction, hex encoded
+    tx: String,
+
+    #[argh(switch)]
+    /// reject cursed envelopes and BRC-20 operations with invalid tickers
+    strict: bool,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "estimate-fee")]
+/// Print the expected cost of an inscription as JSON.
+struct EstimateFeeCommand {
+    #[argh(
+        option,
+        short = 's',
+        default = "ScriptType::P2TR",
+        from_str_fn(parse_script_type)
+    )]
+    /// script type of the commit output (p2tr, p2wsh, p2sh-p2wsh)
+    script_type: ScriptType,
+
+    #[argh(
+        option,
+        short = 'f',
+        default = "FeeRate::from_sat_per_vb_unchecked(1)",
+        from_str_fn(parse_fee_rate)
+    )]
+    /// fee rate in sat/vB
+    fee_rate: FeeRate,
+
+    #[argh(option, default = "ord_rs::constants::POSTAGE.to_sat()")]
+    /// value of the inscription output in sats
+    postage: u64,
+
+    #[argh(
+        option,
+        short = 'n',
+        default = "Network::Testnet",
+        from_str_fn(parse_network)
+    )]
+    /// network (mainnet, testnet, signet, regtest)
+    network: Network,
+
+    #[argh(subcommand)]
+    inscription: InscriptionKind,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand)]
+enum InscriptionKind {
+    Brc20(Brc20Args),
+    Nft(NftArgs),
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "brc20")]
+/// A BRC-20 operation.
+struct Brc20Args {
+    #[argh(option)]
+    /// operation (deploy, mint, transfer)
+    op: String,
+
+    #[argh(option, short = 'T')]
+    /// ticker
+    tick: String,
+
+    #[argh(option, short = 'a')]
+    /// amount to mint or transfer, or maximum supply to deploy
+    amount: u64,
+
+    #[argh(option)]
+    /// mint limit of a deployed ticker
+    limit: Option<u64>,
+
+    #[argh(option)]
+    /// decimals of a deployed ticker
+    decimals: Option<u64>,
+}
+
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "nft")]
+/// An NFT with the content of a file.
+struct NftArgs {
+    #[argh(option, short = 'c')]
+    /// content type of the file, e.g. text/plain;charset=utf-8
+    content_type: String,
+
+    #[argh(option)]
+    /// file to inscribe
+    file: PathBuf,
+}
+
+/// The inscription given on the command line.
+enum CliInscription {
+    Brc20(Brc20),
+    Nft(Nft),
+}
+
+impl InscriptionKind {
+    fn to_inscription(&self) -> anyhow::Result<CliInscription> {
+        match self {
+            Self::Brc20(args) => {
+                let tick = &args.tick;
+                let op = match args.op.as_str() {
+                    "deploy" => Brc20::deploy(tick, args.amount, args.limit, args.decimals, None),
+                    "mint" => Brc20::mint(tick, args.amount),
+                    "transfer" => Brc20::transfer(tick, args.amount),
+                    op => anyhow::bail!("invalid BRC-20 operation {op}"),
+                };
+                Ok(CliInscription::Brc20(op))
+            }
+            Self::Nft(args) => {
+                let body = std::fs::read(&args.file)
+                    .with_context(|| format!("failed to read {}", args.file.display()))?;
+                Ok(CliInscription::Nft(Nft::new(
+                    Some(args.content_type.clone().into_bytes()),
+                    Some(body),
+                )))
+            }
+        }
+    }
+}
+
+fn parse_network(value: &str) -> Result<Network, String> {
+    match value {
+        "mainnet" | "bitcoin" | "prod" => Ok(Network::Bitcoin),
+        "testnet" | "test" => Ok(Network::Testnet),
+        "signet" => Ok(Network::Signet),
+        "regtest" => Ok(Network::Regtest),
+        _ => Err(format!("invalid network {value}")),
+    }
+}
+
+fn parse_script_type(value: &str) -> Result<ScriptType, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "p2tr" => Ok(ScriptType::P2TR),
+        "p2wsh" => Ok(ScriptType::P2WSH),
+        "p2sh-p2wsh" => Ok(ScriptType::P2ShP2Wsh),
+        _ => Err(format!("invalid script type {value}")),
+    }
+}
+
+fn parse_fee_rate(value: &str) -> Result<FeeRate, String> {
+    value
+        .parse()
+        .ok()
+        .and_then(FeeRate::from_sat_per_vb)
+        .ok_or_else(|| format!("invalid fee rate {value}"))
+}
+
+/// Parses an output given as `txid:vout`, or `txid:vout:sats` to skip looking up its value.
+fn parse_output(value: &str) -> anyhow::Result<(OutPoint, Option<Amount>)> {
+    let mut parts = value.splitn(3, ':');
+    let (Some(txid), Some(vout)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("invalid output {value}, expected txid:vout or txid:vout:sats");
+    };
+    let outpoint = OutPoint {
+        txid: Txid::from_str(txid).with_context(|| format!("invalid txid {txid}"))?,
+        vout: vout
+            .parse()
+            .with_context(|| format!("invalid output index {vout}"))?,
+    };
+    let amount = parts
+        .next()
+        .map(|sats| sats.parse().map(Amount::from_sat))
+        .transpose()
+        .with_context(|| format!("invalid amount in {value}"))?;
+
+    Ok((outpoint, amount))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let cli: Cli = argh::from_env();
+
+    match cli.command {
+        Command::Inscribe(command) => commands::inscribe(command).await,
+        Command::SendInscription(command) => commands::send_inscription(command).await,
+        #[cfg(feature = "rune")]
+        Command::Etch(command) => commands::etch(command).await,
+        Command::ParseTx(command) => commands::parse_tx(command),
+        Command::EstimateFee(command) => commands::estimate_fee(command),
+    }
+}
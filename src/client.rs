@@ -0,0 +1,8 @@
+//! Client
+//!
+//! Clients of the public services backing the inscription workflows, implementing the
+//! [`crate::provider`] traits, built with the `esplora` feature.
+
+mod esplora;
+
+pub use self::esplora::EsploraClient;
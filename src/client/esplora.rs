@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+use std::str::FromStr as _;
+use std::time::Duration;
+
+use bitcoin::{Address, Amount, BlockHash, FeeRate, Network, OutPoint, Transaction, TxOut, Txid};
+use reqwest::StatusCode;
+
+use crate::provider::{BlockRef, Broadcaster, ChainProvider, FeeProvider, TxStatus};
+use crate::wallet::utxo_selector::UtxoCandidate;
+use crate::wallet::Utxo;
+use crate::{OrdError, OrdResult};
+
+/// Delay between two polls of the chain while waiting for confirmations.
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Client of the REST API of an esplora instance, e.g. <https://blockstream.info/api> or
+/// <https://mempool.space/api>.
+#[derive(Debug, Clone)]
+pub struct EsploraClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiUtxo {
+    txid: Txid,
+    vout: u32,
+    value: u64,
+    status: ApiTxStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiTxStatus {
+    confirmed: bool,
+    block_height: Option<u64>,
+    block_hash: Option<BlockHash>,
+}
+
+impl EsploraClient {
+    /// Creates a client of the esplora instance at the given URL, or of the public blockstream
+    /// instance of the network if `None`.
+    pub fn new(url: Option<String>, network: Network) -> Self {
+        let url =
+            url.unwrap_or_else(|| format!("https://blockstream.info{}/api", network_path(network)));
+
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Creates a client of the public mempool.space instance of the network.
+    pub fn mempool_space(network: Network) -> Self {
+        Self::new(
+            Some(format!(
+                "https://mempool.space{}/api",
+                network_path(network)
+            )),
+            network,
+        )
+    }
+
+    /// Returns the URL of the API.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the transaction.
+    pub async fn transaction(&self, txid: &Txid) -> OrdResult<Transaction> {
+        let bytes = self
+            .get(&format!("tx/{txid}/raw"))
+            .await?
+            .bytes()
+            .await
+            .map_err(esplora_error)?;
+        bitcoin::consensus::deserialize(&bytes)
+            .map_err(|err| OrdError::Esplora(format!("invalid transaction {txid}: {err}")))
+    }
+
+    /// Returns the output.
+    pub async fn output(&self, outpoint: OutPoint) -> OrdResult<TxOut> {
+        self.transaction(&outpoint.txid)
+            .await?
+            .output
+            .get(outpoint.vout as usize)
+            .cloned()
+            .ok_or_else(|| OrdError::Esplora(format!("output {outpoint} not found")))
+    }
+
+    /// Returns the value of the output.
+    pub async fn output_value(&self, outpoint: OutPoint) -> OrdResult<Amount> {
+        Ok(self.output(outpoint).await?.value)
+    }
+
+    /// Returns the outputs as UTXOs, looking up their values.
+    pub async fn resolve_utxos(
+        &self,
+        outpoints: impl IntoIterator<Item = OutPoint>,
+    ) -> OrdResult<Vec<Utxo>> {
+        let mut utxos = Vec::new();
+        for outpoint in outpoints {
+            utxos.push(Utxo {
+                id: outpoint.txid,
+                index: outpoint.vout,
+                amount: self.output_value(outpoint).await?,
+            });
+        }
+
+        Ok(utxos)
+    }
+
+    /// Returns the unspent outputs of the address, including the unconfirmed ones, to be
+    /// selected by a [`crate::wallet::utxo_selector::UtxoSelector`].
+    pub async fn utxos(&self, address: &Address) -> OrdResult<Vec<UtxoCandidate>> {
+        let utxos: Vec<ApiUtxo> = self
+            .get(&format!("address/{address}/utxo"))
+            .await?
+            .json()
+            .await
+            .map_err(esplora_error)?;
+
+        Ok(utxos
+            .into_iter()
+            .map(|utxo| UtxoCandidate {
+                utxo: Utxo {
+                    id: utxo.txid,
+                    index: utxo.vout,
+                    amount: Amount::from_sat(utxo.value),
+                },
+                height: utxo
+                    .status
+                    .block_height
+                    .filter(|_| utxo.status.confirmed)
+                    .map(|height| height as u32),
+            })
+            .collect())
+    }
+
+    async fn get(&self, path: &str) -> OrdResult<reqwest::Response> {
+        let url = format!("{}/{path}", self.url);
+        debug!("GET {url}");
+        self.client
+            .get(&url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(esplora_error)
+    }
+
+    async fn get_text(&self, path: &str) -> OrdResult<String> {
+        self.get(path).await?.text().await.map_err(esplora_error)
+    }
+}
+
+#[async_trait::async_trait]
+impl Broadcaster for EsploraClient {
+    async fn broadcast(&self, tx: &Transaction) -> OrdResult<Txid> {
+        let url = format!("{}/tx", self.url);
+        debug!("broadcasting {} to {url}", tx.txid());
+        let response = self
+            .client
+            .post(&url)
+            .body(bitcoin::consensus::encode::serialize_hex(tx))
+            .send()
+            .await
+            .map_err(esplora_error)?;
+
+        let status = response.status();
+        let body = response.text().await.map_err(esplora_error)?;
+        if !status.is_success() {
+            return Err(OrdError::Esplora(format!(
+                "failed to broadcast {}: {body}",
+                tx.txid()
+            )));
+        }
+
+        Txid::from_str(body.trim()).map_err(|err| OrdError::Esplora(err.to_string()))
+    }
+}
+
+#[async_trait::async_trait]
+impl ChainProvider for EsploraClient {
+    async fn tip(&self) -> OrdResult<BlockRef> {
+        let height = self.get_text("blocks/tip/height").await?;
+        let hash = self.get_text("blocks/tip/hash").await?;
+
+        Ok(BlockRef {
+            height: height
+                .trim()
+                .parse()
+                .map_err(|_| OrdError::Esplora(format!("invalid tip height: {height}")))?,
+            hash: BlockHash::from_str(hash.trim())
+                .map_err(|err| OrdError::Esplora(err.to_string()))?,
+        })
+    }
+
+    async fn transaction_status(&self, txid: &Txid) -> OrdResult<TxStatus> {
+        let url = format!("{}/tx/{txid}/status", self.url);
+        let response = self.client.get(&url).send().await.map_err(esplora_error)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(TxStatus::Unknown);
+        }
+
+        let status: ApiTxStatus = response
+            .error_for_status()
+            .map_err(esplora_error)?
+            .json()
+            .await
+            .map_err(esplora_error)?;
+
+        match status {
+            ApiTxStatus {
+                confirmed: true,
+                block_height: Some(height),
+                block_hash: Some(hash),
+            } => Ok(TxStatus::Confirmed(BlockRef { height, hash })),
+            _ => Ok(TxStatus::Mempool),
+        }
+    }
+
+    async fn wait_for_block(&self) -> OrdResult<()> {
+        info!("waiting for the next block...");
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl FeeProvider for EsploraClient {
+    async fn fee_estimates(&self) -> OrdResult<BTreeMap<u16, FeeRate>> {
+        let estimates: BTreeMap<String, f64> = self
+            .get("fee-estimates")
+            .await?
+            .json()
+            .await
+            .map_err(esplora_error)?;
+
+        parse_fee_estimates(estimates)
+    }
+}
+
+/// Parses the fee estimates of the API, in sat/vB keyed by the number of blocks, rounding the
+/// fee rates up to the next sat/kwu.
+fn parse_fee_estimates(estimates: BTreeMap<String, f64>) -> OrdResult<BTreeMap<u16, FeeRate>> {
+    estimates
+        .into_iter()
+        .map(|(blocks, sat_per_vb)| {
+            let blocks = blocks
+                .parse()
+                .map_err(|_| OrdError::Esplora(format!("invalid fee estimate target: {blocks}")))?;
+            let sat_per_kwu = (sat_per_vb * 250.0).ceil().max(0.0) as u64;
+            Ok((blocks, FeeRate::from_sat_per_kwu(sat_per_kwu)))
+        })
+        .collect()
+}
+
+/// Returns the path of the network in the URLs of the public instances.
+fn network_path(network: Network) -> &'static str {
+    match network {
+        Network::Testnet => "/testnet",
+        Network::Signet => "/signet",
+        _ => "",
+    }
+}
+
+fn esplora_error(err: reqwest::Error) -> OrdError {
+    OrdError::Esplora(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_parse_fee_estimates() {
+        let estimates = parse_fee_estimates(BTreeMap::from([
+            ("1".to_string(), 12.5),
+            ("144".to_string(), 1.001),
+        ]))
+        .unwrap();
+
+        assert_eq!(estimates[&1], FeeRate::from_sat_per_kwu(3125));
+        assert_eq!(estimates[&144], FeeRate::from_sat_per_kwu(251));
+        assert!(parse_fee_estimates(BTreeMap::from([("next".to_string(), 1.0)])).is_err());
+    }
+
+    #[test]
+    fn test_should_use_public_instances() {
+        assert_eq!(
+            EsploraClient::new(None, Network::Testnet).url(),
+            "https://blockstream.info/testnet/api"
+        );
+        assert_eq!(
+            EsploraClient::mempool_space(Network::Bitcoin).url(),
+            "https://mempool.space/api"
+        );
+        assert_eq!(
+            EsploraClient::new(Some("http://localhost:3000/".to_string()), Network::Regtest).url(),
+            "http://localhost:3000"
+        );
+    }
+}
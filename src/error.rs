@@ -1,3 +1,5 @@
+use std::fmt;
+
 use thiserror::Error;
 
 /// Ordinal transaction handling error types
@@ -9,14 +11,154 @@ pub enum OrdError {
     HexCodec(#[from] hex::FromHexError),
     #[error("Ord codec error: {0}")]
     Codec(#[from] serde_json::Error),
+    #[error("Invalid inscription metadata: {0}")]
+    Metadata(String),
+    #[error("Invalid inscription template: {0}")]
+    Template(String),
+    #[error("Invalid inscription content encoding: {0}")]
+    ContentEncoding(String),
     #[error("Bitcoin sighash error: {0}")]
     BitcoinSigHash(#[from] bitcoin::sighash::Error),
     #[error("Bitcoin script error: {0}")]
     PushBytes(#[from] bitcoin::script::PushBytesError),
     #[error("Bad transaction input: {0}")]
     InputNotFound(usize),
-    #[error("Insufficient balance")]
-    InsufficientBalance { required: u64, available: u64 },
+    #[error("Bad transaction output: {0}")]
+    OutputNotFound(usize),
+    #[error(
+        "Insufficient balance: required {} sats, available {} sats",
+        .required.to_sat(),
+        .available.to_sat()
+    )]
+    InsufficientBalance {
+        required: bitcoin::Amount,
+        available: bitcoin::Amount,
+    },
+    #[error("Invalid BRC-20 ticker {0}: tickers must be 4 bytes long, or 5 bytes for self mint")]
+    Brc20InvalidTicker(String),
+    #[error("BRC-20 ticker {0} is not deployed")]
+    Brc20TickerNotDeployed(String),
+    #[error("BRC-20 ticker {0} is fully minted")]
+    Brc20TickerFullyMinted(String),
+    #[error("BRC-20 mint of {amount} {tick} exceeds the limit of {limit}")]
+    Brc20MintLimitExceeded {
+        tick: String,
+        amount: u64,
+        limit: u64,
+    },
+    #[error("Invalid BRC-20 amount {value:?} for \"{field}\": {reason}")]
+    Brc20InvalidAmount {
+        field: &'static str,
+        value: String,
+        reason: &'static str,
+    },
+    #[error("Fee overflow: fee rate of {fee_rate} sat/kwu for {vbytes} vbytes")]
+    FeeOverflow { fee_rate: u64, vbytes: u64 },
+    #[error(
+        "Output value of {} sats is below the dust limit of {} sats",
+        .value.to_sat(),
+        .dust_limit.to_sat()
+    )]
+    DustOutput {
+        value: bitcoin::Amount,
+        dust_limit: bitcoin::Amount,
+    },
+    #[error(
+        "Sponsored amount of {} sats exceeds the offered {} sats",
+        .amount.to_sat(),
+        .max_amount.to_sat()
+    )]
+    SponsorshipExceeded {
+        amount: bitcoin::Amount,
+        max_amount: bitcoin::Amount,
+    },
+    #[error(
+        "Leftovers of {} sats exceed the tolerance of {} sats of a commit without change",
+        .leftover.to_sat(),
+        .tolerance.to_sat()
+    )]
+    UnexpectedLeftovers {
+        leftover: bitcoin::Amount,
+        tolerance: bitcoin::Amount,
+    },
+    #[error("Amount of {0} msats is not a whole number of sats")]
+    SubSatAmount(u64),
+    #[error("Push of {0} bytes exceeds the maximum script element size")]
+    PushTooLarge(usize),
+    #[error("Transaction weight of {weight} WU exceeds the standard maximum of {max} WU")]
+    TransactionTooHeavy { weight: u64, max: u64 },
+    #[error("Invalid etching: {0}")]
+    InvalidEtching(String),
+    #[error("Invalid edict: {0}")]
+    InvalidEdict(String),
+    #[error("Insufficient balance of rune {rune}: required {required}, available {available}")]
+    InsufficientRuneBalance {
+        rune: String,
+        required: u128,
+        available: u128,
+    },
+    #[error("Invalid rune commitment of {0} bytes: commitments are at most 16 bytes long")]
+    RuneCommitment(usize),
+    #[error(
+        "Commit transaction {txid} has {confirmations} confirmations, {required} are required"
+    )]
+    ImmatureCommit {
+        txid: bitcoin::Txid,
+        confirmations: u32,
+        required: u32,
+    },
+    #[error("Invalid inscription workflow transition from {from} to {to}")]
+    InvalidTransition {
+        from: crate::wallet::InscriptionStage,
+        to: crate::wallet::InscriptionStage,
+    },
+    #[error("Transaction {0} is neither in the mempool nor in the best chain")]
+    TransactionDropped(bitcoin::Txid),
+    #[error("UTXO {0} is reserved by another transaction")]
+    UtxoReserved(bitcoin::OutPoint),
+    #[error("No fee estimate available")]
+    NoFeeEstimate,
+    #[error("Esplora client error: {0}")]
+    Esplora(String),
+    #[error("Package relay is not supported by the broadcaster")]
+    PackageRelayUnsupported,
+    #[error("Invalid package: {0}")]
+    InvalidPackage(&'static str),
+    #[error("Redeem script doesn't match the inscription and the public key")]
+    RedeemScriptMismatch,
+    #[error("Commit output {0} doesn't match the output spent by the reveal transaction")]
+    CommitOutputMismatch(u32),
+    #[error("Redeem script timelock not satisfied: {0}")]
+    Timelock(String),
+    #[error("Invalid satpoint {satpoint}: {reason}")]
+    InvalidSatPoint {
+        satpoint: crate::wallet::SatPoint,
+        reason: &'static str,
+    },
+    #[error("Inscription doesn't declare {0} as its parent")]
+    MissingParentTag(crate::InscriptionId),
+    #[error(
+        "Parent output of {} sats doesn't cover the reveal fee of {} sats preceding the parent at offset {offset}",
+        .value.to_sat(),
+        .fee.to_sat()
+    )]
+    ParentValueTooLow {
+        value: bitcoin::Amount,
+        fee: bitcoin::Amount,
+        offset: u64,
+    },
+    #[error(transparent)]
+    InputSigning(InputSigningError),
+    #[error("The signer doesn't support signing with tweaked schnorr keys")]
+    TweakedSigningUnsupported,
+    #[error("Invalid taproot annex: {0}")]
+    InvalidAnnex(&'static str),
+    #[error("Invalid witness of input {index}: {reason}")]
+    InvalidWitness { index: usize, reason: &'static str },
+    #[error("BIP-32 error: {0}")]
+    Bip32(#[from] bitcoin::bip32::Error),
+    #[error("PSBT error: {0}")]
+    Psbt(#[from] bitcoin::psbt::Error),
     #[error("Invalid signature: {0}")]
     Signature(#[from] bitcoin::secp256k1::Error),
     #[error("Failed to convert slice to public key: {0}")]
@@ -71,3 +213,80 @@ pub enum InscriptionParseError {
     #[error("cannot convert non-Brc20 inscription to Brc20")]
     NotBrc20,
 }
+
+/// Error signing an input of a transaction, with the transaction input it refers to.
+///
+/// Its [`source`](std::error::Error::source) is the error of the input, so it can be downcast to
+/// an [`OrdError`].
+#[derive(Debug)]
+pub struct InputSigningError {
+    /// ID of the transaction
+    pub txid: bitcoin::Txid,
+    /// Index of the input
+    pub index: usize,
+    /// Error of the input
+    pub source: Box<OrdError>,
+}
+
+impl fmt::Display for InputSigningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to sign input {} of transaction {}: {}",
+            self.index, self.txid, self.source
+        )
+    }
+}
+
+impl std::error::Error for InputSigningError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl OrdError {
+    /// Wraps the error with the transaction input it refers to.
+    pub(crate) fn for_input(self, txid: bitcoin::Txid, index: usize) -> Self {
+        match self {
+            Self::InputSigning(_) => self,
+            source => Self::InputSigning(InputSigningError {
+                txid,
+                index,
+                source: Box::new(source),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use bitcoin::hashes::Hash as _;
+
+    use super::*;
+
+    #[test]
+    fn test_should_preserve_error_source() {
+        let err = OrdError::from(serde_json::from_str::<u64>("foo").unwrap_err());
+        assert!(err.source().is_some());
+
+        let txid = bitcoin::Txid::all_zeros();
+        let err = OrdError::InputNotFound(1).for_input(txid, 1);
+        assert_eq!(
+            err.to_string(),
+            format!("Failed to sign input 1 of transaction {txid}: Bad transaction input: 1")
+        );
+        let source = err
+            .source()
+            .and_then(|source| source.downcast_ref::<OrdError>());
+        assert!(matches!(source, Some(OrdError::InputNotFound(1))));
+
+        // context is not nested twice
+        let err = err.for_input(txid, 2);
+        assert!(matches!(
+            err,
+            OrdError::InputSigning(InputSigningError { index: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,420 @@
+//! IC
+//!
+//! [`CandidType`] and [`Storable`] implementations of the core types, so canisters can take
+//! them as arguments and keep them in stable memory directly.
+//!
+//! The candid types mirror the serde representation of the types in binary formats, so values
+//! are decoded with their `Deserialize` implementations: txids, scripts and transactions are
+//! blobs, amounts and fee rates are `nat64` sats, and BRC-20 operations are variants of records
+//! of their JSON fields.
+//!
+//! Inscription IDs and UTXOs are stored with a fixed size encoding, so they can be used as keys
+//! of stable maps, while the other types are stored candid encoded.
+
+use std::borrow::Cow;
+
+use bitcoin::hashes::Hash as _;
+use bitcoin::{consensus, Amount, TxOut, Txid};
+use candid::types::{Serializer, Type};
+use candid::{CandidType, Decode, Encode};
+use ic_stable_structures::storable::{Bound, Storable};
+
+use crate::inscription::brc20::{Brc20, Brc20Binary};
+use crate::wallet::{ChangePolicy, CreateCommitTransaction, PendingInscription, Utxo};
+use crate::{InscriptionId, MultisigConfig, Nft};
+
+/// Size of the stable encoding of an [`InscriptionId`]: the txid and the index.
+const INSCRIPTION_ID_SIZE: usize = 32 + 4;
+/// Size of the stable encoding of a [`Utxo`]: the txid, the index and the amount.
+const UTXO_SIZE: usize = 32 + 4 + 8;
+
+impl CandidType for InscriptionId {
+    fn _ty() -> Type {
+        String::ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_text(&self.to_string())
+    }
+}
+
+impl Storable for InscriptionId {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(INSCRIPTION_ID_SIZE);
+        bytes.extend_from_slice(self.txid.as_byte_array());
+        bytes.extend_from_slice(&self.index.to_le_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        let (txid, index) = bytes.split_at(32);
+        Self {
+            txid: Txid::from_slice(txid).expect("invalid stored inscription ID"),
+            index: u32::from_le_bytes(index.try_into().expect("invalid stored inscription ID")),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: INSCRIPTION_ID_SIZE as u32,
+        is_fixed_size: true,
+    };
+}
+
+#[derive(CandidType)]
+struct UtxoRecord {
+    id: Vec<u8>,
+    index: u32,
+    amount: u64,
+}
+
+impl CandidType for Utxo {
+    fn _ty() -> Type {
+        UtxoRecord::_ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        UtxoRecord {
+            id: self.id.to_byte_array().to_vec(),
+            index: self.index,
+            amount: self.amount.to_sat(),
+        }
+        .idl_serialize(serializer)
+    }
+}
+
+impl Storable for Utxo {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(UTXO_SIZE);
+        bytes.extend_from_slice(self.id.as_byte_array());
+        bytes.extend_from_slice(&self.index.to_le_bytes());
+        bytes.extend_from_slice(&self.amount.to_sat().to_le_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        let (id, rest) = bytes.split_at(32);
+        let (index, amount) = rest.split_at(4);
+        Self {
+            id: Txid::from_slice(id).expect("invalid stored UTXO"),
+            index: u32::from_le_bytes(index.try_into().expect("invalid stored UTXO")),
+            amount: Amount::from_sat(u64::from_le_bytes(
+                amount.try_into().expect("invalid stored UTXO"),
+            )),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: UTXO_SIZE as u32,
+        is_fixed_size: true,
+    };
+}
+
+impl CandidType for Brc20 {
+    fn _ty() -> Type {
+        Brc20Binary::_ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        Brc20Binary::from(self).idl_serialize(serializer)
+    }
+}
+
+impl CandidType for MultisigConfig {
+    fn _ty() -> Type {
+        MultisigConfigRecord::_ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        MultisigConfigRecord {
+            required: self.required as u64,
+            total: self.total as u64,
+        }
+        .idl_serialize(serializer)
+    }
+}
+
+#[derive(CandidType)]
+struct MultisigConfigRecord {
+    required: u64,
+    total: u64,
+}
+
+#[derive(CandidType)]
+struct CreateCommitTransactionRecord {
+    unsigned_tx: Vec<u8>,
+    redeem_script: Vec<u8>,
+    reveal_balance: u64,
+    commit_fee: u64,
+    reveal_fee: u64,
+    leftover_amount: u64,
+    postage: u64,
+}
+
+impl From<&CreateCommitTransaction> for CreateCommitTransactionRecord {
+    fn from(commit: &CreateCommitTransaction) -> Self {
+        Self {
+            unsigned_tx: consensus::serialize(&commit.unsigned_tx),
+            redeem_script: commit.redeem_script.to_bytes(),
+            reveal_balance: commit.reveal_balance.to_sat(),
+            commit_fee: commit.commit_fee.to_sat(),
+            reveal_fee: commit.reveal_fee.to_sat(),
+            leftover_amount: commit.leftover_amount.to_sat(),
+            postage: commit.postage.to_sat(),
+        }
+    }
+}
+
+impl CandidType for CreateCommitTransaction {
+    fn _ty() -> Type {
+        CreateCommitTransactionRecord::_ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        CreateCommitTransactionRecord::from(self).idl_serialize(serializer)
+    }
+}
+
+#[derive(CandidType)]
+struct TxOutRecord {
+    value: u64,
+    script_pubkey: Vec<u8>,
+}
+
+impl From<&TxOut> for TxOutRecord {
+    fn from(output: &TxOut) -> Self {
+        Self {
+            value: output.value.to_sat(),
+            script_pubkey: output.script_pubkey.to_bytes(),
+        }
+    }
+}
+
+#[derive(CandidType)]
+enum ChangePolicyRecord {
+    Always,
+    Sweep { threshold: u64 },
+    MergeIntoPostage,
+    Omit { tolerance: u64 },
+}
+
+impl From<ChangePolicy> for ChangePolicyRecord {
+    fn from(change_policy: ChangePolicy) -> Self {
+        match change_policy {
+            ChangePolicy::Always => Self::Always,
+            ChangePolicy::Sweep { threshold } => Self::Sweep {
+                threshold: threshold.to_sat(),
+            },
+            ChangePolicy::MergeIntoPostage => Self::MergeIntoPostage,
+            ChangePolicy::Omit { tolerance } => Self::Omit {
+                tolerance: tolerance.to_sat(),
+            },
+        }
+    }
+}
+
+#[derive(CandidType)]
+struct PendingInscriptionRecord<'a> {
+    commit: &'a CreateCommitTransaction,
+    prevouts: Vec<TxOutRecord>,
+    change_policy: ChangePolicyRecord,
+    reveal_recipient: String,
+    fee_rate: u64,
+    multisig_config: &'a Option<MultisigConfig>,
+    created_at: u64,
+    deadline: Option<u64>,
+}
+
+impl CandidType for PendingInscription {
+    fn _ty() -> Type {
+        PendingInscriptionRecord::_ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: Serializer,
+    {
+        PendingInscriptionRecord {
+            commit: &self.commit,
+            prevouts: self.prevouts.iter().map(TxOutRecord::from).collect(),
+            change_policy: self.change_policy.into(),
+            reveal_recipient: self.reveal_recipient.to_string(),
+            fee_rate: self.fee_rate.to_sat_per_kwu(),
+            multisig_config: &self.multisig_config,
+            created_at: self.created_at,
+            deadline: self.deadline,
+        }
+        .idl_serialize(serializer)
+    }
+}
+
+impl Storable for Brc20 {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode BRC-20 operation"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode BRC-20 operation")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for Nft {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode NFT"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode NFT")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for PendingInscription {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("failed to encode pending inscription"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(&bytes, Self).expect("failed to decode pending inscription")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{
+        Address, FeeRate, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+    };
+    use candid::Deserialize;
+
+    use super::*;
+
+    fn candid_round_trip<T>(value: &T) -> T
+    where
+        T: CandidType + for<'de> Deserialize<'de>,
+    {
+        let bytes = Encode!(value).unwrap();
+        Decode!(&bytes, T).unwrap()
+    }
+
+    fn stable_round_trip<T: Storable>(value: &T) -> T {
+        T::from_bytes(value.to_bytes_checked())
+    }
+
+    fn txid() -> Txid {
+        Txid::from_str("ff314aebaa91a3f10cfba576d3be958127aba982d29146735e612869567e7808").unwrap()
+    }
+
+    #[test]
+    fn test_should_store_inscription_ids_and_utxos() {
+        let id = InscriptionId {
+            txid: txid(),
+            index: 3,
+        };
+        assert_eq!(candid_round_trip(&id), id);
+        assert_eq!(stable_round_trip(&id), id);
+
+        let utxo = Utxo {
+            id: txid(),
+            index: 1,
+            amount: Amount::from_sat(10_000),
+        };
+        assert_eq!(candid_round_trip(&utxo), utxo);
+        assert_eq!(stable_round_trip(&utxo), utxo);
+    }
+
+    #[test]
+    fn test_should_store_inscriptions() {
+        for brc20 in [
+            Brc20::deploy("ordi", 21_000_000, Some(1_000), Some(8), Some(true)),
+            Brc20::deploy("ordi", 21_000_000, None, None, None),
+            Brc20::mint("ordi", 1_000),
+            Brc20::transfer("ordi", 100),
+        ] {
+            assert_eq!(candid_round_trip(&brc20), brc20);
+            assert_eq!(stable_round_trip(&brc20), brc20);
+        }
+
+        let mut nft = Nft::new(Some(b"text/plain".to_vec()), Some(b"hello".to_vec()));
+        nft.parents = vec![vec![1; 36]];
+        assert_eq!(candid_round_trip(&nft), nft);
+        assert_eq!(stable_round_trip(&nft), nft);
+    }
+
+    #[test]
+    fn test_should_store_pending_inscriptions() {
+        let recipient = Address::from_str("tb1qax89amll2uas5k92tmuc8rdccmqddqw94vrr86")
+            .unwrap()
+            .require_network(Network::Testnet)
+            .unwrap();
+        let commit = CreateCommitTransaction {
+            unsigned_tx: Transaction {
+                version: Version::TWO,
+                lock_time: LockTime::from_height(840_000).unwrap(),
+                input: vec![TxIn {
+                    previous_output: OutPoint::new(txid(), 1),
+                    script_sig: ScriptBuf::from_bytes(vec![0x51]),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::from_slice(&[vec![1, 2, 3], vec![]]),
+                }],
+                output: vec![TxOut {
+                    value: Amount::from_sat(5_000),
+                    script_pubkey: recipient.script_pubkey(),
+                }],
+            },
+            redeem_script: ScriptBuf::from_bytes(vec![0x51, 0x52]),
+            reveal_balance: Amount::from_sat(5_000),
+            commit_fee: Amount::from_sat(300),
+            reveal_fee: Amount::from_sat(200),
+            leftover_amount: Amount::from_sat(4_500),
+            postage: Amount::from_sat(546),
+            parent: None,
+            tapleaf_hash: None,
+            merkle_root: None,
+        };
+        let prevouts = vec![TxOut {
+            value: Amount::from_sat(10_000),
+            script_pubkey: recipient.script_pubkey(),
+        }];
+        let mut pending = PendingInscription::new(
+            commit,
+            prevouts,
+            recipient,
+            FeeRate::from_sat_per_vb(10).unwrap(),
+            1_700_000_000,
+        )
+        .with_change_policy(ChangePolicy::Sweep {
+            threshold: Amount::from_sat(1_000),
+        });
+        pending.multisig_config = Some(MultisigConfig {
+            required: 2,
+            total: 3,
+        });
+        pending.deadline = Some(1_700_003_600);
+
+        assert_eq!(candid_round_trip(&pending), pending);
+        assert_eq!(stable_round_trip(&pending), pending);
+    }
+}
@@ -1,11 +1,19 @@
 pub mod brc20;
+pub mod encoding;
+pub mod envelope;
 pub mod iid;
+pub mod media_type;
 pub mod nft;
+pub mod template;
 
+use bitcoin::opcodes::all::OP_CHECKSIG;
 use bitcoin::script::{Builder as ScriptBuilder, PushBytesBuf};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::inscription::envelope::EnvelopeBuilder;
+use crate::utils::constants::CONTENT_TYPE_TAG;
+use crate::utils::push_bytes::bytes_to_push_chunks;
 use crate::wallet::RedeemScriptPubkey;
 use crate::{OrdError, OrdResult};
 
@@ -14,9 +22,15 @@ use crate::{OrdError, OrdResult};
 ///
 /// These are methods for encoding, decoding, and managing
 /// the inscriptions, tailored to specific types (e.g. `Brc20`, `Nft`).
+///
+/// New inscription kinds only need to describe their envelope with [`Inscription::fields`], from
+/// which the redeem script is generated.
 pub trait Inscription: DeserializeOwned {
     /// Generates the redeem script from a script pubkey and the inscription.
     ///
+    /// The default implementation writes `<pubkey> OP_CHECKSIG` followed by the envelope
+    /// returned by [`Inscription::fields`].
+    ///
     /// # Errors
     ///
     /// May return an `OrdError` if (de)serialization of any of the inscription fields
@@ -25,7 +39,32 @@ pub trait Inscription: DeserializeOwned {
         &self,
         builder: ScriptBuilder,
         pubkey: RedeemScriptPubkey,
-    ) -> OrdResult<ScriptBuilder>;
+    ) -> OrdResult<ScriptBuilder> {
+        let encoded_pubkey = pubkey.encode()?;
+        let builder = builder
+            .push_slice(encoded_pubkey.as_push_bytes())
+            .push_opcode(OP_CHECKSIG);
+
+        self.fields()?.append_to(builder)
+    }
+
+    /// Returns the fields and the body of the inscription envelope.
+    ///
+    /// The default implementation writes the [`Inscription::content_type`] field followed by
+    /// the body returned by [`Inscription::data_chunks`].
+    ///
+    /// # Errors
+    ///
+    /// May return an `OrdError` if encoding the body fails.
+    fn fields(&self) -> OrdResult<EnvelopeBuilder> {
+        let mut fields =
+            EnvelopeBuilder::new().field(CONTENT_TYPE_TAG, self.content_type().into_bytes());
+        for chunk in self.data_chunks()? {
+            fields = fields.body_chunk(chunk.as_bytes());
+        }
+
+        Ok(fields)
+    }
 
     /// Encodes the inscription object into a JSON string.
     ///
@@ -39,6 +78,21 @@ pub trait Inscription: DeserializeOwned {
         serde_json::to_string(self).map_err(OrdError::from)
     }
 
+    /// Encodes the inscription object as JSON, appending it to `buf`.
+    ///
+    /// Unlike [`Inscription::encode`], this doesn't allocate an intermediate `String`,
+    /// so the same buffer can be reused when encoding many inscriptions.
+    ///
+    /// # Errors
+    ///
+    /// May return an `OrdError` if serialization fails.
+    fn encode_into(&self, buf: &mut Vec<u8>) -> OrdResult<()>
+    where
+        Self: Serialize,
+    {
+        serde_json::to_writer(buf, self).map_err(OrdError::from)
+    }
+
     /// Returns the MIME content type of the inscription.
     ///
     /// It should provide the MIME type string that best represents the
@@ -65,8 +119,35 @@ pub trait Inscription: DeserializeOwned {
     ///   - OP_ENDIF
     ///
     /// So for example in case of a BRC20, this function must return the JSON encoded BRC20 operation as `PushBytes`.
+    ///
+    /// The body is returned whole, so it may exceed the maximum push size; use
+    /// [`Inscription::data_chunks`] to write it to a script.
     fn data(&self) -> OrdResult<PushBytesBuf>;
 
+    /// Returns the body of the inscription, as returned by [`Inscription::data`], split into
+    /// pushes of at most [`MAX_SCRIPT_ELEMENT_SIZE`](crate::constants::MAX_SCRIPT_ELEMENT_SIZE)
+    /// bytes, so it can be written to the redeem script whatever its size.
+    ///
+    /// # Errors
+    ///
+    /// May return an `OrdError` if encoding fails.
+    fn data_chunks(&self) -> OrdResult<Vec<PushBytesBuf>> {
+        Ok(bytes_to_push_chunks(self.data()?.as_bytes()))
+    }
+
+    /// Appends the body of the inscription, as returned by [`Inscription::data`], to `buf`.
+    ///
+    /// The default implementation copies the result of [`Inscription::data`]; implementors
+    /// should override it to write into the buffer directly.
+    ///
+    /// # Errors
+    ///
+    /// May return an `OrdError` if encoding fails or if the data doesn't fit in `buf`.
+    fn data_into(&self, buf: &mut PushBytesBuf) -> OrdResult<()> {
+        buf.extend_from_slice(self.data()?.as_bytes())?;
+        Ok(())
+    }
+
     /// Parses inscription data from the serialized bytes found in the witness script.
     ///
     /// Decodes the inscription data embedded within the witness script of
@@ -6,27 +6,75 @@
 //! 1. Deployments initialize the BRC-20. Do not affect state.
 //! 2. Mints provide a balance to only the first owner of the mint function inscription.
 //! 3. Transfers deduct from the sender's balance and add to the receiver's balance,
-//!     only upon the first transfer of the transfer function. That is,
+//!    only upon the first transfer of the transfer function. That is,
 //!     - step 1. Sender inscribes the transfer function to sender's (own) address.
 //!     - step 2. Sender transfers transfer function to final destination address.
+//!
+//! The effects of a transaction on the balances can be simulated against a local [`Brc20State`]
+//! before broadcasting it.
+
+pub mod cost;
+mod state;
+pub mod validate;
 
+use std::fmt;
+use std::marker::PhantomData;
 use std::str::FromStr;
 
-use bitcoin::opcodes::all::{OP_CHECKSIG, OP_ENDIF, OP_IF};
-use bitcoin::opcodes::{OP_0, OP_FALSE};
-use bitcoin::script::{Builder as ScriptBuilder, PushBytesBuf};
-use serde_with::{serde_as, DisplayFromStr};
+use bitcoin::script::PushBytesBuf;
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+use serde_with::{serde_as, DeserializeAs, DisplayFromStr, SerializeAs};
+use unicode_normalization::UnicodeNormalization as _;
 
-use crate::utils::push_bytes::bytes_to_push_bytes;
-use crate::wallet::RedeemScriptPubkey;
-use crate::{Inscription, OrdError, OrdResult};
+pub use self::cost::Brc20Cost;
+pub use self::state::{Brc20Balance, Brc20Effect, Brc20Rejection, Brc20State};
+pub use self::validate::Brc20ValidationError;
+use crate::utils::push_bytes::PushBytesWriter;
+use crate::{Inscription, MediaType, OrdError, OrdResult};
 
 const PROTOCOL: &str = "brc-20";
 
+/// Decimals of a ticker deployed without `dec`.
+pub const DEFAULT_DECIMALS: u64 = 18;
+
+/// Length in bytes of BRC-20 tickers.
+pub const TICKER_LENGTH: usize = 4;
+/// Length in bytes of self mint BRC-20 tickers.
+pub const SELF_MINT_TICKER_LENGTH: usize = 5;
+
+/// Normalizes a BRC-20 ticker for comparisons.
+///
+/// Tickers are case-insensitive and may contain multi-byte unicode characters, so they're
+/// compared in lowercase and in Unicode Normalization Form C.
+pub fn normalize_ticker(tick: &str) -> String {
+    tick.nfc().collect::<String>().to_lowercase()
+}
+
+/// Returns whether two BRC-20 tickers refer to the same token.
+pub fn tickers_eq(a: &str, b: &str) -> bool {
+    normalize_ticker(a) == normalize_ticker(b)
+}
+
 /// Represents a BRC-20 operation: (Deploy, Mint, Transfer)
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(tag = "op")]
+///
+/// Operations are (de)serialized as their JSON documents, tagged by `op`, in human-readable
+/// formats, and as externally tagged enums in binary formats.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Brc20 {
+    /// Deploy a BRC-20 token
+    Deploy(Brc20Deploy),
+    /// Mint BRC-20 tokens
+    Mint(Brc20Mint),
+    /// Transfer BRC-20 tokens
+    Transfer(Brc20Transfer),
+}
+
+/// Human-readable representation of [`Brc20`].
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(remote = "Brc20", rename = "Brc20", tag = "op")]
+enum Brc20Document {
     /// Deploy a BRC-20 token
     #[serde(rename = "deploy")]
     Deploy(Brc20Deploy),
@@ -38,6 +86,131 @@ pub enum Brc20 {
     Transfer(Brc20Transfer),
 }
 
+/// Binary representation of [`Brc20`], with the fields of the JSON documents as strings, since
+/// binary formats can't always decode internally tagged enums, nor optional amounts.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "ic", derive(candid::CandidType))]
+#[serde(rename = "Brc20")]
+pub(crate) enum Brc20Binary {
+    #[serde(rename = "deploy")]
+    Deploy {
+        p: String,
+        tick: String,
+        max: String,
+        lim: Option<String>,
+        dec: Option<String>,
+        self_mint: Option<String>,
+    },
+    #[serde(rename = "mint")]
+    Mint {
+        p: String,
+        tick: String,
+        amt: String,
+    },
+    #[serde(rename = "transfer")]
+    Transfer {
+        p: String,
+        tick: String,
+        amt: String,
+    },
+}
+
+impl From<&Brc20> for Brc20Binary {
+    fn from(brc20: &Brc20) -> Self {
+        match brc20 {
+            Brc20::Deploy(deploy) => Self::Deploy {
+                p: deploy.protocol.clone(),
+                tick: deploy.tick.clone(),
+                max: deploy.max.to_string(),
+                lim: deploy.lim.map(|lim| lim.to_string()),
+                dec: deploy.dec.map(|dec| dec.to_string()),
+                self_mint: deploy.self_mint.map(|self_mint| self_mint.to_string()),
+            },
+            Brc20::Mint(mint) => Self::Mint {
+                p: mint.protocol.clone(),
+                tick: mint.tick.clone(),
+                amt: mint.amt.to_string(),
+            },
+            Brc20::Transfer(transfer) => Self::Transfer {
+                p: transfer.protocol.clone(),
+                tick: transfer.tick.clone(),
+                amt: transfer.amt.to_string(),
+            },
+        }
+    }
+}
+
+impl Brc20Binary {
+    fn into_brc20<E: de::Error>(self) -> Result<Brc20, E> {
+        let amount = |field, value: Option<String>| {
+            value
+                .map(|value| parse_amount(field, &value).map_err(E::custom))
+                .transpose()
+        };
+
+        Ok(match self {
+            Self::Deploy {
+                p,
+                tick,
+                max,
+                lim,
+                dec,
+                self_mint,
+            } => Brc20::Deploy(Brc20Deploy {
+                protocol: p,
+                tick,
+                max: parse_amount(Max::NAME, &max).map_err(E::custom)?,
+                lim: amount(Lim::NAME, lim)?,
+                dec: amount(Dec::NAME, dec)?,
+                self_mint: self_mint
+                    .map(|self_mint| self_mint.parse().map_err(E::custom))
+                    .transpose()?,
+            }),
+            Self::Mint { p, tick, amt } => Brc20::Mint(Brc20Mint {
+                protocol: p,
+                tick,
+                amt: parse_amount(Amt::NAME, &amt).map_err(E::custom)?,
+            }),
+            Self::Transfer { p, tick, amt } => Brc20::Transfer(Brc20Transfer {
+                protocol: p,
+                tick,
+                amt: parse_amount(Amt::NAME, &amt).map_err(E::custom)?,
+            }),
+        })
+    }
+}
+
+impl serde::Serialize for Brc20 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            Brc20Document::serialize(self, serializer)
+        } else {
+            Brc20Binary::from(self).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Brc20 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            Brc20Document::deserialize(deserializer)
+        } else {
+            Brc20Binary::deserialize(deserializer)?.into_brc20()
+        }
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for Brc20 {
+    fn schema_name() -> String {
+        Brc20Document::schema_name()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        Brc20Document::json_schema(gen)
+    }
+}
+
 impl Brc20 {
     /// Create a new BRC-20 deploy operation
     pub fn deploy(
@@ -75,24 +248,137 @@ impl Brc20 {
         })
     }
 
-    fn append_reveal_script_to_builder(
-        &self,
-        builder: ScriptBuilder,
-        pubkey: RedeemScriptPubkey,
-    ) -> OrdResult<ScriptBuilder> {
-        let encoded_pubkey = pubkey.encode()?;
+    /// Returns the ticker of the operation.
+    pub fn tick(&self) -> &str {
+        match self {
+            Self::Deploy(deploy) => &deploy.tick,
+            Self::Mint(mint) => &mint.tick,
+            Self::Transfer(transfer) => &transfer.tick,
+        }
+    }
 
-        Ok(builder
-            .push_slice(encoded_pubkey.as_push_bytes())
-            .push_opcode(OP_CHECKSIG)
-            .push_opcode(OP_FALSE)
-            .push_opcode(OP_IF)
-            .push_slice(b"ord")
-            .push_slice(b"\x01")
-            .push_slice(bytes_to_push_bytes(self.content_type().as_bytes())?.as_push_bytes())
-            .push_opcode(OP_0)
-            .push_slice(self.data()?.as_push_bytes())
-            .push_opcode(OP_ENDIF))
+    /// Validates the length of the ticker, which is counted in bytes rather than characters,
+    /// e.g. a single 4 bytes emoji is a valid ticker.
+    ///
+    /// Tickers are [`TICKER_LENGTH`] bytes long, or [`SELF_MINT_TICKER_LENGTH`] bytes long
+    /// for tickers deployed with `self_mint`, which is ignored for shorter tickers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::Brc20InvalidTicker`] if the ticker has an invalid length.
+    pub fn validate_ticker(&self) -> OrdResult<()> {
+        let tick = self.tick();
+        let valid = match self {
+            Self::Deploy(deploy) => {
+                tick.len() == TICKER_LENGTH
+                    || (tick.len() == SELF_MINT_TICKER_LENGTH && deploy.self_mint == Some(true))
+            }
+            Self::Mint(_) | Self::Transfer(_) => {
+                tick.len() == TICKER_LENGTH || tick.len() == SELF_MINT_TICKER_LENGTH
+            }
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(OrdError::Brc20InvalidTicker(tick.to_string()))
+        }
+    }
+
+    /// Create a new BRC-20 mint operation, checking with the resolver that the ticker
+    /// is deployed and that the amount can still be minted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::Brc20InvalidTicker`], [`OrdError::Brc20TickerNotDeployed`],
+    /// [`OrdError::Brc20TickerFullyMinted`] or [`OrdError::Brc20MintLimitExceeded`]
+    /// if the mint would be invalid.
+    pub async fn try_mint(
+        tick: impl ToString,
+        amt: u64,
+        resolver: &(impl TickerResolver + ?Sized),
+    ) -> OrdResult<Self> {
+        let tick = tick.to_string();
+        Self::mint(&tick, amt).validate_ticker()?;
+        let info = Self::resolve(&tick, resolver).await?;
+
+        let remaining = info.max.saturating_sub(info.minted);
+        if remaining == 0 {
+            return Err(OrdError::Brc20TickerFullyMinted(tick));
+        }
+        let limit = info.lim.unwrap_or(info.max).min(remaining);
+        if amt > limit {
+            return Err(OrdError::Brc20MintLimitExceeded {
+                tick,
+                amount: amt,
+                limit,
+            });
+        }
+
+        Ok(Self::mint(tick, amt))
+    }
+
+    /// Create a new BRC-20 transfer operation, checking with the resolver that the ticker
+    /// is deployed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::Brc20InvalidTicker`] if the ticker is invalid,
+    /// or [`OrdError::Brc20TickerNotDeployed`] if it doesn't exist.
+    pub async fn try_transfer(
+        tick: impl ToString,
+        amt: u64,
+        resolver: &(impl TickerResolver + ?Sized),
+    ) -> OrdResult<Self> {
+        let transfer = Self::transfer(tick, amt);
+        transfer.validate_ticker()?;
+        Self::resolve(transfer.tick(), resolver).await?;
+
+        Ok(transfer)
+    }
+
+    /// Returns the JSON schema of the BRC-20 operations, to validate user-provided payloads
+    /// before decoding them.
+    ///
+    /// The schema can't express lengths in bytes, so tickers must still be checked with
+    /// [`Brc20::validate_ticker`].
+    #[cfg(feature = "json-schema")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json-schema")))]
+    pub fn json_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(Brc20)
+    }
+
+    async fn resolve(
+        tick: &str,
+        resolver: &(impl TickerResolver + ?Sized),
+    ) -> OrdResult<Brc20TickerInfo> {
+        resolver
+            .resolve_ticker(&normalize_ticker(tick))
+            .await?
+            .ok_or_else(|| OrdError::Brc20TickerNotDeployed(tick.to_string()))
+    }
+}
+
+impl Brc20 {
+    /// Decodes a JSON encoded operation.
+    ///
+    /// Serde errors only carry a message, so when decoding fails the numeric fields are checked
+    /// again to return an [`OrdError::Brc20InvalidAmount`] pointing at the malformed one.
+    fn decode(data: &[u8]) -> OrdResult<Self> {
+        serde_json::from_slice(data).map_err(|err| {
+            serde_json::from_slice::<serde_json::Value>(data)
+                .ok()
+                .and_then(|value| find_invalid_amount(&value))
+                .unwrap_or(OrdError::Codec(err))
+        })
+    }
+}
+
+/// Formats the operation as its canonical JSON document, i.e. the body inscribed by
+/// [`Inscription::data`], which [`Brc20::from_str`] parses back.
+impl fmt::Display for Brc20 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode().map_err(|_| fmt::Error)?)
     }
 }
 
@@ -100,87 +386,406 @@ impl FromStr for Brc20 {
     type Err = OrdError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        serde_json::from_str(s).map_err(OrdError::from)
+        Self::decode(s.as_bytes())
     }
 }
 
 impl Inscription for Brc20 {
-    fn generate_redeem_script(
-        &self,
-        builder: ScriptBuilder,
-        pubkey: RedeemScriptPubkey,
-    ) -> OrdResult<ScriptBuilder> {
-        self.append_reveal_script_to_builder(builder, pubkey)
+    fn content_type(&self) -> String {
+        Brc20ContentType::default().as_str().to_string()
+    }
+
+    fn data(&self) -> OrdResult<PushBytesBuf> {
+        let mut data = PushBytesBuf::new();
+        self.data_into(&mut data)?;
+        Ok(data)
     }
 
+    fn data_into(&self, buf: &mut PushBytesBuf) -> OrdResult<()> {
+        serde_json::to_writer(PushBytesWriter(buf), self).map_err(OrdError::from)
+    }
+
+    fn parse(data: &[u8]) -> OrdResult<Self> {
+        Self::decode(data)
+    }
+}
+
+impl Brc20 {
+    /// Returns the operation inscribed with the given content type, instead of
+    /// `text/plain;charset=utf-8`.
+    pub fn with_content_type(self, content_type: Brc20ContentType) -> Brc20Inscription {
+        Brc20Inscription {
+            operation: self,
+            content_type,
+        }
+    }
+}
+
+/// Content type of BRC-20 inscriptions, both of which are processed by indexers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Brc20ContentType {
+    /// `text/plain;charset=utf-8`, as in the original BRC-20 inscriptions
+    #[default]
+    TextPlain,
+    /// `application/json`
+    ApplicationJson,
+}
+
+impl Brc20ContentType {
+    /// Returns the content type as inscribed.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TextPlain => "text/plain;charset=utf-8",
+            Self::ApplicationJson => "application/json",
+        }
+    }
+
+    /// Returns the BRC-20 content type of a media type, whatever its parameters, or `None` if
+    /// indexers don't process BRC-20 operations inscribed with it.
+    pub fn from_media_type(media_type: &MediaType) -> Option<Self> {
+        match (media_type.type_(), media_type.subtype()) {
+            ("text", "plain") => Some(Self::TextPlain),
+            ("application", "json") => Some(Self::ApplicationJson),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Brc20ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A BRC-20 operation along with the content type to inscribe it with, returned by
+/// [`Brc20::with_content_type`].
+///
+/// It's (de)serialized as the operation, so deserialized inscriptions have the default content
+/// type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Brc20", into = "Brc20")]
+pub struct Brc20Inscription {
+    /// The BRC-20 operation
+    pub operation: Brc20,
+    /// The content type of the inscription
+    pub content_type: Brc20ContentType,
+}
+
+impl From<Brc20> for Brc20Inscription {
+    fn from(operation: Brc20) -> Self {
+        operation.with_content_type(Brc20ContentType::default())
+    }
+}
+
+impl From<Brc20Inscription> for Brc20 {
+    fn from(inscription: Brc20Inscription) -> Self {
+        inscription.operation
+    }
+}
+
+impl Inscription for Brc20Inscription {
     fn content_type(&self) -> String {
-        "text/plain;charset=utf-8".to_string()
+        self.content_type.as_str().to_string()
     }
 
     fn data(&self) -> OrdResult<PushBytesBuf> {
-        bytes_to_push_bytes(self.encode()?.as_bytes())
+        self.operation.data()
     }
+
+    fn data_into(&self, buf: &mut PushBytesBuf) -> OrdResult<()> {
+        self.operation.data_into(buf)
+    }
+
+    fn parse(data: &[u8]) -> OrdResult<Self> {
+        Brc20::parse(data).map(Self::from)
+    }
+}
+
+/// State of a deployed BRC-20 ticker, as returned by a [`TickerResolver`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Brc20TickerInfo {
+    /// Max supply of the ticker
+    pub max: u64,
+    /// Mint limit per inscription, if any
+    pub lim: Option<u64>,
+    /// Amount minted so far
+    pub minted: u64,
+    /// Decimals of the ticker, [`DEFAULT_DECIMALS`] if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dec: Option<u64>,
+}
+
+impl Brc20TickerInfo {
+    /// Returns the decimals of the ticker, i.e. the maximum number of fractional digits of the
+    /// amounts of its operations.
+    pub fn decimals(&self) -> u64 {
+        self.dec.unwrap_or(DEFAULT_DECIMALS)
+    }
+}
+
+/// Resolves the state of deployed BRC-20 tickers, e.g. from an indexer.
+///
+/// Used by [`Brc20::try_mint`] and [`Brc20::try_transfer`] to reject invalid operations
+/// before paying the fees to inscribe them.
+#[async_trait::async_trait]
+pub trait TickerResolver {
+    /// Returns the state of the given ticker, normalized with [`normalize_ticker`],
+    /// or `None` if it is not deployed.
+    async fn resolve_ticker(&self, tick: &str) -> OrdResult<Option<Brc20TickerInfo>>;
 }
 
 /// `deploy` op
 #[serde_as]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Brc20Deploy {
     /// Protocol (required): Helps other systems identify and process brc-20 events
     #[serde(rename = "p")]
-    protocol: String,
-    /// Ticker (required): 4 or 5 letter identifier of the brc-20
+    #[cfg_attr(feature = "json-schema", schemars(regex(pattern = "^brc-20$")))]
+    pub(crate) protocol: String,
+    /// Ticker (required): 4 or 5 bytes identifier of the brc-20
+    #[cfg_attr(feature = "json-schema", schemars(length(min = 1, max = 5)))]
     pub tick: String,
     /// Max supply (required): Set max supply of the brc-20
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Brc20Amount<Max>")]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "String", regex(pattern = r"^\d+$"))
+    )]
     pub max: u64,
     /// Mint limit (optional): If letting users mint to themsleves, limit per ordinal
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde_as(as = "Option<Brc20Amount<Lim>>")]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "Option<String>", regex(pattern = r"^\d+$"))
+    )]
     pub lim: Option<u64>,
     /// Decimals (optional): Set decimal precision, default to 18
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde_as(as = "Option<Brc20Amount<Dec>>")]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "Option<String>", regex(pattern = r"^\d+$"))
+    )]
     pub dec: Option<u64>,
     /// Self mint (optional): Set the ticker to be mintable only by the deployment holder
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde_as(as = "Option<DisplayFromStr>")]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "Option<String>", regex(pattern = "^(true|false)$"))
+    )]
     pub self_mint: Option<bool>,
 }
 
 /// `mint` op
 #[serde_as]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Brc20Mint {
     /// Protocol (required): Helps other systems identify and process brc-20 events
     #[serde(rename = "p")]
-    protocol: String,
-    /// Ticker (required): 4 or 5 letter identifier of the brc-20
+    #[cfg_attr(feature = "json-schema", schemars(regex(pattern = "^brc-20$")))]
+    pub(crate) protocol: String,
+    /// Ticker (required): 4 or 5 bytes identifier of the brc-20
+    #[cfg_attr(feature = "json-schema", schemars(length(min = 1, max = 5)))]
     pub tick: String,
     /// Amount to mint (required): States the amount of the brc-20 to mint.
     /// Has to be less than "lim" of the `deploy` op if stated.
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Brc20Amount<Amt>")]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "String", regex(pattern = r"^\d+$"))
+    )]
     pub amt: u64,
 }
 
 /// `transfer` op
 #[serde_as]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Brc20Transfer {
     /// Protocol (required): Helps other systems identify and process brc-20 events
     #[serde(rename = "p")]
-    protocol: String,
-    /// Ticker (required): 4 or 5 letter identifier of the brc-20
+    #[cfg_attr(feature = "json-schema", schemars(regex(pattern = "^brc-20$")))]
+    pub(crate) protocol: String,
+    /// Ticker (required): 4 or 5 bytes identifier of the brc-20
+    #[cfg_attr(feature = "json-schema", schemars(length(min = 1, max = 5)))]
     pub tick: String,
     /// Amount to transfer (required): States the amount of the brc-20 to transfer.
-    #[serde_as(as = "DisplayFromStr")]
+    #[serde_as(as = "Brc20Amount<Amt>")]
+    #[cfg_attr(
+        feature = "json-schema",
+        schemars(with = "String", regex(pattern = r"^\d+$"))
+    )]
     pub amt: u64,
 }
 
+/// Name of a numeric BRC-20 field, as reported by [`OrdError::Brc20InvalidAmount`].
+trait AmountField {
+    const NAME: &'static str;
+}
+
+struct Amt;
+struct Max;
+struct Lim;
+struct Dec;
+
+impl AmountField for Amt {
+    const NAME: &'static str = "amt";
+}
+
+impl AmountField for Max {
+    const NAME: &'static str = "max";
+}
+
+impl AmountField for Lim {
+    const NAME: &'static str = "lim";
+}
+
+impl AmountField for Dec {
+    const NAME: &'static str = "dec";
+}
+
+/// Returns the error of the first malformed numeric field of a JSON encoded operation.
+fn find_invalid_amount(value: &serde_json::Value) -> Option<OrdError> {
+    [Amt::NAME, Max::NAME, Lim::NAME, Dec::NAME]
+        .into_iter()
+        .find_map(|field| match value.get(field)? {
+            serde_json::Value::String(amount) => parse_amount(field, amount).err(),
+            serde_json::Value::Null => None,
+            other => Some(invalid_amount(
+                field,
+                other.to_string(),
+                "amounts must be strings",
+            )),
+        })
+}
+
+/// (De)serializes a numeric BRC-20 field as a decimal string, rejecting the notations
+/// indexers don't accept with an [`OrdError::Brc20InvalidAmount`] message.
+struct Brc20Amount<F>(PhantomData<F>);
+
+impl<F> SerializeAs<u64> for Brc20Amount<F> {
+    fn serialize_as<S>(source: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        DisplayFromStr::serialize_as(source, serializer)
+    }
+}
+
+impl<'de, F: AmountField> DeserializeAs<'de, u64> for Brc20Amount<F> {
+    fn deserialize_as<D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(AmountVisitor::<F>(PhantomData))
+    }
+}
+
+struct AmountVisitor<F>(PhantomData<F>);
+
+impl<F: AmountField> AmountVisitor<F> {
+    fn reject<E: de::Error>(value: impl ToString) -> E {
+        E::custom(invalid_amount(
+            F::NAME,
+            value.to_string(),
+            "amounts must be strings",
+        ))
+    }
+}
+
+impl<'de, F: AmountField> Visitor<'de> for AmountVisitor<F> {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a decimal string for \"{}\"", F::NAME)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<u64, E> {
+        parse_amount(F::NAME, v).map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<u64, E> {
+        Err(Self::reject(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<u64, E> {
+        Err(Self::reject(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<u64, E> {
+        Err(Self::reject(v))
+    }
+}
+
+/// Parses a numeric BRC-20 field, explaining why the value is malformed.
+fn parse_amount(field: &'static str, value: &str) -> OrdResult<u64> {
+    let reason = if value.is_empty() {
+        "the amount is empty"
+    } else if value.bytes().all(|b| b.is_ascii_digit()) {
+        return value
+            .parse()
+            .map_err(|_| invalid_amount(field, value, "the amount is too large"));
+    } else if value.contains([',', '_', ' ']) {
+        "digit separators are not allowed"
+    } else if value.contains(['e', 'E']) {
+        "exponent notation is not allowed"
+    } else if value.contains('.') {
+        "decimal amounts are not supported"
+    } else if value.starts_with(['+', '-']) {
+        "signs are not allowed"
+    } else {
+        "the amount must only contain digits"
+    };
+
+    Err(invalid_amount(field, value, reason))
+}
+
+/// Parses a mint or transfer whose amount has a fractional part, which [`Brc20`] can't hold
+/// since it only supports whole amounts.
+///
+/// Returns the operation with the whole part of the amount, and the fractional digits as
+/// inscribed, e.g. `"50"` for `"1.50"`, or `None` if the body is not such an operation.
+pub(crate) fn parse_fractional_operation(body: &[u8]) -> Option<(Brc20, String)> {
+    let json = serde_json::from_slice::<serde_json::Value>(body).ok()?;
+    let field = |name| json.get(name).and_then(serde_json::Value::as_str);
+    if field("p")? != PROTOCOL {
+        return None;
+    }
+
+    let (whole, fraction) = field("amt")?.split_once('.')?;
+    if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let whole = parse_amount(Amt::NAME, whole).ok()?;
+    let tick = field("tick")?;
+    let operation = match field("op")? {
+        "mint" => Brc20::mint(tick, whole),
+        "transfer" => Brc20::transfer(tick, whole),
+        _ => return None,
+    };
+
+    Some((operation, fraction.to_string()))
+}
+
+fn invalid_amount(field: &'static str, value: impl Into<String>, reason: &'static str) -> OrdError {
+    OrdError::Brc20InvalidAmount {
+        field,
+        value: value.into(),
+        reason,
+    }
+}
+
 #[cfg(test)]
 mod test {
 
+    use bitcoin::script::Builder as ScriptBuilder;
+
     use super::*;
+    use crate::wallet::RedeemScriptPubkey;
 
     #[test]
     fn test_should_decode_deploy() {
@@ -293,4 +898,289 @@ mod test {
 
         assert_eq!(Brc20::from_str(&s).unwrap(), op);
     }
+
+    struct Resolver;
+
+    #[async_trait::async_trait]
+    impl TickerResolver for Resolver {
+        async fn resolve_ticker(&self, tick: &str) -> OrdResult<Option<Brc20TickerInfo>> {
+            Ok(match tick {
+                "ordi" => Some(Brc20TickerInfo {
+                    max: 21_000_000,
+                    lim: Some(1_000),
+                    minted: 20_999_500,
+                    dec: None,
+                }),
+                "sats" => Some(Brc20TickerInfo {
+                    max: 1_000,
+                    lim: None,
+                    minted: 1_000,
+                    dec: None,
+                }),
+                _ => None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_check_ticker_before_minting() {
+        assert_eq!(
+            Brc20::try_mint("ORDI", 500, &Resolver).await.unwrap(),
+            Brc20::mint("ORDI", 500)
+        );
+        assert!(matches!(
+            Brc20::try_mint("ordi", 501, &Resolver).await,
+            Err(OrdError::Brc20MintLimitExceeded { limit: 500, .. })
+        ));
+        assert!(matches!(
+            Brc20::try_mint("sats", 1, &Resolver).await,
+            Err(OrdError::Brc20TickerFullyMinted(_))
+        ));
+        assert!(matches!(
+            Brc20::try_mint("mona", 1, &Resolver).await,
+            Err(OrdError::Brc20TickerNotDeployed(_))
+        ));
+
+        assert!(Brc20::try_transfer("sats", 10, &Resolver).await.is_ok());
+        assert!(matches!(
+            Brc20::try_transfer("mona", 10, &Resolver).await,
+            Err(OrdError::Brc20TickerNotDeployed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_should_validate_ticker_bytes() {
+        assert!(Brc20::mint("ordi", 1).validate_ticker().is_ok());
+        // a single emoji is 4 bytes long
+        assert!(Brc20::mint("\u{1f600}", 1).validate_ticker().is_ok());
+        assert!(Brc20::transfer("pizza", 1).validate_ticker().is_ok());
+        assert!(matches!(
+            Brc20::mint("\u{1f600}\u{1f600}", 1).validate_ticker(),
+            Err(OrdError::Brc20InvalidTicker(_))
+        ));
+        assert!(Brc20::mint("ord", 1).validate_ticker().is_err());
+
+        assert!(Brc20::deploy("ordi", 1, None, None, None)
+            .validate_ticker()
+            .is_ok());
+        assert!(Brc20::deploy("pizza", 1, None, None, None)
+            .validate_ticker()
+            .is_err());
+        assert!(Brc20::deploy("pizza", 1, None, None, Some(true))
+            .validate_ticker()
+            .is_ok());
+
+        assert!(matches!(
+            Brc20::try_mint("ord", 1, &Resolver).await,
+            Err(OrdError::Brc20InvalidTicker(_))
+        ));
+    }
+
+    #[test]
+    fn test_should_normalize_ticker() {
+        // "é" as a single code point and as "e" followed by a combining acute accent
+        assert!(tickers_eq("caf\u{e9}", "CAFE\u{301}"));
+        assert_eq!(normalize_ticker("ORDI"), "ordi");
+        assert!(!tickers_eq("ordi", "sats"));
+    }
+
+    #[test]
+    fn test_should_encode_into_buffer() {
+        let op = Brc20::mint("ordi", 1000);
+
+        let mut buf = Vec::new();
+        op.encode_into(&mut buf).unwrap();
+        assert_eq!(buf, op.encode().unwrap().into_bytes());
+
+        let mut data = PushBytesBuf::new();
+        op.data_into(&mut data).unwrap();
+        assert_eq!(data.as_bytes(), buf.as_slice());
+        assert_eq!(op.data().unwrap(), data);
+    }
+
+    #[test]
+    fn test_should_display_canonical_json() {
+        let deploy = Brc20::deploy("ordi", 21_000_000, Some(1_000), Some(8), None);
+        assert_eq!(
+            deploy.to_string(),
+            r#"{"op":"deploy","p":"brc-20","tick":"ordi","max":"21000000","lim":"1000","dec":"8"}"#
+        );
+
+        for op in [
+            deploy,
+            Brc20::mint("ordi", 1_000),
+            Brc20::transfer("ordi", 100),
+        ] {
+            assert_eq!(op.to_string().as_bytes(), op.data().unwrap().as_bytes());
+            assert_eq!(Brc20::from_str(&op.to_string()).unwrap(), op);
+        }
+    }
+
+    #[test]
+    fn test_should_chunk_oversized_body() {
+        use bitcoin::absolute::LockTime;
+        use bitcoin::script::Instruction;
+        use bitcoin::transaction::Version;
+        use bitcoin::{OutPoint, PublicKey, ScriptBuf, Sequence, Transaction, TxIn, Witness};
+
+        use crate::utils::constants::MAX_SCRIPT_ELEMENT_SIZE;
+        use crate::OrdParser;
+
+        // the ticker is invalid, but it can still be inscribed
+        let op = Brc20::transfer("x".repeat(1_000), 1);
+        let chunks = op.data_chunks().unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks
+                .iter()
+                .flat_map(|chunk| chunk.as_bytes())
+                .copied()
+                .collect::<Vec<_>>(),
+            op.data().unwrap().as_bytes()
+        );
+
+        let pubkey = PublicKey::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        let script = op
+            .generate_redeem_script(ScriptBuilder::new(), RedeemScriptPubkey::Ecdsa(pubkey))
+            .unwrap()
+            .into_script();
+        assert!(script
+            .instructions()
+            .all(|instruction| match instruction.unwrap() {
+                Instruction::PushBytes(push) => push.len() <= MAX_SCRIPT_ELEMENT_SIZE,
+                Instruction::Op(_) => true,
+            }));
+
+        let reveal_tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[vec![0; 72], script.into_bytes()]),
+            }],
+            output: Vec::new(),
+        };
+        let parsed = OrdParser::parse_all(&reveal_tx).unwrap();
+        assert_eq!(Brc20::try_from(&parsed[0].1).unwrap(), op);
+    }
+
+    #[test]
+    fn test_should_report_invalid_amounts() {
+        for (json, field, value, reason) in [
+            (
+                r#"{"p":"brc-20","op":"mint","tick":"ordi","amt":"1,000"}"#,
+                "amt",
+                "1,000",
+                "digit separators are not allowed",
+            ),
+            (
+                r#"{"p":"brc-20","op":"transfer","tick":"ordi","amt":"1e3"}"#,
+                "amt",
+                "1e3",
+                "exponent notation is not allowed",
+            ),
+            (
+                r#"{"p":"brc-20","op":"deploy","tick":"ordi","max":"21000000","lim":"1.5"}"#,
+                "lim",
+                "1.5",
+                "decimal amounts are not supported",
+            ),
+            (
+                r#"{"p":"brc-20","op":"deploy","tick":"ordi","max":21000000}"#,
+                "max",
+                "21000000",
+                "amounts must be strings",
+            ),
+            (
+                r#"{"p":"brc-20","op":"mint","tick":"ordi","amt":"99999999999999999999"}"#,
+                "amt",
+                "99999999999999999999",
+                "the amount is too large",
+            ),
+        ] {
+            let err = Brc20::from_str(json).unwrap_err();
+            assert!(
+                matches!(
+                    &err,
+                    OrdError::Brc20InvalidAmount {
+                        field: f,
+                        value: v,
+                        reason: r,
+                    } if *f == field && v == value && *r == reason
+                ),
+                "unexpected error for {json}: {err}"
+            );
+
+            // serde users get the same explanation in the message
+            let err = serde_json::from_str::<Brc20>(json).unwrap_err();
+            assert!(err.to_string().contains(reason), "{err}");
+        }
+
+        let err =
+            Brc20::parse(br#"{"p":"brc-20","op":"mint","tick":"ordi","amt":"-1"}"#).unwrap_err();
+        assert!(matches!(
+            err,
+            OrdError::Brc20InvalidAmount {
+                field: "amt",
+                reason: "signs are not allowed",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_should_generate_redeem_script_from_fields() {
+        use bitcoin::opcodes::all::{OP_CHECKSIG, OP_ENDIF, OP_IF};
+        use bitcoin::opcodes::{OP_0, OP_FALSE};
+        use bitcoin::PublicKey;
+
+        let pubkey = PublicKey::from_str(
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        let op = Brc20::transfer("ordi".to_string(), 1_000);
+
+        let script = op
+            .generate_redeem_script(ScriptBuilder::new(), RedeemScriptPubkey::Ecdsa(pubkey))
+            .unwrap()
+            .into_script();
+
+        // the envelope layout BRC-20 inscriptions have always been written with
+        let data = op.data().unwrap();
+        let expected = ScriptBuilder::new()
+            .push_slice(pubkey.inner.serialize())
+            .push_opcode(OP_CHECKSIG)
+            .push_opcode(OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_slice(b"ord")
+            .push_slice([1])
+            .push_slice(b"text/plain;charset=utf-8")
+            .push_opcode(OP_0)
+            .push_slice(&data)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(script, expected);
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_should_export_json_schema() {
+        let schema = serde_json::to_value(Brc20::json_schema()).unwrap();
+        let variants = schema["oneOf"].as_array().unwrap();
+        assert_eq!(variants.len(), 3);
+
+        for (variant, op) in variants.iter().zip(["deploy", "mint", "transfer"]) {
+            assert_eq!(variant["properties"]["op"]["enum"][0], op);
+            assert_eq!(variant["properties"]["p"]["pattern"], "^brc-20$");
+            assert_eq!(variant["properties"]["tick"]["maxLength"], 5);
+        }
+        // amounts are encoded as strings
+        assert_eq!(variants[1]["properties"]["amt"]["type"], "string");
+        assert_eq!(variants[0]["properties"]["max"]["pattern"], r"^\d+$");
+    }
 }
@@ -0,0 +1,204 @@
+//! Sizes and costs of BRC-20 operations
+//!
+//! The cost of inscribing an operation only depends on the size of its JSON body, which only
+//! depends on the length of its fields, so pricing pages and quoting endpoints can compute it
+//! from the field lengths, e.g. [`mint_body_size`]`(4, `[`amount_length`]`(1000))`, or list it
+//! for a set of fee rates with [`cost_table`].
+//!
+//! Lengths are in bytes, assuming the ticker has no character escaped in JSON, i.e. quotes,
+//! backslashes and control characters.
+
+use bitcoin::{Amount, FeeRate, Network};
+
+use super::Brc20;
+use crate::utils::fees::{estimate_inscription_cost, InscriptionCost};
+use crate::wallet::ScriptType;
+use crate::OrdResult;
+
+/// Size of the body of a mint operation with an empty ticker and amount.
+pub const MINT_BODY_OVERHEAD: usize = r#"{"op":"mint","p":"brc-20","tick":"","amt":""}"#.len();
+/// Size of the body of a transfer operation with an empty ticker and amount.
+pub const TRANSFER_BODY_OVERHEAD: usize =
+    r#"{"op":"transfer","p":"brc-20","tick":"","amt":""}"#.len();
+/// Size of the body of a deploy operation with an empty ticker and max supply, and without
+/// optional fields.
+pub const DEPLOY_BODY_OVERHEAD: usize = r#"{"op":"deploy","p":"brc-20","tick":"","max":""}"#.len();
+/// Size added to a deploy body by an empty `lim` or `dec` field.
+pub const AMOUNT_FIELD_OVERHEAD: usize = r#","lim":"""#.len();
+/// Size added to a deploy body by an empty `self_mint` field.
+pub const SELF_MINT_FIELD_OVERHEAD: usize = r#","self_mint":"""#.len();
+
+/// Returns the number of digits of an amount, as inscribed in the body of an operation.
+pub const fn amount_length(amount: u64) -> usize {
+    match amount.checked_ilog10() {
+        Some(log) => log as usize + 1,
+        None => 1,
+    }
+}
+
+/// Returns the size of the body of a mint operation.
+pub const fn mint_body_size(tick_length: usize, amt_length: usize) -> usize {
+    MINT_BODY_OVERHEAD + tick_length + amt_length
+}
+
+/// Returns the size of the body of a transfer operation.
+pub const fn transfer_body_size(tick_length: usize, amt_length: usize) -> usize {
+    TRANSFER_BODY_OVERHEAD + tick_length + amt_length
+}
+
+/// Returns the size of the body of a deploy operation, given the lengths of its optional
+/// fields, if set.
+pub const fn deploy_body_size(
+    tick_length: usize,
+    max_length: usize,
+    lim_length: Option<usize>,
+    dec_length: Option<usize>,
+    self_mint: Option<bool>,
+) -> usize {
+    let mut size = DEPLOY_BODY_OVERHEAD + tick_length + max_length;
+    if let Some(lim_length) = lim_length {
+        size += AMOUNT_FIELD_OVERHEAD + lim_length;
+    }
+    if let Some(dec_length) = dec_length {
+        size += AMOUNT_FIELD_OVERHEAD + dec_length;
+    }
+    match self_mint {
+        Some(true) => size += SELF_MINT_FIELD_OVERHEAD + "true".len(),
+        Some(false) => size += SELF_MINT_FIELD_OVERHEAD + "false".len(),
+        None => {}
+    }
+
+    size
+}
+
+/// Returns the size of the body of an operation.
+pub fn body_size(operation: &Brc20) -> usize {
+    match operation {
+        Brc20::Deploy(deploy) => deploy_body_size(
+            deploy.tick.len(),
+            amount_length(deploy.max),
+            deploy.lim.map(amount_length),
+            deploy.dec.map(amount_length),
+            deploy.self_mint,
+        ),
+        Brc20::Mint(mint) => mint_body_size(mint.tick.len(), amount_length(mint.amt)),
+        Brc20::Transfer(transfer) => {
+            transfer_body_size(transfer.tick.len(), amount_length(transfer.amt))
+        }
+    }
+}
+
+/// Cost of inscribing an operation at a fee rate, as listed by [`cost_table`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Brc20Cost {
+    /// Fee rate of the commit and reveal transactions
+    pub fee_rate: FeeRate,
+    /// Cost of the inscription at the fee rate
+    pub cost: InscriptionCost,
+}
+
+/// Lists the cost of inscribing an operation on the network at each of the given fee rates,
+/// estimated as by [`estimate_inscription_cost`].
+///
+/// Operations with the same field lengths cost the same, so the table of a representative
+/// operation, e.g. a mint of `u64::MAX`, is an upper bound for all the operations with shorter
+/// fields.
+///
+/// # Errors
+///
+/// Returns an error if the redeem script can't be generated, the reveal transaction isn't
+/// standard on the network or the fees overflow.
+pub fn cost_table(
+    operation: &Brc20,
+    fee_rates: impl IntoIterator<Item = FeeRate>,
+    script_type: ScriptType,
+    postage: Amount,
+    network: Network,
+) -> OrdResult<Vec<Brc20Cost>> {
+    fee_rates
+        .into_iter()
+        .map(|fee_rate| {
+            estimate_inscription_cost(operation, fee_rate, script_type, postage, network)
+                .map(|cost| Brc20Cost { fee_rate, cost })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::constants::POSTAGE;
+    use crate::Inscription as _;
+
+    #[test]
+    fn test_should_compute_body_sizes() {
+        assert_eq!(amount_length(0), 1);
+        assert_eq!(amount_length(9), 1);
+        assert_eq!(amount_length(10), 2);
+        assert_eq!(amount_length(u64::MAX), 20);
+
+        for operation in [
+            Brc20::mint("ordi", 1_000),
+            Brc20::mint("ordi", 0),
+            Brc20::transfer("orde", u64::MAX),
+            Brc20::transfer("🪙", 42),
+            Brc20::deploy("ordi", 21_000_000, None, None, None),
+            Brc20::deploy("ordi", 21_000_000, Some(1_000), Some(18), None),
+            Brc20::deploy("ordix", 21_000_000, Some(1_000), None, Some(true)),
+            Brc20::deploy("ordix", 21_000_000, None, Some(8), Some(false)),
+        ] {
+            assert_eq!(
+                body_size(&operation),
+                operation.data().unwrap().len(),
+                "{operation:?}"
+            );
+        }
+
+        assert_eq!(mint_body_size(4, 4), MINT_BODY_OVERHEAD + 8);
+    }
+
+    #[test]
+    fn test_should_list_costs_by_fee_rate() {
+        let operation = Brc20::mint("ordi", u64::MAX);
+        let fee_rates = [1, 10, 100].map(FeeRate::from_sat_per_vb_unchecked);
+        let table = cost_table(
+            &operation,
+            fee_rates,
+            ScriptType::P2TR,
+            POSTAGE,
+            Network::Bitcoin,
+        )
+        .unwrap();
+
+        assert_eq!(table.len(), 3);
+        for (entry, fee_rate) in table.iter().zip(fee_rates) {
+            assert_eq!(entry.fee_rate, fee_rate);
+            assert_eq!(
+                entry.cost,
+                estimate_inscription_cost(
+                    &operation,
+                    fee_rate,
+                    ScriptType::P2TR,
+                    POSTAGE,
+                    Network::Bitcoin
+                )
+                .unwrap()
+            );
+        }
+        assert!(table.windows(2).all(|w| w[0].cost.total < w[1].cost.total));
+
+        // shorter fields don't cost more
+        let shorter = cost_table(
+            &Brc20::mint("ordi", 1),
+            fee_rates,
+            ScriptType::P2TR,
+            POSTAGE,
+            Network::Bitcoin,
+        )
+        .unwrap();
+        assert!(shorter
+            .iter()
+            .zip(&table)
+            .all(|(shorter, entry)| shorter.cost.total <= entry.cost.total));
+    }
+}
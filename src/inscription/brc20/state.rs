@@ -0,0 +1,940 @@
+//! Local BRC-20 state
+//!
+//! A minimal model of the state kept by BRC-20 indexers, to simulate the effects of a transaction
+//! before broadcasting it. Mistakes such as minting over the limit or inscribing a transfer
+//! without enough balance are valid bitcoin transactions, so the chain won't reject them, but
+//! indexers ignore the operations and the fees are lost.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use bitcoin::{OutPoint, ScriptBuf, Transaction};
+
+use super::{normalize_ticker, parse_fractional_operation, Brc20, Brc20TickerInfo, TickerResolver};
+use crate::wallet::{OrdParser, ParseMode};
+use crate::{InscriptionId, OrdResult};
+
+/// Balance of a BRC-20 ticker held by a script.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Brc20Balance {
+    /// Amount which can be inscribed in transfers
+    pub available: u64,
+    /// Amount locked in transfer inscriptions which haven't been sent yet
+    pub transferable: u64,
+}
+
+impl Brc20Balance {
+    /// Returns the overall balance.
+    pub fn total(&self) -> u64 {
+        self.available + self.transferable
+    }
+}
+
+/// Transfer inscription which hasn't been sent yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PendingTransfer {
+    id: InscriptionId,
+    outpoint: OutPoint,
+    tick: String,
+    amount: u64,
+    sender: ScriptBuf,
+}
+
+/// Effect of a transaction on the [`Brc20State`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Brc20Effect {
+    /// A ticker was deployed.
+    Deployed {
+        /// ID of the deploy inscription
+        id: InscriptionId,
+        /// Normalized ticker
+        tick: String,
+    },
+    /// The owner was credited with minted tokens.
+    Minted {
+        /// ID of the mint inscription
+        id: InscriptionId,
+        /// Normalized ticker
+        tick: String,
+        /// Amount credited, which is less than the inscribed amount if the mint exceeds the
+        /// remaining supply
+        amount: u64,
+        /// Script credited with the tokens
+        owner: ScriptBuf,
+    },
+    /// The owner moved tokens from the available to the transferable balance.
+    TransferInscribed {
+        /// ID of the transfer inscription
+        id: InscriptionId,
+        /// Normalized ticker
+        tick: String,
+        /// Amount of the transfer
+        amount: u64,
+        /// Script debited by the transfer
+        owner: ScriptBuf,
+    },
+    /// A transfer inscription was sent, debiting the sender and crediting the receiver.
+    Transferred {
+        /// ID of the transfer inscription
+        id: InscriptionId,
+        /// Normalized ticker
+        tick: String,
+        /// Amount transferred
+        amount: u64,
+        /// Script debited by the transfer
+        sender: ScriptBuf,
+        /// Script credited by the transfer
+        receiver: ScriptBuf,
+    },
+    /// A non BRC-20 inscription was created.
+    Inscribed {
+        /// ID of the inscription
+        id: InscriptionId,
+        /// Script holding the inscription
+        owner: ScriptBuf,
+    },
+    /// A mint or transfer whose amount has a fractional part is valid, but isn't applied since
+    /// the state only tracks whole amounts, so the balances of the ticker may differ from those
+    /// of indexers.
+    Untracked {
+        /// ID of the inscription
+        id: InscriptionId,
+        /// Normalized ticker
+        tick: String,
+    },
+    /// A BRC-20 operation is ignored by indexers.
+    Rejected {
+        /// ID of the inscription
+        id: InscriptionId,
+        /// Why the operation is ignored
+        reason: Brc20Rejection,
+    },
+}
+
+/// Reason why indexers ignore a BRC-20 operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Brc20Rejection {
+    /// The envelope of the inscription is cursed.
+    Cursed,
+    /// The ticker has an invalid length.
+    InvalidTicker,
+    /// The ticker is already deployed.
+    AlreadyDeployed,
+    /// The ticker is not deployed.
+    NotDeployed,
+    /// The amount of the operation is zero.
+    ZeroAmount,
+    /// The amount of the operation has more fractional digits than the decimals of the ticker.
+    PrecisionExceeded {
+        /// Decimals of the ticker
+        decimals: u64,
+    },
+    /// The whole supply of the ticker is minted.
+    FullyMinted,
+    /// The mint amount exceeds the mint limit of the ticker.
+    MintLimitExceeded {
+        /// Mint limit of the ticker
+        limit: u64,
+    },
+    /// The transfer amount exceeds the available balance of the owner.
+    InsufficientBalance {
+        /// Available balance of the owner
+        available: u64,
+    },
+    /// The transfer inscription is not spent by the first input, so where it lands depends on
+    /// the values of the inputs. The state assumes it returns to the sender.
+    UnknownDestination,
+    /// The operation would overflow the minted amount of the ticker or a balance.
+    Overflow,
+}
+
+impl fmt::Display for Brc20Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cursed => f.write_str("cursed inscription"),
+            Self::InvalidTicker => f.write_str("invalid ticker"),
+            Self::AlreadyDeployed => f.write_str("ticker already deployed"),
+            Self::NotDeployed => f.write_str("ticker not deployed"),
+            Self::ZeroAmount => f.write_str("zero amount"),
+            Self::PrecisionExceeded { decimals } => {
+                write!(f, "amount precision exceeds {decimals} decimals")
+            }
+            Self::FullyMinted => f.write_str("ticker fully minted"),
+            Self::MintLimitExceeded { limit } => write!(f, "mint over limit {limit}"),
+            Self::InsufficientBalance { available } => {
+                write!(f, "insufficient balance, {available} available")
+            }
+            Self::UnknownDestination => f.write_str("unknown transfer destination"),
+            Self::Overflow => f.write_str("amount overflow"),
+        }
+    }
+}
+
+/// Local BRC-20 state: the deployed tickers, the balances and the transfer inscriptions which
+/// haven't been sent yet, e.g. seeded from an indexer.
+///
+/// Inscriptions are assumed to land on the first output of the transaction revealing or
+/// sending them, as with the transactions built by this crate, and owned by its script.
+/// Self mint tickers are minted as regular tickers, since the parent of mints isn't checked.
+#[derive(Debug, Default, Clone)]
+pub struct Brc20State {
+    tickers: HashMap<String, Brc20TickerInfo>,
+    balances: HashMap<(String, ScriptBuf), Brc20Balance>,
+    transfers: BTreeMap<InscriptionId, PendingTransfer>,
+}
+
+impl Brc20State {
+    /// Creates an empty state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the state of a deployed ticker.
+    pub fn insert_ticker(&mut self, tick: &str, info: Brc20TickerInfo) {
+        self.tickers.insert(normalize_ticker(tick), info);
+    }
+
+    /// Returns the state of a deployed ticker.
+    pub fn ticker(&self, tick: &str) -> Option<&Brc20TickerInfo> {
+        self.tickers.get(&normalize_ticker(tick))
+    }
+
+    /// Sets the balance of a ticker held by a script.
+    pub fn set_balance(&mut self, tick: &str, owner: ScriptBuf, balance: Brc20Balance) {
+        self.balances
+            .insert((normalize_ticker(tick), owner), balance);
+    }
+
+    /// Returns the balance of a ticker held by a script.
+    pub fn balance(&self, tick: &str, owner: &ScriptBuf) -> Brc20Balance {
+        self.balances
+            .get(&(normalize_ticker(tick), owner.clone()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Tracks a transfer inscription held by the given output, which hasn't been sent yet.
+    ///
+    /// An output may hold several transfer inscriptions, which are all sent when it's spent.
+    /// The amount must already be counted in the transferable balance of the sender.
+    pub fn insert_transfer(
+        &mut self,
+        outpoint: OutPoint,
+        id: InscriptionId,
+        tick: &str,
+        amount: u64,
+        sender: ScriptBuf,
+    ) {
+        self.transfers.insert(
+            id,
+            PendingTransfer {
+                id,
+                outpoint,
+                tick: normalize_ticker(tick),
+                amount,
+                sender,
+            },
+        );
+    }
+
+    /// Returns the effects of the transaction on the state, without applying them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the inscriptions of the transaction can't be parsed.
+    pub fn simulate(&self, tx: &Transaction) -> OrdResult<Vec<Brc20Effect>> {
+        self.clone().apply(tx)
+    }
+
+    /// Applies the transaction to the state, returning its effects.
+    ///
+    /// Transfer inscriptions spent by the transaction are sent first, in the order of the
+    /// inputs and then of their IDs, then the inscriptions revealed by the transaction are
+    /// processed in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the inscriptions of the transaction can't be parsed.
+    pub fn apply(&mut self, tx: &Transaction) -> OrdResult<Vec<Brc20Effect>> {
+        let inscriptions = OrdParser::parse_all_with_mode(tx, ParseMode::Lenient)?;
+        let receiver = tx.output.first().map(|output| &output.script_pubkey);
+        let mut effects = Vec::new();
+
+        for (index, input) in tx.input.iter().enumerate() {
+            let spent = self
+                .transfers
+                .values()
+                .filter(|transfer| transfer.outpoint == input.previous_output)
+                .map(|transfer| transfer.id)
+                .collect::<Vec<_>>();
+            for id in spent {
+                let Some(transfer) = self.transfers.remove(&id) else {
+                    continue;
+                };
+                effects.push(self.send_transfer(transfer, receiver.filter(|_| index == 0)));
+            }
+        }
+
+        let Some(owner) = receiver else {
+            return Ok(effects);
+        };
+        let outpoint = OutPoint::new(tx.txid(), 0);
+        for inscription in inscriptions {
+            let id = inscription.id;
+            let cursed = inscription.is_cursed();
+            let effect = match inscription.inscription {
+                OrdParser::Brc20 { .. } if cursed => Brc20Effect::Rejected {
+                    id,
+                    reason: Brc20Rejection::Cursed,
+                },
+                OrdParser::Brc20 { inscription, .. } => match inscription.validate_ticker() {
+                    Ok(()) => self.apply_operation(id, inscription, owner, outpoint),
+                    Err(_) => Brc20Effect::Rejected {
+                        id,
+                        reason: Brc20Rejection::InvalidTicker,
+                    },
+                },
+                OrdParser::Ordinal(nft) => {
+                    match nft.body.as_deref().and_then(parse_fractional_operation) {
+                        Some(_) if cursed => Brc20Effect::Rejected {
+                            id,
+                            reason: Brc20Rejection::Cursed,
+                        },
+                        Some((operation, fraction)) => match operation.validate_ticker() {
+                            Ok(()) => self.apply_fractional_operation(
+                                id, operation, &fraction, owner, outpoint,
+                            ),
+                            Err(_) => Brc20Effect::Rejected {
+                                id,
+                                reason: Brc20Rejection::InvalidTicker,
+                            },
+                        },
+                        None => Brc20Effect::Inscribed {
+                            id,
+                            owner: owner.clone(),
+                        },
+                    }
+                }
+            };
+            effects.push(effect);
+        }
+
+        Ok(effects)
+    }
+
+    fn apply_operation(
+        &mut self,
+        id: InscriptionId,
+        operation: Brc20,
+        owner: &ScriptBuf,
+        outpoint: OutPoint,
+    ) -> Brc20Effect {
+        let tick = normalize_ticker(operation.tick());
+        let rejected = |reason| Brc20Effect::Rejected { id, reason };
+
+        match operation {
+            Brc20::Deploy(_) if self.tickers.contains_key(&tick) => {
+                rejected(Brc20Rejection::AlreadyDeployed)
+            }
+            Brc20::Deploy(deploy) if deploy.max == 0 => rejected(Brc20Rejection::ZeroAmount),
+            Brc20::Deploy(deploy) => {
+                self.tickers.insert(
+                    tick.clone(),
+                    Brc20TickerInfo {
+                        max: deploy.max,
+                        lim: deploy.lim,
+                        minted: 0,
+                        dec: deploy.dec,
+                    },
+                );
+                Brc20Effect::Deployed { id, tick }
+            }
+            Brc20::Mint(mint) => {
+                let Some(info) = self.tickers.get(&tick) else {
+                    return rejected(Brc20Rejection::NotDeployed);
+                };
+                let remaining = info.max.saturating_sub(info.minted);
+                let limit = info.lim.unwrap_or(info.max);
+                if mint.amt == 0 {
+                    return rejected(Brc20Rejection::ZeroAmount);
+                }
+                if remaining == 0 {
+                    return rejected(Brc20Rejection::FullyMinted);
+                }
+                if mint.amt > limit {
+                    return rejected(Brc20Rejection::MintLimitExceeded { limit });
+                }
+
+                let amount = mint.amt.min(remaining);
+                let (Some(minted), Some(available)) = (
+                    info.minted.checked_add(amount),
+                    self.balance(&tick, owner).available.checked_add(amount),
+                ) else {
+                    return rejected(Brc20Rejection::Overflow);
+                };
+                if let Some(info) = self.tickers.get_mut(&tick) {
+                    info.minted = minted;
+                }
+                self.balance_mut(&tick, owner).available = available;
+                Brc20Effect::Minted {
+                    id,
+                    tick,
+                    amount,
+                    owner: owner.clone(),
+                }
+            }
+            Brc20::Transfer(transfer) => {
+                if !self.tickers.contains_key(&tick) {
+                    return rejected(Brc20Rejection::NotDeployed);
+                }
+                if transfer.amt == 0 {
+                    return rejected(Brc20Rejection::ZeroAmount);
+                }
+                let balance = self.balance_mut(&tick, owner);
+                if transfer.amt > balance.available {
+                    return rejected(Brc20Rejection::InsufficientBalance {
+                        available: balance.available,
+                    });
+                }
+                let Some(transferable) = balance.transferable.checked_add(transfer.amt) else {
+                    return rejected(Brc20Rejection::Overflow);
+                };
+
+                balance.available -= transfer.amt;
+                balance.transferable = transferable;
+                self.transfers.insert(
+                    id,
+                    PendingTransfer {
+                        id,
+                        outpoint,
+                        tick: tick.clone(),
+                        amount: transfer.amt,
+                        sender: owner.clone(),
+                    },
+                );
+                Brc20Effect::TransferInscribed {
+                    id,
+                    tick,
+                    amount: transfer.amt,
+                    owner: owner.clone(),
+                }
+            }
+        }
+    }
+
+    /// Applies a mint or transfer whose amount has the given fractional digits, as inscribed.
+    ///
+    /// Indexers ignore the operation if the amount has more fractional digits than the decimals
+    /// of the ticker. Otherwise it's applied with the whole amount if the fractional digits are
+    /// zeros, e.g. `"100.0"`.
+    fn apply_fractional_operation(
+        &mut self,
+        id: InscriptionId,
+        operation: Brc20,
+        fraction: &str,
+        owner: &ScriptBuf,
+        outpoint: OutPoint,
+    ) -> Brc20Effect {
+        let tick = normalize_ticker(operation.tick());
+        let Some(info) = self.tickers.get(&tick) else {
+            return Brc20Effect::Rejected {
+                id,
+                reason: Brc20Rejection::NotDeployed,
+            };
+        };
+        let decimals = info.decimals();
+        if fraction.len() as u64 > decimals {
+            return Brc20Effect::Rejected {
+                id,
+                reason: Brc20Rejection::PrecisionExceeded { decimals },
+            };
+        }
+
+        if fraction.bytes().all(|digit| digit == b'0') {
+            self.apply_operation(id, operation, owner, outpoint)
+        } else {
+            Brc20Effect::Untracked { id, tick }
+        }
+    }
+
+    /// Sends a transfer inscription to the receiver, or back to the sender if the receiver
+    /// is unknown or its balance would overflow.
+    fn send_transfer(
+        &mut self,
+        transfer: PendingTransfer,
+        receiver: Option<&ScriptBuf>,
+    ) -> Brc20Effect {
+        let sender = self.balance_mut(&transfer.tick, &transfer.sender);
+        sender.transferable = sender.transferable.saturating_sub(transfer.amount);
+
+        let Some(receiver) = receiver else {
+            return self.return_transfer(transfer, Brc20Rejection::UnknownDestination);
+        };
+        // transfers sent to an OP_RETURN output are burned
+        if !receiver.is_op_return() {
+            let balance = self.balance_mut(&transfer.tick, receiver);
+            let Some(available) = balance.available.checked_add(transfer.amount) else {
+                return self.return_transfer(transfer, Brc20Rejection::Overflow);
+            };
+            balance.available = available;
+        }
+
+        Brc20Effect::Transferred {
+            id: transfer.id,
+            tick: transfer.tick,
+            amount: transfer.amount,
+            sender: transfer.sender,
+            receiver: receiver.clone(),
+        }
+    }
+
+    /// Returns a transfer inscription to its sender, which can inscribe the amount again.
+    fn return_transfer(
+        &mut self,
+        transfer: PendingTransfer,
+        reason: Brc20Rejection,
+    ) -> Brc20Effect {
+        let sender = self.balance_mut(&transfer.tick, &transfer.sender);
+        sender.available = sender.available.saturating_add(transfer.amount);
+        Brc20Effect::Rejected {
+            id: transfer.id,
+            reason,
+        }
+    }
+
+    fn balance_mut(&mut self, tick: &str, owner: &ScriptBuf) -> &mut Brc20Balance {
+        self.balances
+            .entry((tick.to_string(), owner.clone()))
+            .or_default()
+    }
+}
+
+#[async_trait::async_trait]
+impl TickerResolver for Brc20State {
+    async fn resolve_ticker(&self, tick: &str) -> OrdResult<Option<Brc20TickerInfo>> {
+        Ok(self.ticker(tick).cloned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bitcoin::absolute::LockTime;
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::script::Builder as ScriptBuilder;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, Sequence, TxIn, TxOut, Txid, Witness, XOnlyPublicKey};
+
+    use super::*;
+    use crate::wallet::RedeemScriptPubkey;
+    use crate::{Inscription, Nft};
+
+    fn script(byte: u8) -> ScriptBuf {
+        ScriptBuf::from_bytes(vec![0x51, byte])
+    }
+
+    fn reveal(operation: &impl Inscription, owner: &ScriptBuf) -> Transaction {
+        let redeem_script = operation
+            .generate_redeem_script(
+                ScriptBuilder::new(),
+                RedeemScriptPubkey::XPublickey(XOnlyPublicKey::from_slice(&[0x02; 32]).unwrap()),
+            )
+            .unwrap()
+            .into_script();
+        // the txid doesn't commit to the witness, so the input is unique to the operation
+        let mut tx = send(
+            &[OutPoint::new(Txid::hash(redeem_script.as_bytes()), 0)],
+            owner,
+        );
+        tx.input[0].witness = Witness::from_slice(&[redeem_script.into_bytes(), Vec::new()]);
+        tx
+    }
+
+    fn send(outpoints: &[OutPoint], receiver: &ScriptBuf) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: outpoints
+                .iter()
+                .map(|outpoint| TxIn {
+                    previous_output: *outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: vec![TxOut {
+                value: Amount::from_sat(546),
+                script_pubkey: receiver.clone(),
+            }],
+        }
+    }
+
+    fn inscription_id(tx: &Transaction) -> InscriptionId {
+        InscriptionId {
+            txid: tx.txid(),
+            index: 0,
+        }
+    }
+
+    #[test]
+    fn test_should_simulate_mints() {
+        let mut state = Brc20State::new();
+        let alice = script(1);
+
+        let deploy = reveal(
+            &Brc20::deploy("ordi", 1_500, Some(1_000), None, None),
+            &alice,
+        );
+        assert_eq!(
+            state.apply(&deploy).unwrap(),
+            vec![Brc20Effect::Deployed {
+                id: inscription_id(&deploy),
+                tick: "ordi".to_string(),
+            }]
+        );
+
+        let over_limit = reveal(&Brc20::mint("ORDI", 1_001), &alice);
+        let effects = state.simulate(&over_limit).unwrap();
+        assert_eq!(
+            effects,
+            vec![Brc20Effect::Rejected {
+                id: inscription_id(&over_limit),
+                reason: Brc20Rejection::MintLimitExceeded { limit: 1_000 },
+            }]
+        );
+
+        for (amount, credited) in [(1_000, 1_000), (1_000, 500)] {
+            let mint = reveal(&Brc20::mint("ordi", amount), &alice);
+            assert_eq!(
+                state.apply(&mint).unwrap(),
+                vec![Brc20Effect::Minted {
+                    id: inscription_id(&mint),
+                    tick: "ordi".to_string(),
+                    amount: credited,
+                    owner: alice.clone(),
+                }]
+            );
+        }
+        assert_eq!(state.balance("ordi", &alice).available, 1_500);
+        assert_eq!(state.ticker("ordi").unwrap().minted, 1_500);
+
+        let fully_minted = reveal(&Brc20::mint("ordi", 1), &alice);
+        assert!(matches!(
+            state.simulate(&fully_minted).unwrap()[..],
+            [Brc20Effect::Rejected {
+                reason: Brc20Rejection::FullyMinted,
+                ..
+            }]
+        ));
+        let not_deployed = reveal(&Brc20::mint("sats", 1), &alice);
+        assert!(matches!(
+            state.simulate(&not_deployed).unwrap()[..],
+            [Brc20Effect::Rejected {
+                reason: Brc20Rejection::NotDeployed,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_should_check_amount_precision() {
+        let mut state = Brc20State::new();
+        let alice = script(1);
+        let operation = |op: &str, tick: &str, amt: &str| {
+            let body = format!(r#"{{"p":"brc-20","op":"{op}","tick":"{tick}","amt":"{amt}"}}"#);
+            reveal(
+                &Nft::new(Some(b"text/plain".to_vec()), Some(body.into_bytes())),
+                &alice,
+            )
+        };
+        state
+            .apply(&reveal(
+                &Brc20::deploy("ordi", 1_000, None, Some(2), None),
+                &alice,
+            ))
+            .unwrap();
+        state
+            .apply(&reveal(
+                &Brc20::deploy("sats", 1_000, None, None, None),
+                &alice,
+            ))
+            .unwrap();
+        assert_eq!(state.ticker("ordi").unwrap().decimals(), 2);
+        assert_eq!(state.ticker("sats").unwrap().decimals(), 18);
+
+        let mint = operation("mint", "ordi", "1.005");
+        assert_eq!(
+            state.apply(&mint).unwrap(),
+            vec![Brc20Effect::Rejected {
+                id: inscription_id(&mint),
+                reason: Brc20Rejection::PrecisionExceeded { decimals: 2 },
+            }]
+        );
+        assert_eq!(
+            Brc20Rejection::PrecisionExceeded { decimals: 2 }.to_string(),
+            "amount precision exceeds 2 decimals"
+        );
+        let transfer = operation("transfer", "ordi", "0.000");
+        assert!(matches!(
+            state.apply(&transfer).unwrap()[..],
+            [Brc20Effect::Rejected {
+                reason: Brc20Rejection::PrecisionExceeded { decimals: 2 },
+                ..
+            }]
+        ));
+
+        // whole amounts written with fractional zeros are applied
+        let mint = operation("mint", "ordi", "10.00");
+        assert!(matches!(
+            state.apply(&mint).unwrap()[..],
+            [Brc20Effect::Minted { amount: 10, .. }]
+        ));
+        let mint = operation("mint", "sats", "10.000000000000000001");
+        assert_eq!(
+            state.apply(&mint).unwrap(),
+            vec![Brc20Effect::Untracked {
+                id: inscription_id(&mint),
+                tick: "sats".to_string(),
+            }]
+        );
+        assert_eq!(state.ticker("sats").unwrap().minted, 0);
+
+        let not_deployed = operation("mint", "rats", "1.5");
+        assert!(matches!(
+            state.apply(&not_deployed).unwrap()[..],
+            [Brc20Effect::Rejected {
+                reason: Brc20Rejection::NotDeployed,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_should_simulate_transfers() {
+        let mut state = Brc20State::new();
+        let alice = script(1);
+        let bob = script(2);
+        state.insert_ticker(
+            "ordi",
+            Brc20TickerInfo {
+                max: 21_000_000,
+                lim: None,
+                minted: 100,
+                dec: None,
+            },
+        );
+        state.set_balance(
+            "ordi",
+            alice.clone(),
+            Brc20Balance {
+                available: 100,
+                transferable: 0,
+            },
+        );
+
+        let too_much = reveal(&Brc20::transfer("ordi", 101), &alice);
+        assert_eq!(
+            state.simulate(&too_much).unwrap(),
+            vec![Brc20Effect::Rejected {
+                id: inscription_id(&too_much),
+                reason: Brc20Rejection::InsufficientBalance { available: 100 },
+            }]
+        );
+
+        let inscribe = reveal(&Brc20::transfer("ordi", 60), &alice);
+        state.apply(&inscribe).unwrap();
+        assert_eq!(
+            state.balance("ordi", &alice),
+            Brc20Balance {
+                available: 40,
+                transferable: 60,
+            }
+        );
+
+        let transfer = send(&[OutPoint::new(inscribe.txid(), 0)], &bob);
+        let effects = state.simulate(&transfer).unwrap();
+        assert_eq!(
+            effects,
+            vec![Brc20Effect::Transferred {
+                id: inscription_id(&inscribe),
+                tick: "ordi".to_string(),
+                amount: 60,
+                sender: alice.clone(),
+                receiver: bob.clone(),
+            }]
+        );
+        assert_eq!(state.balance("ordi", &alice).transferable, 60);
+
+        state.apply(&transfer).unwrap();
+        assert_eq!(state.balance("ordi", &alice).total(), 40);
+        assert_eq!(state.balance("ordi", &bob).available, 60);
+    }
+
+    #[test]
+    fn test_should_return_transfers_with_unknown_destination() {
+        let mut state = Brc20State::new();
+        let alice = script(1);
+        state.insert_ticker(
+            "ordi",
+            Brc20TickerInfo {
+                max: 1_000,
+                lim: None,
+                minted: 0,
+                dec: None,
+            },
+        );
+        state
+            .apply(&reveal(&Brc20::mint("ordi", 100), &alice))
+            .unwrap();
+        let inscribe = reveal(&Brc20::transfer("ordi", 100), &alice);
+        state.apply(&inscribe).unwrap();
+
+        let transfer = send(
+            &[OutPoint::null(), OutPoint::new(inscribe.txid(), 0)],
+            &script(2),
+        );
+        assert_eq!(
+            state.apply(&transfer).unwrap(),
+            vec![Brc20Effect::Rejected {
+                id: inscription_id(&inscribe),
+                reason: Brc20Rejection::UnknownDestination,
+            }]
+        );
+        assert_eq!(state.balance("ordi", &alice).available, 100);
+    }
+
+    #[test]
+    fn test_should_track_transfers_inscribed_in_the_same_transaction() {
+        let mut state = Brc20State::new();
+        let alice = script(1);
+        let bob = script(2);
+        state.insert_ticker(
+            "ordi",
+            Brc20TickerInfo {
+                max: 1_000,
+                lim: None,
+                minted: 100,
+                dec: None,
+            },
+        );
+        state.set_balance(
+            "ordi",
+            alice.clone(),
+            Brc20Balance {
+                available: 100,
+                transferable: 0,
+            },
+        );
+
+        // a batch reveal inscribing two transfers on its first output
+        let mut inscribe = reveal(&Brc20::transfer("ordi", 30), &alice);
+        inscribe
+            .input
+            .push(reveal(&Brc20::transfer("ordi", 50), &alice).input[0].clone());
+        let ids = [0, 1].map(|index| InscriptionId {
+            txid: inscribe.txid(),
+            index,
+        });
+        assert!(matches!(
+            state.apply(&inscribe).unwrap()[..],
+            [
+                Brc20Effect::TransferInscribed { amount: 30, .. },
+                Brc20Effect::TransferInscribed { amount: 50, .. }
+            ]
+        ));
+        assert_eq!(state.balance("ordi", &alice).transferable, 80);
+
+        let transfer = send(&[OutPoint::new(inscribe.txid(), 0)], &bob);
+        assert_eq!(
+            state.apply(&transfer).unwrap(),
+            [(ids[0], 30), (ids[1], 50)].map(|(id, amount)| Brc20Effect::Transferred {
+                id,
+                tick: "ordi".to_string(),
+                amount,
+                sender: alice.clone(),
+                receiver: bob.clone(),
+            })
+        );
+        assert_eq!(state.balance("ordi", &alice).total(), 20);
+        assert_eq!(state.balance("ordi", &bob).available, 80);
+    }
+
+    #[test]
+    fn test_should_reject_overflowing_operations() {
+        let mut state = Brc20State::new();
+        let alice = script(1);
+        let bob = script(2);
+        state.insert_ticker(
+            "ordi",
+            Brc20TickerInfo {
+                max: u64::MAX,
+                lim: None,
+                minted: 0,
+                dec: None,
+            },
+        );
+        state.set_balance(
+            "ordi",
+            alice.clone(),
+            Brc20Balance {
+                available: u64::MAX,
+                transferable: 10,
+            },
+        );
+        state.set_balance(
+            "ordi",
+            bob.clone(),
+            Brc20Balance {
+                available: u64::MAX,
+                transferable: 0,
+            },
+        );
+
+        let mint = reveal(&Brc20::mint("ordi", 1), &alice);
+        assert_eq!(
+            state.apply(&mint).unwrap(),
+            vec![Brc20Effect::Rejected {
+                id: inscription_id(&mint),
+                reason: Brc20Rejection::Overflow,
+            }]
+        );
+        assert_eq!(state.ticker("ordi").unwrap().minted, 0);
+
+        let inscribe = reveal(&Brc20::transfer("ordi", u64::MAX), &alice);
+        assert!(matches!(
+            state.apply(&inscribe).unwrap()[..],
+            [Brc20Effect::Rejected {
+                reason: Brc20Rejection::Overflow,
+                ..
+            }]
+        ));
+        assert_eq!(state.balance("ordi", &alice).available, u64::MAX);
+
+        // a transfer overflowing the balance of the receiver returns to the sender
+        let inscribe = reveal(&Brc20::transfer("ordi", 10), &bob);
+        state.apply(&inscribe).unwrap();
+        state.set_balance(
+            "ordi",
+            alice.clone(),
+            Brc20Balance {
+                available: u64::MAX - 5,
+                transferable: 0,
+            },
+        );
+        let transfer = send(&[OutPoint::new(inscribe.txid(), 0)], &alice);
+        assert_eq!(
+            state.apply(&transfer).unwrap(),
+            vec![Brc20Effect::Rejected {
+                id: inscription_id(&inscribe),
+                reason: Brc20Rejection::Overflow,
+            }]
+        );
+        assert_eq!(
+            state.balance("ordi", &bob),
+            Brc20Balance {
+                available: u64::MAX,
+                transferable: 0,
+            }
+        );
+    }
+}
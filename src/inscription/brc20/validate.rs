@@ -0,0 +1,421 @@
+//! Semantic validation of BRC-20 operations
+//!
+//! [`Brc20`](super::Brc20) only decodes the shape of an operation, so indexers may still ignore
+//! an operation it accepts, e.g. a mint of a 3 bytes ticker. [`validate`] checks the raw JSON
+//! body against the rules indexers apply, and reports every violation.
+
+use std::fmt;
+
+use super::{
+    parse_amount, AmountField as _, Amt, Dec, Lim, Max, DEFAULT_DECIMALS, PROTOCOL,
+    SELF_MINT_TICKER_LENGTH, TICKER_LENGTH,
+};
+use crate::OrdError;
+
+/// Maximum decimals of a ticker.
+pub const MAX_DECIMALS: u64 = DEFAULT_DECIMALS;
+
+/// Reason why a BRC-20 operation is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Brc20ValidationError {
+    /// The body is not a JSON object.
+    NotAnObject,
+    /// A required field is missing.
+    MissingField(&'static str),
+    /// A field is not a string.
+    NotAString(&'static str),
+    /// The protocol is not `brc-20`.
+    InvalidProtocol(String),
+    /// The operation is not `deploy`, `mint` nor `transfer`.
+    UnknownOp(String),
+    /// The ticker has an invalid length in bytes.
+    InvalidTicker {
+        /// The ticker as inscribed
+        tick: String,
+        /// Whether 5 bytes tickers are allowed, i.e. for mints, transfers and self mint deploys
+        five_bytes_allowed: bool,
+    },
+    /// A numeric field is malformed.
+    InvalidAmount {
+        /// Name of the field
+        field: &'static str,
+        /// The value as inscribed
+        value: String,
+        /// Why the value is malformed
+        reason: &'static str,
+    },
+    /// The whole part of a numeric field doesn't fit in a `u64`.
+    AmountOutOfRange {
+        /// Name of the field
+        field: &'static str,
+        /// The value as inscribed
+        value: String,
+    },
+    /// A numeric field has more fractional digits than the decimals of the ticker.
+    PrecisionExceeded {
+        /// Name of the field
+        field: &'static str,
+        /// The value as inscribed
+        value: String,
+        /// Decimals of the ticker
+        decimals: u64,
+    },
+    /// The decimals of a deploy exceed [`MAX_DECIMALS`].
+    DecimalsOutOfRange(u64),
+    /// The `self_mint` field is neither `true` nor `false`.
+    InvalidSelfMint(String),
+}
+
+impl fmt::Display for Brc20ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAnObject => f.write_str("the body is not a JSON object"),
+            Self::MissingField(field) => write!(f, "missing field \"{field}\""),
+            Self::NotAString(field) => write!(f, "field \"{field}\" must be a string"),
+            Self::InvalidProtocol(protocol) => write!(f, "invalid protocol {protocol:?}"),
+            Self::UnknownOp(op) => write!(f, "unknown operation {op:?}"),
+            Self::InvalidTicker {
+                tick,
+                five_bytes_allowed,
+            } => write!(
+                f,
+                "ticker {tick:?} is {} bytes long, expected {}",
+                tick.len(),
+                if *five_bytes_allowed {
+                    "4 or 5"
+                } else {
+                    "4, or 5 for self mint"
+                }
+            ),
+            Self::InvalidAmount {
+                field,
+                value,
+                reason,
+            } => write!(f, "invalid \"{field}\" {value:?}: {reason}"),
+            Self::AmountOutOfRange { field, value } => {
+                write!(f, "\"{field}\" {value} exceeds the maximum amount")
+            }
+            Self::PrecisionExceeded {
+                field,
+                value,
+                decimals,
+            } => write!(f, "\"{field}\" {value} has more than {decimals} decimals"),
+            Self::DecimalsOutOfRange(dec) => {
+                write!(f, "{dec} decimals exceed the maximum of {MAX_DECIMALS}")
+            }
+            Self::InvalidSelfMint(value) => write!(f, "invalid \"self_mint\" {value:?}"),
+        }
+    }
+}
+
+impl std::error::Error for Brc20ValidationError {}
+
+/// Validates the JSON body of a BRC-20 inscription.
+///
+/// Checks that:
+///
+/// - `p` is `brc-20` and `op` is `deploy`, `mint` or `transfer`;
+/// - the ticker is 4 bytes long, or 5 bytes for mints, transfers and self mint deploys;
+/// - `max`, `lim` and `amt` are decimal strings, without signs, exponents nor separators, whose
+///   whole part fits in a `u64` and with at most as many fractional digits as the decimals;
+/// - `dec` is a whole decimal string of at most [`MAX_DECIMALS`].
+///
+/// The decimals of a mint or a transfer are those of the deploy of its ticker, which is not
+/// known here, so their amounts are only checked against [`MAX_DECIMALS`].
+///
+/// # Errors
+///
+/// Returns all the violations of the rules, in the order of the fields.
+pub fn validate(body: &[u8]) -> Result<(), Vec<Brc20ValidationError>> {
+    let json = serde_json::from_slice::<serde_json::Value>(body)
+        .map_err(|_| vec![Brc20ValidationError::NotAnObject])?;
+    let object = json
+        .as_object()
+        .ok_or_else(|| vec![Brc20ValidationError::NotAnObject])?;
+
+    let mut errors = Vec::new();
+    let mut field = |name: &'static str, required: bool| match object.get(name) {
+        Some(serde_json::Value::String(value)) => Some(value.as_str()),
+        None | Some(serde_json::Value::Null) if !required => None,
+        None | Some(serde_json::Value::Null) => {
+            errors.push(Brc20ValidationError::MissingField(name));
+            None
+        }
+        Some(_) => {
+            errors.push(Brc20ValidationError::NotAString(name));
+            None
+        }
+    };
+
+    let protocol = field("p", true);
+    let op = field("op", true);
+    let tick = field("tick", true);
+    let (amounts, self_mint) = match op {
+        Some("deploy") => {
+            let max = field(Max::NAME, true);
+            let lim = field(Lim::NAME, false);
+            let dec = field(Dec::NAME, false);
+            let self_mint = field("self_mint", false);
+            (
+                vec![(Max::NAME, max), (Lim::NAME, lim)],
+                Some((dec, self_mint)),
+            )
+        }
+        Some("mint" | "transfer") => (vec![(Amt::NAME, field(Amt::NAME, true))], None),
+        _ => (Vec::new(), None),
+    };
+
+    if let Some(protocol) = protocol.filter(|protocol| *protocol != PROTOCOL) {
+        errors.push(Brc20ValidationError::InvalidProtocol(protocol.to_string()));
+    }
+    if let Some(op) = op.filter(|op| !matches!(*op, "deploy" | "mint" | "transfer")) {
+        errors.push(Brc20ValidationError::UnknownOp(op.to_string()));
+    }
+
+    // the ticker and amounts of a deploy depend on its self mint flag and decimals
+    let mut decimals = MAX_DECIMALS;
+    let mut allows_self_mint_ticker = op != Some("deploy");
+    if let Some((dec, self_mint)) = self_mint {
+        match self_mint {
+            Some("true") => allows_self_mint_ticker = true,
+            Some("false") | None => {}
+            Some(other) => errors.push(Brc20ValidationError::InvalidSelfMint(other.to_string())),
+        }
+        if let Some(dec) = dec {
+            match validate_decimals(dec) {
+                Ok(dec) => decimals = dec,
+                Err(err) => errors.push(err),
+            }
+        }
+    }
+
+    if let Some(tick) = tick {
+        let valid = tick.len() == TICKER_LENGTH
+            || (tick.len() == SELF_MINT_TICKER_LENGTH && allows_self_mint_ticker);
+        if !valid {
+            errors.push(Brc20ValidationError::InvalidTicker {
+                tick: tick.to_string(),
+                five_bytes_allowed: allows_self_mint_ticker,
+            });
+        }
+    }
+
+    for (name, value) in amounts {
+        if let Some(Err(err)) = value.map(|value| validate_amount(name, value, decimals)) {
+            errors.push(err);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validates a numeric field with at most `decimals` fractional digits.
+fn validate_amount(
+    field: &'static str,
+    value: &str,
+    decimals: u64,
+) -> Result<(), Brc20ValidationError> {
+    let (whole, fraction) = match value.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (value, None),
+    };
+    let invalid = |reason| Brc20ValidationError::InvalidAmount {
+        field,
+        value: value.to_string(),
+        reason,
+    };
+
+    if let Some(fraction) = fraction {
+        if whole.is_empty() || fraction.is_empty() {
+            return Err(invalid(
+                "decimal amounts must have digits on both sides of the point",
+            ));
+        }
+        if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid("the amount must only contain digits"));
+        }
+    }
+    parse_whole(field, whole, value)?;
+
+    let fraction_digits = fraction.map_or(0, str::len) as u64;
+    if fraction_digits > decimals {
+        return Err(Brc20ValidationError::PrecisionExceeded {
+            field,
+            value: value.to_string(),
+            decimals,
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates the `dec` field of a deploy, returning the decimals.
+fn validate_decimals(value: &str) -> Result<u64, Brc20ValidationError> {
+    let dec = parse_whole(Dec::NAME, value, value)?;
+    if dec > MAX_DECIMALS {
+        return Err(Brc20ValidationError::DecimalsOutOfRange(dec));
+    }
+
+    Ok(dec)
+}
+
+/// Parses the whole part of a numeric field, with the reasons of [`parse_amount`] for the
+/// malformed ones.
+fn parse_whole(field: &'static str, whole: &str, value: &str) -> Result<u64, Brc20ValidationError> {
+    if !whole.is_empty() && whole.bytes().all(|b| b.is_ascii_digit()) {
+        return whole
+            .parse()
+            .map_err(|_| Brc20ValidationError::AmountOutOfRange {
+                field,
+                value: value.to_string(),
+            });
+    }
+
+    let reason = match parse_amount(field, whole) {
+        Err(OrdError::Brc20InvalidAmount { reason, .. }) => reason,
+        _ => "the amount must only contain digits",
+    };
+    Err(Brc20ValidationError::InvalidAmount {
+        field,
+        value: value.to_string(),
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_validate_brc20_operations() {
+        assert_eq!(
+            validate(br#"{"p":"brc-20","op":"deploy","tick":"ordi","max":"21000000","lim":"1000","dec":"8"}"#),
+            Ok(())
+        );
+        assert_eq!(
+            validate(br#"{"p":"brc-20","op":"deploy","tick":"ordis","max":"21000000","self_mint":"true"}"#),
+            Ok(())
+        );
+        assert_eq!(
+            validate(br#"{"p":"brc-20","op":"mint","tick":"ordis","amt":"1000.5"}"#),
+            Ok(())
+        );
+        assert_eq!(
+            validate(
+                br#"{"p":"brc-20","op":"transfer","tick":"ordi","amt":"18446744073709551615"}"#
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_should_report_all_violations() {
+        assert_eq!(
+            validate(br#"{"p":"brc-21","op":"burn","tick":"ord"}"#),
+            Err(vec![
+                Brc20ValidationError::InvalidProtocol("brc-21".to_string()),
+                Brc20ValidationError::UnknownOp("burn".to_string()),
+                Brc20ValidationError::InvalidTicker {
+                    tick: "ord".to_string(),
+                    five_bytes_allowed: true,
+                },
+            ])
+        );
+
+        // the decimals of the deploy bound the precision of its amounts
+        assert_eq!(
+            validate(br#"{"p":"brc-20","op":"deploy","tick":"ordis","max":"21000000.123","lim":1000,"dec":"2"}"#),
+            Err(vec![
+                Brc20ValidationError::NotAString("lim"),
+                Brc20ValidationError::InvalidTicker {
+                    tick: "ordis".to_string(),
+                    five_bytes_allowed: false,
+                },
+                Brc20ValidationError::PrecisionExceeded {
+                    field: "max",
+                    value: "21000000.123".to_string(),
+                    decimals: 2,
+                },
+            ])
+        );
+        assert_eq!(
+            validate(br#"{"p":"brc-20","op":"deploy","tick":"ordi","max":"1","dec":"19","self_mint":"yes"}"#),
+            Err(vec![
+                Brc20ValidationError::InvalidSelfMint("yes".to_string()),
+                Brc20ValidationError::DecimalsOutOfRange(19),
+            ])
+        );
+
+        assert_eq!(
+            validate(br#"{"p":"brc-20","op":"mint","tick":"ordi"}"#),
+            Err(vec![Brc20ValidationError::MissingField("amt")])
+        );
+        assert_eq!(
+            validate(b"[1, 2, 3]"),
+            Err(vec![Brc20ValidationError::NotAnObject])
+        );
+    }
+
+    #[test]
+    fn test_should_check_amount_formatting() {
+        let amount_error = |amt: &str| {
+            let body = format!(r#"{{"p":"brc-20","op":"transfer","tick":"ordi","amt":"{amt}"}}"#);
+            validate(body.as_bytes()).unwrap_err().remove(0)
+        };
+
+        assert_eq!(
+            amount_error("18446744073709551616"),
+            Brc20ValidationError::AmountOutOfRange {
+                field: "amt",
+                value: "18446744073709551616".to_string(),
+            }
+        );
+        assert_eq!(
+            amount_error("1.0000000000000000001"),
+            Brc20ValidationError::PrecisionExceeded {
+                field: "amt",
+                value: "1.0000000000000000001".to_string(),
+                decimals: MAX_DECIMALS,
+            }
+        );
+        for (amt, reason) in [
+            ("", "the amount is empty"),
+            ("1e3", "exponent notation is not allowed"),
+            ("-1", "signs are not allowed"),
+            ("1,000", "digit separators are not allowed"),
+            (
+                ".5",
+                "decimal amounts must have digits on both sides of the point",
+            ),
+            (
+                "1.",
+                "decimal amounts must have digits on both sides of the point",
+            ),
+            ("1.2.3", "the amount must only contain digits"),
+        ] {
+            assert_eq!(
+                amount_error(amt),
+                Brc20ValidationError::InvalidAmount {
+                    field: "amt",
+                    value: amt.to_string(),
+                    reason,
+                },
+                "{amt}"
+            );
+        }
+
+        assert_eq!(
+            Brc20ValidationError::PrecisionExceeded {
+                field: "amt",
+                value: "1.123".to_string(),
+                decimals: 2,
+            }
+            .to_string(),
+            "\"amt\" 1.123 has more than 2 decimals"
+        );
+    }
+}
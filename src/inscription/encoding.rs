@@ -0,0 +1,155 @@
+//! Content encodings
+//!
+//! Inscriptions may be compressed, in which case the content encoding field, with a tag of 9,
+//! holds the encoding of the body, as the `Content-Encoding` HTTP header with which `ord`
+//! serves it. Compressing and decompressing bodies requires the `compression` feature.
+
+use std::fmt;
+#[cfg(feature = "compression")]
+use std::io::{Read as _, Write as _};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::OrdError;
+#[cfg(feature = "compression")]
+use crate::OrdResult;
+
+/// Quality of the brotli compression, the best one as inscription bytes are costly.
+#[cfg(feature = "compression")]
+const BROTLI_QUALITY: u32 = 11;
+/// Log2 of the brotli window size.
+#[cfg(feature = "compression")]
+const BROTLI_WINDOW: u32 = 22;
+/// Size of the buffers used by the brotli (de)compressor.
+#[cfg(feature = "compression")]
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+/// Content encoding of the body of an inscription.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentEncoding {
+    /// Brotli, `br`, the encoding used by `ord` when compressing inscriptions
+    #[serde(rename = "br")]
+    Brotli,
+    /// Gzip, `gzip`
+    Gzip,
+}
+
+impl ContentEncoding {
+    /// Returns the value of the content encoding field.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    /// Compresses the content.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::ContentEncoding`] if the compression fails.
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn encode(&self, content: &[u8]) -> OrdResult<Vec<u8>> {
+        let mut encoded = Vec::new();
+        match self {
+            Self::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(
+                    &mut encoded,
+                    BROTLI_BUFFER_SIZE,
+                    BROTLI_QUALITY,
+                    BROTLI_WINDOW,
+                );
+                encoder.write_all(content).map_err(self.error())?;
+            }
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(&mut encoded, flate2::Compression::best());
+                encoder.write_all(content).map_err(self.error())?;
+                encoder.finish().map_err(self.error())?;
+            }
+        }
+
+        Ok(encoded)
+    }
+
+    /// Decompresses the content.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::ContentEncoding`] if the content is not validly encoded.
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn decode(&self, content: &[u8]) -> OrdResult<Vec<u8>> {
+        let mut decoded = Vec::new();
+        match self {
+            Self::Brotli => {
+                brotli::Decompressor::new(content, BROTLI_BUFFER_SIZE)
+                    .read_to_end(&mut decoded)
+                    .map_err(self.error())?;
+            }
+            Self::Gzip => {
+                flate2::read::GzDecoder::new(content)
+                    .read_to_end(&mut decoded)
+                    .map_err(self.error())?;
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    #[cfg(feature = "compression")]
+    fn error(&self) -> impl Fn(std::io::Error) -> OrdError + '_ {
+        move |err| OrdError::ContentEncoding(format!("{self}: {err}"))
+    }
+}
+
+impl fmt::Display for ContentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ContentEncoding {
+    type Err = OrdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "br" => Ok(Self::Brotli),
+            "gzip" => Ok(Self::Gzip),
+            other => Err(OrdError::ContentEncoding(format!(
+                "unsupported encoding {other:?}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_parse_content_encodings() {
+        for encoding in [ContentEncoding::Brotli, ContentEncoding::Gzip] {
+            assert_eq!(
+                encoding.as_str().parse::<ContentEncoding>().unwrap(),
+                encoding
+            );
+        }
+        assert!("deflate".parse::<ContentEncoding>().is_err());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_should_roundtrip_content_encodings() {
+        let content = b"<svg>".repeat(100);
+        for encoding in [ContentEncoding::Brotli, ContentEncoding::Gzip] {
+            let encoded = encoding.encode(&content).unwrap();
+            assert!(encoded.len() < content.len());
+            assert_eq!(encoding.decode(&encoded).unwrap(), content);
+            assert!(encoding.decode(b"not encoded").is_err());
+        }
+    }
+}
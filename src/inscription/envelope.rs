@@ -0,0 +1,221 @@
+//! Envelope
+//!
+//! Low-level writer of inscription envelopes, i.e. `OP_FALSE OP_IF "ord" ... OP_ENDIF`
+//! sequences of (tag, value) pairs followed by an optional body, as read by [`crate::OrdParser`].
+//!
+//! [Reference](https://docs.ordinals.com/inscriptions.html#fields)
+
+use bitcoin::opcodes;
+use bitcoin::script::{Builder as ScriptBuilder, PushBytes, ScriptBuf};
+
+use crate::utils::constants::{BODY_TAG, MAX_SCRIPT_ELEMENT_SIZE, PROTOCOL_ID};
+use crate::utils::push_bytes::bytes_to_push_chunks;
+use crate::{OrdError, OrdResult};
+
+/// Builds an inscription envelope from arbitrary fields and body chunks, e.g. to experiment
+/// with new metaprotocols without modifying [`crate::Nft`].
+///
+/// Fields are written in the order they are added, so duplicate or unrecognized fields can be
+/// produced on purpose.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvelopeBuilder {
+    protocol_id: Option<Vec<u8>>,
+    fields: Vec<(Vec<u8>, Vec<u8>)>,
+    body: Option<Vec<Vec<u8>>>,
+}
+
+impl EnvelopeBuilder {
+    /// Creates an empty envelope.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the protocol ID pushed after `OP_IF`, [`PROTOCOL_ID`] if not set, e.g. for research
+    /// protocols and testnets experimenting with their own namespace.
+    ///
+    /// Envelopes with another protocol ID are only parsed with the same
+    /// [`crate::wallet::ParseOptions::protocol_id`].
+    pub fn protocol_id(mut self, protocol_id: impl Into<Vec<u8>>) -> Self {
+        self.protocol_id = Some(protocol_id.into());
+        self
+    }
+
+    /// Appends a field, written as a tag push followed by a value push.
+    pub fn field(mut self, tag: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> Self {
+        self.fields.push((tag.into(), value.into()));
+        self
+    }
+
+    /// Appends a field whose value may exceed the maximum push size, repeating the tag for
+    /// each chunk of the value, as done for the metadata.
+    pub fn chunked_field(mut self, tag: impl Into<Vec<u8>>, value: &[u8]) -> Self {
+        let tag = tag.into();
+        for chunk in bytes_to_push_chunks(value) {
+            self.fields.push((tag.clone(), chunk.as_bytes().to_vec()));
+        }
+        self
+    }
+
+    /// Appends a chunk to the body, written as a single push.
+    pub fn body_chunk(mut self, chunk: impl Into<Vec<u8>>) -> Self {
+        self.body.get_or_insert_with(Vec::new).push(chunk.into());
+        self
+    }
+
+    /// Appends the bytes to the body, split into pushes of the maximum size.
+    pub fn body(mut self, body: &[u8]) -> Self {
+        let chunks = self.body.get_or_insert_with(Vec::new);
+        chunks.extend(
+            bytes_to_push_chunks(body)
+                .into_iter()
+                .map(|chunk| chunk.as_bytes().to_vec()),
+        );
+        self
+    }
+
+    /// Appends the envelope to the script builder.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::PushTooLarge`] if the protocol ID, a tag, a value or a body chunk
+    /// exceeds [`MAX_SCRIPT_ELEMENT_SIZE`], which would make the script unspendable.
+    pub fn append_to(&self, mut builder: ScriptBuilder) -> OrdResult<ScriptBuilder> {
+        let protocol_id = self.protocol_id.as_deref().unwrap_or(&PROTOCOL_ID);
+        builder = builder
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(opcodes::all::OP_IF)
+            .push_slice(push_bytes(protocol_id)?);
+
+        for (tag, value) in &self.fields {
+            builder = builder
+                .push_slice(push_bytes(tag)?)
+                .push_slice(push_bytes(value)?);
+        }
+
+        if let Some(body) = &self.body {
+            builder = builder.push_slice(BODY_TAG);
+            for chunk in body {
+                builder = builder.push_slice(push_bytes(chunk)?);
+            }
+        }
+
+        Ok(builder.push_opcode(opcodes::all::OP_ENDIF))
+    }
+
+    /// Returns the envelope as a standalone script.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::append_to`].
+    pub fn to_script(&self) -> OrdResult<ScriptBuf> {
+        Ok(self.append_to(ScriptBuilder::new())?.into_script())
+    }
+}
+
+fn push_bytes(bytes: &[u8]) -> OrdResult<&PushBytes> {
+    if bytes.len() > MAX_SCRIPT_ELEMENT_SIZE {
+        return Err(OrdError::PushTooLarge(bytes.len()));
+    }
+    Ok(bytes.try_into()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{OutPoint, Sequence, Transaction, TxIn, Witness};
+
+    use super::*;
+    use crate::utils::constants::{CONTENT_TYPE_TAG, METADATA_TAG, METAPROTOCOL_TAG};
+    use crate::wallet::{Curse, OrdParser, ParseMode, ParseOptions};
+    use crate::Nft;
+
+    fn reveal_transaction(script: ScriptBuf) -> Transaction {
+        Transaction {
+            version: Version::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[script.into_bytes(), Vec::new()]),
+            }],
+            output: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_should_match_nft_envelope() {
+        let mut nft = Nft::new(Some(b"text/plain".to_vec()), Some(vec![b'a'; 1_000]));
+        nft.metadata = Some(vec![0xa0; 600]);
+        nft.metaprotocol = Some(b"brc-721".to_vec());
+
+        let envelope = EnvelopeBuilder::new()
+            .field(CONTENT_TYPE_TAG, b"text/plain".as_slice())
+            .field(METAPROTOCOL_TAG, b"brc-721".as_slice())
+            .chunked_field(METADATA_TAG, &[0xa0; 600])
+            .body(&[b'a'; 1_000]);
+
+        assert_eq!(
+            envelope.to_script().unwrap(),
+            nft.append_reveal_script_to_builder(ScriptBuilder::new())
+                .unwrap()
+                .into_script()
+        );
+    }
+
+    #[test]
+    fn test_should_be_read_by_the_parser() {
+        let script = EnvelopeBuilder::new()
+            .field(CONTENT_TYPE_TAG, b"application/json".as_slice())
+            .field(CONTENT_TYPE_TAG, b"text/plain".as_slice())
+            .body_chunk(b"hello ".as_slice())
+            .body_chunk(b"world".as_slice())
+            .to_script()
+            .unwrap();
+
+        let parsed =
+            OrdParser::parse_all_with_mode(&reveal_transaction(script), ParseMode::Lenient)
+                .unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].curses, vec![Curse::DuplicateField]);
+        let nft = Nft::try_from(&parsed[0].inscription).unwrap();
+        assert_eq!(nft.content_type(), Some("application/json"));
+        assert_eq!(nft.body(), Some("hello world"));
+    }
+
+    #[test]
+    fn test_should_parse_custom_protocol_id() {
+        let script = EnvelopeBuilder::new()
+            .protocol_id(b"ord-test".as_slice())
+            .field(CONTENT_TYPE_TAG, b"text/plain".as_slice())
+            .body(b"hello")
+            .to_script()
+            .unwrap();
+        let tx = reveal_transaction(script);
+
+        // envelopes of other namespaces are ignored by default
+        assert!(OrdParser::parse_all(&tx).unwrap().is_empty());
+
+        let parsed = OrdParser::parse_all_with_options(
+            &tx,
+            &ParseOptions {
+                protocol_id: b"ord-test".to_vec(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(parsed.len(), 1);
+        let nft = Nft::try_from(&parsed[0].inscription).unwrap();
+        assert_eq!(nft.body(), Some("hello"));
+    }
+
+    #[test]
+    fn test_should_reject_oversized_pushes() {
+        let envelope = EnvelopeBuilder::new().body_chunk(vec![0; MAX_SCRIPT_ELEMENT_SIZE + 1]);
+        assert!(matches!(
+            envelope.to_script(),
+            Err(OrdError::PushTooLarge(521))
+        ));
+    }
+}
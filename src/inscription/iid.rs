@@ -45,6 +45,31 @@ impl InscriptionId {
             .collect()
     }
 
+    /// Creates a new InscriptionId from its raw bytes, as returned by [`Self::get_raw`].
+    ///
+    /// Returns `None` if the bytes are not a txid followed by up to 4 bytes of index, or if the
+    /// index has trailing zeros, as `ord` ignores such values.
+    pub fn from_raw(raw: &[u8]) -> Option<Self> {
+        const TXID_LEN: usize = 32;
+
+        if !(TXID_LEN..=TXID_LEN + 4).contains(&raw.len()) {
+            return None;
+        }
+
+        let (txid, index) = raw.split_at(TXID_LEN);
+        if index.last() == Some(&0) {
+            return None;
+        }
+
+        let mut index_bytes = [0; 4];
+        index_bytes[..index.len()].copy_from_slice(index);
+
+        Some(Self {
+            txid: Txid::from_slice(txid).ok()?,
+            index: u32::from_le_bytes(index_bytes),
+        })
+    }
+
     /// Creates a new InscriptionId from a transaction's output reference.
     pub fn from_outpoint(outpoint: OutPoint) -> Self {
         Self {
@@ -233,6 +258,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn raw_round_trip() {
+        for index in [0, 1, 0x100, 0xFFFFFFFF] {
+            let iid = InscriptionId {
+                txid: txid(1),
+                index,
+            };
+            assert_eq!(InscriptionId::from_raw(&iid.get_raw()), Some(iid));
+        }
+        assert_eq!(set_using(1).get_raw().len(), 33);
+
+        // index with trailing zeros
+        let mut raw = set_using(1).get_raw();
+        raw.push(0);
+        assert_eq!(InscriptionId::from_raw(&raw), None);
+        assert_eq!(InscriptionId::from_raw(&[1; 31]), None);
+        assert_eq!(InscriptionId::from_raw(&[1; 37]), None);
+    }
+
     #[test]
     fn from_str_bad_character() {
         assert_matches!(
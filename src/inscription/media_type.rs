@@ -0,0 +1,172 @@
+//! Media type
+//!
+//! Parsing of the content type of inscriptions, as defined by [RFC 2045](https://www.rfc-editor.org/rfc/rfc2045#section-5.1),
+//! e.g. `text/plain;charset=utf-8`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{InscriptionParseError, OrdError};
+
+/// A parsed media type, split into its essence and parameters.
+///
+/// Type, subtype and parameter names are case-insensitive, so they're stored in lowercase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    type_: String,
+    subtype: String,
+    parameters: Vec<(String, String)>,
+}
+
+impl MediaType {
+    /// Returns the top-level type, e.g. `text`.
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// Returns the subtype, e.g. `plain`.
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Returns the media type without parameters, e.g. `text/plain`.
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_, self.subtype)
+    }
+
+    /// Returns the parameters in the order they appear.
+    pub fn parameters(&self) -> &[(String, String)] {
+        &self.parameters
+    }
+
+    /// Returns the value of the first parameter with the given name.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the `charset` parameter, e.g. `utf-8`.
+    pub fn charset(&self) -> Option<&str> {
+        self.parameter("charset")
+    }
+
+    /// Returns the `boundary` parameter of multipart media types.
+    pub fn boundary(&self) -> Option<&str> {
+        self.parameter("boundary")
+    }
+
+    /// Returns whether the media type is textual, i.e. of the `text` type.
+    pub fn is_text(&self) -> bool {
+        self.type_ == "text"
+    }
+
+    /// Returns whether the media type is JSON, including `+json` structured syntax suffixes.
+    pub fn is_json(&self) -> bool {
+        self.type_ == "application" && (self.subtype == "json" || self.subtype.ends_with("+json"))
+    }
+
+    /// Returns whether the media type is an image.
+    pub fn is_image(&self) -> bool {
+        self.type_ == "image"
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = OrdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || OrdError::InscriptionParser(InscriptionParseError::ContentType);
+        let is_token = |token: &str| {
+            !token.is_empty()
+                && token
+                    .chars()
+                    .all(|c| c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?=".contains(c))
+        };
+
+        let mut parts = s.split(';');
+        let essence = parts.next().unwrap_or_default().trim();
+        let (type_, subtype) = essence.split_once('/').ok_or_else(invalid)?;
+        if !is_token(type_) || !is_token(subtype) {
+            return Err(invalid());
+        }
+
+        let mut parameters = Vec::new();
+        for parameter in parts
+            .map(str::trim)
+            .filter(|parameter| !parameter.is_empty())
+        {
+            let (name, value) = parameter.split_once('=').ok_or_else(invalid)?;
+            let name = name.trim();
+            if !is_token(name) {
+                return Err(invalid());
+            }
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            parameters.push((name.to_ascii_lowercase(), value.to_string()));
+        }
+
+        Ok(Self {
+            type_: type_.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            parameters,
+        })
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+        for (name, value) in &self.parameters {
+            write!(f, ";{name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_parse_media_type() {
+        let media_type = MediaType::from_str("text/plain;charset=utf-8").unwrap();
+        assert_eq!(media_type.essence(), "text/plain");
+        assert_eq!(media_type.charset(), Some("utf-8"));
+        assert!(media_type.is_text());
+        assert!(!media_type.is_json());
+        assert_eq!(media_type.to_string(), "text/plain;charset=utf-8");
+
+        let media_type =
+            MediaType::from_str(r#"Multipart/Mixed; Boundary="frontier"; charset=UTF-8"#).unwrap();
+        assert_eq!(media_type.type_(), "multipart");
+        assert_eq!(media_type.subtype(), "mixed");
+        assert_eq!(media_type.boundary(), Some("frontier"));
+        assert_eq!(media_type.parameter("CHARSET"), Some("UTF-8"));
+
+        let media_type = MediaType::from_str("application/ld+json").unwrap();
+        assert!(media_type.is_json());
+        assert!(media_type.parameters().is_empty());
+        assert!(MediaType::from_str("image/png;").unwrap().is_image());
+    }
+
+    #[test]
+    fn test_should_not_parse_invalid_media_type() {
+        for content_type in [
+            "plain",
+            "text/",
+            "/plain",
+            "text/pl ain",
+            "text/plain;charset",
+        ] {
+            assert!(
+                MediaType::from_str(content_type).is_err(),
+                "{content_type} should be invalid"
+            );
+        }
+    }
+}
@@ -2,20 +2,18 @@
 //!
 //! Closely follows <https://github.com/ordinals/ord/blob/master/src/inscriptions/inscription.rs>
 
+#[cfg(feature = "compression")]
+use std::borrow::Cow;
 use std::io::Cursor;
-use std::mem;
 use std::str::FromStr;
 
-use bitcoin::constants::MAX_SCRIPT_ELEMENT_SIZE;
-use bitcoin::opcodes;
-use bitcoin::opcodes::all::OP_CHECKSIG;
-use bitcoin::script::{Builder as ScriptBuilder, PushBytes, PushBytesBuf, ScriptBuf};
+use bitcoin::script::{Builder as ScriptBuilder, PushBytesBuf, ScriptBuf};
 use serde::{Deserialize, Serialize};
 
+use crate::inscription::envelope::EnvelopeBuilder;
 use crate::utils::constants;
-use crate::utils::push_bytes::bytes_to_push_bytes;
-use crate::wallet::RedeemScriptPubkey;
-use crate::{Inscription, InscriptionParseError, OrdError, OrdResult};
+use crate::utils::push_bytes::{bytes_to_push_bytes, PushBytesWriter};
+use crate::{ContentEncoding, Inscription, InscriptionId, MediaType, OrdError, OrdResult};
 
 /// Represents an arbitrary Ordinal inscription.
 ///
@@ -30,6 +28,7 @@ use crate::{Inscription, InscriptionParseError, OrdError, OrdResult};
 ///
 /// [Reference](https://docs.ordinals.com/inscriptions.html#fields)
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "ic", derive(candid::CandidType))]
 pub struct Nft {
     /// The main body of the NFT. This is the core data or content of the NFT,
     /// which might represent an image, text, or other types of digital assets.
@@ -80,91 +79,38 @@ impl Nft {
         }
     }
 
+    /// Creates a new `Nft` whose body is the content compressed with the given encoding, which
+    /// is set as its content encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::ContentEncoding`] if the compression fails.
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn new_encoded(
+        content_type: Option<Vec<u8>>,
+        content: &[u8],
+        encoding: ContentEncoding,
+    ) -> OrdResult<Self> {
+        Ok(Self {
+            content_encoding: Some(encoding.as_str().as_bytes().to_vec()),
+            ..Self::new(content_type, Some(encoding.encode(content)?))
+        })
+    }
+
     pub fn append_reveal_script_to_builder(
         &self,
-        mut builder: ScriptBuilder,
+        builder: ScriptBuilder,
     ) -> OrdResult<ScriptBuilder> {
-        builder = builder
-            .push_opcode(opcodes::OP_FALSE)
-            .push_opcode(opcodes::all::OP_IF)
-            .push_slice(constants::PROTOCOL_ID);
-
-        Self::append(
-            constants::CONTENT_TYPE_TAG,
-            &mut builder,
-            &self.content_type,
-        );
-        Self::append(
-            constants::CONTENT_ENCODING_TAG,
-            &mut builder,
-            &self.content_encoding,
-        );
-        Self::append(
-            constants::METAPROTOCOL_TAG,
-            &mut builder,
-            &self.metaprotocol,
-        );
-        Self::append_array(constants::PARENT_TAG, &mut builder, &self.parents);
-        Self::append(constants::DELEGATE_TAG, &mut builder, &self.delegate);
-        Self::append(constants::POINTER_TAG, &mut builder, &self.pointer);
-        Self::append(constants::METADATA_TAG, &mut builder, &self.metadata);
-        Self::append(constants::RUNE_TAG, &mut builder, &self.rune);
-
-        if let Some(body) = &self.body {
-            builder = builder.push_slice(constants::BODY_TAG);
-            for chunk in body.chunks(MAX_SCRIPT_ELEMENT_SIZE) {
-                builder = builder.push_slice::<&PushBytes>(chunk.try_into().unwrap());
-            }
-        }
-
-        Ok(builder.push_opcode(opcodes::all::OP_ENDIF))
-    }
-
-    fn append(tag: [u8; 1], builder: &mut ScriptBuilder, value: &Option<Vec<u8>>) {
-        if let Some(value) = value {
-            let mut tmp = ScriptBuilder::new();
-            mem::swap(&mut tmp, builder);
-
-            if is_chunked(tag) {
-                for chunk in value.chunks(MAX_SCRIPT_ELEMENT_SIZE) {
-                    tmp = tmp
-                        .push_slice::<&PushBytes>(tag.as_slice().try_into().unwrap())
-                        .push_slice::<&PushBytes>(chunk.try_into().unwrap());
-                }
-            } else {
-                tmp = tmp
-                    .push_slice::<&PushBytes>(tag.as_slice().try_into().unwrap())
-                    .push_slice::<&PushBytes>(value.as_slice().try_into().unwrap());
-            }
-
-            mem::swap(&mut tmp, builder);
-        }
-    }
-
-    fn append_array(tag: [u8; 1], builder: &mut ScriptBuilder, values: &Vec<Vec<u8>>) {
-        let mut tmp = ScriptBuilder::new();
-        mem::swap(&mut tmp, builder);
-
-        for value in values {
-            tmp = tmp
-                .push_slice::<&PushBytes>(tag.as_slice().try_into().unwrap())
-                .push_slice::<&PushBytes>(value.as_slice().try_into().unwrap());
-        }
-
-        mem::swap(&mut tmp, builder);
+        self.fields()?.append_to(builder)
     }
 
     /// Validates the NFT's content type.
     fn validate_content_type(&self) -> OrdResult<Self> {
         if let Some(content_type) = &self.content_type {
-            let content_type_str =
-                std::str::from_utf8(content_type).map_err(OrdError::Utf8Encoding)?;
-
-            if !content_type_str.contains('/') {
-                return Err(OrdError::InscriptionParser(
-                    InscriptionParseError::ContentType,
-                ));
-            }
+            std::str::from_utf8(content_type)
+                .map_err(OrdError::Utf8Encoding)?
+                .parse::<MediaType>()?;
         }
 
         Ok(self.clone())
@@ -184,14 +130,137 @@ impl Nft {
         std::str::from_utf8(self.body.as_ref()?).ok()
     }
 
+    /// Returns the content encoding of the body, or `None` if it's not encoded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::ContentEncoding`] if the encoding is not supported.
+    pub fn content_encoding(&self) -> OrdResult<Option<ContentEncoding>> {
+        self.content_encoding
+            .as_ref()
+            .map(|encoding| {
+                std::str::from_utf8(encoding)
+                    .map_err(OrdError::Utf8Encoding)?
+                    .parse()
+            })
+            .transpose()
+    }
+
+    /// Returns the body decompressed according to its content encoding, or `None` if there's no
+    /// body.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::ContentEncoding`] if the encoding is not supported or the body is not
+    /// validly encoded.
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn decoded_body(&self) -> OrdResult<Option<Cow<'_, [u8]>>> {
+        let Some(body) = &self.body else {
+            return Ok(None);
+        };
+
+        Ok(Some(match self.content_encoding()? {
+            Some(encoding) => Cow::Owned(encoding.decode(body)?),
+            None => Cow::Borrowed(body),
+        }))
+    }
+
     pub fn content_type(&self) -> Option<&str> {
         std::str::from_utf8(self.content_type.as_ref()?).ok()
     }
 
+    /// Returns the content type parsed into its essence and parameters,
+    /// or `None` if it's missing or malformed.
+    pub fn media_type(&self) -> Option<MediaType> {
+        self.content_type()?.parse().ok()
+    }
+
+    /// Returns the pointer to the inscribed sat, as an offset into the reveal transaction outputs.
+    pub fn pointer(&self) -> Option<u64> {
+        let pointer = self.pointer.as_ref()?;
+        if pointer.iter().skip(8).any(|byte| *byte != 0) {
+            return None;
+        }
+
+        let mut bytes = [0; 8];
+        let len = pointer.len().min(8);
+        bytes[..len].copy_from_slice(&pointer[..len]);
+        Some(u64::from_le_bytes(bytes))
+    }
+
+    /// Sets the pointer to the inscribed sat, encoded as in `ord`.
+    pub fn with_pointer(mut self, pointer: u64) -> Self {
+        let mut bytes = pointer.to_le_bytes().to_vec();
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        self.pointer = Some(bytes);
+        self
+    }
+
+    /// Returns the inscription this inscription delegates its content to, or `None` if it's
+    /// missing or malformed.
+    pub fn delegate(&self) -> Option<InscriptionId> {
+        InscriptionId::from_raw(self.delegate.as_ref()?)
+    }
+
+    /// Sets the inscription this inscription delegates its content to.
+    ///
+    /// The delegate is encoded as in `ord`: the txid followed by the little-endian index without
+    /// its trailing zeros, i.e. up to 36 bytes. A delegate inscription usually has no body nor
+    /// content type, as they're taken from the delegate.
+    pub fn with_delegate(mut self, delegate: InscriptionId) -> Self {
+        self.delegate = Some(delegate.get_raw());
+        self
+    }
+
+    /// Returns the parent inscriptions of the inscription, skipping malformed parent fields.
+    pub fn parents(&self) -> Vec<InscriptionId> {
+        self.parents
+            .iter()
+            .filter_map(|parent| InscriptionId::from_raw(parent))
+            .collect()
+    }
+
+    /// Adds a parent inscription, encoded as the delegate is.
+    ///
+    /// The inscription is only recognized as a child if the transaction revealing it also spends
+    /// the parent inscription, see [`crate::wallet::ParentInfo`].
+    pub fn with_parent(mut self, parent: InscriptionId) -> Self {
+        self.parents.push(parent.get_raw());
+        self
+    }
+
+    /// Returns the rune committed to by the inscription, if it's revealed by an etching.
+    #[cfg(feature = "rune")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+    pub fn rune(&self) -> Option<ordinals::Rune> {
+        crate::wallet::rune_from_commitment_bytes(self.rune.as_ref()?).ok()
+    }
+
+    /// Sets the commitment to the rune etched by the transaction revealing the inscription.
+    #[cfg(feature = "rune")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+    pub fn with_rune(mut self, rune: ordinals::Rune) -> Self {
+        self.rune = Some(crate::wallet::rune_commitment_bytes(rune));
+        self
+    }
+
     pub fn metadata(&self) -> Option<ciborium::Value> {
         ciborium::from_reader(Cursor::new(self.metadata.as_ref()?)).ok()
     }
 
+    /// Sets the metadata, encoded as deterministic CBOR, see [`encode_canonical_metadata`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::Metadata`] if a map of the metadata has duplicate keys.
+    pub fn with_metadata(mut self, metadata: &ciborium::Value) -> OrdResult<Self> {
+        self.metadata = Some(encode_canonical_metadata(metadata)?);
+        Ok(self)
+    }
+
     pub fn reveal_script_as_scriptbuf(&self, builder: ScriptBuilder) -> OrdResult<ScriptBuf> {
         Ok(self.append_reveal_script_to_builder(builder)?.into_script())
     }
@@ -206,18 +275,39 @@ impl FromStr for Nft {
 }
 
 impl Inscription for Nft {
-    fn generate_redeem_script(
-        &self,
-        builder: ScriptBuilder,
-        pubkey: RedeemScriptPubkey,
-    ) -> OrdResult<ScriptBuilder> {
-        let encoded_pubkey = pubkey.encode()?;
-
-        let builder = builder
-            .push_slice(encoded_pubkey.as_push_bytes())
-            .push_opcode(OP_CHECKSIG);
+    fn fields(&self) -> OrdResult<EnvelopeBuilder> {
+        let mut fields = EnvelopeBuilder::new();
+        for (tag, value) in [
+            (constants::CONTENT_TYPE_TAG, &self.content_type),
+            (constants::CONTENT_ENCODING_TAG, &self.content_encoding),
+            (constants::METAPROTOCOL_TAG, &self.metaprotocol),
+        ] {
+            if let Some(value) = value {
+                fields = fields.field(tag, value.as_slice());
+            }
+        }
+        for parent in &self.parents {
+            fields = fields.field(constants::PARENT_TAG, parent.as_slice());
+        }
+        for (tag, value) in [
+            (constants::DELEGATE_TAG, &self.delegate),
+            (constants::POINTER_TAG, &self.pointer),
+        ] {
+            if let Some(value) = value {
+                fields = fields.field(tag, value.as_slice());
+            }
+        }
+        if let Some(metadata) = &self.metadata {
+            fields = fields.chunked_field(constants::METADATA_TAG, metadata);
+        }
+        if let Some(rune) = &self.rune {
+            fields = fields.field(constants::RUNE_TAG, rune.as_slice());
+        }
+        if let Some(body) = &self.body {
+            fields = fields.body(body);
+        }
 
-        self.append_reveal_script_to_builder(builder)
+        Ok(fields)
     }
 
     fn content_type(&self) -> String {
@@ -228,12 +318,74 @@ impl Inscription for Nft {
     }
 
     fn data(&self) -> OrdResult<PushBytesBuf> {
-        bytes_to_push_bytes(self.encode()?.as_bytes())
+        let mut data = PushBytesBuf::new();
+        self.data_into(&mut data)?;
+        Ok(data)
+    }
+
+    fn data_into(&self, buf: &mut PushBytesBuf) -> OrdResult<()> {
+        serde_json::to_writer(PushBytesWriter(buf), self).map_err(OrdError::from)
     }
 }
 
-fn is_chunked(tag: [u8; 1]) -> bool {
-    matches!(tag, constants::METADATA_TAG)
+/// Encodes inscription metadata as deterministic CBOR, following the core deterministic encoding
+/// requirements of [RFC 8949 §4.2.1](https://www.rfc-editor.org/rfc/rfc8949.html#section-4.2.1),
+/// so identical logical metadata always produces identical bytes, e.g. to dedupe inscriptions by
+/// content.
+///
+/// Integers, lengths and floats take their shortest form, all lengths are definite and the keys
+/// of maps are sorted by the bytewise order of their encoding.
+///
+/// # Errors
+///
+/// Returns [`OrdError::Metadata`] if a map has duplicate keys, which deterministic CBOR forbids.
+pub fn encode_canonical_metadata(metadata: &ciborium::Value) -> OrdResult<Vec<u8>> {
+    encode_cbor(&canonicalize_cbor(metadata.clone())?)
+}
+
+fn encode_cbor(value: &ciborium::Value) -> OrdResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).map_err(|err| OrdError::Metadata(err.to_string()))?;
+    Ok(bytes)
+}
+
+/// Sorts the keys of the maps nested in the value, as deterministic CBOR requires.
+fn canonicalize_cbor(value: ciborium::Value) -> OrdResult<ciborium::Value> {
+    use ciborium::Value;
+
+    Ok(match value {
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(canonicalize_cbor)
+                .collect::<OrdResult<_>>()?,
+        ),
+        Value::Tag(tag, value) => Value::Tag(tag, Box::new(canonicalize_cbor(*value)?)),
+        Value::Map(entries) => {
+            let mut entries = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = canonicalize_cbor(key)?;
+                    Ok((encode_cbor(&key)?, key, canonicalize_cbor(value)?))
+                })
+                .collect::<OrdResult<Vec<_>>>()?;
+            entries.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+            if let Some(duplicate) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(OrdError::Metadata(format!(
+                    "duplicate map key {:?}",
+                    duplicate[0].1
+                )));
+            }
+
+            Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(_, key, value)| (key, value))
+                    .collect(),
+            )
+        }
+        value => value,
+    })
 }
 
 #[allow(unused)]
@@ -255,6 +407,49 @@ mod tests {
         assert!(nft.metadata().is_none());
     }
 
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_should_decode_encoded_body() {
+        let content = b"<html><body>compressed</body></html>".repeat(10);
+        let nft = Nft::new_encoded(
+            Some(b"text/html".to_vec()),
+            &content,
+            ContentEncoding::Brotli,
+        )
+        .unwrap();
+        assert_eq!(nft.content_encoding.as_deref(), Some(b"br".as_slice()));
+        assert_eq!(
+            nft.content_encoding().unwrap(),
+            Some(ContentEncoding::Brotli)
+        );
+        assert!(nft.body.as_ref().unwrap().len() < content.len());
+        assert_eq!(nft.decoded_body().unwrap().unwrap(), content.as_slice());
+
+        let plain = create_nft("text/plain", "plain");
+        assert_eq!(plain.decoded_body().unwrap().unwrap(), b"plain".as_slice());
+
+        let mut unsupported = plain;
+        unsupported.content_encoding = Some(b"deflate".to_vec());
+        assert!(matches!(
+            unsupported.decoded_body(),
+            Err(OrdError::ContentEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn nft_pointer() {
+        let nft = create_nft("text/plain", "Hello, world!");
+        assert_eq!(nft.pointer(), None);
+
+        let nft = nft.with_pointer(0x1234);
+        assert_eq!(nft.pointer, Some(vec![0x34, 0x12]));
+        assert_eq!(nft.pointer(), Some(0x1234));
+
+        let nft = nft.with_pointer(0);
+        assert_eq!(nft.pointer, Some(vec![]));
+        assert_eq!(nft.pointer(), Some(0));
+    }
+
     #[test]
     fn json_serialization_deserialization() {
         let nft = create_nft("text/plain", "Hello, world!");
@@ -414,6 +609,18 @@ mod tests {
         assert!(nft.is_err());
     }
 
+    #[test]
+    fn nft_media_type() {
+        let nft = create_nft("text/html;charset=utf-8", "<h1>Hello</h1>");
+        let media_type = nft.media_type().unwrap();
+        assert_eq!(media_type.essence(), "text/html");
+        assert_eq!(media_type.charset(), Some("utf-8"));
+        assert!(media_type.is_text());
+
+        assert!(create_nft("plain", "foo").media_type().is_none());
+        assert!(Nft::new(None, None).media_type().is_none());
+    }
+
     #[test]
     fn metadata_function_decodes_metadata() {
         assert_eq!(
@@ -427,6 +634,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn metadata_is_encoded_as_deterministic_cbor() {
+        use ciborium::Value;
+
+        let text = |text: &str| Value::Text(text.to_string());
+        let metadata = |entries: Vec<(Value, Value)>| {
+            Value::Map(vec![
+                (text("name"), text("ordinal")),
+                (text("attributes"), Value::Map(entries)),
+                (Value::Integer(10.into()), Value::Float(1.5)),
+            ])
+        };
+        let nft = Nft::default()
+            .with_metadata(&metadata(vec![
+                (text("b"), Value::Integer(1.into())),
+                (text("a"), Value::Integer(1_000.into())),
+            ]))
+            .unwrap();
+        let reordered = Nft::default()
+            .with_metadata(&metadata(vec![
+                (text("a"), Value::Integer(1_000.into())),
+                (text("b"), Value::Integer(1.into())),
+            ]))
+            .unwrap();
+        assert_eq!(nft.metadata, reordered.metadata);
+
+        // keys sorted by their encoding: 10, "name", "attributes", and the float as a half
+        assert_eq!(
+            nft.metadata.unwrap(),
+            [
+                &[0xa3, 0x0a, 0xf9, 0x3e, 0x00][..],
+                &[0x64, b'n', b'a', b'm', b'e', 0x67],
+                b"ordinal",
+                &[0x6a],
+                b"attributes",
+                &[0xa2, 0x61, b'a', 0x19, 0x03, 0xe8, 0x61, b'b', 0x01],
+            ]
+            .concat()
+        );
+        assert_eq!(
+            reordered.metadata(),
+            Some(Value::Map(vec![
+                (Value::Integer(10.into()), Value::Float(1.5)),
+                (text("name"), text("ordinal")),
+                (
+                    text("attributes"),
+                    Value::Map(vec![
+                        (text("a"), Value::Integer(1_000.into())),
+                        (text("b"), Value::Integer(1.into())),
+                    ])
+                ),
+            ]))
+        );
+
+        assert!(matches!(
+            Nft::default().with_metadata(&Value::Map(vec![
+                (text("a"), Value::Null),
+                (text("a"), Value::Bool(true)),
+            ])),
+            Err(OrdError::Metadata(_))
+        ));
+    }
+
     #[test]
     fn metadata_function_returns_none_if_no_metadata() {
         assert_eq!(
@@ -0,0 +1,228 @@
+//! Collection templates
+//!
+//! Renders the inscriptions of a collection, e.g. the 10k items of a PFP collection, from a
+//! manifest with a body and metadata template and the traits of each item, to be inscribed in
+//! batches with [`crate::wallet::CreateBatchCommitTransactionArgs`].
+//!
+//! Templates substitute:
+//!
+//! - `{index}` with the 0-based position of the item in the manifest;
+//! - `{id}` with the identifier of the item, its 1-based position if not set;
+//! - `{trait:<name>}` with the value of the trait `<name>` of the item.
+//!
+//! Any other text between braces is kept as is, so CSS or JSON bodies don't need escaping.
+
+use std::collections::BTreeMap;
+
+use crate::{MediaType, Nft, OrdError, OrdResult};
+
+/// Manifest of a collection, rendering an [`Nft`] per item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionManifest {
+    /// Content type of the items
+    pub content_type: String,
+    /// Template of the body of the items
+    pub body: String,
+    /// Template of the metadata of the items, whose string values are rendered, encoded as
+    /// deterministic CBOR
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    /// Items of the collection, in inscription order
+    pub items: Vec<CollectionItem>,
+}
+
+/// Item of a [`CollectionManifest`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionItem {
+    /// Identifier substituted for `{id}`, the 1-based position of the item if not set
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Traits substituted for `{trait:<name>}`
+    #[serde(default)]
+    pub traits: BTreeMap<String, String>,
+}
+
+impl CollectionManifest {
+    /// Renders the inscriptions of all the items, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::Template`] if an item lacks a trait used by the templates,
+    /// [`OrdError::InscriptionParser`] if the content type is malformed, or
+    /// [`OrdError::Metadata`] if the rendered metadata can't be encoded.
+    pub fn render(&self) -> OrdResult<Vec<Nft>> {
+        self.content_type.parse::<MediaType>()?;
+
+        (0..self.items.len())
+            .map(|index| self.render_item(index))
+            .collect()
+    }
+
+    /// Renders the inscription of the item at `index`.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::render`], or [`OrdError::Template`] if there's no item at `index`.
+    pub fn render_item(&self, index: usize) -> OrdResult<Nft> {
+        let item = self
+            .items
+            .get(index)
+            .ok_or_else(|| OrdError::Template(format!("no item at index {index}")))?;
+
+        let body = render_template(&self.body, index, item)?;
+        let nft = Nft::new(
+            Some(self.content_type.as_bytes().to_vec()),
+            Some(body.into_bytes()),
+        );
+        let Some(metadata) = &self.metadata else {
+            return Ok(nft);
+        };
+
+        let metadata = render_json(metadata, index, item)?;
+        let metadata = ciborium::Value::serialized(&metadata)
+            .map_err(|err| OrdError::Metadata(err.to_string()))?;
+        nft.with_metadata(&metadata)
+    }
+}
+
+/// Renders a template for the item at `index`, see the [module documentation](self).
+///
+/// # Errors
+///
+/// Returns [`OrdError::Template`] if the item lacks a trait used by the template.
+pub fn render_template(template: &str, index: usize, item: &CollectionItem) -> OrdResult<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..];
+        let Some(end) = placeholder.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let substitution = match &placeholder[..end] {
+            "index" => Some(index.to_string()),
+            "id" => Some(item.id.clone().unwrap_or_else(|| (index + 1).to_string())),
+            name => match name.strip_prefix("trait:") {
+                Some(name) => Some(item.traits.get(name).cloned().ok_or_else(|| {
+                    OrdError::Template(format!("item {index} has no trait {name:?}"))
+                })?),
+                None => None,
+            },
+        };
+        match substitution {
+            Some(substitution) => {
+                rendered.push_str(&substitution);
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = placeholder;
+            }
+        }
+    }
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+/// Renders the string values of a JSON template, keeping the keys as is.
+fn render_json(
+    template: &serde_json::Value,
+    index: usize,
+    item: &CollectionItem,
+) -> OrdResult<serde_json::Value> {
+    Ok(match template {
+        serde_json::Value::String(value) => {
+            serde_json::Value::String(render_template(value, index, item)?)
+        }
+        serde_json::Value::Array(values) => serde_json::Value::Array(
+            values
+                .iter()
+                .map(|value| render_json(value, index, item))
+                .collect::<OrdResult<_>>()?,
+        ),
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, value)| Ok((key.clone(), render_json(value, index, item)?)))
+                .collect::<OrdResult<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: Option<&str>, traits: &[(&str, &str)]) -> CollectionItem {
+        CollectionItem {
+            id: id.map(str::to_string),
+            traits: traits
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_should_render_templates() {
+        let item = item(None, &[("color", "red")]);
+        assert_eq!(
+            render_template(
+                "<svg>{index} #{id} <style>rect {fill: {trait:color}}</style>{",
+                9,
+                &item
+            )
+            .unwrap(),
+            "<svg>9 #10 <style>rect {fill: red}</style>{"
+        );
+        assert!(matches!(
+            render_template("{trait:size}", 0, &item),
+            Err(OrdError::Template(_))
+        ));
+    }
+
+    #[test]
+    fn test_should_render_collection() {
+        let manifest = CollectionManifest {
+            content_type: "text/plain;charset=utf-8".to_string(),
+            body: "{id}: {trait:hat}".to_string(),
+            metadata: Some(serde_json::json!({
+                "name": "Punk #{id}",
+                "attributes": [{"hat": "{trait:hat}"}],
+                "edition": 1,
+            })),
+            items: vec![
+                item(Some("alice"), &[("hat", "cap")]),
+                item(None, &[("hat", "crown")]),
+            ],
+        };
+
+        let nfts = manifest.render().unwrap();
+        assert_eq!(nfts.len(), 2);
+        assert_eq!(nfts[0].body(), Some("alice: cap"));
+        assert_eq!(nfts[1].body(), Some("2: crown"));
+
+        let metadata: ciborium::Value =
+            ciborium::from_reader(nfts[1].metadata.as_deref().unwrap()).unwrap();
+        let expected = ciborium::Value::serialized(&serde_json::json!({
+            "name": "Punk #2",
+            "attributes": [{"hat": "crown"}],
+            "edition": 1,
+        }))
+        .unwrap();
+        assert_eq!(
+            nfts[1].metadata,
+            Some(crate::encode_canonical_metadata(&expected).unwrap())
+        );
+        assert_eq!(metadata.as_map().unwrap().len(), 3);
+
+        let mut invalid = manifest;
+        invalid.items.push(CollectionItem::default());
+        assert!(matches!(invalid.render(), Err(OrdError::Template(_))));
+        invalid.content_type = "text".to_string();
+        assert!(invalid.render().is_err());
+    }
+}
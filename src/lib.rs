@@ -26,10 +26,7 @@
 //! ```rust
 //! use bitcoin::secp256k1::Secp256k1;
 //! use bitcoin::{Address, Amount, FeeRate, Network, PrivateKey, Txid};
-//! use ord_rs::wallet::{
-//!     CreateCommitTransactionArgs, RevealTransactionArgs, SignCommitTransactionArgs,
-//! };
-//! use ord_rs::{Brc20, OrdTransactionBuilder, Utxo};
+//! use ord_rs::prelude::*;
 //!
 //! use std::str::FromStr;
 //!
@@ -57,15 +54,13 @@
 //!     .build_commit_transaction(
 //!         network,
 //!         sender_address.clone(),
-//!         CreateCommitTransactionArgs {
-//!             fee_rate: FeeRate::from_sat_per_vb(1).unwrap(),
-//!             inputs: inputs.clone(),
-//!             inscription: Brc20::transfer(ticker, amount),
-//!             txin_script_pubkey: sender_address.script_pubkey(),
-//!             leftovers_recipient: sender_address.clone(),
-//!             derivation_path: None,
-//!             multisig_config: None,
-//!         },
+//!         CreateCommitTransactionArgs::new(
+//!             inputs.clone(),
+//!             Brc20::transfer(ticker, amount),
+//!             sender_address.clone(),
+//!             sender_address.script_pubkey(),
+//!             FeeRate::from_sat_per_vb(1).unwrap(),
+//!         ),
 //!     )
 //!     .await?;
 //!
@@ -84,16 +79,15 @@
 //!     // TODO: send commit_tx to the network
 //!
 //!     let reveal_transaction = builder
-//!         .build_reveal_transaction(RevealTransactionArgs {
-//!             input: ord_rs::wallet::Utxo {
+//!         .build_reveal_transaction(RevealTransactionArgs::new(
+//!             Utxo {
 //!                 id: commit_txid,
 //!                 index: 0,
 //!                 amount: commit_tx.reveal_balance,
 //!             },
-//!             recipient_address: sender_address, // NOTE: it's correct, see README.md to read about how transfer works
-//!             redeem_script: commit_tx.redeem_script,
-//!             derivation_path: None,
-//!         })
+//!             sender_address, // NOTE: it's correct, see README.md to read about how transfer works
+//!             commit_tx.redeem_script,
+//!         ))
 //!         .await?;
 //!
 //!     Ok(())
@@ -107,21 +101,49 @@ extern crate log;
 extern crate serde;
 
 pub use bitcoin;
-pub use error::{InscriptionParseError, OrdError};
-pub use inscription::brc20::Brc20;
+pub use error::{InputSigningError, InscriptionParseError, OrdError};
+pub use inscription::brc20::{
+    normalize_ticker, tickers_eq, Brc20, Brc20Balance, Brc20ContentType, Brc20Cost, Brc20Effect,
+    Brc20Inscription, Brc20Rejection, Brc20State, Brc20TickerInfo, Brc20ValidationError,
+    TickerResolver,
+};
+pub use inscription::encoding::ContentEncoding;
+pub use inscription::envelope::EnvelopeBuilder;
 pub use inscription::iid::InscriptionId;
-pub use inscription::nft::Nft;
+pub use inscription::media_type::MediaType;
+pub use inscription::nft::{encode_canonical_metadata, Nft};
+pub use inscription::template::{CollectionItem, CollectionManifest};
 pub use inscription::Inscription;
 pub use result::OrdResult;
-pub use utils::fees::{self, MultisigConfig};
-pub use utils::{constants, push_bytes};
+pub use utils::fees::{
+    self, amount_from_msat, amount_to_msat, discard_uneconomical_inputs, effective_value,
+    estimate_inscription_cost, estimate_reveal_weight, estimate_taproot_transaction_fees,
+    estimate_transaction_fees_for_prevouts, InscriptionCost, MultisigConfig, TaprootSpendProfile,
+};
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub use utils::test_utils;
+pub use utils::{constants, hash, push_bytes};
 pub use wallet::{
-    BtcTxSigner, CreateCommitTransaction, CreateCommitTransactionArgs, OrdParser,
-    OrdTransactionBuilder, RevealTransactionArgs, SignCommitTransactionArgs, Utxo, Wallet,
+    diff_transactions, BtcTxSigner, ChangePolicy, CreateCommitTransaction,
+    CreateCommitTransactionArgs, CreateCommitTransactionArgsV2, CreateMixedCommitTransactionArgs,
+    LocalSigner, OrdParser, OrdTransactionBuilder, ParentInfo, RevealTransactionArgs, RuneBalance,
+    ScriptType, SignCommitTransactionArgs, TxInputInfo, Utxo, Wallet,
 };
+#[cfg(feature = "rune")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+pub use wallet::{CreateEdictTxArgs, EtchingTransactionArgs, Runestone, RunestoneParser};
 
+#[cfg(feature = "esplora")]
+#[cfg_attr(docsrs, doc(cfg(feature = "esplora")))]
+pub mod client;
 mod error;
+#[cfg(feature = "ic")]
+mod ic;
 pub mod inscription;
+pub mod prelude;
+pub mod provider;
 mod result;
+pub mod sat;
 mod utils;
 pub mod wallet;
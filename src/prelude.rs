@@ -0,0 +1,24 @@
+//! Prelude
+//!
+//! Re-exports the types and traits needed by the standard flows: inscribing BRC-20 operations
+//! and NFTs with a commit and a reveal transaction, parsing inscriptions, and etching and
+//! transferring runes with the `rune` feature.
+//!
+//! ```
+//! use ord_rs::prelude::*;
+//! ```
+
+pub use crate::provider::{Broadcaster, ChainProvider, FeeProvider, FeeTarget, UtxoReservation};
+pub use crate::wallet::{
+    BtcTxSigner, ChangePolicy, CreateCommitTransaction, CreateCommitTransactionArgs,
+    CreateCommitTransactionArgsV2, CreateMixedCommitTransactionArgs, LocalSigner, OrdParser,
+    OrdTransactionBuilder, RevealTransactionArgs, RuneBalance, ScriptType,
+    SignCommitTransactionArgs, TxInputInfo, Utxo, Wallet,
+};
+#[cfg(feature = "rune")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+pub use crate::wallet::{CreateEdictTxArgs, EtchingTransactionArgs, Runestone, RunestoneParser};
+pub use crate::{
+    Brc20, Brc20ContentType, Inscription, InscriptionId, MultisigConfig, Nft, OrdError, OrdResult,
+    TickerResolver,
+};
@@ -0,0 +1,52 @@
+//! Provider
+//!
+//! Abstractions over the services backing the inscription workflows, e.g. a bitcoin node,
+//! an esplora instance or the bitcoin canister, so the same flows run in services and canisters.
+
+mod broadcast;
+mod confirmation;
+mod fee;
+mod reservation;
+
+use bitcoin::{BlockHash, Txid};
+
+pub use self::broadcast::{submit_package, Broadcaster, PackageBroadcast};
+pub use self::confirmation::{await_confirmations, ConfirmationStatus, ConfirmationWatch};
+pub use self::fee::{
+    fee_rate_for_target, FeeProvider, FeeTarget, InscriptionFeeRates, InscriptionFeeTargets,
+};
+pub use self::reservation::{select_and_reserve, InMemoryUtxoReservation, UtxoReservation};
+use crate::OrdResult;
+
+/// A block of the best chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BlockRef {
+    /// Height of the block
+    pub height: u64,
+    /// Hash of the block
+    pub hash: BlockHash,
+}
+
+/// Status of a transaction, as seen by a [`ChainProvider`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxStatus {
+    /// The transaction is neither in the mempool nor in the best chain
+    Unknown,
+    /// The transaction is in the mempool
+    Mempool,
+    /// The transaction is included in a block of the best chain
+    Confirmed(BlockRef),
+}
+
+/// Provides the state of the best chain.
+#[async_trait::async_trait]
+pub trait ChainProvider {
+    /// Returns the tip of the best chain.
+    async fn tip(&self) -> OrdResult<BlockRef>;
+
+    /// Returns the status of the transaction.
+    async fn transaction_status(&self, txid: &Txid) -> OrdResult<TxStatus>;
+
+    /// Waits before the chain is polled again, e.g. until a new block is likely to be mined.
+    async fn wait_for_block(&self) -> OrdResult<()>;
+}
@@ -0,0 +1,175 @@
+use bitcoin::{Transaction, Txid};
+
+use crate::{OrdError, OrdResult};
+
+/// How a commit and reveal pair was broadcast by [`submit_package`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PackageBroadcast {
+    /// The pair was submitted as a package, e.g. with bitcoind's `submitpackage`, so the
+    /// reveal can pay for a commit below the mempool minimum fee
+    Package,
+    /// The broadcaster doesn't support package relay, so the commit was broadcast before
+    /// the reveal
+    Sequential,
+}
+
+/// Broadcasts transactions to the network, e.g. through a bitcoin node or an esplora instance.
+#[async_trait::async_trait]
+pub trait Broadcaster {
+    /// Broadcasts the transaction and returns its txid.
+    async fn broadcast(&self, tx: &Transaction) -> OrdResult<Txid>;
+
+    /// Submits a package of transactions, sorted parents first, to be accepted into the
+    /// mempool together, e.g. with bitcoind's `submitpackage`.
+    ///
+    /// The default implementation returns [`OrdError::PackageRelayUnsupported`].
+    async fn submit_package(&self, txs: &[Transaction]) -> OrdResult<Vec<Txid>> {
+        let _ = txs;
+        Err(OrdError::PackageRelayUnsupported)
+    }
+}
+
+/// Broadcasts a commit transaction together with the reveal transaction spending it, so the
+/// reveal doesn't have to wait for the commit to be relayed, or confirmed, to be broadcast.
+///
+/// The pair is submitted as a package when the broadcaster supports it, otherwise the commit
+/// is broadcast first and the reveal right after it.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidPackage`] if the reveal doesn't spend the commit, or any error
+/// of the broadcaster.
+pub async fn submit_package<B>(
+    commit: &Transaction,
+    reveal: &Transaction,
+    broadcaster: &B,
+) -> OrdResult<PackageBroadcast>
+where
+    B: Broadcaster + Sync + ?Sized,
+{
+    let commit_txid = commit.txid();
+    if !reveal
+        .input
+        .iter()
+        .any(|input| input.previous_output.txid == commit_txid)
+    {
+        return Err(OrdError::InvalidPackage(
+            "the reveal transaction doesn't spend the commit transaction",
+        ));
+    }
+
+    match broadcaster
+        .submit_package(&[commit.clone(), reveal.clone()])
+        .await
+    {
+        Ok(_) => return Ok(PackageBroadcast::Package),
+        Err(OrdError::PackageRelayUnsupported) => {
+            debug!("package relay unsupported, broadcasting {commit_txid} before its reveal");
+        }
+        Err(err) => return Err(err),
+    }
+
+    broadcaster.broadcast(commit).await?;
+    broadcaster.broadcast(reveal).await?;
+
+    Ok(PackageBroadcast::Sequential)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, OutPoint, ScriptBuf, Sequence, TxIn, TxOut, Witness};
+
+    use super::*;
+
+    /// Records the transactions it broadcasts.
+    struct RecordingBroadcaster {
+        package_relay: bool,
+        broadcast: Mutex<Vec<Vec<Txid>>>,
+    }
+
+    impl RecordingBroadcaster {
+        fn new(package_relay: bool) -> Self {
+            Self {
+                package_relay,
+                broadcast: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Broadcaster for RecordingBroadcaster {
+        async fn broadcast(&self, tx: &Transaction) -> OrdResult<Txid> {
+            self.broadcast.lock().unwrap().push(vec![tx.txid()]);
+            Ok(tx.txid())
+        }
+
+        async fn submit_package(&self, txs: &[Transaction]) -> OrdResult<Vec<Txid>> {
+            if !self.package_relay {
+                return Err(OrdError::PackageRelayUnsupported);
+            }
+            let txids = txs.iter().map(Transaction::txid).collect::<Vec<_>>();
+            self.broadcast.lock().unwrap().push(txids.clone());
+            Ok(txids)
+        }
+    }
+
+    fn spending(previous_output: OutPoint) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(1_000),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_submit_commit_and_reveal_as_package() {
+        let commit = spending(OutPoint::null());
+        let reveal = spending(OutPoint::new(commit.txid(), 0));
+
+        let broadcaster = RecordingBroadcaster::new(true);
+        let broadcast = submit_package(&commit, &reveal, &broadcaster)
+            .await
+            .unwrap();
+        assert_eq!(broadcast, PackageBroadcast::Package);
+        assert_eq!(
+            broadcaster.broadcast.into_inner().unwrap(),
+            vec![vec![commit.txid(), reveal.txid()]]
+        );
+
+        let broadcaster = RecordingBroadcaster::new(false);
+        let broadcast = submit_package(&commit, &reveal, &broadcaster)
+            .await
+            .unwrap();
+        assert_eq!(broadcast, PackageBroadcast::Sequential);
+        assert_eq!(
+            broadcaster.broadcast.into_inner().unwrap(),
+            vec![vec![commit.txid()], vec![reveal.txid()]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_reject_unrelated_reveal() {
+        let commit = spending(OutPoint::null());
+        let reveal = spending(OutPoint::null());
+
+        let broadcaster = RecordingBroadcaster::new(true);
+        let err = submit_package(&commit, &reveal, &broadcaster)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, OrdError::InvalidPackage(_)));
+        assert!(broadcaster.broadcast.into_inner().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+use bitcoin::Txid;
+
+use super::{BlockRef, ChainProvider, TxStatus};
+use crate::{OrdError, OrdResult};
+
+/// Progress of a transaction towards the required confirmations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfirmationStatus {
+    /// The transaction is in the mempool
+    Unconfirmed,
+    /// The transaction is confirmed, but not deep enough yet
+    Confirming {
+        /// Current number of confirmations
+        confirmations: u32,
+    },
+    /// The transaction has the required confirmations
+    Confirmed {
+        /// Block including the transaction
+        block: BlockRef,
+        /// Current number of confirmations
+        confirmations: u32,
+    },
+}
+
+/// Watches a transaction until it gets the required confirmations, detecting reorgs.
+///
+/// The watch is serializable, so it can be persisted between polls and resumed, e.g. after a
+/// service restart or across canister calls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfirmationWatch {
+    /// The watched transaction
+    pub txid: Txid,
+    /// Number of confirmations required
+    pub required: u32,
+    /// Block including the transaction when it was last polled
+    pub confirmed_in: Option<BlockRef>,
+    /// Number of reorgs which removed the transaction from the block it was confirmed in
+    pub reorgs: u32,
+}
+
+impl ConfirmationWatch {
+    /// Creates a watch for a broadcast transaction.
+    pub fn new(txid: Txid, required: u32) -> Self {
+        Self {
+            txid,
+            required,
+            confirmed_in: None,
+            reorgs: 0,
+        }
+    }
+
+    /// Polls the provider once, updating the watch.
+    ///
+    /// A reorg is detected when the transaction is no longer included in the block it was
+    /// confirmed in, in which case the confirmations start over.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::TransactionDropped`] if the transaction is neither in the mempool nor
+    /// in the best chain, e.g. if it was evicted or double spent after a reorg.
+    pub async fn poll<P>(&mut self, provider: &P) -> OrdResult<ConfirmationStatus>
+    where
+        P: ChainProvider + ?Sized,
+    {
+        let confirmed_in = match provider.transaction_status(&self.txid).await? {
+            TxStatus::Unknown => return Err(OrdError::TransactionDropped(self.txid)),
+            TxStatus::Mempool => None,
+            TxStatus::Confirmed(block) => Some(block),
+        };
+        if self.confirmed_in.is_some() && self.confirmed_in != confirmed_in {
+            debug!(
+                "transaction {} was reorged out of {:?}",
+                self.txid, self.confirmed_in
+            );
+            self.reorgs += 1;
+        }
+        self.confirmed_in = confirmed_in;
+
+        let Some(block) = confirmed_in else {
+            return Ok(ConfirmationStatus::Unconfirmed);
+        };
+        let tip = provider.tip().await?;
+        let confirmations = tip
+            .height
+            .saturating_sub(block.height)
+            .saturating_add(1)
+            .try_into()
+            .unwrap_or(u32::MAX);
+
+        if confirmations >= self.required {
+            Ok(ConfirmationStatus::Confirmed {
+                block,
+                confirmations,
+            })
+        } else {
+            Ok(ConfirmationStatus::Confirming { confirmations })
+        }
+    }
+
+    /// Polls the provider until the transaction has the required confirmations, waiting for
+    /// new blocks in between, and returns the block including the transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of [`Self::poll`] or of the provider.
+    pub async fn wait<P>(&mut self, provider: &P) -> OrdResult<BlockRef>
+    where
+        P: ChainProvider + ?Sized,
+    {
+        loop {
+            if let ConfirmationStatus::Confirmed { block, .. } = self.poll(provider).await? {
+                return Ok(block);
+            }
+            provider.wait_for_block().await?;
+        }
+    }
+}
+
+/// Waits until the transaction has the given number of confirmations, e.g. before revealing
+/// an inscription whose commit must be buried, and returns the block including it.
+///
+/// Use a [`ConfirmationWatch`] directly to persist the progress and resume waiting later.
+pub async fn await_confirmations<P>(
+    txid: Txid,
+    confirmations: u32,
+    provider: &P,
+) -> OrdResult<BlockRef>
+where
+    P: ChainProvider + ?Sized,
+{
+    ConfirmationWatch::new(txid, confirmations)
+        .wait(provider)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::BlockHash;
+
+    use super::*;
+
+    /// Replays a sequence of chain states, one per poll.
+    struct ScriptedProvider {
+        states: Mutex<VecDeque<(u64, TxStatus)>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(states: impl IntoIterator<Item = (u64, TxStatus)>) -> Self {
+            Self {
+                states: Mutex::new(states.into_iter().collect()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ChainProvider for ScriptedProvider {
+        async fn tip(&self) -> OrdResult<BlockRef> {
+            let height = self.states.lock().unwrap().front().unwrap().0;
+            Ok(block(height, 0))
+        }
+
+        async fn transaction_status(&self, _txid: &Txid) -> OrdResult<TxStatus> {
+            Ok(self.states.lock().unwrap().front().unwrap().1)
+        }
+
+        async fn wait_for_block(&self) -> OrdResult<()> {
+            self.states.lock().unwrap().pop_front();
+            Ok(())
+        }
+    }
+
+    fn block(height: u64, fork: u8) -> BlockRef {
+        let mut hash = [fork; 32];
+        hash[..8].copy_from_slice(&height.to_le_bytes());
+        BlockRef {
+            height,
+            hash: BlockHash::from_byte_array(hash),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_await_confirmations() {
+        let provider = ScriptedProvider::new([
+            (100, TxStatus::Mempool),
+            (101, TxStatus::Confirmed(block(101, 0))),
+            (102, TxStatus::Confirmed(block(101, 0))),
+            (103, TxStatus::Confirmed(block(101, 0))),
+        ]);
+
+        let confirmed_in = await_confirmations(Txid::all_zeros(), 3, &provider)
+            .await
+            .unwrap();
+        assert_eq!(confirmed_in, block(101, 0));
+    }
+
+    #[tokio::test]
+    async fn test_should_restart_confirmations_after_reorg() {
+        let provider = ScriptedProvider::new([
+            (101, TxStatus::Confirmed(block(101, 0))),
+            (102, TxStatus::Confirmed(block(102, 1))),
+            (103, TxStatus::Mempool),
+        ]);
+        let mut watch = ConfirmationWatch::new(Txid::all_zeros(), 2);
+
+        assert_eq!(
+            watch.poll(&provider).await.unwrap(),
+            ConfirmationStatus::Confirming { confirmations: 1 }
+        );
+        provider.wait_for_block().await.unwrap();
+
+        // the block including the transaction was replaced
+        assert_eq!(
+            watch.poll(&provider).await.unwrap(),
+            ConfirmationStatus::Confirming { confirmations: 1 }
+        );
+        assert_eq!(watch.reorgs, 1);
+        assert_eq!(watch.confirmed_in, Some(block(102, 1)));
+        provider.wait_for_block().await.unwrap();
+
+        // the watch can be persisted and resumed
+        let mut watch: ConfirmationWatch =
+            serde_json::from_str(&serde_json::to_string(&watch).unwrap()).unwrap();
+        assert_eq!(
+            watch.poll(&provider).await.unwrap(),
+            ConfirmationStatus::Unconfirmed
+        );
+        assert_eq!(watch.reorgs, 2);
+    }
+
+    #[tokio::test]
+    async fn test_should_fail_on_dropped_transaction() {
+        let provider = ScriptedProvider::new([(100, TxStatus::Mempool), (101, TxStatus::Unknown)]);
+
+        let result = await_confirmations(Txid::all_zeros(), 1, &provider).await;
+        assert!(matches!(result, Err(OrdError::TransactionDropped(_))));
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+
+use bitcoin::FeeRate;
+
+use crate::{OrdError, OrdResult};
+
+/// Confirmation target of a transaction, mapped to a fee rate by a [`FeeProvider`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FeeTarget {
+    /// Confirmation in the next block
+    NextBlock,
+    /// Confirmation within the given number of blocks
+    Blocks(u16),
+    /// Confirmation within about a day, at the lowest fee rate
+    Economical,
+}
+
+impl FeeTarget {
+    /// Number of blocks the economical target confirms within.
+    pub const ECONOMICAL_BLOCKS: u16 = 144;
+
+    /// Returns the number of blocks the transaction should be confirmed within.
+    pub fn blocks(&self) -> u16 {
+        match self {
+            Self::NextBlock => 1,
+            Self::Blocks(blocks) => (*blocks).max(1),
+            Self::Economical => Self::ECONOMICAL_BLOCKS,
+        }
+    }
+}
+
+/// Provides fee rate estimates, e.g. from the `estimatesmartfee` RPC of a bitcoin node, the
+/// fee estimates of an esplora instance or the fee percentiles of the bitcoin canister.
+#[async_trait::async_trait]
+pub trait FeeProvider {
+    /// Returns the fee rate estimates, keyed by the number of blocks the transaction is
+    /// confirmed within.
+    async fn fee_estimates(&self) -> OrdResult<BTreeMap<u16, FeeRate>>;
+
+    /// Returns the fee rate of the target, as mapped by [`fee_rate_for_target`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::NoFeeEstimate`] if the provider has no estimate.
+    async fn fee_rate(&self, target: FeeTarget) -> OrdResult<FeeRate> {
+        fee_rate_for_target(&self.fee_estimates().await?, target)
+    }
+}
+
+/// Maps the fee rate estimates, keyed by the number of blocks the transaction is confirmed
+/// within, to the fee rate of the target.
+///
+/// The estimate of the largest number of blocks within the target is used, or the fastest
+/// estimate if none is. The fee rate is never lower than the minimum relay fee rate.
+///
+/// # Errors
+///
+/// Returns [`OrdError::NoFeeEstimate`] if there are no estimates.
+pub fn fee_rate_for_target(
+    estimates: &BTreeMap<u16, FeeRate>,
+    target: FeeTarget,
+) -> OrdResult<FeeRate> {
+    let fee_rate = estimates
+        .range(..=target.blocks())
+        .next_back()
+        .or_else(|| estimates.iter().next())
+        .map(|(_, fee_rate)| *fee_rate)
+        .ok_or(OrdError::NoFeeEstimate)?;
+
+    Ok(fee_rate.max(FeeRate::BROADCAST_MIN))
+}
+
+/// Confirmation targets of the commit and reveal transactions of an inscription.
+///
+/// They're chosen independently: the reveal transaction usually targets a faster confirmation,
+/// as its inscription can be front-run once its witness is in the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InscriptionFeeTargets {
+    /// Confirmation target of the commit transaction
+    pub commit: FeeTarget,
+    /// Confirmation target of the reveal transaction
+    pub reveal: FeeTarget,
+}
+
+impl Default for InscriptionFeeTargets {
+    fn default() -> Self {
+        Self {
+            commit: FeeTarget::Blocks(6),
+            reveal: FeeTarget::NextBlock,
+        }
+    }
+}
+
+impl InscriptionFeeTargets {
+    /// Returns the fee rates of the targets, from a single query of the provider.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::NoFeeEstimate`] if the provider has no estimate.
+    pub async fn fee_rates<P>(&self, provider: &P) -> OrdResult<InscriptionFeeRates>
+    where
+        P: FeeProvider + Sync + ?Sized,
+    {
+        let estimates = provider.fee_estimates().await?;
+
+        Ok(InscriptionFeeRates {
+            commit: fee_rate_for_target(&estimates, self.commit)?,
+            reveal: fee_rate_for_target(&estimates, self.reveal)?,
+        })
+    }
+}
+
+/// Fee rates of the commit and reveal transactions of an inscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InscriptionFeeRates {
+    /// Fee rate of the commit transaction
+    pub commit: FeeRate,
+    /// Fee rate of the reveal transaction
+    pub reveal: FeeRate,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticFeeProvider(BTreeMap<u16, FeeRate>);
+
+    #[async_trait::async_trait]
+    impl FeeProvider for StaticFeeProvider {
+        async fn fee_estimates(&self) -> OrdResult<BTreeMap<u16, FeeRate>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn sat_per_vb(sats: u64) -> FeeRate {
+        FeeRate::from_sat_per_vb(sats).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_should_map_fee_targets_to_estimates() {
+        let provider = StaticFeeProvider(BTreeMap::from([
+            (2, sat_per_vb(40)),
+            (6, sat_per_vb(20)),
+            (144, sat_per_vb(5)),
+            (504, sat_per_vb(2)),
+        ]));
+
+        // no estimate within a block: the fastest one is used
+        assert_eq!(
+            provider.fee_rate(FeeTarget::NextBlock).await.unwrap(),
+            sat_per_vb(40)
+        );
+        assert_eq!(
+            provider.fee_rate(FeeTarget::Blocks(3)).await.unwrap(),
+            sat_per_vb(40)
+        );
+        assert_eq!(
+            provider.fee_rate(FeeTarget::Blocks(10)).await.unwrap(),
+            sat_per_vb(20)
+        );
+        assert_eq!(
+            provider.fee_rate(FeeTarget::Economical).await.unwrap(),
+            sat_per_vb(5)
+        );
+
+        let fee_rates = InscriptionFeeTargets::default()
+            .fee_rates(&provider)
+            .await
+            .unwrap();
+        assert_eq!(
+            fee_rates,
+            InscriptionFeeRates {
+                commit: sat_per_vb(20),
+                reveal: sat_per_vb(40),
+            }
+        );
+    }
+
+    #[test]
+    fn test_should_floor_fee_rate_and_fail_without_estimates() {
+        let estimates = BTreeMap::from([(1, FeeRate::from_sat_per_kwu(100))]);
+        assert_eq!(
+            fee_rate_for_target(&estimates, FeeTarget::Economical).unwrap(),
+            FeeRate::BROADCAST_MIN
+        );
+
+        assert!(matches!(
+            fee_rate_for_target(&BTreeMap::new(), FeeTarget::NextBlock),
+            Err(OrdError::NoFeeEstimate)
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use bitcoin::{Amount, OutPoint};
+
+use crate::wallet::utxo_selector::{UtxoCandidate, UtxoSelection, UtxoSelector};
+use crate::{OrdError, OrdResult};
+
+/// Reserves UTXOs while the transactions spending them are built and broadcast, so concurrent
+/// builds from the same UTXO set, e.g. in a canister, don't pick the same inputs.
+///
+/// Reservations expire, so the UTXOs of a build which failed before releasing them can be
+/// spent again. Times are given in seconds by the caller, as canisters have no system clock.
+#[async_trait::async_trait]
+pub trait UtxoReservation {
+    /// Reserves all the outpoints until `expires_at`, or none of them if any is already
+    /// reserved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::UtxoReserved`] with the first outpoint reserved by another build.
+    async fn reserve(&self, outpoints: &[OutPoint], now: u64, expires_at: u64) -> OrdResult<()>;
+
+    /// Releases the reservations of the outpoints, e.g. once the transaction spending them is
+    /// broadcast or its build failed.
+    async fn release(&self, outpoints: &[OutPoint]) -> OrdResult<()>;
+
+    /// Drops the reservations expired at `now` and returns their outpoints.
+    async fn expire(&self, now: u64) -> OrdResult<Vec<OutPoint>>;
+
+    /// Returns whether the outpoint is reserved at `now`.
+    async fn is_reserved(&self, outpoint: &OutPoint, now: u64) -> OrdResult<bool>;
+}
+
+/// [`UtxoReservation`] keeping the reservations in memory, e.g. in the heap of a canister.
+#[derive(Debug, Default)]
+pub struct InMemoryUtxoReservation {
+    /// Expiration time of the reservation of each outpoint
+    reservations: Mutex<HashMap<OutPoint, u64>>,
+}
+
+impl InMemoryUtxoReservation {
+    /// Creates a reservation set without reservations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn reservations(&self) -> std::sync::MutexGuard<'_, HashMap<OutPoint, u64>> {
+        // reservations are only updated once checked, so they're consistent even if a thread
+        // panicked while holding the lock
+        self.reservations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait::async_trait]
+impl UtxoReservation for InMemoryUtxoReservation {
+    async fn reserve(&self, outpoints: &[OutPoint], now: u64, expires_at: u64) -> OrdResult<()> {
+        let mut reservations = self.reservations();
+        if let Some(outpoint) = outpoints.iter().find(|outpoint| {
+            reservations
+                .get(outpoint)
+                .is_some_and(|expiration| now < *expiration)
+        }) {
+            return Err(OrdError::UtxoReserved(*outpoint));
+        }
+
+        reservations.extend(outpoints.iter().map(|outpoint| (*outpoint, expires_at)));
+        Ok(())
+    }
+
+    async fn release(&self, outpoints: &[OutPoint]) -> OrdResult<()> {
+        let mut reservations = self.reservations();
+        for outpoint in outpoints {
+            reservations.remove(outpoint);
+        }
+        Ok(())
+    }
+
+    async fn expire(&self, now: u64) -> OrdResult<Vec<OutPoint>> {
+        let mut reservations = self.reservations();
+        let expired = reservations
+            .iter()
+            .filter(|(_, expiration)| now >= **expiration)
+            .map(|(outpoint, _)| *outpoint)
+            .collect::<Vec<_>>();
+        for outpoint in &expired {
+            reservations.remove(outpoint);
+        }
+        Ok(expired)
+    }
+
+    async fn is_reserved(&self, outpoint: &OutPoint, now: u64) -> OrdResult<bool> {
+        Ok(self
+            .reservations()
+            .get(outpoint)
+            .is_some_and(|expiration| now < *expiration))
+    }
+}
+
+/// Selects the inputs of a transaction among the candidates which are not reserved, and
+/// reserves them for `ttl` seconds.
+///
+/// If another build reserves a selected UTXO in the meantime, the selection is retried without
+/// it. The inputs must be released once the transaction is broadcast, or its build failed.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InsufficientBalance`] if the unreserved candidates don't cover the
+/// target, or any error of the reservation.
+pub async fn select_and_reserve<R>(
+    reservation: &R,
+    selector: &UtxoSelector,
+    candidates: &[UtxoCandidate],
+    target: Amount,
+    now: u64,
+    ttl: u64,
+) -> OrdResult<UtxoSelection>
+where
+    R: UtxoReservation + Sync + ?Sized,
+{
+    let mut available = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        let outpoint = OutPoint::new(candidate.utxo.id, candidate.utxo.index);
+        if !reservation.is_reserved(&outpoint, now).await? {
+            available.push(candidate.clone());
+        }
+    }
+
+    loop {
+        let selection = selector.select(&available, target)?;
+        let outpoints = selection
+            .inputs
+            .iter()
+            .map(|utxo| OutPoint::new(utxo.id, utxo.index))
+            .collect::<Vec<_>>();
+        match reservation
+            .reserve(&outpoints, now, now.saturating_add(ttl))
+            .await
+        {
+            Ok(()) => return Ok(selection),
+            Err(OrdError::UtxoReserved(reserved)) => {
+                debug!("UTXO {reserved} was reserved concurrently, selecting again");
+                available.retain(|candidate| {
+                    OutPoint::new(candidate.utxo.id, candidate.utxo.index) != reserved
+                });
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use bitcoin::{FeeRate, ScriptBuf, Txid};
+
+    use super::*;
+    use crate::wallet::utxo_selector::SelectionStrategy;
+    use crate::wallet::Utxo;
+
+    fn outpoint(vout: u32) -> OutPoint {
+        OutPoint::new(
+            Txid::from_str("9100acad2da80d2198b257acc5d98a6265fda510bc8f1252334876dad4c289f4")
+                .unwrap(),
+            vout,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_should_reserve_release_and_expire_utxos() {
+        let reservation = InMemoryUtxoReservation::new();
+
+        reservation
+            .reserve(&[outpoint(0), outpoint(1)], 100, 160)
+            .await
+            .unwrap();
+        assert!(reservation.is_reserved(&outpoint(0), 159).await.unwrap());
+        assert!(!reservation.is_reserved(&outpoint(0), 160).await.unwrap());
+
+        // all or nothing
+        assert!(matches!(
+            reservation.reserve(&[outpoint(2), outpoint(1)], 120, 180).await,
+            Err(OrdError::UtxoReserved(reserved)) if reserved == outpoint(1)
+        ));
+        assert!(!reservation.is_reserved(&outpoint(2), 120).await.unwrap());
+
+        reservation.release(&[outpoint(1)]).await.unwrap();
+        reservation
+            .reserve(&[outpoint(2), outpoint(1)], 120, 200)
+            .await
+            .unwrap();
+
+        assert_eq!(reservation.expire(170).await.unwrap(), vec![outpoint(0)]);
+        assert!(reservation.expire(170).await.unwrap().is_empty());
+        // expired reservations can be taken over
+        reservation.reserve(&[outpoint(1)], 200, 260).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_should_not_select_reserved_utxos() {
+        let reservation = InMemoryUtxoReservation::new();
+        let script_pubkey = ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_raw_hash(
+            bitcoin::hashes::Hash::all_zeros(),
+        ));
+        let selector = UtxoSelector::new(
+            SelectionStrategy::LargestFirst,
+            &script_pubkey,
+            &script_pubkey,
+            FeeRate::from_sat_per_vb(1).unwrap(),
+        )
+        .unwrap();
+        let candidates = [50_000, 20_000, 10_000]
+            .into_iter()
+            .enumerate()
+            .map(|(vout, sats)| {
+                UtxoCandidate::from(Utxo {
+                    id: outpoint(0).txid,
+                    index: vout as u32,
+                    amount: Amount::from_sat(sats),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let first = select_and_reserve(
+            &reservation,
+            &selector,
+            &candidates,
+            Amount::from_sat(15_000),
+            100,
+            60,
+        )
+        .await
+        .unwrap();
+        let second = select_and_reserve(
+            &reservation,
+            &selector,
+            &candidates,
+            Amount::from_sat(15_000),
+            100,
+            60,
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.inputs, vec![candidates[0].utxo.clone()]);
+        assert_eq!(
+            second.inputs,
+            vec![candidates[1].utxo.clone()],
+            "the first UTXO is reserved"
+        );
+
+        assert!(matches!(
+            select_and_reserve(
+                &reservation,
+                &selector,
+                &candidates,
+                Amount::from_sat(15_000),
+                100,
+                60,
+            )
+            .await,
+            Err(OrdError::InsufficientBalance { .. })
+        ));
+
+        // once expired, the UTXOs can be selected again
+        let third = select_and_reserve(
+            &reservation,
+            &selector,
+            &candidates,
+            Amount::from_sat(15_000),
+            160,
+            60,
+        )
+        .await
+        .unwrap();
+        assert_eq!(third.inputs, first.inputs);
+    }
+}
@@ -0,0 +1,270 @@
+//! Sat
+//!
+//! Ordinal numbering of sats as defined by [Ordinal Theory](https://docs.ordinals.com/overview.html),
+//! used to locate the rare sats held by an UTXO, so they are not spent as fees by mistake.
+//!
+//! Sats are numbered in the order they are mined. The first sat of each block is uncommon, the
+//! first sat of each difficulty adjustment period is rare, the first sat of each halving epoch
+//! is epic and the first sat of each cycle (6 halvings) is legendary.
+
+use std::fmt;
+use std::ops::Range;
+
+/// Number of sats in a bitcoin.
+pub const COIN_VALUE: u64 = 100_000_000;
+/// Number of blocks between two halvings.
+pub const SUBSIDY_HALVING_INTERVAL: u32 = 210_000;
+/// Number of blocks between two difficulty adjustments.
+pub const DIFFCHANGE_INTERVAL: u32 = 2016;
+/// Number of halving epochs in a cycle.
+pub const CYCLE_EPOCHS: u32 = 6;
+
+/// An ordinal number of a sat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Sat(pub u64);
+
+/// A halving epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Epoch(pub u32);
+
+/// Rarity of a sat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+    Mythic,
+}
+
+impl Epoch {
+    /// First epoch without any block subsidy.
+    pub const FIRST_POST_SUBSIDY: Epoch = Epoch(33);
+
+    /// Block subsidy of the epoch, in sats.
+    pub fn subsidy(self) -> u64 {
+        if self < Self::FIRST_POST_SUBSIDY {
+            (50 * COIN_VALUE) >> self.0
+        } else {
+            0
+        }
+    }
+
+    /// Height of the first block of the epoch.
+    pub fn starting_height(self) -> u32 {
+        self.0.saturating_mul(SUBSIDY_HALVING_INTERVAL)
+    }
+
+    /// First sat mined in the epoch.
+    pub fn starting_sat(self) -> Sat {
+        let epochs = self.0.min(Self::FIRST_POST_SUBSIDY.0);
+        Sat((0..epochs)
+            .map(|epoch| Epoch(epoch).subsidy() * u64::from(SUBSIDY_HALVING_INTERVAL))
+            .sum())
+    }
+
+    /// Returns the epoch of the block at the given height.
+    pub fn from_height(height: u32) -> Self {
+        Self(height / SUBSIDY_HALVING_INTERVAL)
+    }
+}
+
+impl From<Sat> for Epoch {
+    fn from(sat: Sat) -> Self {
+        let mut epoch = Epoch(0);
+        while epoch < Self::FIRST_POST_SUBSIDY && Epoch(epoch.0 + 1).starting_sat() <= sat {
+            epoch.0 += 1;
+        }
+        epoch
+    }
+}
+
+impl Sat {
+    /// Total number of sats that will ever be mined.
+    pub const SUPPLY: u64 = 2_099_999_997_690_000;
+    /// Last sat that will ever be mined.
+    pub const LAST: Sat = Sat(Self::SUPPLY - 1);
+
+    /// Returns the ordinal number of the sat.
+    pub fn n(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the first sat mined in the block at the given height.
+    pub fn first_of_block(height: u32) -> Self {
+        let epoch = Epoch::from_height(height);
+        Sat(epoch.starting_sat().0 + u64::from(height - epoch.starting_height()) * epoch.subsidy())
+    }
+
+    /// Returns the halving epoch in which the sat was mined.
+    pub fn epoch(self) -> Epoch {
+        self.into()
+    }
+
+    /// Returns the height of the block in which the sat was mined.
+    ///
+    /// Sats past [`Sat::LAST`] are never mined, so they're counted as the last sat.
+    pub fn height(self) -> u32 {
+        let sat = self.min(Self::LAST);
+        let epoch = sat.epoch();
+        let position = (sat.0 - epoch.starting_sat().0) / epoch.subsidy();
+        epoch.starting_height() + position as u32
+    }
+
+    /// Returns the cycle (6 halving epochs) in which the sat was mined.
+    pub fn cycle(self) -> u32 {
+        self.epoch().0 / CYCLE_EPOCHS
+    }
+
+    /// Returns the difficulty adjustment period in which the sat was mined.
+    pub fn period(self) -> u32 {
+        self.height() / DIFFCHANGE_INTERVAL
+    }
+
+    /// Returns the offset of the sat within the subsidy of its block.
+    ///
+    /// Sats past [`Sat::LAST`] are never mined, so they're counted as the last sat.
+    pub fn third(self) -> u64 {
+        let sat = self.min(Self::LAST);
+        let epoch = sat.epoch();
+        (sat.0 - epoch.starting_sat().0) % epoch.subsidy()
+    }
+
+    /// Returns the rarity of the sat.
+    pub fn rarity(self) -> Rarity {
+        let height = self.height();
+
+        if self.0 == 0 {
+            Rarity::Mythic
+        } else if self.third() != 0 {
+            Rarity::Common
+        } else if height.is_multiple_of(SUBSIDY_HALVING_INTERVAL * CYCLE_EPOCHS) {
+            Rarity::Legendary
+        } else if height.is_multiple_of(SUBSIDY_HALVING_INTERVAL) {
+            Rarity::Epic
+        } else if height.is_multiple_of(DIFFCHANGE_INTERVAL) {
+            Rarity::Rare
+        } else {
+            Rarity::Uncommon
+        }
+    }
+
+    /// Returns whether the sat is rarer than common.
+    pub fn is_rare(self) -> bool {
+        self.rarity() > Rarity::Common
+    }
+}
+
+impl fmt::Display for Sat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for Rarity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rarity = match self {
+            Self::Common => "common",
+            Self::Uncommon => "uncommon",
+            Self::Rare => "rare",
+            Self::Epic => "epic",
+            Self::Legendary => "legendary",
+            Self::Mythic => "mythic",
+        };
+        f.write_str(rarity)
+    }
+}
+
+/// Returns the sats rarer than common in the given range of sats, e.g. the sat range of an UTXO.
+///
+/// Only the first sat of a block can be rare, so the range is walked block by block.
+pub fn rare_sats_in_range(range: Range<Sat>) -> Vec<Sat> {
+    let mut rare_sats = Vec::new();
+    if range.start >= range.end || range.start > Sat::LAST {
+        return rare_sats;
+    }
+
+    let mut height = range.start.height();
+    if range.start.third() != 0 {
+        height += 1;
+    }
+
+    loop {
+        let sat = Sat::first_of_block(height);
+        if sat >= range.end || Epoch::from_height(height) >= Epoch::FIRST_POST_SUBSIDY {
+            break;
+        }
+        rare_sats.push(sat);
+        height += 1;
+    }
+
+    rare_sats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch() {
+        assert_eq!(Epoch(0).subsidy(), 50 * COIN_VALUE);
+        assert_eq!(Epoch(32).subsidy(), 1);
+        assert_eq!(Epoch(33).subsidy(), 0);
+        assert_eq!(Epoch(1).starting_sat(), Sat(1_050_000_000_000_000));
+        assert_eq!(Epoch(33).starting_sat(), Sat(Sat::SUPPLY));
+        assert_eq!(Sat(0).epoch(), Epoch(0));
+        assert_eq!(Sat(1_050_000_000_000_000).epoch(), Epoch(1));
+        assert_eq!(Sat::LAST.epoch(), Epoch(32));
+    }
+
+    #[test]
+    fn test_height() {
+        assert_eq!(Sat(0).height(), 0);
+        assert_eq!(Sat(50 * COIN_VALUE - 1).height(), 0);
+        assert_eq!(Sat(50 * COIN_VALUE).height(), 1);
+        assert_eq!(Sat::LAST.height(), 6_929_999);
+        // sats past the supply are never mined
+        assert_eq!(Sat(Sat::SUPPLY).height(), Sat::LAST.height());
+        assert_eq!(Sat(u64::MAX).height(), Sat::LAST.height());
+        assert_eq!(Sat(Sat::SUPPLY).third(), 0);
+        assert_eq!(Sat::first_of_block(210_001), Sat(1_050_002_500_000_000));
+        assert_eq!(Sat(2_067_187_500_000_000).cycle(), 1);
+        assert_eq!(Sat(2016 * 50 * COIN_VALUE).period(), 1);
+    }
+
+    #[test]
+    fn test_rarity() {
+        assert_eq!(Sat(0).rarity(), Rarity::Mythic);
+        assert_eq!(Sat(1).rarity(), Rarity::Common);
+        assert_eq!(Sat(50 * COIN_VALUE).rarity(), Rarity::Uncommon);
+        assert_eq!(Sat(2016 * 50 * COIN_VALUE).rarity(), Rarity::Rare);
+        assert_eq!(Sat(1_050_000_000_000_000).rarity(), Rarity::Epic);
+        assert_eq!(Sat(2_067_187_500_000_000).rarity(), Rarity::Legendary);
+        assert!(!Sat(50 * COIN_VALUE + 1).is_rare());
+        // the block subsidy of the last epoch is a single sat
+        assert_eq!(Sat::LAST.rarity(), Rarity::Uncommon);
+        assert_eq!(Sat(Sat::SUPPLY).rarity(), Sat::LAST.rarity());
+        assert_eq!(Rarity::Epic.to_string(), "epic");
+    }
+
+    #[test]
+    fn test_rare_sats_in_range() {
+        let block = 50 * COIN_VALUE;
+        assert!(rare_sats_in_range(Sat(1)..Sat(block)).is_empty());
+        assert_eq!(
+            rare_sats_in_range(Sat(1)..Sat(2 * block + 1)),
+            vec![Sat(block), Sat(2 * block)]
+        );
+        assert_eq!(
+            rare_sats_in_range(Sat(block)..Sat(block + 1)),
+            vec![Sat(block)]
+        );
+        assert_eq!(
+            rare_sats_in_range(Sat::LAST..Sat(Sat::SUPPLY + 10)),
+            vec![Sat::LAST]
+        );
+    }
+}
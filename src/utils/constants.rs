@@ -1,3 +1,7 @@
+use bitcoin::{Amount, Network, Script, ScriptBuf};
+
+use crate::{OrdError, OrdResult};
+
 pub const PROTOCOL_ID: [u8; 3] = *b"ord";
 pub const BODY_TAG: [u8; 0] = [];
 /// Tag 1, representing the MIME type of the body.
@@ -18,4 +22,131 @@ pub const DELEGATE_TAG: [u8; 1] = [11];
 /// Tag 13, denoting an optional rune.
 pub const RUNE_TAG: [u8; 1] = [13];
 /// Maximum allowed postage
-pub const POSTAGE: u64 = 333;
+pub const POSTAGE: Amount = Amount::from_sat(333);
+
+/// Maximum size in bytes of a single data push in a script.
+pub const MAX_SCRIPT_ELEMENT_SIZE: usize = bitcoin::constants::MAX_SCRIPT_ELEMENT_SIZE;
+/// Maximum weight of a transaction to be relayed by standard nodes.
+pub const MAX_STANDARD_TX_WEIGHT: u64 = bitcoin::policy::MAX_STANDARD_TX_WEIGHT as u64;
+
+/// Dust limit of a P2PKH output.
+pub const P2PKH_DUST_LIMIT: Amount = Amount::from_sat(546);
+/// Dust limit of a P2SH output.
+pub const P2SH_DUST_LIMIT: Amount = Amount::from_sat(540);
+/// Dust limit of a P2WPKH output.
+pub const P2WPKH_DUST_LIMIT: Amount = Amount::from_sat(294);
+/// Dust limit of a P2WSH output.
+pub const P2WSH_DUST_LIMIT: Amount = Amount::from_sat(330);
+/// Dust limit of a P2TR output.
+pub const P2TR_DUST_LIMIT: Amount = Amount::from_sat(330);
+
+/// Script pubkey of a pay-to-anchor output, `OP_1 <0x4e73>`, which anyone can spend
+/// without a witness to bump the fee of its transaction.
+///
+/// Its [`dust_limit`] is 240 sats: a zero-value anchor is only standard as ephemeral dust, in a
+/// transaction paying no fee whose child spends the anchor.
+pub const ANCHOR_SCRIPT_PUBKEY: [u8; 4] = [0x51, 0x02, 0x4e, 0x73];
+
+/// Returns the [`ANCHOR_SCRIPT_PUBKEY`] as a script.
+pub fn anchor_script_pubkey() -> ScriptBuf {
+    ScriptBuf::from_bytes(ANCHOR_SCRIPT_PUBKEY.to_vec())
+}
+
+/// Returns the minimum value an output with the given script pubkey must hold
+/// to be relayed by standard nodes.
+pub fn dust_limit(script_pubkey: &Script) -> Amount {
+    if script_pubkey.is_p2pkh() {
+        P2PKH_DUST_LIMIT
+    } else if script_pubkey.is_p2sh() {
+        P2SH_DUST_LIMIT
+    } else if script_pubkey.is_p2wpkh() {
+        P2WPKH_DUST_LIMIT
+    } else if script_pubkey.is_p2wsh() {
+        P2WSH_DUST_LIMIT
+    } else if script_pubkey.is_p2tr() {
+        P2TR_DUST_LIMIT
+    } else {
+        script_pubkey.dust_value()
+    }
+}
+
+/// Returns whether nodes on the given network reject non-standard transactions by default.
+///
+/// Testnet and regtest nodes accept non-standard transactions, so transactions exceeding
+/// [`MAX_STANDARD_TX_WEIGHT`] or with dust outputs may still be relayed there.
+pub fn requires_standard_transactions(network: Network) -> bool {
+    matches!(network, Network::Bitcoin | Network::Signet)
+}
+
+/// Returns the maximum weight of a transaction relayed on the given network,
+/// or `None` if standardness is not enforced.
+pub fn max_standard_tx_weight(network: Network) -> Option<u64> {
+    requires_standard_transactions(network).then_some(MAX_STANDARD_TX_WEIGHT)
+}
+
+/// Checks that a transaction of the given weight is relayed on the network, as returned by
+/// [`max_standard_tx_weight`].
+///
+/// # Errors
+///
+/// Returns [`OrdError::TransactionTooHeavy`] if the weight exceeds the maximum of the network.
+pub(crate) fn check_standard_tx_weight(network: Network, weight: u64) -> OrdResult<()> {
+    match max_standard_tx_weight(network) {
+        Some(max) if weight > max => Err(OrdError::TransactionTooHeavy { weight, max }),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the block height at which runes are activated on the given network.
+pub fn first_rune_height(network: Network) -> u32 {
+    match network {
+        Network::Bitcoin => 840_000,
+        Network::Testnet => 2_520_000,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::{Address, ScriptBuf};
+
+    use super::*;
+
+    #[test]
+    fn test_dust_limit_matches_rust_bitcoin() {
+        for address in [
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2",
+            "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1pxwww0ct9ue7e8tdnlmug5m2tamfn7q06sahstg39ys4c9f3340qqxrdu9k",
+        ] {
+            let script = Address::from_str(address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey();
+            assert_eq!(dust_limit(&script), script.dust_value(), "{address}");
+        }
+
+        assert_eq!(dust_limit(&ScriptBuf::new_op_return([0])), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_network_aware_getters() {
+        assert_eq!(
+            max_standard_tx_weight(Network::Bitcoin),
+            Some(MAX_STANDARD_TX_WEIGHT)
+        );
+        assert_eq!(max_standard_tx_weight(Network::Regtest), None);
+        assert!(check_standard_tx_weight(Network::Bitcoin, MAX_STANDARD_TX_WEIGHT).is_ok());
+        assert!(matches!(
+            check_standard_tx_weight(Network::Signet, MAX_STANDARD_TX_WEIGHT + 1),
+            Err(OrdError::TransactionTooHeavy { .. })
+        ));
+        assert!(check_standard_tx_weight(Network::Regtest, MAX_STANDARD_TX_WEIGHT + 1).is_ok());
+        assert_eq!(first_rune_height(Network::Bitcoin), 840_000);
+        assert_eq!(first_rune_height(Network::Regtest), 0);
+    }
+}
@@ -1,12 +1,17 @@
 use bitcoin::absolute::LockTime;
+use bitcoin::key::TweakedPublicKey;
+use bitcoin::script::Builder as ScriptBuilder;
+use bitcoin::taproot::ControlBlock;
 use bitcoin::transaction::Version;
 use bitcoin::{
-    Address, Amount, FeeRate, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+    Address, Amount, FeeRate, Network, OutPoint, PublicKey, Script, ScriptBuf, Sequence,
+    SignedAmount, Transaction, TxIn, TxOut, Witness, XOnlyPublicKey,
 };
 use serde::{Deserialize, Serialize};
 
-use super::constants::POSTAGE;
-use crate::wallet::ScriptType;
+use super::constants::{check_standard_tx_weight, ANCHOR_SCRIPT_PUBKEY, POSTAGE};
+use crate::wallet::{RedeemScriptPubkey, ScriptType, TxInputInfo};
+use crate::{Inscription, OrdError, OrdResult};
 
 /// Single ECDSA signature + SIGHASH type size in bytes.
 const ECDSA_SIGHASH_SIZE: usize = 72 + 1;
@@ -15,7 +20,7 @@ const SCHNORR_SIGHASH_SIZE: usize = 64 + 1;
 
 /// Represents multisig configuration (m of n) for a transaction, if applicable.
 /// Encapsulates the number of required signatures and the total number of signatories.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct MultisigConfig {
     /// Number of required signatures (m)
     pub required: usize,
@@ -23,13 +28,205 @@ pub struct MultisigConfig {
     pub total: usize,
 }
 
+/// Describes how a taproot input is spent, to estimate the size of its witness.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TaprootSpendProfile {
+    /// Key path spend, with a single schnorr signature
+    KeyPath,
+    /// Script path spend
+    ScriptPath {
+        /// Number of schnorr signatures in the witness
+        signatures: usize,
+        /// Number of empty signatures in the witness, e.g. for the keys of a multisig which
+        /// don't sign
+        empty_signatures: usize,
+        /// Size of the leaf script in bytes
+        script_size: usize,
+        /// Depth of the leaf in the script tree, 0 if it's the only leaf
+        merkle_depth: usize,
+    },
+}
+
+impl TaprootSpendProfile {
+    /// Returns the profile of a m-of-n multisig leaf, made of `n` public keys checked with
+    /// `OP_CHECKSIG` and `OP_CHECKSIGADD`, followed by `<m> OP_NUMEQUAL`.
+    pub fn multisig(config: &MultisigConfig, merkle_depth: usize) -> Self {
+        // <pubkey> OP_CHECKSIG(ADD) for each key
+        let keys_size = config.total * (1 + 32 + 1);
+        // <m> OP_NUMEQUAL
+        let threshold_size = ScriptBuilder::new()
+            .push_int(config.required as i64)
+            .into_script()
+            .len()
+            + 1;
+
+        Self::ScriptPath {
+            signatures: config.required,
+            empty_signatures: config.total.saturating_sub(config.required),
+            script_size: keys_size + threshold_size,
+            merkle_depth,
+        }
+    }
+
+    /// Returns a witness with the same size as the one spending the input.
+    fn witness(&self) -> Witness {
+        match self {
+            Self::KeyPath => Witness::from_slice(&[vec![0; SCHNORR_SIGHASH_SIZE]]),
+            Self::ScriptPath {
+                signatures,
+                empty_signatures,
+                script_size,
+                merkle_depth,
+            } => {
+                let mut witness = Witness::new();
+                for _ in 0..*signatures {
+                    witness.push(vec![0; SCHNORR_SIGHASH_SIZE]);
+                }
+                for _ in 0..*empty_signatures {
+                    witness.push([]);
+                }
+                witness.push(vec![0; *script_size]);
+                // leaf version and internal key, followed by the merkle path
+                witness.push(vec![0; 33 + 32 * merkle_depth]);
+                witness
+            }
+        }
+    }
+}
+
+/// Public key used in place of the actual key of the inscriber when quoting an inscription,
+/// since the size of the redeem script doesn't depend on it.
+const QUOTE_PUBKEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+/// Expected cost of an inscription, as returned by [`estimate_inscription_cost`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InscriptionCost {
+    /// Fee of the commit transaction
+    pub commit_fee: Amount,
+    /// Fee of the reveal transaction
+    pub reveal_fee: Amount,
+    /// Value of the output holding the inscription
+    pub postage: Amount,
+    /// Total amount of sats needed to inscribe
+    pub total: Amount,
+}
+
+/// Estimates the cost of an inscription before selecting the inputs, e.g. to quote the price of
+/// a mint.
+///
+/// The commit transaction is assumed to spend a single input and to send the leftovers to a
+/// change output, while the inscription is assumed to be revealed to a P2TR address.
+///
+/// # Errors
+///
+/// Returns [`OrdError::TransactionTooHeavy`] if the reveal transaction would exceed the
+/// [`max_standard_tx_weight`](super::constants::max_standard_tx_weight) of the network, or an
+/// error if the redeem script can't be generated or the fees overflow.
+pub fn estimate_inscription_cost<T>(
+    inscription: &T,
+    fee_rate: FeeRate,
+    script_type: ScriptType,
+    postage: Amount,
+    network: Network,
+) -> OrdResult<InscriptionCost>
+where
+    T: Inscription,
+{
+    let (commit_output, reveal_fee, reveal_weight) =
+        quote_commit_output(inscription, fee_rate, script_type, postage)?;
+    check_standard_tx_weight(network, reveal_weight)?;
+    let change_output = TxOut {
+        value: Amount::ZERO,
+        script_pubkey: quote_p2tr_script_pubkey()?,
+    };
+    let commit_fee = estimate_transaction_fees(
+        script_type,
+        1,
+        fee_rate,
+        &None,
+        vec![commit_output, change_output],
+    )?;
+
+    Ok(InscriptionCost {
+        commit_fee,
+        reveal_fee,
+        postage,
+        total: commit_fee + reveal_fee + postage,
+    })
+}
+
+/// Returns the commit output of an inscription revealed to a P2TR address, holding the postage
+/// and the reveal fee, along with the reveal fee and the weight of the reveal transaction.
+pub(crate) fn quote_commit_output<T>(
+    inscription: &T,
+    fee_rate: FeeRate,
+    script_type: ScriptType,
+    postage: Amount,
+) -> OrdResult<(TxOut, Amount, u64)>
+where
+    T: Inscription,
+{
+    let pubkey = QUOTE_PUBKEY
+        .parse::<PublicKey>()
+        .map_err(OrdError::PubkeyConversion)?;
+    let x_only_pubkey = XOnlyPublicKey::from(pubkey.inner);
+    let p2tr_script_pubkey = quote_p2tr_script_pubkey()?;
+
+    let redeem_script_pubkey = match script_type {
+        ScriptType::P2WSH | ScriptType::P2ShP2Wsh => RedeemScriptPubkey::Ecdsa(pubkey),
+        ScriptType::P2TR => RedeemScriptPubkey::XPublickey(x_only_pubkey),
+    };
+    let redeem_script = inscription
+        .generate_redeem_script(ScriptBuilder::new(), redeem_script_pubkey)?
+        .into_script();
+    let commit_script_pubkey = script_type
+        .commit_script_pubkey(&redeem_script)
+        .unwrap_or_else(|| p2tr_script_pubkey.clone());
+
+    let reveal_weight = estimate_reveal_weight(
+        &p2tr_script_pubkey,
+        &redeem_script,
+        script_type,
+        &None,
+        None,
+    )?;
+    let reveal_fee = estimate_reveal_fee_for_script(
+        vec![OutPoint::null()],
+        &p2tr_script_pubkey,
+        redeem_script,
+        script_type,
+        fee_rate,
+        &None,
+    )?;
+
+    Ok((
+        TxOut {
+            value: postage + reveal_fee,
+            script_pubkey: commit_script_pubkey,
+        },
+        reveal_fee,
+        reveal_weight,
+    ))
+}
+
+/// Returns the key path P2TR script pubkey of [`QUOTE_PUBKEY`].
+fn quote_p2tr_script_pubkey() -> OrdResult<ScriptBuf> {
+    let pubkey = QUOTE_PUBKEY
+        .parse::<PublicKey>()
+        .map_err(OrdError::PubkeyConversion)?;
+
+    Ok(ScriptBuf::new_p2tr_tweaked(
+        TweakedPublicKey::dangerous_assume_tweaked(XOnlyPublicKey::from(pubkey.inner)),
+    ))
+}
+
 /// Estimates the commit fee for a transaction.
 pub fn estimate_commit_fee(
     unsigned_commit_tx: Transaction,
     script_type: ScriptType,
     current_fee_rate: FeeRate,
     multisig_config: &Option<MultisigConfig>,
-) -> Amount {
+) -> OrdResult<Amount> {
     estimate_transaction_fees(
         script_type,
         unsigned_commit_tx.input.len(),
@@ -47,51 +244,393 @@ pub fn estimate_reveal_fee(
     script_type: ScriptType,
     current_fee_rate: FeeRate,
     multisig_config: &Option<MultisigConfig>,
-) -> Amount {
-    let tx_out = vec![TxOut {
-        value: Amount::from_sat(POSTAGE),
-        script_pubkey: recipient_address.script_pubkey(),
-    }];
+) -> OrdResult<Amount> {
+    estimate_reveal_fee_for_script(
+        inputs,
+        &recipient_address.script_pubkey(),
+        redeem_script,
+        script_type,
+        current_fee_rate,
+        multisig_config,
+    )
+}
 
-    let mut tx_in: Vec<TxIn> = inputs
-        .iter()
-        .map(|outpoint| TxIn {
-            previous_output: *outpoint,
-            script_sig: ScriptBuf::new(),
-            sequence: Sequence::from_consensus(0xffffffff),
-            witness: Witness::new(),
-        })
-        .collect();
+/// Estimates the reveal fee for a transaction sending the inscription to an arbitrary script,
+/// such as a bare multisig.
+///
+/// The fee is computed from the size of the actual reveal witness, i.e. the signatures, the
+/// redeem script and, for P2TR, the control block, so it grows with the inscription.
+pub fn estimate_reveal_fee_for_script(
+    inputs: Vec<OutPoint>,
+    recipient_script_pubkey: &Script,
+    redeem_script: ScriptBuf,
+    script_type: ScriptType,
+    current_fee_rate: FeeRate,
+    multisig_config: &Option<MultisigConfig>,
+) -> OrdResult<Amount> {
+    if inputs.is_empty() {
+        return Err(OrdError::NoInputs);
+    }
 
-    tx_in[0].witness.push(redeem_script.into_bytes());
+    let reveal_tx = estimate_reveal_transaction(
+        inputs.len(),
+        recipient_script_pubkey,
+        &redeem_script,
+        script_type,
+        multisig_config,
+    )?;
 
-    let unsigned_reveal_tx = Transaction {
-        version: Version::TWO,
-        lock_time: LockTime::ZERO,
-        input: tx_in,
-        output: tx_out.clone(),
-    };
+    fee_for_vbytes(current_fee_rate, reveal_tx.vsize() as u64)
+}
 
-    estimate_transaction_fees(
+/// Estimates the reveal fee for a transaction revealing a child inscription, which spends the
+/// output holding its parent inscription in its second input and returns it in its second
+/// output, see [`crate::wallet::ParentInfo`].
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidScriptType`] if the parent output is not a P2WPKH, P2TR or P2SH
+/// output.
+pub fn estimate_child_reveal_fee(
+    recipient_script_pubkey: &Script,
+    redeem_script: ScriptBuf,
+    script_type: ScriptType,
+    current_fee_rate: FeeRate,
+    multisig_config: &Option<MultisigConfig>,
+    parent_output: TxOut,
+) -> OrdResult<Amount> {
+    let reveal_tx = estimate_child_reveal_transaction(
+        recipient_script_pubkey,
+        &redeem_script,
         script_type,
-        unsigned_reveal_tx.input.len(),
-        current_fee_rate,
         multisig_config,
-        unsigned_reveal_tx.output,
-    )
+        parent_output,
+    )?;
+
+    fee_for_vbytes(current_fee_rate, reveal_tx.vsize() as u64)
+}
+
+/// Estimates the weight of the transaction revealing the redeem script, as the fees estimated by
+/// [`estimate_reveal_fee_for_script`], or by [`estimate_child_reveal_fee`] if it spends and
+/// returns the output of a parent inscription, e.g. to check it against the
+/// [`max_standard_tx_weight`](super::constants::max_standard_tx_weight) of the network before
+/// committing to the inscription.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidScriptType`] if the parent output is not a P2WPKH, P2TR or P2SH
+/// output.
+pub fn estimate_reveal_weight(
+    recipient_script_pubkey: &Script,
+    redeem_script: &Script,
+    script_type: ScriptType,
+    multisig_config: &Option<MultisigConfig>,
+    parent_output: Option<TxOut>,
+) -> OrdResult<u64> {
+    let reveal_tx = match parent_output {
+        Some(parent_output) => estimate_child_reveal_transaction(
+            recipient_script_pubkey,
+            redeem_script,
+            script_type,
+            multisig_config,
+            parent_output,
+        )?,
+        None => estimate_reveal_transaction(
+            1,
+            recipient_script_pubkey,
+            redeem_script,
+            script_type,
+            multisig_config,
+        )?,
+    };
+
+    Ok(reveal_tx.weight().to_wu())
+}
+
+/// Returns a reveal transaction with the same size as the one spending the commit output with
+/// its first input, and outputs of the commit with the others.
+fn estimate_reveal_transaction(
+    inputs: usize,
+    recipient_script_pubkey: &Script,
+    redeem_script: &Script,
+    script_type: ScriptType,
+    multisig_config: &Option<MultisigConfig>,
+) -> OrdResult<Transaction> {
+    let tx_out = vec![TxOut {
+        value: POSTAGE,
+        script_pubkey: recipient_script_pubkey.to_owned(),
+    }];
+
+    let witness = reveal_witness(redeem_script, script_type, multisig_config);
+
+    // only the first input spends the commit output, the others are signed as by the commit
+    let mut reveal_tx = estimate_transaction(inputs, &witness, tx_out);
+    reveal_tx.input[0].script_sig = script_type.reveal_script_sig(redeem_script)?;
+    let signature_witness = Witness::from_slice(&[vec![0; sighash_size(script_type, &None)]]);
+    for input in reveal_tx.input.iter_mut().skip(1) {
+        input.witness = signature_witness.clone();
+    }
+
+    Ok(reveal_tx)
+}
+
+/// Returns a reveal transaction with the same size as the one revealing a child inscription.
+fn estimate_child_reveal_transaction(
+    recipient_script_pubkey: &Script,
+    redeem_script: &Script,
+    script_type: ScriptType,
+    multisig_config: &Option<MultisigConfig>,
+    parent_output: TxOut,
+) -> OrdResult<Transaction> {
+    let parent_txin = estimated_txin(&parent_output.script_pubkey)?;
+    let tx_out = vec![
+        TxOut {
+            value: POSTAGE,
+            script_pubkey: recipient_script_pubkey.to_owned(),
+        },
+        parent_output,
+    ];
+
+    let witness = reveal_witness(redeem_script, script_type, multisig_config);
+    let mut reveal_tx = estimate_transaction(1, &witness, tx_out);
+    reveal_tx.input[0].script_sig = script_type.reveal_script_sig(redeem_script)?;
+    reveal_tx.input.push(parent_txin);
+
+    Ok(reveal_tx)
+}
+
+/// Estimates the weight of an unsigned reveal transaction once signed, given the control block
+/// and the annex of its first input, for P2TR commit outputs, and the output spent by its second
+/// input, for child inscriptions.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidScriptType`] if the parent output is not a P2WPKH, P2TR or P2SH
+/// output.
+pub(crate) fn estimate_signed_reveal_weight(
+    unsigned_tx: &Transaction,
+    redeem_script: &Script,
+    control_block: Option<&ControlBlock>,
+    annex: Option<&[u8]>,
+    parent_output: Option<&TxOut>,
+) -> OrdResult<u64> {
+    let mut reveal_tx = unsigned_tx.clone();
+    if let Some(input) = reveal_tx.input.first_mut() {
+        let mut witness = Witness::new();
+        match control_block {
+            Some(control_block) => {
+                witness.push(vec![0; SCHNORR_SIGHASH_SIZE]);
+                witness.push(redeem_script.as_bytes());
+                witness.push(control_block.serialize());
+                if let Some(annex) = annex {
+                    witness.push(annex);
+                }
+            }
+            None => {
+                witness.push(vec![0; ECDSA_SIGHASH_SIZE]);
+                witness.push(redeem_script.as_bytes());
+            }
+        }
+        input.witness = witness;
+    }
+    if let (Some(input), Some(parent_output)) = (reveal_tx.input.get_mut(1), parent_output) {
+        let parent_txin = estimated_txin(&parent_output.script_pubkey)?;
+        input.script_sig = parent_txin.script_sig;
+        input.witness = parent_txin.witness;
+    }
+
+    Ok(reveal_tx.weight().to_wu())
+}
+
+/// Returns a witness with the same size as the one spending a commit output through the redeem
+/// script.
+pub(crate) fn reveal_witness(
+    redeem_script: &Script,
+    script_type: ScriptType,
+    multisig_config: &Option<MultisigConfig>,
+) -> Witness {
+    let mut witness = Witness::new();
+    witness.push(vec![0; sighash_size(script_type, multisig_config)]);
+    witness.push(redeem_script.as_bytes());
+    if script_type == ScriptType::P2TR {
+        // leaf version and internal key, the redeem script being the only leaf
+        witness.push(vec![0; 33]);
+    }
+    witness
 }
 
 /// Estimates the transaction fees for a transaction.
+///
+/// # Errors
+///
+/// Returns [`OrdError::FeeOverflow`] if the fee doesn't fit in an amount of sats.
 pub fn estimate_transaction_fees(
     script_type: ScriptType,
     number_of_inputs: usize,
     current_fee_rate: FeeRate,
     multisig_config: &Option<MultisigConfig>,
     outputs: Vec<TxOut>,
-) -> Amount {
-    let vbytes = estimate_vbytes(number_of_inputs, script_type, multisig_config, outputs);
+) -> OrdResult<Amount> {
+    let vbytes = estimate_vbytes(number_of_inputs, script_type, multisig_config, outputs) as u64;
+
+    fee_for_vbytes(current_fee_rate, vbytes)
+}
+
+/// Estimates the transaction fees for a transaction spending outputs of different script types,
+/// e.g. a commit transaction funded by P2WPKH, P2TR and P2SH-P2WPKH outputs.
+///
+/// P2TR outputs are assumed to be spent through the key path and P2SH outputs to wrap a P2WPKH
+/// program. Pay-to-anchor outputs are spent with an empty witness.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidScriptType`] if a prevout has another script type, or
+/// [`OrdError::FeeOverflow`] if the fee doesn't fit in an amount of sats.
+pub fn estimate_transaction_fees_for_prevouts(
+    prevouts: &[TxOut],
+    current_fee_rate: FeeRate,
+    outputs: Vec<TxOut>,
+) -> OrdResult<Amount> {
+    let input = prevouts
+        .iter()
+        .map(|prevout| estimated_txin(&prevout.script_pubkey))
+        .collect::<OrdResult<Vec<_>>>()?;
+
+    let vbytes = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input,
+        output: outputs,
+    }
+    .vsize() as u64;
+
+    fee_for_vbytes(current_fee_rate, vbytes)
+}
+
+/// Returns the effective value of an output, i.e. its value minus the fee paid to spend it at
+/// the given fee rate, with the same assumptions as
+/// [`estimate_transaction_fees_for_prevouts`].
+///
+/// An output with a negative effective value costs more to spend than it brings, e.g. a dust
+/// output while fees are high.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidScriptType`] if the output is not a P2WPKH, P2TR or P2SH output,
+/// or [`OrdError::FeeOverflow`] if the fee doesn't fit in an amount of sats.
+pub fn effective_value(prevout: &TxOut, fee_rate: FeeRate) -> OrdResult<SignedAmount> {
+    let spend_fee = spend_fee(&prevout.script_pubkey, fee_rate)?;
+
+    Ok(to_signed(prevout.value) - to_signed(spend_fee))
+}
+
+/// Returns the fee paid to spend an output with the given script pubkey at the given fee rate,
+/// i.e. the fee of the input, with the same assumptions as
+/// [`estimate_transaction_fees_for_prevouts`].
+pub(crate) fn spend_fee(script_pubkey: &Script, fee_rate: FeeRate) -> OrdResult<Amount> {
+    let weight = estimated_txin(script_pubkey)?.segwit_weight();
+
+    fee_rate.fee_wu(weight).ok_or(OrdError::FeeOverflow {
+        fee_rate: fee_rate.to_sat_per_kwu(),
+        vbytes: weight.to_vbytes_ceil(),
+    })
+}
+
+/// Converts an amount to a signed amount, saturating amounts above the maximum signed amount,
+/// which are way above the bitcoin supply.
+pub(crate) fn to_signed(amount: Amount) -> SignedAmount {
+    amount.to_signed().unwrap_or(SignedAmount::MAX)
+}
+
+/// Discards the inputs which cost more to spend than they bring at the given fee rate, i.e.
+/// whose [`effective_value`] is not positive, so commits don't lose money consolidating dust
+/// while fees are high.
+///
+/// The remaining inputs keep their order.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidScriptType`] if an input is not a P2WPKH, P2TR or P2SH output,
+/// or [`OrdError::FeeOverflow`] if a fee doesn't fit in an amount of sats.
+pub fn discard_uneconomical_inputs(
+    inputs: Vec<TxInputInfo>,
+    fee_rate: FeeRate,
+) -> OrdResult<Vec<TxInputInfo>> {
+    let mut economical = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        if effective_value(&input.tx_out, fee_rate)? > SignedAmount::ZERO {
+            economical.push(input);
+        }
+    }
+
+    Ok(economical)
+}
+
+/// Returns an input of the same size as the one spending an output with the given script
+/// pubkey, assuming P2TR outputs are spent through the key path and P2SH outputs wrap a P2WPKH
+/// program.
+fn estimated_txin(script_pubkey: &Script) -> OrdResult<TxIn> {
+    let p2wpkh_witness = || Witness::from_slice(&[vec![0; ECDSA_SIGHASH_SIZE], vec![0; 33]]);
+    let (script_sig, witness) = if script_pubkey.is_p2wpkh() {
+        (ScriptBuf::new(), p2wpkh_witness())
+    } else if script_pubkey.is_p2tr() {
+        (ScriptBuf::new(), TaprootSpendProfile::KeyPath.witness())
+    } else if script_pubkey.is_p2sh() {
+        // push of the `OP_0 <20 bytes>` redeem script
+        (ScriptBuf::from_bytes(vec![0; 23]), p2wpkh_witness())
+    } else if script_pubkey.as_bytes() == ANCHOR_SCRIPT_PUBKEY {
+        (ScriptBuf::new(), Witness::new())
+    } else {
+        return Err(OrdError::InvalidScriptType);
+    };
+
+    Ok(TxIn {
+        previous_output: OutPoint::null(),
+        script_sig,
+        sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+        witness,
+    })
+}
+
+/// Estimates the transaction fees for a transaction spending taproot inputs as described by the
+/// spend profile, e.g. through a m-of-n multisig leaf.
+///
+/// # Errors
+///
+/// Returns [`OrdError::FeeOverflow`] if the fee doesn't fit in an amount of sats.
+pub fn estimate_taproot_transaction_fees(
+    spend_profile: &TaprootSpendProfile,
+    number_of_inputs: usize,
+    current_fee_rate: FeeRate,
+    outputs: Vec<TxOut>,
+) -> OrdResult<Amount> {
+    let vbytes =
+        estimate_vbytes_with_witness(number_of_inputs, &spend_profile.witness(), outputs) as u64;
+
+    fee_for_vbytes(current_fee_rate, vbytes)
+}
 
-    current_fee_rate.fee_vb(vbytes as u64).unwrap()
+pub(crate) fn fee_for_vbytes(fee_rate: FeeRate, vbytes: u64) -> OrdResult<Amount> {
+    fee_rate.fee_vb(vbytes).ok_or(OrdError::FeeOverflow {
+        fee_rate: fee_rate.to_sat_per_kwu(),
+        vbytes,
+    })
+}
+
+/// Converts an amount in millisats, as used by some wallet and lightning APIs, to an [`Amount`].
+///
+/// Outputs can only hold whole sats, so amounts with a fraction of a sat are rejected rather
+/// than silently rounded.
+pub fn amount_from_msat(msat: u64) -> OrdResult<Amount> {
+    if !msat.is_multiple_of(1_000) {
+        return Err(OrdError::SubSatAmount(msat));
+    }
+    Ok(Amount::from_sat(msat / 1_000))
+}
+
+/// Converts an [`Amount`] to millisats, returning `None` on overflow.
+pub fn amount_to_msat(amount: Amount) -> Option<u64> {
+    amount.to_sat().checked_mul(1_000)
 }
 
 #[cfg(feature = "rune")]
@@ -109,7 +648,7 @@ pub struct EstimateEdictTxFeesArgs {
 
 /// Estimates the transaction fees for an edict transaction.
 #[cfg(feature = "rune")]
-pub fn estimate_edict_transaction_fees(args: EstimateEdictTxFeesArgs) -> Amount {
+pub fn estimate_edict_transaction_fees(args: EstimateEdictTxFeesArgs) -> OrdResult<Amount> {
     use crate::wallet::RUNE_POSTAGE;
     let runestone = ordinals::Runestone {
         edicts: vec![ordinals::Edict {
@@ -161,16 +700,31 @@ fn estimate_vbytes(
     multisig_config: &Option<MultisigConfig>,
     outputs: Vec<TxOut>,
 ) -> usize {
-    let sighash_size = match script_type {
+    estimate_vbytes_with_witness(
+        inputs,
+        &Witness::from_slice(&[&vec![0; sighash_size(script_type, multisig_config)]]),
+        outputs,
+    )
+}
+
+/// Returns the size of the signatures in the witness of an input.
+fn sighash_size(script_type: ScriptType, multisig_config: &Option<MultisigConfig>) -> usize {
+    match script_type {
         // For P2WSH, calculate based on the multisig configuration if provided.
-        ScriptType::P2WSH => match multisig_config {
+        ScriptType::P2WSH | ScriptType::P2ShP2Wsh => match multisig_config {
             Some(config) => ECDSA_SIGHASH_SIZE * config.required,
             None => ECDSA_SIGHASH_SIZE, // Default to single signature size if no multisig config is provided.
         },
         // For P2TR, use the fixed Schnorr signature size.
         ScriptType::P2TR => SCHNORR_SIGHASH_SIZE,
-    };
+    }
+}
+
+fn estimate_vbytes_with_witness(inputs: usize, witness: &Witness, outputs: Vec<TxOut>) -> usize {
+    estimate_transaction(inputs, witness, outputs).vsize()
+}
 
+fn estimate_transaction(inputs: usize, witness: &Witness, outputs: Vec<TxOut>) -> Transaction {
     Transaction {
         version: Version::TWO,
         lock_time: LockTime::ZERO,
@@ -179,12 +733,11 @@ fn estimate_vbytes(
                 previous_output: OutPoint::null(),
                 script_sig: ScriptBuf::new(),
                 sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
-                witness: Witness::from_slice(&[&vec![0; sighash_size]]),
+                witness: witness.clone(),
             })
             .collect(),
         output: outputs,
     }
-    .vsize()
 }
 
 #[cfg(test)]
@@ -210,6 +763,41 @@ mod tests {
         ]
     }
 
+    #[test]
+    fn test_should_estimate_inscription_cost() {
+        let fee_rate = FeeRate::from_sat_per_vb(10).unwrap();
+        let postage = POSTAGE;
+
+        for script_type in [ScriptType::P2TR, ScriptType::P2WSH] {
+            let cost = estimate_inscription_cost(
+                &crate::Brc20::transfer("ordi", 100),
+                fee_rate,
+                script_type,
+                postage,
+                Network::Bitcoin,
+            )
+            .unwrap();
+
+            assert!(cost.commit_fee > Amount::ZERO);
+            assert!(cost.reveal_fee > Amount::ZERO);
+            assert_eq!(cost.postage, postage);
+            assert_eq!(cost.total, cost.commit_fee + cost.reveal_fee + postage);
+        }
+
+        // a reveal heavier than the standard weight is only quoted where it's relayed
+        let inscription = crate::Nft::new(Some(b"text/plain".to_vec()), Some(vec![0; 410_000]));
+        for (network, standard) in [(Network::Bitcoin, false), (Network::Testnet, true)] {
+            let result = estimate_inscription_cost(
+                &inscription,
+                fee_rate,
+                ScriptType::P2TR,
+                postage,
+                network,
+            );
+            assert_eq!(result.is_ok(), standard, "{network}");
+        }
+    }
+
     #[test]
     fn test_should_estimate_vbytes() {
         let before = estimate_vbytes(0, ScriptType::P2TR, &None, Vec::new());
@@ -267,7 +855,8 @@ mod tests {
             current_fee_rate,
             &multisig_config,
             outputs.clone(),
-        );
+        )
+        .unwrap();
 
         // Expected fee calculation: (100 + (5 * 73)) * 5 = 2325 satoshis
         let tx_size = estimate_vbytes(
@@ -318,7 +907,8 @@ mod tests {
             current_fee_rate,
             &multisig_config,
             outputs,
-        );
+        )
+        .unwrap();
 
         // Expected fee calculation: (200 + (10 * 73 * 2)) * 10 = 16600 satoshis
         assert_eq!(fee, Amount::from_sat((tx_size * 10) as u64));
@@ -346,12 +936,123 @@ mod tests {
             current_fee_rate,
             &multisig_config,
             outputs(2),
-        );
+        )
+        .unwrap();
 
         // Expected fee calculation: (150 + (5 * 65)) * 1 = 475 satoshis
         assert_eq!(fee, Amount::from_sat(tx_size as u64));
     }
 
+    #[test]
+    fn estimate_taproot_transaction_fees_for_script_path() {
+        let fee_rate = FeeRate::from_sat_per_vb(1).unwrap();
+        let key_path = estimate_taproot_transaction_fees(
+            &TaprootSpendProfile::KeyPath,
+            2,
+            fee_rate,
+            outputs(1),
+        )
+        .unwrap();
+        assert_eq!(
+            key_path,
+            estimate_transaction_fees(ScriptType::P2TR, 2, fee_rate, &None, outputs(1)).unwrap()
+        );
+
+        let config = MultisigConfig {
+            required: 2,
+            total: 3,
+        };
+        let profile = TaprootSpendProfile::multisig(&config, 0);
+        assert_eq!(
+            profile,
+            TaprootSpendProfile::ScriptPath {
+                signatures: 2,
+                empty_signatures: 1,
+                script_size: 3 * 34 + 2,
+                merkle_depth: 0,
+            }
+        );
+        let witness = profile.witness();
+        assert_eq!(witness.len(), 5);
+        assert_eq!(witness.last().unwrap().len(), 33);
+
+        let multisig =
+            estimate_taproot_transaction_fees(&profile, 2, fee_rate, outputs(1)).unwrap();
+        let deeper_multisig = estimate_taproot_transaction_fees(
+            &TaprootSpendProfile::multisig(&config, 2),
+            2,
+            fee_rate,
+            outputs(1),
+        )
+        .unwrap();
+        assert!(multisig > key_path);
+        // 2 inputs with 2 more merkle nodes of 32 bytes, as witness data
+        assert_eq!(deeper_multisig - multisig, Amount::from_sat(32));
+    }
+
+    #[test]
+    fn test_should_estimate_fees_for_mixed_prevouts() {
+        let fee_rate = FeeRate::from_sat_per_vb(1).unwrap();
+        let prevout = |script_pubkey: &str| TxOut {
+            value: Amount::from_sat(10_000),
+            script_pubkey: script_pubkey
+                .parse::<Address<NetworkUnchecked>>()
+                .unwrap()
+                .assume_checked()
+                .script_pubkey(),
+        };
+        let p2wpkh = prevout("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        let p2tr = prevout("bc1pxwww0ct9ue7e8tdnlmug5m2tamfn7q06sahstg39ys4c9f3340qqxrdu9k");
+        let p2sh = prevout("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy");
+
+        let fee = |prevouts: &[TxOut]| {
+            estimate_transaction_fees_for_prevouts(prevouts, fee_rate, outputs(2)).unwrap()
+        };
+        let p2wpkh_fee = fee(std::slice::from_ref(&p2wpkh));
+        let p2tr_fee = fee(std::slice::from_ref(&p2tr));
+        let p2sh_fee = fee(std::slice::from_ref(&p2sh));
+
+        // the schnorr witness is lighter, while the nested redeem script weighs in the script sig
+        assert!(p2tr_fee < p2wpkh_fee);
+        assert_eq!(p2sh_fee - p2wpkh_fee, Amount::from_sat(23));
+
+        // the transaction overhead and the outputs are paid once
+        let mixed = fee(&[p2wpkh, p2tr, p2sh]);
+        assert!(mixed > p2sh_fee);
+        assert!(mixed < p2wpkh_fee + p2tr_fee + p2sh_fee);
+
+        let bare = TxOut {
+            value: Amount::from_sat(10_000),
+            script_pubkey: ScriptBuf::new_op_return([0]),
+        };
+        assert!(matches!(
+            estimate_transaction_fees_for_prevouts(&[bare], fee_rate, outputs(1)),
+            Err(OrdError::InvalidScriptType)
+        ));
+    }
+
+    #[test]
+    fn estimate_transaction_fees_should_not_panic_on_overflow() {
+        let fee = estimate_transaction_fees(
+            ScriptType::P2TR,
+            1,
+            FeeRate::from_sat_per_kwu(u64::MAX),
+            &None,
+            outputs(2),
+        );
+        assert!(matches!(fee, Err(OrdError::FeeOverflow { .. })));
+
+        let fee = estimate_reveal_fee_for_script(
+            vec![],
+            &ScriptBuf::new(),
+            ScriptBuf::new(),
+            ScriptType::P2TR,
+            FeeRate::from_sat_per_vb(1).unwrap(),
+            &None,
+        );
+        assert!(matches!(fee, Err(OrdError::NoInputs)));
+    }
+
     #[test]
     #[cfg(feature = "rune")]
     fn test_estimate_transaction_edict() {
@@ -378,8 +1079,67 @@ mod tests {
             rune_amount: 9500,
         };
 
-        let fee = estimate_edict_transaction_fees(args);
+        let fee = estimate_edict_transaction_fees(args).unwrap();
 
         assert_eq!(fee, Amount::from_sat(448));
     }
+
+    #[test]
+    fn test_msat_conversion() {
+        assert_eq!(amount_from_msat(333_000).unwrap(), POSTAGE);
+        assert_eq!(amount_to_msat(POSTAGE), Some(333_000));
+        assert!(matches!(
+            amount_from_msat(333_500),
+            Err(OrdError::SubSatAmount(333_500))
+        ));
+        assert_eq!(amount_to_msat(Amount::MAX), None);
+    }
+
+    #[test]
+    fn test_should_discard_uneconomical_inputs() {
+        let fee_rate = FeeRate::from_sat_per_vb(100).unwrap();
+        let script_pubkey = outputs(1)[0].script_pubkey.clone();
+        let input = |vout, sats| TxInputInfo {
+            outpoint: OutPoint {
+                vout,
+                ..OutPoint::null()
+            },
+            tx_out: TxOut {
+                value: Amount::from_sat(sats),
+                script_pubkey: script_pubkey.clone(),
+            },
+            derivation_path: Default::default(),
+            key_spend: Default::default(),
+            rune_balances: None,
+        };
+
+        // a key path spend weighs 231 wu, i.e. 5_775 sats at 100 sat/vB
+        assert_eq!(
+            effective_value(&input(0, 10_000).tx_out, fee_rate).unwrap(),
+            SignedAmount::from_sat(10_000 - 5_775)
+        );
+        assert!(effective_value(&input(0, 546).tx_out, fee_rate).unwrap() < SignedAmount::ZERO);
+
+        let inputs = vec![
+            input(0, 546),
+            input(1, 10_000),
+            input(2, 5_750),
+            input(3, 20_000),
+        ];
+        let economical = discard_uneconomical_inputs(inputs.clone(), fee_rate).unwrap();
+        assert_eq!(economical, vec![inputs[1].clone(), inputs[3].clone()]);
+
+        // at a low fee rate, dust is worth spending
+        let economical =
+            discard_uneconomical_inputs(inputs.clone(), FeeRate::from_sat_per_vb(1).unwrap())
+                .unwrap();
+        assert_eq!(economical, inputs);
+
+        let mut p2wsh = input(4, 10_000);
+        p2wsh.tx_out.script_pubkey = ScriptBuf::new_p2wsh(&ScriptBuf::new().wscript_hash());
+        assert!(matches!(
+            discard_uneconomical_inputs(vec![p2wsh], fee_rate),
+            Err(OrdError::InvalidScriptType)
+        ));
+    }
 }
@@ -0,0 +1,110 @@
+//! Hash helpers
+//!
+//! Small cryptographic helpers commonly needed to recreate addresses and taproot control blocks.
+
+use bitcoin::hashes::{hash160, sha256, Hash, HashEngine};
+use bitcoin::taproot::{LeafVersion, TapLeafHash, TapNodeHash, TapTweakHash};
+use bitcoin::{Script, XOnlyPublicKey};
+
+/// Returns the SHA-256 digest of the data.
+pub fn sha256sum(data: &[u8]) -> [u8; 32] {
+    sha256::Hash::hash(data).to_byte_array()
+}
+
+/// Returns the RIPEMD-160 digest of the SHA-256 digest of the data,
+/// as used by P2PKH and P2WPKH addresses.
+pub fn h160sum(data: &[u8]) -> [u8; 20] {
+    hash160::Hash::hash(data).to_byte_array()
+}
+
+/// Returns the BIP-340 tagged hash of the data: `SHA256(SHA256(tag) || SHA256(tag) || data)`.
+pub fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag_hash = sha256::Hash::hash(tag.as_bytes());
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag_hash.as_ref());
+    engine.input(tag_hash.as_ref());
+    engine.input(data);
+
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// Returns the `TapLeaf` tagged hash of a script.
+pub fn tap_leaf_hash(script: &Script, leaf_version: LeafVersion) -> TapLeafHash {
+    TapLeafHash::from_script(script, leaf_version)
+}
+
+/// Returns the `TapTweak` tagged hash of an internal key and an optional script tree merkle root.
+pub fn tap_tweak_hash(
+    internal_key: XOnlyPublicKey,
+    merkle_root: Option<TapNodeHash>,
+) -> TapTweakHash {
+    TapTweakHash::from_key_and_tweak(internal_key, merkle_root)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::ScriptBuf;
+    use hex_literal::hex;
+
+    use super::*;
+
+    #[test]
+    fn test_sha256sum() {
+        assert_eq!(
+            sha256sum(b""),
+            hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+    }
+
+    #[test]
+    fn test_h160sum() {
+        // <https://en.bitcoin.it/wiki/Technical_background_of_version_1_Bitcoin_addresses>
+        assert_eq!(
+            h160sum(&hex!(
+                "0250863ad64a87ae8a2fe83c1af1a8403cb53f53e486d8511dad8a04887e5b2352"
+            )),
+            hex!("f54a5851e9372b87810a8e60cdd2e7cfd80b6e31")
+        );
+    }
+
+    #[test]
+    fn test_tagged_hash_matches_tap_leaf_hash() {
+        let script = ScriptBuf::from_hex(
+            "20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac",
+        )
+        .unwrap();
+
+        let mut data = vec![LeafVersion::TapScript.to_consensus()];
+        data.push(script.len() as u8);
+        data.extend_from_slice(script.as_bytes());
+
+        let leaf_hash = tap_leaf_hash(&script, LeafVersion::TapScript);
+        assert_eq!(tagged_hash("TapLeaf", &data), leaf_hash.to_byte_array());
+        // <https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json>
+        assert_eq!(
+            leaf_hash.to_byte_array(),
+            hex!("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21")
+        );
+    }
+
+    #[test]
+    fn test_tap_tweak_hash() {
+        // <https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json>
+        let internal_key = XOnlyPublicKey::from_str(
+            "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+        )
+        .unwrap();
+
+        let tweak = tap_tweak_hash(internal_key, None);
+        assert_eq!(
+            tweak.to_byte_array(),
+            hex!("b86e7be8f39bab32a6f2c0443abbc210f0edac0e2c53d501b36b64437d9c6c70")
+        );
+        assert_eq!(
+            tweak.to_byte_array(),
+            tagged_hash("TapTweak", &internal_key.serialize())
+        );
+    }
+}
@@ -1,5 +1,7 @@
 pub mod constants;
 pub mod fees;
+pub mod hash;
 pub mod push_bytes;
-#[cfg(test)]
+pub(crate) mod serde_helpers;
+#[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
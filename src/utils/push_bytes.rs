@@ -1,7 +1,14 @@
+use std::io;
+
 use bitcoin::script::PushBytesBuf;
 
+use crate::utils::constants::MAX_SCRIPT_ELEMENT_SIZE;
 use crate::OrdResult;
 
+/// Converts the bytes into a single push.
+///
+/// Fails if the bytes exceed the maximum push size;
+/// use [`bytes_to_push_chunks`] to embed larger payloads.
 pub fn bytes_to_push_bytes(bytes: &[u8]) -> OrdResult<PushBytesBuf> {
     let mut push_bytes = PushBytesBuf::with_capacity(bytes.len());
     push_bytes.extend_from_slice(bytes)?;
@@ -9,6 +16,36 @@ pub fn bytes_to_push_bytes(bytes: &[u8]) -> OrdResult<PushBytesBuf> {
     Ok(push_bytes)
 }
 
+/// Splits the bytes into pushes of at most [`MAX_SCRIPT_ELEMENT_SIZE`] bytes.
+///
+/// Empty data results in no pushes.
+pub fn bytes_to_push_chunks(bytes: &[u8]) -> Vec<PushBytesBuf> {
+    bytes
+        .chunks(MAX_SCRIPT_ELEMENT_SIZE)
+        .map(|chunk| {
+            PushBytesBuf::try_from(chunk.to_vec()).expect("chunk can't exceed the max push size")
+        })
+        .collect()
+}
+
+/// [`io::Write`] adapter appending the written bytes to a [`PushBytesBuf`].
+///
+/// Writes fail once the buffer would exceed the maximum push size.
+pub(crate) struct PushBytesWriter<'a>(pub &'a mut PushBytesBuf);
+
+impl io::Write for PushBytesWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .extend_from_slice(buf)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,4 +56,34 @@ mod tests {
         let push_bytes = bytes_to_push_bytes(&bytes).unwrap();
         assert_eq!(push_bytes.as_bytes(), bytes.as_slice());
     }
+
+    #[test]
+    fn test_push_bytes_writer() {
+        use std::io::Write as _;
+
+        let mut push_bytes = PushBytesBuf::new();
+        PushBytesWriter(&mut push_bytes).write_all(b"ord").unwrap();
+        PushBytesWriter(&mut push_bytes).write_all(b"-rs").unwrap();
+        assert_eq!(push_bytes.as_bytes(), b"ord-rs");
+    }
+
+    #[test]
+    fn test_bytes_to_push_chunks() {
+        assert!(bytes_to_push_chunks(&[]).is_empty());
+        assert_eq!(bytes_to_push_chunks(&[0; 520]).len(), 1);
+
+        let bytes = (0..1041).map(|i| i as u8).collect::<Vec<_>>();
+        let chunks = bytes_to_push_chunks(&bytes);
+        assert_eq!(
+            chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>(),
+            vec![520, 520, 1]
+        );
+        assert_eq!(
+            chunks
+                .iter()
+                .flat_map(|chunk| chunk.as_bytes().to_vec())
+                .collect::<Vec<_>>(),
+            bytes
+        );
+    }
 }
@@ -0,0 +1,115 @@
+//! Serde helpers for `bitcoin` types which don't implement the serde traits
+//! the way they are needed by the args structs.
+
+pub mod address {
+    //! `bitcoin` only implements `Deserialize` for `Address<NetworkUnchecked>`, so structs holding
+    //! a checked [`Address`] can't derive it. These helpers deserialize the unchecked address and
+    //! assume it is valid for the network it is going to be used on, the same way a caller would
+    //! when building the struct by hand.
+
+    use bitcoin::address::NetworkUnchecked;
+    use bitcoin::Address;
+    use serde::{Deserialize, Deserializer};
+
+    /// Deserializes an [`Address`] without checking its network.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Address, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Address::<NetworkUnchecked>::deserialize(deserializer).map(Address::assume_checked)
+    }
+}
+
+pub mod control_block {
+    //! (De)serializes an optional [`ControlBlock`] as its hex encoded consensus serialization.
+
+    use bitcoin::taproot::ControlBlock;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(
+        control_block: &Option<ControlBlock>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match control_block {
+            Some(control_block) => {
+                serializer.serialize_some(&hex::encode(control_block.serialize()))
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<ControlBlock>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|control_block| {
+                let bytes = hex::decode(control_block).map_err(D::Error::custom)?;
+                ControlBlock::decode(&bytes).map_err(D::Error::custom)
+            })
+            .transpose()
+    }
+}
+
+pub mod transaction {
+    //! (De)serializes a [`Transaction`] as a struct in human-readable formats, and as its
+    //! consensus serialization in binary formats, since some of them, e.g. candid, can't decode
+    //! the binary representation of its outpoints.
+
+    use std::fmt;
+
+    use bitcoin::{consensus, Transaction};
+    use serde::de::{Error as _, SeqAccess, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(tx: &Transaction, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            tx.serialize(serializer)
+        } else {
+            serializer.serialize_bytes(&consensus::serialize(tx))
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Transaction, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            Transaction::deserialize(deserializer)
+        } else {
+            deserializer.deserialize_bytes(TransactionVisitor)
+        }
+    }
+
+    struct TransactionVisitor;
+
+    impl<'de> Visitor<'de> for TransactionVisitor {
+        type Value = Transaction;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a consensus encoded transaction")
+        }
+
+        fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Transaction, E> {
+            consensus::deserialize(bytes).map_err(E::custom)
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Transaction, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            consensus::deserialize(&bytes).map_err(A::Error::custom)
+        }
+    }
+}
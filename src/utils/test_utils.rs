@@ -1,11 +1,153 @@
+//! Test utils
+//!
+//! Deterministic keys, addresses and scripts for every network, to write tests against the
+//! builder without duplicating the helpers of the examples.
+
+#[cfg(test)]
 mod rpc_client;
 
+use bitcoin::hashes::Hash as _;
+use bitcoin::script::Builder as ScriptBuilder;
 use bitcoin::secp256k1::Secp256k1;
-use bitcoin::{Address, Network, PrivateKey};
+use bitcoin::{Address, Amount, Network, PrivateKey, PublicKey, ScriptBuf, Txid, XOnlyPublicKey};
+#[cfg(test)]
 use rand::Rng as _;
+#[cfg(test)]
 pub use rpc_client::get_transaction_by_id;
 
+use super::hash::sha256sum;
+use crate::wallet::{build_p2tr_commit_output, RedeemScriptPubkey, ScriptType, Utxo};
+use crate::{Inscription, OrdResult};
+
+/// Networks covered by [`TestWallet::for_all_networks`].
+pub const TEST_NETWORKS: [Network; 4] = [
+    Network::Bitcoin,
+    Network::Testnet,
+    Network::Signet,
+    Network::Regtest,
+];
+
+/// Deterministic key and addresses of a test wallet on a network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestWallet {
+    pub network: Network,
+    pub private_key: PrivateKey,
+    pub public_key: PublicKey,
+    pub x_public_key: XOnlyPublicKey,
+    /// P2WPKH address of the public key, used to fund the commit transactions
+    pub p2wpkh_address: Address,
+    /// BIP-86 P2TR address of the public key
+    pub p2tr_address: Address,
+}
+
+impl TestWallet {
+    /// Derives the wallet with the given seed on the network.
+    ///
+    /// The same seed gives the same key on every network, only the addresses differ.
+    pub fn new(network: Network, seed: u8) -> Self {
+        let secp = Secp256k1::new();
+        let private_key = PrivateKey::from_slice(&sha256sum(&[seed]), network)
+            .expect("sha256 digest should be a valid private key");
+        let public_key = private_key.public_key(&secp);
+        let x_public_key = XOnlyPublicKey::from(public_key.inner);
+
+        Self {
+            network,
+            private_key,
+            public_key,
+            x_public_key,
+            p2wpkh_address: Address::p2wpkh(&public_key, network)
+                .expect("public key should be compressed"),
+            p2tr_address: Address::p2tr(&secp, x_public_key, None, network),
+        }
+    }
+
+    /// Derives the wallet with the given seed on each of the [`TEST_NETWORKS`].
+    pub fn for_all_networks(seed: u8) -> Vec<Self> {
+        TEST_NETWORKS
+            .into_iter()
+            .map(|network| Self::new(network, seed))
+            .collect()
+    }
+
+    /// Returns the script pubkey of the funding UTXOs, i.e. of the P2WPKH address.
+    pub fn funding_script_pubkey(&self) -> ScriptBuf {
+        self.p2wpkh_address.script_pubkey()
+    }
+
+    /// Returns a UTXO of the given amount, paying to [`Self::funding_script_pubkey`], with a
+    /// txid derived from `index`.
+    pub fn funding_utxo(&self, index: u32, amount: Amount) -> Utxo {
+        Utxo {
+            id: Txid::from_byte_array(sha256sum(&index.to_le_bytes())),
+            index,
+            amount,
+        }
+    }
+
+    /// Returns the redeem script expected from a builder of this wallet for the inscription.
+    pub fn redeem_script<T>(&self, inscription: &T, script_type: ScriptType) -> OrdResult<ScriptBuf>
+    where
+        T: Inscription,
+    {
+        let pubkey = match script_type {
+            ScriptType::P2WSH | ScriptType::P2ShP2Wsh => RedeemScriptPubkey::Ecdsa(self.public_key),
+            ScriptType::P2TR => RedeemScriptPubkey::XPublickey(self.x_public_key),
+        };
+
+        Ok(inscription
+            .generate_redeem_script(ScriptBuilder::new(), pubkey)?
+            .into_script())
+    }
+
+    /// Returns the script pubkey of the commit output expected for the inscription.
+    pub fn commit_script_pubkey<T>(
+        &self,
+        inscription: &T,
+        script_type: ScriptType,
+    ) -> OrdResult<ScriptBuf>
+    where
+        T: Inscription,
+    {
+        let redeem_script = self.redeem_script(inscription, script_type)?;
+        match script_type.commit_script_pubkey(&redeem_script) {
+            Some(script_pubkey) => Ok(script_pubkey),
+            None => build_p2tr_commit_output(&redeem_script, self.x_public_key)
+                .map(|(script_pubkey, _, _)| script_pubkey),
+        }
+    }
+}
+
+/// WIF of the testnet key signing the transactions of the unit tests.
+#[cfg(test)]
+pub(crate) const TEST_WIF: &str = "cVkWbHmoCx6jS8AyPNQqvFr8V9r2qzDHJLaxGDQgDJfxT73w6fuU";
+
+/// Returns the private key of [`TEST_WIF`].
+#[cfg(test)]
+pub(crate) fn test_private_key() -> PrivateKey {
+    PrivateKey::from_wif(TEST_WIF).expect("test WIF should be valid")
+}
+
+/// Returns the txid of the outputs funding the transactions of the unit tests.
+#[cfg(test)]
+pub(crate) fn test_txid() -> Txid {
+    "791b415dc6946d864d368a0e5ec5c09ee2ad39cf298bc6e3f9aec293732cfda7"
+        .parse()
+        .expect("test txid should be valid")
+}
+
+/// Returns the output `index` of [`test_txid`], holding the given amount.
+#[cfg(test)]
+pub(crate) fn test_utxo(index: u32, amount: Amount) -> Utxo {
+    Utxo {
+        id: test_txid(),
+        index,
+        amount,
+    }
+}
+
 /// Generate a random P2WPKH BTC address and its private key.
+#[cfg(test)]
 pub fn generate_btc_address(network: Network) -> (Address, PrivateKey) {
     let entropy = rand::thread_rng().gen::<[u8; 16]>();
     let mnemonic = bip39::Mnemonic::from_entropy(&entropy).unwrap();
@@ -21,6 +163,7 @@ pub fn generate_btc_address(network: Network) -> (Address, PrivateKey) {
 }
 
 /// Converts a SEC1 ECDSA signature to the DER format.
+#[cfg(test)]
 #[allow(unused)]
 pub fn sec1_to_der(sec1_signature: Vec<u8>) -> Result<Vec<u8>, String> {
     if sec1_signature.len() != 64 {
@@ -61,4 +204,30 @@ mod test {
         assert_eq!(address.script_pubkey().to_bytes().len(), 22);
         assert_eq!(private_key.network, Network::Bitcoin);
     }
+
+    #[test]
+    fn test_should_derive_test_wallets_for_all_networks() {
+        let wallets = TestWallet::for_all_networks(1);
+        assert_eq!(wallets.len(), TEST_NETWORKS.len());
+        for wallet in &wallets {
+            assert_eq!(wallet.public_key, wallets[0].public_key);
+            assert!(wallet
+                .p2wpkh_address
+                .as_unchecked()
+                .is_valid_for_network(wallet.network));
+            assert!(wallet
+                .p2tr_address
+                .as_unchecked()
+                .is_valid_for_network(wallet.network));
+        }
+
+        let regtest = TestWallet::new(Network::Regtest, 1);
+        assert!(regtest.p2wpkh_address.to_string().starts_with("bcrt1q"));
+        assert!(regtest.p2tr_address.to_string().starts_with("bcrt1p"));
+        assert_eq!(regtest, TestWallet::new(Network::Regtest, 1));
+        assert_ne!(
+            regtest.public_key,
+            TestWallet::new(Network::Regtest, 2).public_key
+        );
+    }
 }
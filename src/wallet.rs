@@ -1,15 +1,39 @@
 mod builder;
+mod diff;
+mod explain;
 mod parser;
+pub mod utxo_selector;
 
 pub use builder::signer::{BtcTxSigner, LocalSigner, Wallet};
 #[cfg(feature = "rune")]
 pub(crate) use builder::RUNE_POSTAGE;
 pub use builder::{
-    CreateCommitTransaction, CreateCommitTransactionArgs, CreateCommitTransactionArgsV2,
-    OrdTransactionBuilder, RedeemScriptPubkey, RevealTransactionArgs, ScriptType,
-    SignCommitTransactionArgs, TaprootPayload, TxInputInfo, Utxo,
+    arrange_inputs_for_sat, audit_child_reveals, bip86_derivation_path, build_p2tr_commit_output,
+    build_p2tr_commit_output_with_leaf_version, derive_reveal_destination, p2tr_bip86_address,
+    should_rebuild, verify_p2tr_commitment, verify_transaction, BatchCommitTransaction,
+    BatchRevealFees, BatchRevealScripts, BatchRevealTransactionArgs, BumpFeeArgs, ChangePolicy,
+    ChildReport, ChildReveal, CommitInput, CpfpArgs, CpfpTransaction,
+    CreateBatchCommitTransactionArgs, CreateCommitTransaction, CreateCommitTransactionArgs,
+    CreateCommitTransactionArgsV2, CreateMixedCommitTransactionArgs, InscriptionReceipt,
+    InscriptionStage, InscriptionWorkflow, OrdTransactionBuilder, ParentInfo, PendingInscription,
+    PostageStrategy, RebuildAdvice, RedeemScriptPubkey, RevealTimelock, RevealToScriptArgs,
+    RevealTransactionArgs, RuneBalance, SatPoint, ScriptType, SignCommitTransactionArgs,
+    SignPartialCommitTransactionArgs, SigningSession, SponsorOffer, SponsoredCommit,
+    SponsorshipRequest, TaprootKeySpend, TaprootPayload, TxInputInfo, UnsignedRevealTransaction,
+    Utxo, WorkflowFailure,
 };
 #[cfg(feature = "rune")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
-pub use builder::{CreateEdictTxArgs, EtchingTransactionArgs, Runestone};
-pub use parser::OrdParser;
+pub use builder::{
+    rune_commitment_bytes, rune_from_commitment_bytes, validate_etching, CreateEdictTxArgs,
+    EtchingTransactionArgs, Runestone,
+};
+pub use diff::{diff_transactions, TransactionDiff, TransactionDifference};
+pub use explain::{explain, InputReport, OutputReport, OutputRole, ScriptKind, TransactionReport};
+#[cfg(feature = "rune")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+pub use parser::{Artifact, Cenotaph, RunestoneParser};
+pub use parser::{
+    Curse, Envelope, OrdParser, ParseMode, ParseOptions, ParsedEnvelope, ParsedInscription,
+    ParserCounters, ParserCountersSnapshot, ParserMetrics, RawEnvelope,
+};
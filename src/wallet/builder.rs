@@ -1,29 +1,74 @@
+mod batch;
+mod cpfp;
+mod parent;
+mod partial;
+mod pending;
+mod rbf;
+mod receipt;
+mod sat_selection;
+mod session;
 pub mod signer;
+mod sponsor;
 mod taproot;
+mod timelock;
+mod verify;
+mod workflow;
 
 use bitcoin::absolute::LockTime;
 use bitcoin::bip32::DerivationPath;
 use bitcoin::script::{Builder as ScriptBuilder, PushBytesBuf};
+use bitcoin::sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{ControlBlock, TapNodeHash};
 use bitcoin::transaction::Version;
 use bitcoin::{
-    secp256k1, Address, Amount, FeeRate, Network, OutPoint, PublicKey, ScriptBuf, Sequence,
-    Transaction, TxIn, TxOut, Txid, Witness, XOnlyPublicKey,
+    secp256k1, Address, Amount, FeeRate, Network, OutPoint, PublicKey, Script, ScriptBuf, Sequence,
+    TapLeafHash, Transaction, TxIn, TxOut, Txid, Witness, XOnlyPublicKey,
 };
 
+pub use self::batch::{
+    BatchCommitTransaction, BatchRevealFees, BatchRevealScripts, BatchRevealTransactionArgs,
+    CreateBatchCommitTransactionArgs, PostageStrategy,
+};
+pub use self::cpfp::{CpfpArgs, CpfpTransaction};
+pub use self::parent::{audit_child_reveals, ChildReport, ChildReveal, ParentInfo};
+pub use self::partial::{CommitInput, SignPartialCommitTransactionArgs};
+pub use self::pending::{should_rebuild, PendingInscription, RebuildAdvice};
+pub use self::rbf::BumpFeeArgs;
+pub use self::receipt::InscriptionReceipt;
+pub use self::sat_selection::{arrange_inputs_for_sat, SatPoint};
+pub use self::session::SigningSession;
 use self::signer::Wallet;
-pub use self::taproot::TaprootPayload;
+pub use self::sponsor::{SponsorOffer, SponsoredCommit, SponsorshipRequest};
+pub use self::taproot::{
+    bip86_derivation_path, build_p2tr_commit_output, build_p2tr_commit_output_with_leaf_version,
+    derive_reveal_destination, p2tr_bip86_address, verify_p2tr_commitment, TaprootPayload,
+};
+pub use self::timelock::RevealTimelock;
+pub use self::verify::verify_transaction;
+pub use self::workflow::{InscriptionStage, InscriptionWorkflow, WorkflowFailure};
 use crate::inscription::Inscription;
-use crate::utils::constants::POSTAGE;
-use crate::utils::fees::{estimate_commit_fee, estimate_reveal_fee, MultisigConfig};
+use crate::provider::{FeeProvider, InscriptionFeeTargets};
+use crate::utils::constants::{
+    anchor_script_pubkey, check_standard_tx_weight, dust_limit, POSTAGE,
+};
+use crate::utils::fees::{
+    estimate_commit_fee, estimate_reveal_fee, estimate_reveal_weight,
+    estimate_signed_reveal_weight, estimate_transaction_fees_for_prevouts, fee_for_vbytes,
+    quote_commit_output, MultisigConfig,
+};
 use crate::utils::push_bytes::bytes_to_push_bytes;
-use crate::{OrdError, OrdResult};
+use crate::wallet::utxo_selector::{SelectionStrategy, UtxoCandidate, UtxoSelection, UtxoSelector};
+use crate::{InscriptionId, OrdError, OrdResult};
 
 #[cfg(feature = "rune")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
 mod rune;
 #[cfg(feature = "rune")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
-pub use rune::{CreateEdictTxArgs, EtchingTransactionArgs, Runestone, RUNE_POSTAGE};
+pub use rune::{
+    rune_commitment_bytes, rune_from_commitment_bytes, validate_etching, CreateEdictTxArgs,
+    EtchingTransactionArgs, Runestone, RUNE_POSTAGE,
+};
 
 use crate::wallet::builder::signer::LocalSigner;
 
@@ -38,7 +83,7 @@ pub struct OrdTransactionBuilder {
 }
 
 /// Unspent transaction output to be used as input of a transaction
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Utxo {
     pub id: Txid,
     pub index: u32,
@@ -49,7 +94,7 @@ pub struct Utxo {
 ///
 /// This struct contains signature script in contrast to [Utxo] so it can be used to sign inputs
 /// from different addresses.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TxInputInfo {
     /// ID of the output.
     pub outpoint: OutPoint,
@@ -58,10 +103,48 @@ pub struct TxInputInfo {
     pub tx_out: TxOut,
 
     pub derivation_path: DerivationPath,
+
+    /// Key used to sign the output if it's a P2TR output spent through the key path.
+    #[serde(default)]
+    pub key_spend: TaprootKeySpend,
+
+    /// Runes held by the output, if known, to check that the edicts of a transaction spending it
+    /// are covered, see [`OrdTransactionBuilder::create_edict_transaction`].
+    #[serde(default)]
+    pub rune_balances: Option<Vec<RuneBalance>>,
 }
 
-#[derive(Debug)]
+/// Amount of a rune held by an output.
+///
+/// The rune is identified by the block and the index in the block of its etching transaction,
+/// i.e. the fields of its `RuneId`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuneBalance {
+    /// Block of the etching transaction of the rune
+    pub block: u64,
+    /// Index of the etching transaction of the rune in its block
+    pub tx: u32,
+    /// Amount of the rune, in its smallest unit
+    pub amount: u128,
+}
+
+/// Key signing the key path spend of a P2TR output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaprootKeySpend {
+    /// The key derived by the signer, used as the output key as is
+    #[default]
+    Untweaked,
+    /// The key derived by the signer, tweaked as the internal key of a [BIP-86] output, i.e.
+    /// with no script tree
+    ///
+    /// [BIP-86]: https://github.com/bitcoin/bips/blob/master/bip-0086.mediawiki
+    Bip86,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// Arguments for creating a commit transaction
+// `T: Inscription` already implies `T: DeserializeOwned`
+#[serde(bound(deserialize = ""))]
 pub struct CreateCommitTransactionArgs<T>
 where
     T: Inscription,
@@ -71,6 +154,7 @@ where
     /// Inscription to write
     pub inscription: T,
     /// Address to send the leftovers BTC of the trasnsaction
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
     pub leftovers_recipient: Address,
     /// Script pubkey of the inputs
     pub txin_script_pubkey: ScriptBuf,
@@ -80,10 +164,109 @@ where
     pub multisig_config: Option<MultisigConfig>,
     /// Derivation path for the keypair
     pub derivation_path: Option<DerivationPath>,
+    /// How the leftovers of the transaction are handled
+    #[serde(default)]
+    pub change_policy: ChangePolicy,
+    /// Parent inscription, spent and returned by the reveal transaction to create the
+    /// inscription as its child
+    #[serde(default)]
+    pub parent: Option<ParentInfo>,
 }
 
-#[derive(Debug)]
+impl<T> CreateCommitTransactionArgs<T>
+where
+    T: Inscription,
+{
+    /// Creates the arguments of a commit transaction with the required fields, without
+    /// multisig configuration, derivation path nor parent, and with the default change policy.
+    ///
+    /// The optional fields can be set with the struct update syntax, so adding one doesn't
+    /// break the callers.
+    pub fn new(
+        inputs: Vec<Utxo>,
+        inscription: T,
+        leftovers_recipient: Address,
+        txin_script_pubkey: ScriptBuf,
+        fee_rate: FeeRate,
+    ) -> Self {
+        Self {
+            inputs,
+            inscription,
+            leftovers_recipient,
+            txin_script_pubkey,
+            fee_rate,
+            multisig_config: None,
+            derivation_path: None,
+            change_policy: ChangePolicy::default(),
+            parent: None,
+        }
+    }
+
+    /// Selects the inputs of the commit transaction among the candidates with the given
+    /// strategy, replacing [`Self::inputs`], so the caller doesn't have to pre-select them.
+    ///
+    /// The candidates are assumed to be locked by [`Self::txin_script_pubkey`], a P2WPKH, P2TR
+    /// or P2SH-P2WPKH output, and the inscription to be revealed to a P2TR address with the
+    /// given postage, e.g. [`POSTAGE`]. The change is sent to [`Self::leftovers_recipient`].
+    ///
+    /// If the leftovers don't cover the cost of a change output, and the change policy lets
+    /// them go to the fee, i.e. it's a [`ChangePolicy::Sweep`] with a threshold covering the
+    /// cost of a change output, the change policy is set to [`ChangePolicy::Omit`], with the
+    /// fee of the selection as tolerance, so the commit has no dust change. Other policies are
+    /// kept as set by the caller.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidScriptType`] if the candidates are not P2WPKH, P2TR or P2SH
+    /// outputs, or [`OrdError::InsufficientBalance`] if they don't cover the inscription.
+    pub fn select_inputs(
+        &mut self,
+        candidates: &[UtxoCandidate],
+        strategy: SelectionStrategy,
+        script_type: ScriptType,
+        postage: Amount,
+    ) -> OrdResult<UtxoSelection> {
+        let selector = UtxoSelector::new(
+            strategy,
+            &self.txin_script_pubkey,
+            &self.leftovers_recipient.script_pubkey(),
+            self.fee_rate,
+        )?;
+
+        // the commit transaction without inputs nor change, with the segwit marker and flag
+        // of its inputs
+        let (commit_output, _, _) =
+            quote_commit_output(&self.inscription, self.fee_rate, script_type, postage)?;
+        let vbytes = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![commit_output.clone()],
+        }
+        .vsize() as u64
+            + 1;
+        let target = commit_output.value + fee_for_vbytes(self.fee_rate, vbytes)?;
+
+        let selection = selector.select(candidates, target)?;
+        self.inputs = selection.inputs.clone();
+        let sweeps_change = matches!(
+            self.change_policy,
+            ChangePolicy::Sweep { threshold } if threshold >= selector.cost_of_change()
+        );
+        if selection.change.is_none() && sweeps_change {
+            self.change_policy = ChangePolicy::Omit {
+                tolerance: selection.fee,
+            };
+        }
+
+        Ok(selection)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// Arguments for creating a commit transaction with fixed fees
+// `T: Inscription` already implies `T: DeserializeOwned`
+#[serde(bound(deserialize = ""))]
 pub struct CreateCommitTransactionArgsV2<T>
 where
     T: Inscription,
@@ -93,6 +276,7 @@ where
     /// Inscription to write
     pub inscription: T,
     /// Address to send the leftovers BTC of the trasnsaction
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
     pub leftovers_recipient: Address,
     /// Fee to pay for the commit transaction
     pub commit_fee: Amount,
@@ -104,7 +288,94 @@ where
     pub derivation_path: Option<DerivationPath>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Arguments for creating a commit transaction funded by outputs of different script types
+#[serde(bound(deserialize = ""))]
+pub struct CreateMixedCommitTransactionArgs<T>
+where
+    T: Inscription,
+{
+    /// Outputs to be spent, which may be P2WPKH, P2TR (key path) or P2SH-P2WPKH outputs
+    pub inputs: Vec<TxInputInfo>,
+    /// Inscription to write
+    pub inscription: T,
+    /// Address to send the leftovers BTC of the transaction
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
+    pub leftovers_recipient: Address,
+    /// Current fee rate on the network
+    pub fee_rate: FeeRate,
+    /// Derivation path of the key of the redeem script
+    pub derivation_path: Option<DerivationPath>,
+    /// How the leftovers of the transaction are handled
+    #[serde(default)]
+    pub change_policy: ChangePolicy,
+}
+
+/// Outputs funding a commit transaction
+enum CommitFunding<'a> {
+    /// Outputs locked by the script type of the builder
+    Uniform(&'a [Utxo]),
+    /// Outputs of different script types
+    Mixed(&'a [TxInputInfo]),
+}
+
+impl CommitFunding<'_> {
+    fn outpoints(&self) -> Box<dyn Iterator<Item = OutPoint> + '_> {
+        match self {
+            Self::Uniform(inputs) => Box::new(inputs.iter().map(|input| OutPoint {
+                txid: input.id,
+                vout: input.index,
+            })),
+            Self::Mixed(inputs) => Box::new(inputs.iter().map(|input| input.outpoint)),
+        }
+    }
+
+    fn input_amount(&self) -> Amount {
+        match self {
+            Self::Uniform(inputs) => inputs.iter().map(|input| input.amount).sum(),
+            Self::Mixed(inputs) => inputs.iter().map(|input| input.tx_out.value).sum(),
+        }
+    }
+}
+
+/// Parameters shared by the commit transaction builders
+struct CommitParams<'a, T> {
+    inscription: &'a T,
+    funding: CommitFunding<'a>,
+    change_script_pubkey: &'a ScriptBuf,
+    fee_rate: FeeRate,
+    reveal_fee_rate: FeeRate,
+    multisig_config: &'a Option<MultisigConfig>,
+    derivation_path: Option<DerivationPath>,
+    change_policy: ChangePolicy,
+    parent: Option<&'a ParentInfo>,
+}
+
+impl<T> CommitParams<'_, T> {
+    fn estimate_commit_fee(
+        &self,
+        unsigned_tx: Transaction,
+        script_type: ScriptType,
+    ) -> OrdResult<Amount> {
+        match self.funding {
+            CommitFunding::Uniform(_) => estimate_commit_fee(
+                unsigned_tx,
+                script_type,
+                self.fee_rate,
+                self.multisig_config,
+            ),
+            CommitFunding::Mixed(inputs) => {
+                let prevouts = inputs
+                    .iter()
+                    .map(|input| input.tx_out.clone())
+                    .collect::<Vec<_>>();
+                estimate_transaction_fees_for_prevouts(&prevouts, self.fee_rate, unsigned_tx.output)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SignCommitTransactionArgs {
     /// UTXOs to be used as inputs of the transaction
     pub inputs: Vec<Utxo>,
@@ -114,9 +385,10 @@ pub struct SignCommitTransactionArgs {
     pub derivation_path: Option<DerivationPath>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CreateCommitTransaction {
     /// The unsigned commit transaction
+    #[serde(with = "crate::utils::serde_helpers::transaction")]
     pub unsigned_tx: Transaction,
     /// The redeem script to be used in the reveal transaction
     pub redeem_script: ScriptBuf,
@@ -128,27 +400,429 @@ pub struct CreateCommitTransaction {
     pub reveal_fee: Amount,
     /// Leftover amount to be sent to the leftovers recipient
     pub leftover_amount: Amount,
+    /// Value of the inscription output of the reveal transaction, to be passed to the reveal
+    /// arguments. It exceeds [`POSTAGE`] when the leftovers are merged into it.
+    #[serde(default = "default_postage")]
+    pub postage: Amount,
+    /// Parent inscription of the inscription, to be passed to the reveal arguments
+    #[serde(default)]
+    pub parent: Option<ParentInfo>,
+    /// Hash of the redeem script leaf of the P2TR commit output, `None` for other script types
+    #[serde(default)]
+    pub tapleaf_hash: Option<TapLeafHash>,
+    /// Merkle root of the script tree of the P2TR commit output, `None` for other script types.
+    /// With the internal key, it lets third parties verify the commitment with
+    /// [`verify_p2tr_commitment`] before the redeem script is revealed.
+    #[serde(default)]
+    pub merkle_root: Option<TapNodeHash>,
+}
+
+fn default_postage() -> Amount {
+    POSTAGE
+}
+
+impl CreateCommitTransaction {
+    /// Returns the txid the commit transaction will have once signed, so the reveal transaction
+    /// can be prepared before the commit is signed or broadcast.
+    ///
+    /// The txid doesn't commit to the witness, so it's only known before signing if all the
+    /// inputs spend segwit outputs natively. Signing inputs spending P2SH wrapped outputs sets
+    /// their script sig, which changes the txid: use [`Self::txid_after_signing`] then.
+    pub fn txid(&self) -> Txid {
+        self.unsigned_tx.txid()
+    }
+
+    /// Returns the txid of the signed commit transaction, checking that it is this commit
+    /// transaction with only its script sigs and witnesses set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the signed transaction is a different transaction.
+    pub fn txid_after_signing(&self, signed: &Transaction) -> OrdResult<Txid> {
+        let unsigned = &self.unsigned_tx;
+        let same_inputs = unsigned.input.len() == signed.input.len()
+            && unsigned
+                .input
+                .iter()
+                .zip(&signed.input)
+                .all(|(unsigned, signed)| {
+                    unsigned.previous_output == signed.previous_output
+                        && unsigned.sequence == signed.sequence
+                });
+        if !same_inputs
+            || unsigned.version != signed.version
+            || unsigned.lock_time != signed.lock_time
+            || unsigned.output != signed.output
+        {
+            return Err(OrdError::InvalidInputs);
+        }
+
+        Ok(signed.txid())
+    }
+
+    /// Returns the commit output to be spent by the reveal transaction, given the txid of the
+    /// commit transaction, as returned by [`Self::txid`] or [`Self::txid_after_signing`].
+    pub fn reveal_input(&self, commit_txid: Txid) -> Utxo {
+        Utxo {
+            id: commit_txid,
+            index: 0,
+            amount: self.reveal_balance,
+        }
+    }
+
+    /// Returns the ID of the inscription revealed by the reveal transaction with the given
+    /// txid, which holds the only inscription of its first input.
+    ///
+    /// The txid doesn't commit to the witness, so it can be taken from the unsigned reveal
+    /// transaction, e.g. to register the inscription before broadcasting it.
+    pub fn inscription_id(&self, reveal_txid: Txid) -> InscriptionId {
+        InscriptionId {
+            txid: reveal_txid,
+            index: 0,
+        }
+    }
 }
 
 /// Arguments for creating a reveal transaction
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RevealTransactionArgs {
     /// Transaction input (output of commit transaction)
     pub input: Utxo,
-    /// Recipient address of the inscription, only support P2PKH
+    /// Recipient address of the inscription.
+    ///
+    /// Any standard address can be used, including P2WSH and P2TR multisig addresses,
+    /// as long as the postage is above its dust limit.
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
     pub recipient_address: Address,
     /// The redeem script returned by `create_commit_transaction`
     pub redeem_script: ScriptBuf,
     /// Derivation path for the keypair
     pub derivation_path: Option<DerivationPath>,
+    /// Control block of the redeem script leaf, for P2TR commit outputs.
+    ///
+    /// When set, it's used instead of the taproot payload held by the builder, so the reveal
+    /// transaction can be built by a different builder than the one which built the commit.
+    #[serde(default, with = "crate::utils::serde_helpers::control_block")]
+    pub control_block: Option<ControlBlock>,
+    /// Lock time and input sequence of the reveal transaction.
+    ///
+    /// When not set, they're derived from the `OP_CHECKLOCKTIMEVERIFY` and
+    /// `OP_CHECKSEQUENCEVERIFY` timelocks of the redeem script, if any.
+    #[serde(default)]
+    pub timelock: Option<RevealTimelock>,
+    /// Value of the inscription output, as returned in [`CreateCommitTransaction::postage`].
+    ///
+    /// When not set, [`POSTAGE`] is used.
+    #[serde(default)]
+    pub postage: Option<Amount>,
+    /// Whether to add a zero-value pay-to-anchor output to the reveal transaction, so it can
+    /// be fee bumped by a CPFP child even when its only other output is the postage.
+    ///
+    /// Standard nodes only accept a zero-value output as ephemeral dust, in a transaction paying
+    /// no fee. The anchored reveal therefore pays no fee: the sats reserved for its fee by the
+    /// commit transaction are added to the inscription output, and the reveal must be submitted
+    /// in a package together with a child spending the anchor, which pays for both.
+    #[serde(default)]
+    pub anchor: bool,
+    /// Parent inscription, as returned in [`CreateCommitTransaction::parent`], spent by the
+    /// second input and returned by the second output of the reveal transaction
+    #[serde(default)]
+    pub parent: Option<ParentInfo>,
+    /// Taproot annex appended to the witness of the reveal input, for P2TR commit outputs.
+    ///
+    /// The annex must start with `0x50`, and is committed to by the signature. It's reserved for
+    /// future soft forks and non-standard, so the reveal transaction is not relayed by default
+    /// nodes: it's meant for experiments, e.g. on regtest. The reveal fee estimated for the
+    /// commit transaction doesn't include the annex.
+    #[serde(default)]
+    pub annex: Option<Vec<u8>>,
+}
+
+/// Arguments for creating a reveal transaction sending the inscription to an arbitrary script
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevealToScriptArgs {
+    /// Transaction input (output of commit transaction)
+    pub input: Utxo,
+    /// Script pubkey of the recipient of the inscription, e.g. a bare multisig script
+    pub recipient_script_pubkey: ScriptBuf,
+    /// The redeem script returned by `create_commit_transaction`
+    pub redeem_script: ScriptBuf,
+    /// Derivation path for the keypair
+    pub derivation_path: Option<DerivationPath>,
+    /// Control block of the redeem script leaf, for P2TR commit outputs
+    #[serde(default, with = "crate::utils::serde_helpers::control_block")]
+    pub control_block: Option<ControlBlock>,
+    /// Lock time and input sequence of the reveal transaction
+    #[serde(default)]
+    pub timelock: Option<RevealTimelock>,
+    /// Value of the inscription output, [`POSTAGE`] if not set
+    #[serde(default)]
+    pub postage: Option<Amount>,
+    /// Whether to add a zero-value pay-to-anchor output, see [`RevealTransactionArgs::anchor`]
+    #[serde(default)]
+    pub anchor: bool,
+    /// Parent inscription, see [`RevealTransactionArgs::parent`]
+    #[serde(default)]
+    pub parent: Option<ParentInfo>,
+    /// Taproot annex of the reveal input, see [`RevealTransactionArgs::annex`]
+    #[serde(default)]
+    pub annex: Option<Vec<u8>>,
+    /// Network the reveal transaction is broadcast to, whose
+    /// [`max_standard_tx_weight`](crate::constants::max_standard_tx_weight) it must not exceed,
+    /// i.e. the network of the recipient address of [`RevealTransactionArgs`]. The weight isn't
+    /// checked if not set.
+    #[serde(default)]
+    pub network: Option<Network>,
+}
+
+impl RevealTransactionArgs {
+    /// Creates the arguments of a reveal transaction spending the commit output, with the
+    /// default values of the optional fields, i.e. the [`POSTAGE`], the timelocks of the redeem
+    /// script, and no derivation path, control block, anchor, parent nor annex.
+    ///
+    /// The optional fields can be set with the struct update syntax, so adding one doesn't
+    /// break the callers.
+    pub fn new(input: Utxo, recipient_address: Address, redeem_script: ScriptBuf) -> Self {
+        Self {
+            input,
+            recipient_address,
+            redeem_script,
+            derivation_path: None,
+            control_block: None,
+            timelock: None,
+            postage: None,
+            anchor: false,
+            parent: None,
+            annex: None,
+        }
+    }
+}
+
+impl RevealToScriptArgs {
+    /// Creates the arguments of a reveal transaction sending the inscription to the script,
+    /// with the default values of the optional fields, see [`RevealTransactionArgs::new`].
+    pub fn new(input: Utxo, recipient_script_pubkey: ScriptBuf, redeem_script: ScriptBuf) -> Self {
+        Self {
+            input,
+            recipient_script_pubkey,
+            redeem_script,
+            derivation_path: None,
+            control_block: None,
+            timelock: None,
+            postage: None,
+            anchor: false,
+            parent: None,
+            annex: None,
+            network: None,
+        }
+    }
+}
+
+impl From<RevealTransactionArgs> for RevealToScriptArgs {
+    fn from(args: RevealTransactionArgs) -> Self {
+        Self {
+            input: args.input,
+            recipient_script_pubkey: args.recipient_address.script_pubkey(),
+            redeem_script: args.redeem_script,
+            derivation_path: args.derivation_path,
+            control_block: args.control_block,
+            timelock: args.timelock,
+            postage: args.postage,
+            anchor: args.anchor,
+            parent: args.parent,
+            annex: args.annex,
+            network: Some(*args.recipient_address.network()),
+        }
+    }
+}
+
+/// Unsigned reveal transaction, returned by [`OrdTransactionBuilder::prepare_reveal_transaction`]
+/// with the data required to sign it, so it can be inspected or signed externally before being
+/// passed to [`OrdTransactionBuilder::sign_reveal`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsignedRevealTransaction {
+    /// The unsigned reveal transaction
+    pub unsigned_tx: Transaction,
+    /// Output of the commit transaction spent by the reveal transaction
+    pub prevout: TxOut,
+    /// The redeem script to be revealed in the witness
+    pub redeem_script: ScriptBuf,
+    /// Derivation path for the keypair
+    pub derivation_path: Option<DerivationPath>,
+    /// Control block of the redeem script leaf, for P2TR commit outputs
+    #[serde(default, with = "crate::utils::serde_helpers::control_block")]
+    pub control_block: Option<ControlBlock>,
+    /// Parent inscription spent by the second input
+    #[serde(default)]
+    pub parent: Option<ParentInfo>,
+    /// Taproot annex of the reveal input, committed to by the sighash
+    #[serde(default)]
+    pub annex: Option<Vec<u8>>,
+}
+
+impl UnsignedRevealTransaction {
+    /// Returns the message to be signed for the commit output, i.e. the taproot script spend
+    /// sighash of the redeem script leaf, committing to the annex if any, for P2TR commit
+    /// outputs, or the segwit v0 sighash of the redeem script for P2WSH commit outputs, with
+    /// `SIGHASH_DEFAULT` and `SIGHASH_ALL` respectively.
+    pub fn sighash(&self) -> OrdResult<secp256k1::Message> {
+        let mut cache = SighashCache::new(&self.unsigned_tx);
+        let message = match &self.control_block {
+            Some(control_block) => secp256k1::Message::from(verify::taproot_script_spend_sighash(
+                &mut cache,
+                0,
+                &Prevouts::All(&self.prevouts()),
+                TapLeafHash::from_script(&self.redeem_script, control_block.leaf_version),
+                self.annex.as_deref(),
+                TapSighashType::Default,
+            )?),
+            None => secp256k1::Message::from(cache.p2wsh_signature_hash(
+                0,
+                &self.redeem_script,
+                self.prevout.value,
+                EcdsaSighashType::All,
+            )?),
+        };
+
+        Ok(message)
+    }
+
+    /// Returns the outputs spent by the inputs of the transaction, i.e. the commit output and
+    /// the parent output, if any.
+    pub fn prevouts(&self) -> Vec<TxOut> {
+        std::iter::once(self.prevout.clone())
+            .chain(self.parent.as_ref().map(ParentInfo::return_output))
+            .collect()
+    }
+
+    /// Returns the ID of the inscription revealed by the transaction.
+    ///
+    /// Signing doesn't change the txid, since it doesn't commit to the witness, so the ID is the
+    /// same once the transaction is signed.
+    pub fn inscription_id(&self) -> InscriptionId {
+        InscriptionId {
+            txid: self.unsigned_tx.txid(),
+            index: 0,
+        }
+    }
+
+    /// Rebinds the reveal input to the commit transaction, e.g. once signed, for reveals
+    /// prepared over a placeholder commit txid, such as an all-zero txid.
+    ///
+    /// Only the txid of the spent outpoint is replaced, so the reveal can be prepared and
+    /// approved ahead of the commit, leaving only the [`Self::sighash`] and the signature to
+    /// compute once the commit txid is known. The reveal txid, and thus the
+    /// [`Self::inscription_id`], change along with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::CommitOutputMismatch`] if the commit output spent by the reveal
+    /// doesn't match its prevout.
+    pub fn rebind_commit(&mut self, commit: &Transaction) -> OrdResult<()> {
+        let vout = self.unsigned_tx.input[0].previous_output.vout;
+        if commit.output.get(vout as usize) != Some(&self.prevout) {
+            return Err(OrdError::CommitOutputMismatch(vout));
+        }
+
+        let txin = &mut self.unsigned_tx.input[0];
+        txin.previous_output.txid = commit.txid();
+        txin.witness.clear();
+        Ok(())
+    }
+}
+
+/// Defines how the leftovers of a commit transaction are handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangePolicy {
+    /// Leftovers are always sent back to the leftovers recipient.
+    #[default]
+    Always,
+    /// Leftovers below the threshold are added to the commit fee, so the commit transaction
+    /// has no change output. This avoids dust change when fees are high and doesn't link
+    /// the inscription to the funding wallet.
+    Sweep {
+        /// Leftovers below this amount are not returned as change
+        threshold: Amount,
+    },
+    /// Leftovers below the dust limit of the leftovers recipient are added to the postage of
+    /// the inscription, rather than to the commit fee, so they're kept by the holder of the
+    /// inscription. The reveal transaction must then be built with
+    /// [`CreateCommitTransaction::postage`].
+    MergeIntoPostage,
+    /// The commit transaction never has a change output, e.g. for commits funded with the exact
+    /// amount of the inscription, which saves the size of the output and avoids dust change.
+    /// Leftovers up to the tolerance are added to the commit fee, and larger leftovers make the
+    /// commit fail with [`OrdError::UnexpectedLeftovers`].
+    Omit {
+        /// Leftovers up to this amount are added to the commit fee
+        tolerance: Amount,
+    },
+}
+
+impl ChangePolicy {
+    /// Returns whether the given leftover amount should be swept into the fee.
+    pub fn should_sweep(&self, leftover_amount: Amount) -> bool {
+        match self {
+            Self::Always | Self::MergeIntoPostage | Self::Omit { .. } => false,
+            Self::Sweep { threshold } => leftover_amount < *threshold,
+        }
+    }
+
+    /// Returns whether the given leftover amount, sent to `leftovers_script_pubkey`, should be
+    /// merged into the postage of the inscription.
+    pub fn should_merge_into_postage(
+        &self,
+        leftover_amount: Amount,
+        leftovers_script_pubkey: &ScriptBuf,
+    ) -> bool {
+        match self {
+            Self::MergeIntoPostage => leftover_amount < dust_limit(leftovers_script_pubkey),
+            Self::Always | Self::Sweep { .. } | Self::Omit { .. } => false,
+        }
+    }
+
+    /// Returns whether the commit transaction has no change output regardless of the leftovers.
+    pub fn omits_change(&self) -> bool {
+        matches!(self, Self::Omit { .. })
+    }
 }
 
-/// Type of the script to use. Both are supported, but P2WSH may not be supported by all the indexers
+/// Type of the script to use. All are supported, but P2WSH may not be supported by all the indexers
 /// So P2TR is preferred
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ScriptType {
     P2WSH,
     P2TR,
+    /// P2WSH wrapped in P2SH, recognized by some legacy indexers. The reveal input carries the
+    /// P2WSH program in its script sig, in addition to the P2WSH witness.
+    P2ShP2Wsh,
+}
+
+impl ScriptType {
+    /// Returns the script pubkey of the commit output locking the redeem script, or `None`
+    /// for P2TR, whose output key also commits to the internal key.
+    pub fn commit_script_pubkey(self, redeem_script: &Script) -> Option<ScriptBuf> {
+        let p2wsh = ScriptBuf::new_p2wsh(&redeem_script.wscript_hash());
+        match self {
+            Self::P2WSH => Some(p2wsh),
+            Self::P2ShP2Wsh => Some(ScriptBuf::new_p2sh(&p2wsh.script_hash())),
+            Self::P2TR => None,
+        }
+    }
+
+    /// Returns the script sig of the reveal input spending the commit output, which is empty
+    /// unless the redeem script is wrapped in P2SH.
+    pub(crate) fn reveal_script_sig(self, redeem_script: &Script) -> OrdResult<ScriptBuf> {
+        match self {
+            Self::P2ShP2Wsh => {
+                let p2wsh = ScriptBuf::new_p2wsh(&redeem_script.wscript_hash());
+                Ok(ScriptBuilder::new()
+                    .push_slice(PushBytesBuf::try_from(p2wsh.into_bytes())?)
+                    .into_script())
+            }
+            Self::P2WSH | Self::P2TR => Ok(ScriptBuf::new()),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -198,13 +872,167 @@ impl OrdTransactionBuilder {
         self.taproot_payload.as_ref()
     }
 
+    /// Returns the leaf hash and the merkle root of the P2TR commit output of the redeem script,
+    /// or `None` for other script types.
+    fn taproot_commitment(
+        &self,
+        redeem_script: &Script,
+    ) -> (Option<TapLeafHash>, Option<TapNodeHash>) {
+        match (self.script_type, self.taproot_payload.as_ref()) {
+            (ScriptType::P2TR, Some(taproot_payload)) => {
+                let (tapleaf_hash, merkle_root) = taproot_payload.commitment(redeem_script);
+                (Some(tapleaf_hash), Some(merkle_root))
+            }
+            _ => (None, None),
+        }
+    }
+
     /// Creates the commit transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::TransactionTooHeavy`] if the reveal transaction would exceed the
+    /// [`max_standard_tx_weight`](crate::constants::max_standard_tx_weight) of the network, so
+    /// the commit output could never be spent by a relayed transaction, or
+    /// [`OrdError::InsufficientBalance`] if the inputs don't cover the postage and the fees.
     pub async fn build_commit_transaction<T>(
         &mut self,
         network: Network,
         recipient_address: Address,
         args: CreateCommitTransactionArgs<T>,
     ) -> OrdResult<CreateCommitTransaction>
+    where
+        T: Inscription,
+    {
+        self.build_commit_transaction_funded_by(
+            network,
+            recipient_address,
+            CommitParams {
+                inscription: &args.inscription,
+                funding: CommitFunding::Uniform(&args.inputs),
+                change_script_pubkey: &args.txin_script_pubkey,
+                fee_rate: args.fee_rate,
+                reveal_fee_rate: args.fee_rate,
+                multisig_config: &args.multisig_config,
+                derivation_path: args.derivation_path,
+                change_policy: args.change_policy,
+                parent: args.parent.as_ref(),
+            },
+        )
+        .await
+    }
+
+    /// Creates the commit transaction, reserving the reveal fee at `reveal_fee_rate` rather than
+    /// at the fee rate of the commit transaction.
+    ///
+    /// The reveal transaction is then built with [`Self::build_reveal_transaction`] as usual:
+    /// its fee is the reveal balance left over by the postage.
+    pub async fn build_commit_transaction_with_reveal_fee_rate<T>(
+        &mut self,
+        network: Network,
+        recipient_address: Address,
+        args: CreateCommitTransactionArgs<T>,
+        reveal_fee_rate: FeeRate,
+    ) -> OrdResult<CreateCommitTransaction>
+    where
+        T: Inscription,
+    {
+        self.build_commit_transaction_funded_by(
+            network,
+            recipient_address,
+            CommitParams {
+                inscription: &args.inscription,
+                funding: CommitFunding::Uniform(&args.inputs),
+                change_script_pubkey: &args.txin_script_pubkey,
+                fee_rate: args.fee_rate,
+                reveal_fee_rate,
+                multisig_config: &args.multisig_config,
+                derivation_path: args.derivation_path,
+                change_policy: args.change_policy,
+                parent: args.parent.as_ref(),
+            },
+        )
+        .await
+    }
+
+    /// Creates the commit transaction with the fee rates of the commit and reveal targets, as
+    /// estimated by the fee provider. The fee rate of the arguments is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::NoFeeEstimate`] if the provider has no estimate, or any error of the
+    /// provider.
+    pub async fn build_commit_transaction_with_fee_targets<T, P>(
+        &mut self,
+        network: Network,
+        recipient_address: Address,
+        mut args: CreateCommitTransactionArgs<T>,
+        provider: &P,
+        targets: InscriptionFeeTargets,
+    ) -> OrdResult<CreateCommitTransaction>
+    where
+        T: Inscription,
+        P: FeeProvider + Sync + ?Sized,
+    {
+        let fee_rates = targets.fee_rates(provider).await?;
+        debug!(
+            "commit fee rate: {}, reveal fee rate: {}",
+            fee_rates.commit, fee_rates.reveal
+        );
+        args.fee_rate = fee_rates.commit;
+
+        self.build_commit_transaction_with_reveal_fee_rate(
+            network,
+            recipient_address,
+            args,
+            fee_rates.reveal,
+        )
+        .await
+    }
+
+    /// Creates the commit transaction funded by outputs of different script types, e.g. the
+    /// P2WPKH, P2TR and P2SH-P2WPKH outputs of a single wallet.
+    ///
+    /// The commit fee accounts for the witness of each input, as for its script type. The
+    /// returned transaction is signed with [`Self::sign_transaction`], given the same inputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidScriptType`] if an input is not a P2WPKH, P2TR or P2SH output,
+    /// or [`OrdError::InsufficientBalance`] if the inputs don't cover the fees.
+    pub async fn build_mixed_commit_transaction<T>(
+        &mut self,
+        network: Network,
+        recipient_address: Address,
+        args: CreateMixedCommitTransactionArgs<T>,
+    ) -> OrdResult<CreateCommitTransaction>
+    where
+        T: Inscription,
+    {
+        self.build_commit_transaction_funded_by(
+            network,
+            recipient_address,
+            CommitParams {
+                inscription: &args.inscription,
+                funding: CommitFunding::Mixed(&args.inputs),
+                change_script_pubkey: &args.leftovers_recipient.script_pubkey(),
+                fee_rate: args.fee_rate,
+                reveal_fee_rate: args.fee_rate,
+                multisig_config: &None,
+                derivation_path: args.derivation_path,
+                change_policy: args.change_policy,
+                parent: None,
+            },
+        )
+        .await
+    }
+
+    async fn build_commit_transaction_funded_by<T>(
+        &mut self,
+        network: Network,
+        recipient_address: Address,
+        args: CommitParams<'_, T>,
+    ) -> OrdResult<CreateCommitTransaction>
     where
         T: Inscription,
     {
@@ -213,36 +1041,62 @@ impl OrdTransactionBuilder {
         let p2tr_pubkey = match self.script_type {
             ScriptType::P2TR => Some(
                 self.signer
-                    .signer
-                    .schnorr_public_key(&args.derivation_path.unwrap_or_default())
+                    .schnorr_public_key(&args.derivation_path.clone().unwrap_or_default())
                     .await?,
             ),
-            ScriptType::P2WSH => None,
+            ScriptType::P2WSH | ScriptType::P2ShP2Wsh => None,
         };
 
         // generate redeem script pubkey based on the current script type
         let redeem_script_pubkey = match self.script_type {
-            ScriptType::P2WSH => RedeemScriptPubkey::Ecdsa(self.public_key),
+            ScriptType::P2WSH | ScriptType::P2ShP2Wsh => RedeemScriptPubkey::Ecdsa(self.public_key),
             ScriptType::P2TR => RedeemScriptPubkey::XPublickey(p2tr_pubkey.unwrap()),
         };
 
-        let redeem_script = self.generate_redeem_script(&args.inscription, redeem_script_pubkey)?;
+        let redeem_script = self.generate_redeem_script(args.inscription, redeem_script_pubkey)?;
         debug!("redeem_script: {redeem_script}");
 
-        let reveal_fee = estimate_reveal_fee(
-            vec![OutPoint::null()],
-            recipient_address,
-            redeem_script.clone(),
-            self.script_type,
-            args.fee_rate,
-            &args.multisig_config,
-        );
+        // a reveal transaction which wouldn't be relayed would leave the funds in the commit
+        check_standard_tx_weight(
+            network,
+            estimate_reveal_weight(
+                &recipient_address.script_pubkey(),
+                &redeem_script,
+                self.script_type,
+                args.multisig_config,
+                args.parent.map(ParentInfo::return_output),
+            )?,
+        )?;
+
+        let reveal_fee = match args.parent {
+            Some(parent) => {
+                parent.check_declared_by(&redeem_script)?;
+                let reveal_fee = parent.estimate_reveal_fee(
+                    &recipient_address.script_pubkey(),
+                    redeem_script.clone(),
+                    self.script_type,
+                    args.reveal_fee_rate,
+                    args.multisig_config,
+                )?;
+                parent.check_returned(reveal_fee)?;
+                reveal_fee
+            }
+            None => estimate_reveal_fee(
+                vec![OutPoint::null()],
+                recipient_address,
+                redeem_script.clone(),
+                self.script_type,
+                args.reveal_fee_rate,
+                args.multisig_config,
+            )?,
+        };
 
-        let reveal_balance = POSTAGE + reveal_fee.to_sat();
+        let reveal_balance = POSTAGE + reveal_fee;
         debug!("reveal_balance: {reveal_balance}");
 
         let script_output_address = match self.script_type {
             ScriptType::P2WSH => Address::p2wsh(&redeem_script, network),
+            ScriptType::P2ShP2Wsh => Address::p2shwsh(&redeem_script, network),
             ScriptType::P2TR => {
                 let taproot_payload = TaprootPayload::build(
                     &secp_ctx,
@@ -261,30 +1115,30 @@ impl OrdTransactionBuilder {
 
         let mut tx_out = vec![
             TxOut {
-                value: Amount::from_sat(reveal_balance),
+                value: reveal_balance,
                 script_pubkey: script_output_address.script_pubkey(),
             },
             TxOut {
                 value: Amount::ZERO, // placeholder for leftover amount, which is calculated later
-                script_pubkey: args.txin_script_pubkey.clone(),
+                script_pubkey: args.change_script_pubkey.clone(),
             },
         ];
+        if args.change_policy.omits_change() {
+            tx_out.truncate(1);
+        }
 
         let tx_in: Vec<TxIn> = args
-            .inputs
-            .iter()
-            .map(|input| TxIn {
-                previous_output: OutPoint {
-                    txid: input.id,
-                    vout: input.index,
-                },
+            .funding
+            .outpoints()
+            .map(|previous_output| TxIn {
+                previous_output,
                 script_sig: ScriptBuf::new(),
                 sequence: Sequence::from_consensus(0xffffffff),
                 witness: Witness::new(),
             })
             .collect();
 
-        let commit_fee = estimate_commit_fee(
+        let commit_fee = args.estimate_commit_fee(
             Transaction {
                 version: Version::TWO,
                 lock_time: LockTime::ZERO,
@@ -292,28 +1146,65 @@ impl OrdTransactionBuilder {
                 output: tx_out.clone(),
             },
             self.script_type,
-            args.fee_rate,
-            &args.multisig_config,
-        );
+        )?;
 
         // calc balance
         // exceeding amount of transaction to send to leftovers recipient
-        let input_amount = args
-            .inputs
-            .iter()
-            .map(|input| input.amount.to_sat())
-            .sum::<u64>();
+        let input_amount = args.funding.input_amount();
         let leftover_amount = input_amount
             .checked_sub(POSTAGE)
-            .and_then(|v| v.checked_sub(commit_fee.to_sat()))
-            .and_then(|v| v.checked_sub(reveal_fee.to_sat()))
+            .and_then(|v| v.checked_sub(commit_fee))
+            .and_then(|v| v.checked_sub(reveal_fee))
             .ok_or(OrdError::InsufficientBalance {
                 available: input_amount,
-                required: POSTAGE + commit_fee.to_sat() + reveal_fee.to_sat(),
+                required: POSTAGE + commit_fee + reveal_fee,
             })?;
         debug!("leftover_amount: {leftover_amount}");
 
-        tx_out[1].value = Amount::from_sat(leftover_amount);
+        let mut postage = POSTAGE;
+        let mut reveal_balance = reveal_balance;
+        let (commit_fee, leftover_amount) =
+            if let ChangePolicy::Omit { tolerance } = args.change_policy {
+                if leftover_amount > tolerance {
+                    return Err(OrdError::UnexpectedLeftovers {
+                        leftover: leftover_amount,
+                        tolerance,
+                    });
+                }
+                debug!("burning leftover_amount to the commit fee");
+                (commit_fee + leftover_amount, Amount::ZERO)
+            } else if args.change_policy.should_sweep(leftover_amount) {
+                debug!("sweeping leftover_amount into the commit fee");
+                tx_out.truncate(1);
+                (commit_fee + leftover_amount, Amount::ZERO)
+            } else if args
+                .change_policy
+                .should_merge_into_postage(leftover_amount, args.change_script_pubkey)
+            {
+                debug!("merging leftover_amount into the postage");
+                // the change output is dropped, so it's no longer paid by the commit fee
+                tx_out.truncate(1);
+                let commit_fee = args.estimate_commit_fee(
+                    Transaction {
+                        version: Version::TWO,
+                        lock_time: LockTime::ZERO,
+                        input: tx_in.clone(),
+                        output: tx_out.clone(),
+                    },
+                    self.script_type,
+                )?;
+                let merged = input_amount - reveal_balance - commit_fee;
+                postage += merged;
+                reveal_balance += merged;
+                tx_out[0].value = reveal_balance;
+                if let Some(taproot_payload) = self.taproot_payload.as_mut() {
+                    taproot_payload.prevouts.value = reveal_balance;
+                }
+                (commit_fee, Amount::ZERO)
+            } else {
+                tx_out[1].value = leftover_amount;
+                (commit_fee, leftover_amount)
+            };
 
         // make transaction and sign it
         let unsigned_tx = Transaction {
@@ -323,13 +1214,18 @@ impl OrdTransactionBuilder {
             output: tx_out,
         };
 
+        let (tapleaf_hash, merkle_root) = self.taproot_commitment(&redeem_script);
         Ok(CreateCommitTransaction {
             unsigned_tx,
             redeem_script,
-            reveal_balance: Amount::from_sat(reveal_balance),
+            reveal_balance,
             commit_fee,
             reveal_fee,
-            leftover_amount: Amount::from_sat(leftover_amount),
+            leftover_amount,
+            postage,
+            parent: args.parent.cloned(),
+            tapleaf_hash,
+            merkle_root,
         })
     }
 
@@ -361,63 +1257,312 @@ impl OrdTransactionBuilder {
     }
 
     /// Create the reveal transaction
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::DustOutput`] if the postage is below the dust limit of the recipient,
+    /// or [`OrdError::Timelock`] if the timelocks of the redeem script are not satisfied.
     pub async fn build_reveal_transaction(
         &mut self,
         args: RevealTransactionArgs,
     ) -> OrdResult<Transaction> {
+        self.build_reveal_transaction_to_script(args.into()).await
+    }
+
+    /// Create the reveal transaction sending the inscription to an arbitrary script,
+    /// so it can be received directly by a multisig.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::DustOutput`] if the postage is below the dust limit of the recipient,
+    /// or [`OrdError::Timelock`] if the timelocks of the redeem script are not satisfied.
+    pub async fn build_reveal_transaction_to_script(
+        &mut self,
+        args: RevealToScriptArgs,
+    ) -> OrdResult<Transaction> {
+        let reveal = self.prepare_reveal_transaction(args)?;
+        self.sign_reveal(reveal).await
+    }
+
+    /// Create the unsigned reveal transaction, accepting either [`RevealTransactionArgs`] or
+    /// [`RevealToScriptArgs`], so it can be inspected, approved or signed externally before
+    /// being signed with [`Self::sign_reveal`], as done for the commit transaction.
+    ///
+    /// The reveal can be prepared before the commit txid is known, with a placeholder txid in
+    /// the input, and bound to the commit with [`UnsignedRevealTransaction::rebind_commit`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::DustOutput`] if the postage is below the dust limit of the recipient,
+    /// [`OrdError::InsufficientBalance`] if it's larger than the input, [`OrdError::Timelock`]
+    /// if the timelocks of the redeem script are not satisfied, [`OrdError::TaprootCompute`] if
+    /// the builder has no taproot payload nor control block for a P2TR commit output, or
+    /// [`OrdError::TransactionTooHeavy`] if the signed reveal would exceed the standard weight
+    /// of its network.
+    pub fn prepare_reveal_transaction(
+        &self,
+        args: impl Into<RevealToScriptArgs>,
+    ) -> OrdResult<UnsignedRevealTransaction> {
+        let args = args.into();
+        let postage = args.postage.unwrap_or(POSTAGE);
+        let dust_limit = dust_limit(&args.recipient_script_pubkey);
+        if postage < dust_limit {
+            return Err(OrdError::DustOutput {
+                value: postage,
+                dust_limit,
+            });
+        }
+
+        let timelock = match args.timelock {
+            Some(timelock) => {
+                timelock.verify(&args.redeem_script)?;
+                timelock
+            }
+            None => RevealTimelock::for_script(&args.redeem_script)?,
+        };
+
         // previous output
         let previous_output = OutPoint {
             txid: args.input.id,
             vout: args.input.index,
         };
 
-        // tx out
-        let tx_out = vec![TxOut {
-            value: Amount::from_sat(POSTAGE),
-            script_pubkey: args.recipient_address.script_pubkey(),
+        let reveal_fee =
+            args.input
+                .amount
+                .checked_sub(postage)
+                .ok_or(OrdError::InsufficientBalance {
+                    required: postage,
+                    available: args.input.amount,
+                })?;
+        // an anchored reveal pays no fee, the sats reserved for it are kept by the inscription
+        let (postage, reveal_fee) = if args.anchor {
+            (args.input.amount, Amount::ZERO)
+        } else {
+            (postage, reveal_fee)
+        };
+
+        // tx out, returning the parent inscription after the inscription
+        let mut tx_out = vec![TxOut {
+            value: postage,
+            script_pubkey: args.recipient_script_pubkey,
         }];
+        if let Some(parent) = &args.parent {
+            parent.check_returned(reveal_fee)?;
+            tx_out.push(parent.return_output());
+        }
+        if args.anchor {
+            tx_out.push(TxOut {
+                value: Amount::ZERO,
+                script_pubkey: anchor_script_pubkey(),
+            });
+        }
+
+        let (prevout, control_block) = match (args.control_block, self.taproot_payload.as_ref()) {
+            (Some(control_block), _) => {
+                let prevout = TxOut {
+                    value: args.input.amount,
+                    script_pubkey: taproot::script_pubkey_from_control_block(
+                        &secp256k1::Secp256k1::verification_only(),
+                        &control_block,
+                        &args.redeem_script,
+                    ),
+                };
+                (prevout, Some(control_block))
+            }
+            (None, Some(taproot_payload)) => (
+                taproot_payload.prevouts.clone(),
+                Some(taproot_payload.control_block.clone()),
+            ),
+            (None, None) => (
+                TxOut {
+                    value: args.input.amount,
+                    // P2TR commit outputs can't be spent without a control block
+                    script_pubkey: self
+                        .script_type
+                        .commit_script_pubkey(&args.redeem_script)
+                        .ok_or(OrdError::TaprootCompute)?,
+                },
+                None,
+            ),
+        };
+
+        if let Some(annex) = &args.annex {
+            if control_block.is_none() {
+                return Err(OrdError::InvalidAnnex(
+                    "only taproot spends can have an annex",
+                ));
+            }
+            verify::parse_annex(annex)?;
+        }
 
         // txin
-        let tx_in = vec![TxIn {
+        let script_sig = if prevout.script_pubkey.is_p2sh() {
+            ScriptType::P2ShP2Wsh.reveal_script_sig(&args.redeem_script)?
+        } else {
+            ScriptBuf::new()
+        };
+        let mut tx_in = vec![TxIn {
             previous_output,
-            script_sig: ScriptBuf::new(),
-            sequence: Sequence::from_consensus(0xffffffff),
+            script_sig,
+            sequence: timelock.sequence,
             witness: Witness::new(),
         }];
+        if let Some(parent) = &args.parent {
+            tx_in.push(TxIn {
+                previous_output: parent.input.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            });
+        }
 
-        // make transaction and sign it
         let unsigned_tx = Transaction {
             version: Version::TWO,
-            lock_time: LockTime::ZERO,
+            lock_time: timelock.lock_time,
             input: tx_in,
             output: tx_out,
         };
+        if let Some(network) = args.network {
+            let weight = estimate_signed_reveal_weight(
+                &unsigned_tx,
+                &args.redeem_script,
+                control_block.as_ref(),
+                args.annex.as_deref(),
+                args.parent.as_ref().map(|parent| &parent.input.tx_out),
+            )?;
+            check_standard_tx_weight(network, weight)?;
+        }
 
-        let tx = match self.taproot_payload.as_ref() {
-            Some(taproot_payload) => {
+        Ok(UnsignedRevealTransaction {
+            unsigned_tx,
+            prevout,
+            redeem_script: args.redeem_script,
+            derivation_path: args.derivation_path,
+            control_block,
+            parent: args.parent,
+            annex: args.annex,
+        })
+    }
+
+    /// Sign the reveal transaction returned by [`Self::prepare_reveal_transaction`].
+    ///
+    /// The parent input of a child inscription, if any, is signed as by
+    /// [`Self::sign_transaction`].
+    pub async fn sign_reveal(
+        &mut self,
+        reveal: UnsignedRevealTransaction,
+    ) -> OrdResult<Transaction> {
+        let prevouts = reveal.prevouts();
+        let UnsignedRevealTransaction {
+            unsigned_tx,
+            prevout,
+            redeem_script,
+            derivation_path,
+            control_block,
+            parent,
+            annex,
+        } = reveal;
+
+        let derivation_path = derivation_path.unwrap_or_default();
+        let tx = match control_block {
+            Some(control_block) => {
                 self.signer
-                    .sign_reveal_transaction_schnorr(
+                    .sign_reveal_input_script_path(
                         &self.public_key,
-                        taproot_payload,
-                        &args.redeem_script,
+                        &control_block,
+                        &prevouts,
+                        &redeem_script,
+                        annex.as_deref(),
                         unsigned_tx,
-                        &args.derivation_path.unwrap_or_default(),
+                        &derivation_path,
                     )
                     .await
             }
             None => {
+                let input = Utxo {
+                    id: unsigned_tx.input[0].previous_output.txid,
+                    index: unsigned_tx.input[0].previous_output.vout,
+                    amount: prevout.value,
+                };
                 self.signer
                     .sign_reveal_transaction_ecdsa(
                         &self.public_key,
-                        &args.input,
+                        &input,
                         unsigned_tx,
-                        &args.redeem_script,
+                        &redeem_script,
+                        &derivation_path,
                     )
                     .await
             }
         }?;
+        let Some(parent) = parent else {
+            return Ok(tx);
+        };
+
+        self.signer
+            .sign_inputs(&tx, &prevouts, std::iter::once((1, &parent.input)))
+            .await
+    }
 
-        Ok(tx)
+    /// Create the reveal transaction, after checking that the redeem script commits to the given
+    /// inscription and to the public key of the builder.
+    ///
+    /// Signing a reveal with a wrong redeem script produces an invalid transaction, which leaves
+    /// the commit funds stuck, so this should be preferred when the redeem script is not taken
+    /// directly from the `CreateCommitTransaction` returned by this builder.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::RedeemScriptMismatch`] if the redeem script doesn't match, or any
+    /// error returned by [`Self::build_reveal_transaction`].
+    pub async fn build_reveal_transaction_checked<T>(
+        &mut self,
+        args: RevealTransactionArgs,
+        inscription: &T,
+    ) -> OrdResult<Transaction>
+    where
+        T: Inscription,
+    {
+        self.verify_redeem_script(
+            inscription,
+            &args.redeem_script,
+            args.derivation_path.as_ref(),
+        )
+        .await?;
+
+        self.build_reveal_transaction(args).await
+    }
+
+    /// Checks that the redeem script is the one generated by the builder for the inscription.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::RedeemScriptMismatch`] if the redeem script doesn't match.
+    pub async fn verify_redeem_script<T>(
+        &self,
+        inscription: &T,
+        redeem_script: &ScriptBuf,
+        derivation_path: Option<&DerivationPath>,
+    ) -> OrdResult<()>
+    where
+        T: Inscription,
+    {
+        let redeem_script_pubkey = match self.script_type {
+            ScriptType::P2WSH | ScriptType::P2ShP2Wsh => RedeemScriptPubkey::Ecdsa(self.public_key),
+            ScriptType::P2TR => RedeemScriptPubkey::XPublickey(
+                self.signer
+                    .schnorr_public_key(&derivation_path.cloned().unwrap_or_default())
+                    .await?,
+            ),
+        };
+
+        let expected = self.generate_redeem_script(inscription, redeem_script_pubkey)?;
+        if &expected != redeem_script {
+            return Err(OrdError::RedeemScriptMismatch);
+        }
+
+        Ok(())
     }
 
     /// Generate redeem script from script pubkey and inscription
@@ -450,6 +1595,12 @@ impl OrdTransactionBuilder {
     }
 
     /// Creates the commit transaction with predetermined commit and reveal fees.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::TransactionTooHeavy`] if the reveal transaction would exceed the
+    /// standard weight of the network, or [`OrdError::InsufficientBalance`] if the inputs don't
+    /// cover the postage and the fees.
     pub async fn build_commit_transaction_with_fixed_fees<T>(
         &mut self,
         network: Network,
@@ -464,45 +1615,53 @@ impl OrdTransactionBuilder {
         let p2tr_pubkey = match self.script_type {
             ScriptType::P2TR => Some(
                 self.signer
-                    .signer
                     .schnorr_public_key(&args.derivation_path.unwrap_or_default())
                     .await?,
             ),
-            ScriptType::P2WSH => None,
+            ScriptType::P2WSH | ScriptType::P2ShP2Wsh => None,
         };
 
         // generate redeem script pubkey based on the current script type
         let redeem_script_pubkey = match self.script_type {
-            ScriptType::P2WSH => RedeemScriptPubkey::Ecdsa(self.public_key),
+            ScriptType::P2WSH | ScriptType::P2ShP2Wsh => RedeemScriptPubkey::Ecdsa(self.public_key),
             ScriptType::P2TR => RedeemScriptPubkey::XPublickey(p2tr_pubkey.unwrap()),
         };
 
         let redeem_script = self.generate_redeem_script(&args.inscription, redeem_script_pubkey)?;
         debug!("redeem_script: {redeem_script}");
 
+        // the recipient of the reveal is unknown, its output weighs about the same as the change
+        check_standard_tx_weight(
+            network,
+            estimate_reveal_weight(
+                &args.leftovers_recipient.script_pubkey(),
+                &redeem_script,
+                self.script_type,
+                &None,
+                None,
+            )?,
+        )?;
+
         // calc balance
         // exceeding amount of transaction to send to leftovers recipient
-        let input_amount = args
-            .inputs
-            .iter()
-            .map(|input| input.amount.to_sat())
-            .sum::<u64>();
+        let input_amount = args.inputs.iter().map(|input| input.amount).sum::<Amount>();
         let leftover_amount = input_amount
             .checked_sub(POSTAGE)
-            .and_then(|v| v.checked_sub(args.commit_fee.to_sat()))
-            .and_then(|v| v.checked_sub(args.reveal_fee.to_sat()))
+            .and_then(|v| v.checked_sub(args.commit_fee))
+            .and_then(|v| v.checked_sub(args.reveal_fee))
             .ok_or(OrdError::InsufficientBalance {
                 available: input_amount,
-                required: POSTAGE + args.commit_fee.to_sat() + args.reveal_fee.to_sat(),
+                required: POSTAGE + args.commit_fee + args.reveal_fee,
             })?;
         debug!("leftover_amount: {leftover_amount}");
 
-        let reveal_balance = POSTAGE + args.reveal_fee.to_sat();
+        let reveal_balance = POSTAGE + args.reveal_fee;
         debug!("reveal_balance: {reveal_balance}");
 
         // get p2wsh or p2tr address for output of inscription
         let script_output_address = match self.script_type {
             ScriptType::P2WSH => Address::p2wsh(&redeem_script, network),
+            ScriptType::P2ShP2Wsh => Address::p2shwsh(&redeem_script, network),
             ScriptType::P2TR => {
                 let taproot_payload = TaprootPayload::build(
                     &secp_ctx,
@@ -521,11 +1680,11 @@ impl OrdTransactionBuilder {
 
         let tx_out = vec![
             TxOut {
-                value: Amount::from_sat(reveal_balance),
+                value: reveal_balance,
                 script_pubkey: script_output_address.script_pubkey(),
             },
             TxOut {
-                value: Amount::from_sat(leftover_amount),
+                value: leftover_amount,
                 script_pubkey: args.txin_script_pubkey.clone(),
             },
         ];
@@ -553,13 +1712,18 @@ impl OrdTransactionBuilder {
             output: tx_out,
         };
 
+        let (tapleaf_hash, merkle_root) = self.taproot_commitment(&redeem_script);
         Ok(CreateCommitTransaction {
             unsigned_tx,
             redeem_script,
-            reveal_balance: Amount::from_sat(reveal_balance),
+            reveal_balance,
             reveal_fee: args.reveal_fee,
             commit_fee: args.commit_fee,
-            leftover_amount: Amount::from_sat(leftover_amount),
+            leftover_amount,
+            postage: POSTAGE,
+            parent: None,
+            tapleaf_hash,
+            merkle_root,
         })
     }
 }
@@ -569,14 +1733,16 @@ mod test {
     use std::str::FromStr;
 
     use bitcoin::secp256k1::Secp256k1;
-    use bitcoin::PrivateKey;
+    use bitcoin::taproot::LeafVersion;
     use hex_literal::hex;
 
     use super::*;
-    use crate::Brc20;
+    use crate::utils::test_utils::{
+        generate_btc_address, test_private_key, test_txid, test_utxo, TestWallet,
+    };
+    use crate::{Brc20, Nft, OrdParser};
 
     // <https://mempool.space/testnet/address/tb1qzc8dhpkg5e4t6xyn4zmexxljc4nkje59dg3ark>
-    const WIF: &str = "cVkWbHmoCx6jS8AyPNQqvFr8V9r2qzDHJLaxGDQgDJfxT73w6fuU";
 
     #[tokio::test]
     async fn test_should_build_transfer_for_brc20_transactions_from_existing_data_with_p2wsh_with_fixed_rate(
@@ -585,18 +1751,13 @@ mod test {
         // <https://mempool.space/testnet/tx/4472899344bce1a6c83c6ec45859f79ab622b55b3faf67e555e3e03cee5139e6>
         // <https://mempool.space/testnet/tx/c769750df54ee38fe2bae876dbf1632c779c3af780958a19cee1ca0497c78e80>
         // made by address tb1qzc8dhpkg5e4t6xyn4zmexxljc4nkje59dg3ark
-        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let private_key = test_private_key();
         let public_key = private_key.public_key(&Secp256k1::new());
         let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
 
         let mut builder = OrdTransactionBuilder::p2wsh(private_key);
 
-        let inputs = vec![Utxo {
-            id: Txid::from_str("791b415dc6946d864d368a0e5ec5c09ee2ad39cf298bc6e3f9aec293732cfda7")
-                .unwrap(), // the transaction that funded our wallet
-            index: 1,
-            amount: Amount::from_sat(8_000),
-        }];
+        let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
         let commit_transaction_args = CreateCommitTransactionArgsV2 {
             inputs: inputs.clone(),
             txin_script_pubkey: address.script_pubkey(),
@@ -610,6 +1771,8 @@ mod test {
             .build_commit_transaction_with_fixed_fees(Network::Testnet, commit_transaction_args)
             .await
             .unwrap();
+        assert!(tx_result.tapleaf_hash.is_none());
+        assert!(tx_result.merkle_root.is_none());
 
         // sign
         let sign_args = SignCommitTransactionArgs {
@@ -642,11 +1805,7 @@ mod test {
         // txin
         assert_eq!(tx.input.len(), 1);
         assert_eq!(tx.input[0].sequence, Sequence::from_consensus(0xffffffff));
-        assert_eq!(
-            tx.input[0].previous_output.txid,
-            Txid::from_str("791b415dc6946d864d368a0e5ec5c09ee2ad39cf298bc6e3f9aec293732cfda7",)
-                .unwrap()
-        );
+        assert_eq!(tx.input[0].previous_output.txid, test_txid());
 
         // txout
         assert_eq!(tx.output.len(), 2);
@@ -660,16 +1819,15 @@ mod test {
             .unwrap();
 
         let reveal_transaction = builder
-            .build_reveal_transaction(RevealTransactionArgs {
-                input: Utxo {
+            .build_reveal_transaction(RevealTransactionArgs::new(
+                Utxo {
                     id: tx_id,
                     index: 0,
                     amount: tx_result.reveal_balance,
                 },
-                recipient_address: recipient_address.clone(),
-                redeem_script: tx_result.redeem_script,
-                derivation_path: None,
-            })
+                recipient_address.clone(),
+                tx_result.redeem_script,
+            ))
             .await
             .unwrap();
 
@@ -682,10 +1840,7 @@ mod test {
         );
 
         assert_eq!(reveal_transaction.output.len(), 1);
-        assert_eq!(
-            reveal_transaction.output[0].value,
-            Amount::from_sat(POSTAGE)
-        );
+        assert_eq!(reveal_transaction.output[0].value, POSTAGE);
         assert_eq!(
             reveal_transaction.output[0].script_pubkey,
             recipient_address.script_pubkey()
@@ -699,18 +1854,13 @@ mod test {
         // <https://mempool.space/testnet/tx/973f78eb7b3cc666dc4133ff6381c363fd29edda0560d36ea3cfd31f1e85d9f9>
         // <https://mempool.space/testnet/tx/a35802655b63f1c99c1fd3ff8fdf3415f3abb735d647d402c0af5e9a73cbe4c6>
         // made by address tb1qzc8dhpkg5e4t6xyn4zmexxljc4nkje59dg3ark
-        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let private_key = test_private_key();
         let public_key = private_key.public_key(&Secp256k1::new());
         let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
 
         let mut builder = OrdTransactionBuilder::p2tr(private_key);
 
-        let inputs = vec![Utxo {
-            id: Txid::from_str("791b415dc6946d864d368a0e5ec5c09ee2ad39cf298bc6e3f9aec293732cfda7")
-                .unwrap(), // the transaction that funded our wallet
-            index: 1,
-            amount: Amount::from_sat(8_000),
-        }];
+        let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
         let commit_transaction_args = CreateCommitTransactionArgsV2 {
             inputs: inputs.clone(),
             txin_script_pubkey: address.script_pubkey(),
@@ -727,6 +1877,29 @@ mod test {
 
         assert!(builder.taproot_payload.is_some());
 
+        // the commit output commits to the redeem script as the only leaf of the script tree
+        let payload = builder.taproot_payload.as_ref().unwrap();
+        let tapleaf_hash = tx_result.tapleaf_hash.unwrap();
+        let merkle_root = tx_result.merkle_root.unwrap();
+        assert_eq!(
+            tapleaf_hash,
+            TapLeafHash::from_script(&tx_result.redeem_script, LeafVersion::TapScript)
+        );
+        assert_eq!(merkle_root, TapNodeHash::from(tapleaf_hash));
+        assert!(verify_p2tr_commitment(
+            &tx_result.unsigned_tx.output[0].script_pubkey,
+            payload.pubkey,
+            merkle_root
+        ));
+        assert!(!verify_p2tr_commitment(
+            &tx_result.unsigned_tx.output[0].script_pubkey,
+            payload.pubkey,
+            TapNodeHash::from(TapLeafHash::from_script(
+                &ScriptBuf::new(),
+                LeafVersion::TapScript
+            ))
+        ));
+
         // sign
         let sign_args = SignCommitTransactionArgs {
             inputs,
@@ -762,16 +1935,15 @@ mod test {
             .unwrap();
 
         let reveal_transaction = builder
-            .build_reveal_transaction(RevealTransactionArgs {
-                input: Utxo {
+            .build_reveal_transaction(RevealTransactionArgs::new(
+                Utxo {
                     id: tx_id,
                     index: 0,
                     amount: tx_result.reveal_balance,
                 },
-                recipient_address: recipient_address.clone(),
-                redeem_script: tx_result.redeem_script,
-                derivation_path: None,
-            })
+                recipient_address.clone(),
+                tx_result.redeem_script,
+            ))
             .await
             .unwrap();
 
@@ -779,33 +1951,485 @@ mod test {
         assert_eq!(witness.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_should_verify_signatures_after_signing() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        for script_type in [ScriptType::P2WSH, ScriptType::P2TR, ScriptType::P2ShP2Wsh] {
+            let wallet = Wallet::new_with_signer(LocalSigner::new(private_key))
+                .with_signature_verification(true);
+            let mut builder = OrdTransactionBuilder::new(public_key, script_type, wallet);
+
+            let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
+            let commit = builder
+                .build_commit_transaction_with_fixed_fees(
+                    Network::Testnet,
+                    CreateCommitTransactionArgsV2 {
+                        inputs: inputs.clone(),
+                        txin_script_pubkey: address.script_pubkey(),
+                        inscription: Brc20::transfer("mona".to_string(), 100),
+                        leftovers_recipient: address.clone(),
+                        commit_fee: Amount::from_sat(2_500),
+                        reveal_fee: Amount::from_sat(4_700),
+                        derivation_path: None,
+                    },
+                )
+                .await
+                .unwrap();
+            let commit_tx = builder
+                .sign_commit_transaction(
+                    commit.unsigned_tx,
+                    SignCommitTransactionArgs {
+                        inputs,
+                        txin_script_pubkey: address.script_pubkey(),
+                        derivation_path: None,
+                    },
+                )
+                .await
+                .unwrap();
+
+            let reveal_tx = builder
+                .build_reveal_transaction(RevealTransactionArgs::new(
+                    Utxo {
+                        id: commit_tx.txid(),
+                        index: 0,
+                        amount: commit.reveal_balance,
+                    },
+                    address.clone(),
+                    commit.redeem_script,
+                ))
+                .await
+                .unwrap();
+
+            verify_transaction(&reveal_tx, &[commit_tx.output[0].clone()]).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_sign_reveals_with_derivation_path() {
+        let private_key = test_private_key();
+        let derivation_path = DerivationPath::from_str("m/86'/1'/1'").unwrap();
+
+        for script_type in [ScriptType::P2WSH, ScriptType::P2TR, ScriptType::P2ShP2Wsh] {
+            let wallet = Wallet::new_with_signer(LocalSigner::new(private_key))
+                .with_signature_verification(true);
+            let public_key = wallet.ecdsa_public_key(&derivation_path).await.unwrap();
+            let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+            let mut builder = OrdTransactionBuilder::new(public_key, script_type, wallet);
+
+            let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
+            let commit = builder
+                .build_commit_transaction_with_fixed_fees(
+                    Network::Testnet,
+                    CreateCommitTransactionArgsV2 {
+                        inputs: inputs.clone(),
+                        txin_script_pubkey: address.script_pubkey(),
+                        inscription: Brc20::transfer("mona".to_string(), 100),
+                        leftovers_recipient: address.clone(),
+                        commit_fee: Amount::from_sat(2_500),
+                        reveal_fee: Amount::from_sat(4_700),
+                        derivation_path: Some(derivation_path.clone()),
+                    },
+                )
+                .await
+                .unwrap();
+            let commit_tx = builder
+                .sign_commit_transaction(
+                    commit.unsigned_tx,
+                    SignCommitTransactionArgs {
+                        inputs,
+                        txin_script_pubkey: address.script_pubkey(),
+                        derivation_path: Some(derivation_path.clone()),
+                    },
+                )
+                .await
+                .unwrap();
+
+            let reveal_tx = builder
+                .build_reveal_transaction(RevealTransactionArgs {
+                    derivation_path: Some(derivation_path.clone()),
+                    ..RevealTransactionArgs::new(
+                        Utxo {
+                            id: commit_tx.txid(),
+                            index: 0,
+                            amount: commit.reveal_balance,
+                        },
+                        address.clone(),
+                        commit.redeem_script,
+                    )
+                })
+                .await
+                .unwrap();
+
+            verify_transaction(&reveal_tx, &[commit_tx.output[0].clone()]).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_fund_reveal_fee_from_redeem_script_size() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let fee_rate = FeeRate::from_sat_per_vb(10).unwrap();
+
+        for script_type in [ScriptType::P2WSH, ScriptType::P2TR, ScriptType::P2ShP2Wsh] {
+            let wallet = Wallet::new_with_signer(LocalSigner::new(private_key));
+            let mut builder = OrdTransactionBuilder::new(public_key, script_type, wallet);
+
+            let commit = builder
+                .build_commit_transaction(
+                    Network::Testnet,
+                    address.clone(),
+                    CreateCommitTransactionArgs::new(
+                        vec![test_utxo(1, Amount::from_sat(100_000))],
+                        crate::Nft::new(Some(b"text/plain".to_vec()), Some(vec![b'a'; 2_000])),
+                        address.clone(),
+                        address.script_pubkey(),
+                        fee_rate,
+                    ),
+                )
+                .await
+                .unwrap();
+            let reveal = builder
+                .build_reveal_transaction(RevealTransactionArgs::new(
+                    Utxo {
+                        id: commit.unsigned_tx.txid(),
+                        index: 0,
+                        amount: commit.reveal_balance,
+                    },
+                    address.clone(),
+                    commit.redeem_script,
+                ))
+                .await
+                .unwrap();
+
+            // the reveal fee covers the actual reveal, with at most a few vbytes of slack
+            let actual_fee = fee_rate.fee_vb(reveal.vsize() as u64).unwrap();
+            assert_eq!(commit.reveal_balance, POSTAGE + commit.reveal_fee);
+            assert!(commit.reveal_fee >= actual_fee);
+            assert!(commit.reveal_fee - actual_fee <= fee_rate.fee_vb(2).unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_prepare_reveal_transaction_before_signing() {
+        let private_key = test_private_key();
+        let secp = Secp256k1::new();
+        let public_key = private_key.public_key(&secp);
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        for script_type in [ScriptType::P2WSH, ScriptType::P2TR, ScriptType::P2ShP2Wsh] {
+            let wallet = Wallet::new_with_signer(LocalSigner::new(private_key));
+            let mut builder = OrdTransactionBuilder::new(public_key, script_type, wallet);
+
+            let commit = builder
+                .build_commit_transaction_with_fixed_fees(
+                    Network::Testnet,
+                    CreateCommitTransactionArgsV2 {
+                        inputs: vec![test_utxo(1, Amount::from_sat(8_000))],
+                        txin_script_pubkey: address.script_pubkey(),
+                        inscription: Brc20::transfer("mona".to_string(), 100),
+                        leftovers_recipient: address.clone(),
+                        commit_fee: Amount::from_sat(2_500),
+                        reveal_fee: Amount::from_sat(4_700),
+                        derivation_path: None,
+                    },
+                )
+                .await
+                .unwrap();
+
+            let reveal = builder
+                .prepare_reveal_transaction(RevealTransactionArgs::new(
+                    Utxo {
+                        id: commit.unsigned_tx.txid(),
+                        index: 0,
+                        amount: commit.reveal_balance,
+                    },
+                    address.clone(),
+                    commit.redeem_script.clone(),
+                ))
+                .unwrap();
+            assert_eq!(reveal.prevout, commit.unsigned_tx.output[0]);
+            assert_eq!(
+                reveal.control_block.is_some(),
+                script_type == ScriptType::P2TR
+            );
+            assert!(reveal.unsigned_tx.input[0].witness.is_empty());
+
+            let json = serde_json::to_string(&reveal).unwrap();
+            assert_eq!(
+                serde_json::from_str::<UnsignedRevealTransaction>(&json).unwrap(),
+                reveal
+            );
+
+            // the signature commits to the exposed sighash
+            let sighash = reveal.sighash().unwrap();
+            let prevout = reveal.prevout.clone();
+            let unsigned_tx = reveal.unsigned_tx.clone();
+            let inscription_id = reveal.inscription_id();
+            let signed = builder.sign_reveal(reveal).await.unwrap();
+            assert_eq!(signed.output, unsigned_tx.output);
+            assert_eq!(inscription_id, commit.inscription_id(signed.txid()));
+            assert_eq!(OrdParser::parse_all(&signed).unwrap()[0].0, inscription_id);
+            verify_transaction(&signed, &[prevout]).unwrap();
+
+            let signature = signed.input[0].witness.nth(0).unwrap();
+            match script_type {
+                ScriptType::P2WSH | ScriptType::P2ShP2Wsh => {
+                    let signature = bitcoin::ecdsa::Signature::from_slice(signature).unwrap();
+                    secp.verify_ecdsa(&sighash, &signature.sig, &public_key.inner)
+                        .unwrap();
+                }
+                ScriptType::P2TR => {
+                    let signature = bitcoin::taproot::Signature::from_slice(signature).unwrap();
+                    secp.verify_schnorr(
+                        &signature.sig,
+                        &sighash,
+                        &public_key.inner.x_only_public_key().0,
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_should_not_prepare_p2tr_reveal_without_control_block() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        // no commit was built, so the builder has no taproot payload
+        let builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let redeem_script = Brc20::transfer("mona".to_string(), 100)
+            .generate_redeem_script(
+                ScriptBuilder::new(),
+                RedeemScriptPubkey::XPublickey(public_key.inner.x_only_public_key().0),
+            )
+            .unwrap()
+            .into_script();
+        let err = builder
+            .prepare_reveal_transaction(RevealTransactionArgs::new(
+                test_utxo(0, Amount::from_sat(5_000)),
+                address,
+                redeem_script,
+            ))
+            .unwrap_err();
+        assert!(matches!(err, OrdError::TaprootCompute));
+    }
+
+    #[tokio::test]
+    async fn test_should_rebind_reveal_to_commit_txid() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        for script_type in [ScriptType::P2WSH, ScriptType::P2TR] {
+            let wallet = Wallet::new_with_signer(LocalSigner::new(private_key));
+            let mut builder = OrdTransactionBuilder::new(public_key, script_type, wallet);
+
+            let commit = builder
+                .build_commit_transaction_with_fixed_fees(
+                    Network::Testnet,
+                    CreateCommitTransactionArgsV2 {
+                        inputs: vec![test_utxo(1, Amount::from_sat(8_000))],
+                        txin_script_pubkey: address.script_pubkey(),
+                        inscription: Brc20::transfer("mona".to_string(), 100),
+                        leftovers_recipient: address.clone(),
+                        commit_fee: Amount::from_sat(2_500),
+                        reveal_fee: Amount::from_sat(4_700),
+                        derivation_path: None,
+                    },
+                )
+                .await
+                .unwrap();
+            let args = |txid| {
+                RevealTransactionArgs::new(
+                    Utxo {
+                        id: txid,
+                        index: 0,
+                        amount: commit.reveal_balance,
+                    },
+                    address.clone(),
+                    commit.redeem_script.clone(),
+                )
+            };
+
+            // prepared before the commit txid is known
+            let mut reveal = builder
+                .prepare_reveal_transaction(args(<Txid as bitcoin::hashes::Hash>::all_zeros()))
+                .unwrap();
+            let placeholder_sighash = reveal.sighash().unwrap();
+
+            let mut other_commit = commit.unsigned_tx.clone();
+            other_commit.output[0].value -= Amount::from_sat(1);
+            assert!(matches!(
+                reveal.rebind_commit(&other_commit),
+                Err(OrdError::CommitOutputMismatch(0))
+            ));
+
+            reveal.rebind_commit(&commit.unsigned_tx).unwrap();
+            let expected = builder
+                .prepare_reveal_transaction(args(commit.unsigned_tx.txid()))
+                .unwrap();
+            assert_eq!(reveal, expected);
+            assert_ne!(reveal.sighash().unwrap(), placeholder_sighash);
+            assert_eq!(
+                reveal.inscription_id(),
+                commit.inscription_id(expected.unsigned_tx.txid())
+            );
+
+            let signed = builder.sign_reveal(reveal).await.unwrap();
+            verify_transaction(&signed, &commit.unsigned_tx.output[..1]).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_add_anchor_output_to_reveal() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let commit = builder
+            .build_commit_transaction_with_fixed_fees(
+                Network::Testnet,
+                CreateCommitTransactionArgsV2 {
+                    inputs: vec![test_utxo(1, Amount::from_sat(8_000))],
+                    txin_script_pubkey: address.script_pubkey(),
+                    inscription: Brc20::transfer("mona".to_string(), 100),
+                    leftovers_recipient: address.clone(),
+                    commit_fee: Amount::from_sat(2_500),
+                    reveal_fee: Amount::from_sat(4_700),
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let reveal = builder
+            .build_reveal_transaction(RevealTransactionArgs {
+                anchor: true,
+                ..RevealTransactionArgs::new(
+                    Utxo {
+                        id: commit.unsigned_tx.txid(),
+                        index: 0,
+                        amount: commit.reveal_balance,
+                    },
+                    address.clone(),
+                    commit.redeem_script.clone(),
+                )
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(reveal.output.len(), 2);
+        assert_eq!(reveal.output[0].script_pubkey, address.script_pubkey());
+        assert_eq!(reveal.output[1].value, Amount::ZERO);
+        assert_eq!(
+            reveal.output[1].script_pubkey.as_bytes(),
+            crate::constants::ANCHOR_SCRIPT_PUBKEY
+        );
+        // the anchor is ephemeral dust, so the reveal pays no fee
+        assert!(reveal.output[1].value < dust_limit(&reveal.output[1].script_pubkey));
+        assert_eq!(reveal.output[0].value, commit.reveal_balance);
+        assert!(reveal.output[0].value > POSTAGE);
+        verify_transaction(&reveal, &commit.unsigned_tx.output[..1]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_should_sign_reveal_with_annex() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let commit = builder
+            .build_commit_transaction_with_fixed_fees(
+                Network::Testnet,
+                CreateCommitTransactionArgsV2 {
+                    inputs: vec![test_utxo(1, Amount::from_sat(8_000))],
+                    txin_script_pubkey: address.script_pubkey(),
+                    inscription: Brc20::transfer("mona".to_string(), 100),
+                    leftovers_recipient: address.clone(),
+                    commit_fee: Amount::from_sat(2_500),
+                    reveal_fee: Amount::from_sat(4_700),
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+        let args = |annex: Option<Vec<u8>>| RevealTransactionArgs {
+            annex,
+            ..RevealTransactionArgs::new(
+                Utxo {
+                    id: commit.unsigned_tx.txid(),
+                    index: 0,
+                    amount: commit.reveal_balance,
+                },
+                address.clone(),
+                commit.redeem_script.clone(),
+            )
+        };
+
+        let annex = vec![0x50, 0xde, 0xad];
+        let unsigned = builder
+            .prepare_reveal_transaction(args(Some(annex.clone())))
+            .unwrap();
+        // the signature commits to the annex
+        assert_ne!(
+            unsigned.sighash().unwrap(),
+            builder
+                .prepare_reveal_transaction(args(None))
+                .unwrap()
+                .sighash()
+                .unwrap()
+        );
+
+        let reveal = builder.sign_reveal(unsigned).await.unwrap();
+        let witness = reveal.input[0].witness.to_vec();
+        assert_eq!(witness.len(), 4);
+        assert_eq!(witness[3], annex);
+        verify_transaction(&reveal, &commit.unsigned_tx.output[..1]).unwrap();
+
+        // a witness whose annex was stripped doesn't verify
+        let mut stripped = reveal.clone();
+        stripped.input[0].witness = Witness::from_slice(&witness[..3]);
+        assert!(verify_transaction(&stripped, &commit.unsigned_tx.output[..1]).is_err());
+
+        assert!(matches!(
+            builder.prepare_reveal_transaction(args(Some(vec![0x51]))),
+            Err(OrdError::InvalidAnnex(_))
+        ));
+        let p2wsh = OrdTransactionBuilder::p2wsh(private_key);
+        assert!(matches!(
+            p2wsh.prepare_reveal_transaction(args(Some(annex))),
+            Err(OrdError::InvalidAnnex(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_should_build_transfer_for_brc20_transactions_from_existing_data_with_p2tr() {
         // this test refers to these testnet transactions, commit and reveal:
         // <https://mempool.space/testnet/tx/973f78eb7b3cc666dc4133ff6381c363fd29edda0560d36ea3cfd31f1e85d9f9>
         // <https://mempool.space/testnet/tx/a35802655b63f1c99c1fd3ff8fdf3415f3abb735d647d402c0af5e9a73cbe4c6>
         // made by address tb1qzc8dhpkg5e4t6xyn4zmexxljc4nkje59dg3ark
-        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let private_key = test_private_key();
         let public_key = private_key.public_key(&Secp256k1::new());
         let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
 
         let mut builder = OrdTransactionBuilder::p2tr(private_key);
 
-        let inputs = vec![Utxo {
-            id: Txid::from_str("791b415dc6946d864d368a0e5ec5c09ee2ad39cf298bc6e3f9aec293732cfda7")
-                .unwrap(), // the transaction that funded our wallet
-            index: 1,
-            amount: Amount::from_sat(8_000),
-        }];
-        let commit_transaction_args = CreateCommitTransactionArgs {
-            inputs: inputs.clone(),
-            txin_script_pubkey: address.script_pubkey(),
-            inscription: Brc20::transfer("mona".to_string(), 100),
-            leftovers_recipient: address.clone(),
-            fee_rate: FeeRate::from_sat_per_vb(1).unwrap(),
-            derivation_path: None,
-            multisig_config: None,
-        };
+        let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
+        let commit_transaction_args = CreateCommitTransactionArgs::new(
+            inputs.clone(),
+            Brc20::transfer("mona".to_string(), 100),
+            address.clone(),
+            address.script_pubkey(),
+            FeeRate::from_sat_per_vb(1).unwrap(),
+        );
         let tx_result = builder
             .build_commit_transaction(Network::Testnet, address.clone(), commit_transaction_args)
             .await
@@ -848,20 +2472,1098 @@ mod test {
             .unwrap();
 
         let reveal_transaction = builder
-            .build_reveal_transaction(RevealTransactionArgs {
-                input: Utxo {
+            .build_reveal_transaction(RevealTransactionArgs::new(
+                Utxo {
                     id: tx_id,
                     index: 0,
                     amount: tx_result.reveal_balance,
                 },
-                recipient_address: recipient_address.clone(),
-                redeem_script: tx_result.redeem_script,
-                derivation_path: None,
-            })
+                recipient_address.clone(),
+                tx_result.redeem_script,
+            ))
             .await
             .unwrap();
 
         let witness = reveal_transaction.input[0].witness.clone().to_vec();
         assert_eq!(witness.len(), 3);
     }
+
+    #[tokio::test]
+    async fn test_should_select_commit_inputs() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let candidate = |index, sats| UtxoCandidate {
+            utxo: test_utxo(index, Amount::from_sat(sats)),
+            height: Some(840_000 + index),
+        };
+        let mut args = CreateCommitTransactionArgs::new(
+            Vec::new(),
+            Brc20::transfer("mona".to_string(), 100),
+            address.clone(),
+            address.script_pubkey(),
+            FeeRate::from_sat_per_vb(10).unwrap(),
+        );
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        // spending a P2WPKH output costs 683 sats, more than the oldest candidate
+        let candidates = vec![
+            candidate(0, 600),
+            candidate(1, 1_000),
+            candidate(2, 1_500),
+            candidate(3, 100_000),
+        ];
+        let selection = args
+            .select_inputs(
+                &candidates,
+                SelectionStrategy::OldestFirst,
+                ScriptType::P2TR,
+                POSTAGE,
+            )
+            .unwrap();
+        assert_eq!(
+            selection
+                .inputs
+                .iter()
+                .map(|utxo| utxo.index)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(args.inputs, selection.inputs);
+        let change = selection.change.unwrap();
+
+        let commit = builder
+            .build_commit_transaction(Network::Testnet, address.clone(), args.clone())
+            .await
+            .unwrap();
+        assert_eq!(commit.unsigned_tx.input.len(), 3);
+        assert!(commit.leftover_amount >= change);
+
+        // a single candidate covering the inscription without change, i.e. without the P2WPKH
+        // change output costing 310 sats and the other inputs
+        let exact = selection.input_amount() - change - Amount::from_sat(310 + 2 * 683);
+        let candidates = vec![candidate(4, 100_000), candidate(5, exact.to_sat())];
+        args.change_policy = ChangePolicy::Always;
+        let selection = args
+            .select_inputs(
+                &candidates,
+                SelectionStrategy::BranchAndBound,
+                ScriptType::P2TR,
+                POSTAGE,
+            )
+            .unwrap();
+        assert_eq!(selection.inputs, vec![candidates[1].utxo.clone()]);
+        assert_eq!(selection.change, None);
+        // the caller's policy is kept unless it lets the leftovers go to the fee
+        assert_eq!(args.change_policy, ChangePolicy::Always);
+
+        args.change_policy = ChangePolicy::Sweep {
+            threshold: Amount::from_sat(1_000),
+        };
+        let selection = args
+            .select_inputs(
+                &candidates,
+                SelectionStrategy::BranchAndBound,
+                ScriptType::P2TR,
+                POSTAGE,
+            )
+            .unwrap();
+        assert_eq!(selection.inputs, vec![candidates[1].utxo.clone()]);
+        assert!(matches!(args.change_policy, ChangePolicy::Omit { .. }));
+
+        let commit = builder
+            .build_commit_transaction(Network::Testnet, address.clone(), args.clone())
+            .await
+            .unwrap();
+        assert_eq!(commit.unsigned_tx.output.len(), 1);
+        assert_eq!(commit.leftover_amount, Amount::ZERO);
+
+        // the change goes to the leftovers recipient, whose P2TR output costs 120 sats more
+        // than a P2WPKH one, and covers the caller's postage
+        let candidates = vec![candidate(6, 100_000)];
+        let p2wpkh_change = args
+            .select_inputs(
+                &candidates,
+                SelectionStrategy::LargestFirst,
+                ScriptType::P2TR,
+                POSTAGE,
+            )
+            .unwrap()
+            .change
+            .unwrap();
+        args.leftovers_recipient = Address::p2tr(
+            &Secp256k1::new(),
+            public_key.inner.x_only_public_key().0,
+            None,
+            Network::Testnet,
+        );
+        let selection = args
+            .select_inputs(
+                &candidates,
+                SelectionStrategy::LargestFirst,
+                ScriptType::P2TR,
+                POSTAGE + Amount::from_sat(1_000),
+            )
+            .unwrap();
+        assert_eq!(
+            selection.change,
+            Some(p2wpkh_change - Amount::from_sat(120 + 1_000))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_build_commit_transaction_with_fee_targets() {
+        struct Estimates;
+
+        #[async_trait::async_trait]
+        impl FeeProvider for Estimates {
+            async fn fee_estimates(&self) -> OrdResult<std::collections::BTreeMap<u16, FeeRate>> {
+                Ok(std::collections::BTreeMap::from([
+                    (1, FeeRate::from_sat_per_vb(30).unwrap()),
+                    (6, FeeRate::from_sat_per_vb(10).unwrap()),
+                ]))
+            }
+        }
+
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let args = |fee_rate| {
+            CreateCommitTransactionArgs::new(
+                vec![test_utxo(1, Amount::from_sat(100_000))],
+                Brc20::transfer("mona".to_string(), 100),
+                address.clone(),
+                address.script_pubkey(),
+                FeeRate::from_sat_per_vb(fee_rate).unwrap(),
+            )
+        };
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let commit = builder
+            .build_commit_transaction_with_fee_targets(
+                Network::Testnet,
+                address.clone(),
+                args(1),
+                &Estimates,
+                InscriptionFeeTargets::default(),
+            )
+            .await
+            .unwrap();
+
+        // the commit transaction targets 6 blocks, the reveal transaction the next block
+        let slow = builder
+            .build_commit_transaction(Network::Testnet, address.clone(), args(10))
+            .await
+            .unwrap();
+        let fast = builder
+            .build_commit_transaction(Network::Testnet, address.clone(), args(30))
+            .await
+            .unwrap();
+        assert_eq!(commit.commit_fee, slow.commit_fee);
+        assert_eq!(commit.reveal_fee, fast.reveal_fee);
+        assert_eq!(commit.reveal_balance, POSTAGE + fast.reveal_fee);
+    }
+
+    #[tokio::test]
+    async fn test_should_serialize_commit_transaction_args() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        let args = CreateCommitTransactionArgs {
+            multisig_config: Some(MultisigConfig {
+                required: 2,
+                total: 3,
+            }),
+            change_policy: ChangePolicy::Sweep {
+                threshold: Amount::from_sat(1_000),
+            },
+            ..CreateCommitTransactionArgs::new(
+                vec![test_utxo(1, Amount::from_sat(8_000))],
+                Brc20::transfer("mona".to_string(), 100),
+                address.clone(),
+                address.script_pubkey(),
+                FeeRate::from_sat_per_vb(1).unwrap(),
+            )
+        };
+
+        let json = serde_json::to_string(&args).unwrap();
+        let decoded: CreateCommitTransactionArgs<Brc20> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, args);
+
+        let commit = OrdTransactionBuilder::p2tr(private_key)
+            .build_commit_transaction(Network::Testnet, address, args)
+            .await
+            .unwrap();
+        let json = serde_json::to_string(&commit).unwrap();
+        let decoded: CreateCommitTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, commit);
+    }
+
+    #[tokio::test]
+    async fn test_should_sweep_leftovers_below_threshold() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let input_amount = Amount::from_sat(8_000);
+
+        let args = |change_policy| CreateCommitTransactionArgs {
+            change_policy,
+            ..CreateCommitTransactionArgs::new(
+                vec![test_utxo(1, input_amount)],
+                Brc20::transfer("mona".to_string(), 100),
+                address.clone(),
+                address.script_pubkey(),
+                FeeRate::from_sat_per_vb(1).unwrap(),
+            )
+        };
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let with_change = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(ChangePolicy::Sweep {
+                    threshold: Amount::from_sat(1_000),
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(with_change.unsigned_tx.output.len(), 2);
+
+        let changeless = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(ChangePolicy::Sweep {
+                    threshold: Amount::from_sat(10_000),
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(changeless.unsigned_tx.output.len(), 1);
+        assert_eq!(changeless.leftover_amount, Amount::ZERO);
+        assert_eq!(changeless.reveal_balance, with_change.reveal_balance);
+        assert_eq!(
+            changeless.reveal_balance + changeless.commit_fee,
+            input_amount
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_inscribe_on_all_networks() {
+        let inscription = Brc20::transfer("mona".to_string(), 100);
+
+        for wallet in TestWallet::for_all_networks(1) {
+            for script_type in [ScriptType::P2WSH, ScriptType::P2TR, ScriptType::P2ShP2Wsh] {
+                let mut builder = OrdTransactionBuilder::new(
+                    wallet.public_key,
+                    script_type,
+                    Wallet::new_with_signer(LocalSigner::new(wallet.private_key)),
+                );
+                let inputs = vec![wallet.funding_utxo(0, Amount::from_sat(10_000))];
+
+                let commit = builder
+                    .build_commit_transaction(
+                        wallet.network,
+                        wallet.p2wpkh_address.clone(),
+                        CreateCommitTransactionArgs::new(
+                            inputs.clone(),
+                            inscription.clone(),
+                            wallet.p2wpkh_address.clone(),
+                            wallet.funding_script_pubkey(),
+                            FeeRate::from_sat_per_vb(2).unwrap(),
+                        ),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    commit.redeem_script,
+                    wallet.redeem_script(&inscription, script_type).unwrap()
+                );
+                assert_eq!(
+                    commit.unsigned_tx.output[0].script_pubkey,
+                    wallet
+                        .commit_script_pubkey(&inscription, script_type)
+                        .unwrap()
+                );
+
+                let commit_tx = builder
+                    .sign_commit_transaction(
+                        commit.unsigned_tx,
+                        SignCommitTransactionArgs {
+                            inputs,
+                            txin_script_pubkey: wallet.funding_script_pubkey(),
+                            derivation_path: None,
+                        },
+                    )
+                    .await
+                    .unwrap();
+                let reveal_tx = builder
+                    .build_reveal_transaction(RevealTransactionArgs::new(
+                        Utxo {
+                            id: commit_tx.txid(),
+                            index: 0,
+                            amount: commit.reveal_balance,
+                        },
+                        wallet.p2tr_address.clone(),
+                        commit.redeem_script,
+                    ))
+                    .await
+                    .unwrap();
+
+                assert_eq!(
+                    reveal_tx.output[0].script_pubkey,
+                    wallet.p2tr_address.script_pubkey()
+                );
+                verify_transaction(&reveal_tx, &[commit_tx.output[0].clone()]).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_build_commit_from_mixed_script_types() {
+        let wallet = TestWallet::new(Network::Regtest, 1);
+        let secp = Secp256k1::new();
+        let derivation_path = DerivationPath::default();
+        let input = |vout, script_pubkey, key_spend| TxInputInfo {
+            outpoint: OutPoint::new(test_txid(), vout),
+            tx_out: TxOut {
+                value: Amount::from_sat(3_000),
+                script_pubkey,
+            },
+            derivation_path: derivation_path.clone(),
+            key_spend,
+            rune_balances: None,
+        };
+        let inputs = vec![
+            input(
+                0,
+                wallet.funding_script_pubkey(),
+                TaprootKeySpend::Untweaked,
+            ),
+            input(
+                1,
+                Address::p2tr(&secp, wallet.x_public_key, None, Network::Regtest).script_pubkey(),
+                TaprootKeySpend::Bip86,
+            ),
+            input(
+                2,
+                Address::p2shwpkh(&wallet.public_key, Network::Regtest)
+                    .unwrap()
+                    .script_pubkey(),
+                TaprootKeySpend::Untweaked,
+            ),
+        ];
+        let prevouts = inputs
+            .iter()
+            .map(|input| input.tx_out.clone())
+            .collect::<Vec<_>>();
+
+        let mut builder = OrdTransactionBuilder::new(
+            wallet.public_key,
+            ScriptType::P2TR,
+            Wallet::new_with_signer(LocalSigner::new(wallet.private_key))
+                .with_signature_verification(true),
+        );
+        let commit = builder
+            .build_mixed_commit_transaction(
+                Network::Regtest,
+                wallet.p2tr_address.clone(),
+                CreateMixedCommitTransactionArgs {
+                    inputs: inputs.clone(),
+                    inscription: Brc20::transfer("mona".to_string(), 100),
+                    leftovers_recipient: wallet.p2wpkh_address.clone(),
+                    fee_rate: FeeRate::from_sat_per_vb(2).unwrap(),
+                    derivation_path: None,
+                    change_policy: ChangePolicy::Always,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(commit.unsigned_tx.input.len(), 3);
+        assert_eq!(
+            commit.unsigned_tx.output[1].script_pubkey,
+            wallet.p2wpkh_address.script_pubkey()
+        );
+        assert_eq!(
+            commit.reveal_balance + commit.commit_fee + commit.leftover_amount,
+            Amount::from_sat(9_000)
+        );
+
+        let commit_tx = builder
+            .sign_transaction(&commit.unsigned_tx, &inputs)
+            .await
+            .unwrap();
+        verify_transaction(&commit_tx, &prevouts).unwrap();
+        assert!(commit_tx.input[0].script_sig.is_empty());
+        assert!(!commit_tx.input[2].script_sig.is_empty());
+
+        // the fee covers the signed transaction
+        let fee_rate = FeeRate::from_sat_per_vb(2).unwrap();
+        assert!(commit.commit_fee >= fee_rate.fee_vb(commit_tx.vsize() as u64).unwrap());
+
+        // nested segwit outputs of other keys are rejected
+        let mut foreign = inputs;
+        foreign[2].tx_out.script_pubkey = Address::p2shwpkh(
+            &TestWallet::new(Network::Regtest, 2).public_key,
+            Network::Regtest,
+        )
+        .unwrap()
+        .script_pubkey();
+        let result = builder
+            .sign_transaction(&commit.unsigned_tx, &foreign)
+            .await;
+        assert!(matches!(
+            result,
+            Err(OrdError::InputSigning(crate::InputSigningError { index: 2, source, .. }))
+                if matches!(*source, OrdError::InvalidScriptType)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_should_reveal_child_inscription() {
+        let wallet = TestWallet::new(Network::Regtest, 1);
+        let parent_id = InscriptionId {
+            txid: test_txid(),
+            index: 0,
+        };
+        let parent = ParentInfo {
+            inscription_id: parent_id,
+            input: TxInputInfo {
+                outpoint: OutPoint::new(
+                    Txid::from_str(
+                        "973f78eb7b3cc666dc4133ff6381c363fd29edda0560d36ea3cfd31f1e85d9f9",
+                    )
+                    .unwrap(),
+                    0,
+                ),
+                tx_out: TxOut {
+                    value: Amount::from_sat(10_000),
+                    script_pubkey: wallet.funding_script_pubkey(),
+                },
+                derivation_path: DerivationPath::default(),
+                key_spend: TaprootKeySpend::Untweaked,
+                rune_balances: None,
+            },
+            offset: 0,
+        };
+        let args = |inscription: Nft, parent: ParentInfo| CreateCommitTransactionArgs {
+            parent: Some(parent),
+            ..CreateCommitTransactionArgs::new(
+                vec![wallet.funding_utxo(0, Amount::from_sat(20_000))],
+                inscription,
+                wallet.p2wpkh_address.clone(),
+                wallet.funding_script_pubkey(),
+                FeeRate::from_sat_per_vb(2).unwrap(),
+            )
+        };
+        let child = Nft::new(
+            Some("text/plain;charset=utf-8".as_bytes().to_vec()),
+            Some("child".as_bytes().to_vec()),
+        );
+
+        let mut builder = OrdTransactionBuilder::new(
+            wallet.public_key,
+            ScriptType::P2TR,
+            Wallet::new_with_signer(LocalSigner::new(wallet.private_key))
+                .with_signature_verification(true),
+        );
+
+        // the parent must be declared by the child
+        let result = builder
+            .build_commit_transaction(
+                Network::Regtest,
+                wallet.p2tr_address.clone(),
+                args(child.clone(), parent.clone()),
+            )
+            .await;
+        assert!(matches!(result, Err(OrdError::MissingParentTag(id)) if id == parent_id));
+
+        // the parent must cover the reveal fee, which precedes it in its return output
+        let mut cheap_parent = parent.clone();
+        cheap_parent.input.tx_out.value = Amount::from_sat(330);
+        let result = builder
+            .build_commit_transaction(
+                Network::Regtest,
+                wallet.p2tr_address.clone(),
+                args(child.clone().with_parent(parent_id), cheap_parent),
+            )
+            .await;
+        assert!(matches!(result, Err(OrdError::ParentValueTooLow { .. })));
+
+        // as must the sats preceding the parent in its output
+        let late_parent = ParentInfo {
+            offset: 9_700,
+            ..parent.clone()
+        };
+        let result = builder
+            .build_commit_transaction(
+                Network::Regtest,
+                wallet.p2tr_address.clone(),
+                args(child.clone().with_parent(parent_id), late_parent),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(OrdError::ParentValueTooLow { offset: 9_700, .. })
+        ));
+
+        let commit = builder
+            .build_commit_transaction(
+                Network::Regtest,
+                wallet.p2tr_address.clone(),
+                args(child.with_parent(parent_id), parent.clone()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(commit.parent.as_ref(), Some(&parent));
+
+        let reveal_tx = builder
+            .build_reveal_transaction(RevealTransactionArgs {
+                postage: Some(commit.postage),
+                parent: commit.parent.clone(),
+                ..RevealTransactionArgs::new(
+                    commit.reveal_input(commit.txid()),
+                    wallet.p2tr_address.clone(),
+                    commit.redeem_script.clone(),
+                )
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(reveal_tx.input.len(), 2);
+        assert_eq!(reveal_tx.input[1].previous_output, parent.input.outpoint);
+        assert_eq!(reveal_tx.output.len(), 2);
+        assert_eq!(reveal_tx.output[1], parent.return_output());
+        verify_transaction(
+            &reveal_tx,
+            &[commit.unsigned_tx.output[0].clone(), parent.return_output()],
+        )
+        .unwrap();
+
+        // the fee covers the signed transaction
+        let fee_rate = FeeRate::from_sat_per_vb(2).unwrap();
+        assert!(commit.reveal_fee >= fee_rate.fee_vb(reveal_tx.vsize() as u64).unwrap());
+
+        let (inscription_id, inscription) = OrdParser::parse_all(&reveal_tx).unwrap().remove(0);
+        assert_eq!(inscription_id, commit.inscription_id(reveal_tx.txid()));
+        let OrdParser::Ordinal(nft) = inscription else {
+            panic!("expected an ordinal inscription");
+        };
+        assert_eq!(nft.parents(), vec![parent_id]);
+    }
+
+    #[tokio::test]
+    async fn test_should_merge_dust_leftovers_into_postage() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        let args = |amount, change_policy| CreateCommitTransactionArgs {
+            change_policy,
+            ..CreateCommitTransactionArgs::new(
+                vec![test_utxo(1, amount)],
+                Brc20::transfer("mona".to_string(), 100),
+                address.clone(),
+                address.script_pubkey(),
+                FeeRate::from_sat_per_vb(1).unwrap(),
+            )
+        };
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        // find the input amount leaving 100 sats of leftovers
+        let reference = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(Amount::from_sat(8_000), ChangePolicy::Always),
+            )
+            .await
+            .unwrap();
+        let input_amount =
+            Amount::from_sat(8_000) - reference.leftover_amount + Amount::from_sat(100);
+
+        let with_change = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(input_amount, ChangePolicy::Always),
+            )
+            .await
+            .unwrap();
+        assert_eq!(with_change.leftover_amount, Amount::from_sat(100));
+        assert_eq!(with_change.postage, POSTAGE);
+
+        let merged = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(input_amount, ChangePolicy::MergeIntoPostage),
+            )
+            .await
+            .unwrap();
+        assert_eq!(merged.unsigned_tx.output.len(), 1);
+        assert_eq!(merged.leftover_amount, Amount::ZERO);
+        assert_eq!(merged.reveal_fee, with_change.reveal_fee);
+        assert!(merged.commit_fee < with_change.commit_fee);
+        assert_eq!(
+            merged.postage,
+            POSTAGE + Amount::from_sat(100) + (with_change.commit_fee - merged.commit_fee)
+        );
+        assert_eq!(merged.reveal_balance, merged.postage + merged.reveal_fee);
+        assert_eq!(merged.reveal_balance + merged.commit_fee, input_amount);
+
+        let reveal = builder
+            .build_reveal_transaction(RevealTransactionArgs {
+                postage: Some(merged.postage),
+                ..RevealTransactionArgs::new(
+                    Utxo {
+                        id: merged.unsigned_tx.txid(),
+                        index: 0,
+                        amount: merged.reveal_balance,
+                    },
+                    address.clone(),
+                    merged.redeem_script.clone(),
+                )
+            })
+            .await
+            .unwrap();
+        assert_eq!(reveal.output[0].value, merged.postage);
+        verify_transaction(&reveal, &[merged.unsigned_tx.output[0].clone()]).unwrap();
+
+        // leftovers above the dust limit are still returned
+        let with_change = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(Amount::from_sat(8_000), ChangePolicy::MergeIntoPostage),
+            )
+            .await
+            .unwrap();
+        assert_eq!(with_change, reference);
+    }
+
+    #[tokio::test]
+    async fn test_should_omit_change_of_exact_funded_commits() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        let args = |amount, change_policy| CreateCommitTransactionArgs {
+            change_policy,
+            ..CreateCommitTransactionArgs::new(
+                vec![test_utxo(1, amount)],
+                Brc20::transfer("mona".to_string(), 100),
+                address.clone(),
+                address.script_pubkey(),
+                FeeRate::from_sat_per_vb(1).unwrap(),
+            )
+        };
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let omit = |tolerance| ChangePolicy::Omit {
+            tolerance: Amount::from_sat(tolerance),
+        };
+
+        // find the exact input amount of a commit without change
+        let Err(OrdError::UnexpectedLeftovers { leftover, .. }) = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(Amount::from_sat(8_000), omit(0)),
+            )
+            .await
+        else {
+            panic!("expected leftovers");
+        };
+        let exact_amount = Amount::from_sat(8_000) - leftover;
+
+        let exact = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(exact_amount, omit(0)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(exact.unsigned_tx.output.len(), 1);
+        assert_eq!(exact.leftover_amount, Amount::ZERO);
+        assert_eq!(exact.reveal_balance + exact.commit_fee, exact_amount);
+
+        let with_change = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(exact_amount, ChangePolicy::Always),
+            )
+            .await;
+        assert!(matches!(
+            with_change,
+            Err(OrdError::InsufficientBalance { .. })
+        ));
+
+        // leftovers within the tolerance are burned to the fee
+        let burned = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(exact_amount + Amount::from_sat(50), omit(50)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(burned.unsigned_tx.output.len(), 1);
+        assert_eq!(burned.commit_fee, exact.commit_fee + Amount::from_sat(50));
+
+        let result = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                args(exact_amount + Amount::from_sat(51), omit(50)),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(OrdError::UnexpectedLeftovers { leftover, tolerance })
+                if leftover == Amount::from_sat(51) && tolerance == Amount::from_sat(50)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_should_reveal_to_multisig_script() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let multisig = ScriptBuilder::new()
+            .push_int(1)
+            .push_key(&public_key)
+            .push_int(1)
+            .push_opcode(bitcoin::opcodes::all::OP_CHECKMULTISIG)
+            .into_script();
+
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let commit = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    vec![test_utxo(1, Amount::from_sat(8_000))],
+                    Brc20::transfer("mona".to_string(), 100),
+                    address.clone(),
+                    address.script_pubkey(),
+                    FeeRate::from_sat_per_vb(1).unwrap(),
+                ),
+            )
+            .await
+            .unwrap();
+        let input = Utxo {
+            id: commit.unsigned_tx.txid(),
+            index: 0,
+            amount: commit.reveal_balance,
+        };
+
+        let p2wsh_multisig = Address::p2wsh(&multisig, Network::Testnet).script_pubkey();
+        let reveal = builder
+            .build_reveal_transaction_to_script(RevealToScriptArgs::new(
+                input.clone(),
+                p2wsh_multisig.clone(),
+                commit.redeem_script.clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(reveal.output[0].script_pubkey, p2wsh_multisig);
+
+        // postage is below the dust limit of bare multisig and p2pkh outputs
+        for recipient_script_pubkey in [
+            multisig,
+            Address::p2pkh(&public_key, Network::Testnet).script_pubkey(),
+        ] {
+            let result = builder
+                .build_reveal_transaction_to_script(RevealToScriptArgs::new(
+                    input.clone(),
+                    recipient_script_pubkey,
+                    commit.redeem_script.clone(),
+                ))
+                .await;
+            assert!(matches!(result, Err(OrdError::DustOutput { .. })));
+        }
+
+        // postage is larger than the input
+        let result = builder.prepare_reveal_transaction(RevealToScriptArgs {
+            postage: Some(input.amount + Amount::from_sat(1)),
+            ..RevealToScriptArgs::new(input.clone(), p2wsh_multisig, commit.redeem_script)
+        });
+        assert!(matches!(
+            result,
+            Err(OrdError::InsufficientBalance { required, available })
+                if required == input.amount + Amount::from_sat(1) && available == input.amount
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_should_reveal_with_external_control_block() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let commit = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    vec![test_utxo(1, Amount::from_sat(8_000))],
+                    Brc20::transfer("mona".to_string(), 100),
+                    address.clone(),
+                    address.script_pubkey(),
+                    FeeRate::from_sat_per_vb(1).unwrap(),
+                ),
+            )
+            .await
+            .unwrap();
+        let control_block = builder
+            .taproot_payload
+            .as_ref()
+            .unwrap()
+            .control_block
+            .clone();
+        let args = RevealTransactionArgs::new(
+            Utxo {
+                id: commit.unsigned_tx.txid(),
+                index: 0,
+                amount: commit.reveal_balance,
+            },
+            address,
+            commit.redeem_script.clone(),
+        );
+        let expected = builder
+            .build_reveal_transaction(args.clone())
+            .await
+            .unwrap();
+
+        // a new builder doesn't know the taproot payload of the commit
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        assert!(builder.taproot_payload.is_none());
+        let args = RevealTransactionArgs {
+            control_block: Some(control_block.clone()),
+            ..args
+        };
+        let json = serde_json::to_string(&args).unwrap();
+        assert_eq!(
+            serde_json::from_str::<RevealTransactionArgs>(&json).unwrap(),
+            args
+        );
+        let reveal = builder.build_reveal_transaction(args).await.unwrap();
+
+        let witness = &reveal.input[0].witness;
+        assert_eq!(witness.len(), 3);
+        assert_eq!(witness.nth(1).unwrap(), commit.redeem_script.as_bytes());
+        assert_eq!(
+            witness.nth(2).unwrap(),
+            control_block.serialize().as_slice()
+        );
+        assert_eq!(reveal.output, expected.output);
+        assert_eq!(
+            reveal.input[0].witness.nth(1),
+            expected.input[0].witness.nth(1)
+        );
+        assert_eq!(
+            reveal.input[0].witness.nth(2),
+            expected.input[0].witness.nth(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_reveal_timelocked_redeem_script() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let x_public_key = XOnlyPublicKey::from(public_key.inner);
+
+        let redeem_script = ScriptBuilder::new()
+            .push_int(840_000)
+            .push_opcode(bitcoin::opcodes::all::OP_CLTV)
+            .push_opcode(bitcoin::opcodes::all::OP_DROP)
+            .push_x_only_key(&x_public_key)
+            .push_opcode(bitcoin::opcodes::all::OP_CHECKSIG)
+            .into_script();
+        let (_, control_block, _) = build_p2tr_commit_output(&redeem_script, x_public_key).unwrap();
+
+        let args = RevealTransactionArgs {
+            control_block: Some(control_block),
+            ..RevealTransactionArgs::new(
+                test_utxo(0, Amount::from_sat(1_000)),
+                address,
+                redeem_script,
+            )
+        };
+
+        // the signature of the leaf key is verified past the timelock of the redeem script
+        let wallet = Wallet::new_with_signer(LocalSigner::new(private_key))
+            .with_signature_verification(true);
+        let mut builder = OrdTransactionBuilder::new(public_key, ScriptType::P2TR, wallet);
+        let reveal = builder
+            .build_reveal_transaction(args.clone())
+            .await
+            .unwrap();
+        assert_eq!(reveal.lock_time, LockTime::from_consensus(840_000));
+        assert!(reveal.input[0].sequence.enables_absolute_lock_time());
+        assert_eq!(reveal.input[0].witness.len(), 3);
+
+        let result = builder
+            .build_reveal_transaction(RevealTransactionArgs {
+                timelock: Some(RevealTimelock::default()),
+                ..args
+            })
+            .await;
+        assert!(matches!(result, Err(OrdError::Timelock(_))));
+    }
+
+    #[tokio::test]
+    async fn test_should_reveal_with_custom_internal_key() {
+        let secp = Secp256k1::new();
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&secp);
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let x_public_key = XOnlyPublicKey::from(public_key.inner);
+
+        // the output is tweaked from another key than the one of the leaf
+        let (_, internal_key) = generate_btc_address(Network::Testnet);
+        let internal_key = XOnlyPublicKey::from(internal_key.public_key(&secp).inner);
+        assert_ne!(internal_key, x_public_key);
+
+        let redeem_script = ScriptBuilder::new()
+            .push_x_only_key(&x_public_key)
+            .push_opcode(bitcoin::opcodes::all::OP_CHECKSIG)
+            .into_script();
+        let (script_pubkey, control_block, _) =
+            build_p2tr_commit_output(&redeem_script, internal_key).unwrap();
+        assert_eq!(control_block.internal_key, internal_key);
+
+        let input = test_utxo(0, Amount::from_sat(1_000));
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let reveal = builder
+            .build_reveal_transaction(RevealTransactionArgs {
+                control_block: Some(control_block),
+                ..RevealTransactionArgs::new(input.clone(), address, redeem_script)
+            })
+            .await
+            .unwrap();
+
+        crate::wallet::verify_transaction(
+            &reveal,
+            &[TxOut {
+                value: input.amount,
+                script_pubkey,
+            }],
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_should_not_commit_to_non_standard_reveal() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        // a body of 410 kB weighs more than the 400 kWU relayed by standard nodes
+        let inscription = Nft::new(Some(b"text/plain".to_vec()), Some(vec![b'a'; 410_000]));
+        let args = |address: &Address| {
+            CreateCommitTransactionArgs::new(
+                vec![test_utxo(1, Amount::from_sat(1_000_000))],
+                inscription.clone(),
+                address.clone(),
+                address.script_pubkey(),
+                FeeRate::from_sat_per_vb(1).unwrap(),
+            )
+        };
+
+        let mainnet = Address::p2wpkh(&public_key, Network::Bitcoin).unwrap();
+        let result = builder
+            .build_commit_transaction(Network::Bitcoin, mainnet.clone(), args(&mainnet))
+            .await;
+        assert!(matches!(
+            result,
+            Err(OrdError::TransactionTooHeavy { weight, max })
+                if weight > max && max == crate::constants::MAX_STANDARD_TX_WEIGHT
+        ));
+
+        let regtest = Address::p2wpkh(&public_key, Network::Regtest).unwrap();
+        let commit = builder
+            .build_commit_transaction(Network::Regtest, regtest.clone(), args(&regtest))
+            .await
+            .unwrap();
+        let input = Utxo {
+            id: commit.unsigned_tx.txid(),
+            index: 0,
+            amount: commit.reveal_balance,
+        };
+
+        // the reveal is checked against the network of its recipient
+        for (recipient_address, standard) in [(mainnet, false), (regtest.clone(), true)] {
+            let result = builder.prepare_reveal_transaction(RevealTransactionArgs::new(
+                input.clone(),
+                recipient_address,
+                commit.redeem_script.clone(),
+            ));
+            assert_eq!(result.is_ok(), standard);
+        }
+        let reveal = builder
+            .build_reveal_transaction(RevealTransactionArgs::new(
+                input,
+                regtest,
+                commit.redeem_script,
+            ))
+            .await
+            .unwrap();
+        assert!(reveal.weight().to_wu() > crate::constants::MAX_STANDARD_TX_WEIGHT);
+    }
+
+    #[tokio::test]
+    async fn test_should_check_redeem_script_before_reveal() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        for mut builder in [
+            OrdTransactionBuilder::p2tr(private_key),
+            OrdTransactionBuilder::p2wsh(private_key),
+        ] {
+            let inscription = Brc20::transfer("mona".to_string(), 100);
+            let commit = builder
+                .build_commit_transaction(
+                    Network::Testnet,
+                    address.clone(),
+                    CreateCommitTransactionArgs::new(
+                        vec![test_utxo(1, Amount::from_sat(8_000))],
+                        inscription.clone(),
+                        address.clone(),
+                        address.script_pubkey(),
+                        FeeRate::from_sat_per_vb(1).unwrap(),
+                    ),
+                )
+                .await
+                .unwrap();
+            let args = RevealTransactionArgs::new(
+                Utxo {
+                    id: commit.unsigned_tx.txid(),
+                    index: 0,
+                    amount: commit.reveal_balance,
+                },
+                address.clone(),
+                commit.redeem_script.clone(),
+            );
+
+            let result = builder
+                .build_reveal_transaction_checked(
+                    args.clone(),
+                    &Brc20::transfer("mona".to_string(), 1_000),
+                )
+                .await;
+            assert!(matches!(result, Err(OrdError::RedeemScriptMismatch)));
+
+            assert!(builder
+                .build_reveal_transaction_checked(args, &inscription)
+                .await
+                .is_ok());
+        }
+    }
 }
@@ -0,0 +1,819 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bitcoin::absolute::LockTime;
+use bitcoin::bip32::DerivationPath;
+use bitcoin::transaction::Version;
+use bitcoin::{
+    secp256k1, Address, Amount, FeeRate, Network, OutPoint, Script, ScriptBuf, Sequence,
+    Transaction, TxIn, TxOut, Witness,
+};
+
+use super::{OrdTransactionBuilder, ScriptType, TaprootPayload, Utxo};
+use crate::inscription::Inscription;
+use crate::utils::constants::{check_standard_tx_weight, dust_limit, POSTAGE};
+use crate::utils::fees::{estimate_commit_fee, fee_for_vbytes, reveal_witness};
+use crate::{InscriptionId, OrdError, OrdResult};
+
+/// Defines how the postage of the inscriptions revealed in a batch is allocated
+/// to the outputs of the reveal transaction.
+///
+/// In both cases each inscription is given `postage` sats and the inscriptions are located at
+/// consecutive offsets of the reveal outputs, so the inscription pointers are the same.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PostageStrategy {
+    /// Every inscription gets its own postage output.
+    ///
+    /// Inscriptions can be transferred independently, but each output adds to the reveal fee
+    /// and creates a new UTXO to be tracked by the recipient.
+    #[default]
+    SeparateOutputs,
+    /// All the inscriptions are packed on a single output.
+    ///
+    /// The reveal transaction is smaller and cheaper, but the inscriptions can only be
+    /// transferred together, unless the output is split by a later transaction.
+    SharedOutput,
+}
+
+impl PostageStrategy {
+    /// Returns the number of reveal outputs holding `count` inscriptions.
+    pub fn output_count(&self, count: usize) -> usize {
+        match self {
+            Self::SeparateOutputs => count,
+            Self::SharedOutput => count.min(1),
+        }
+    }
+
+    /// Returns the pointer of the inscription at `index` in the batch,
+    /// i.e. its offset in the outputs of the reveal transaction.
+    pub fn pointer(&self, index: usize, postage: Amount) -> u64 {
+        index as u64 * postage.to_sat()
+    }
+
+    /// Returns the output index and the offset within it of the inscription at `index`.
+    pub fn location(&self, index: usize, postage: Amount) -> (usize, u64) {
+        match self {
+            Self::SeparateOutputs => (index, 0),
+            Self::SharedOutput => (0, self.pointer(index, postage)),
+        }
+    }
+
+    /// Builds the reveal outputs holding `count` inscriptions sent to `script_pubkey`.
+    pub fn reveal_outputs(
+        &self,
+        count: usize,
+        postage: Amount,
+        script_pubkey: &ScriptBuf,
+    ) -> Vec<TxOut> {
+        let value = match self {
+            Self::SeparateOutputs => postage,
+            Self::SharedOutput => postage * count as u64,
+        };
+
+        (0..self.output_count(count))
+            .map(|_| TxOut {
+                value,
+                script_pubkey: script_pubkey.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Redeem scripts of the inputs of a batch reveal transaction, each input spending its own
+/// commit output.
+///
+/// Batches of identical inscriptions, e.g. the same BRC-20 mint, share the same redeem script,
+/// which is stored once no matter the number of inputs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchRevealScripts {
+    unique: Vec<Arc<ScriptBuf>>,
+    positions: HashMap<Arc<ScriptBuf>, usize>,
+    inputs: Vec<usize>,
+}
+
+impl BatchRevealScripts {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an input spending a commit output through the redeem script, returning its index.
+    pub fn push(&mut self, redeem_script: ScriptBuf) -> usize {
+        let position = match self.positions.get(&redeem_script) {
+            Some(position) => *position,
+            None => {
+                let script = Arc::new(redeem_script);
+                self.unique.push(script.clone());
+                self.positions.insert(script, self.unique.len() - 1);
+                self.unique.len() - 1
+            }
+        };
+        self.inputs.push(position);
+        self.inputs.len() - 1
+    }
+
+    /// Returns the redeem script of the input at `index`.
+    pub fn get(&self, index: usize) -> Option<&Script> {
+        self.inputs
+            .get(index)
+            .map(|position| self.unique[*position].as_script())
+    }
+
+    /// Returns the redeem scripts of the inputs, in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Script> + '_ {
+        self.inputs
+            .iter()
+            .map(|position| self.unique[*position].as_script())
+    }
+
+    /// Returns the number of inputs.
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns whether the batch has no inputs.
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    /// Returns the number of distinct redeem scripts.
+    pub fn unique_count(&self) -> usize {
+        self.unique.len()
+    }
+
+    /// Estimates the fee of the batch reveal transaction, along with the marginal fee of each
+    /// inscription, i.e. how much the fee grows when it's added to the batch.
+    ///
+    /// The transaction overhead is paid by the first inscription only, while every inscription
+    /// pays for its own witness, since the redeem scripts are repeated on chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::FeeOverflow`](crate::OrdError::FeeOverflow) if a fee doesn't fit in
+    /// an amount of sats.
+    pub fn estimate_reveal_fees(
+        &self,
+        recipient_script_pubkey: &ScriptBuf,
+        script_type: ScriptType,
+        postage_strategy: PostageStrategy,
+        postage: Amount,
+        fee_rate: FeeRate,
+    ) -> OrdResult<BatchRevealFees> {
+        let witnesses = self
+            .unique
+            .iter()
+            .map(|script| reveal_witness(script, script_type, &None))
+            .collect::<Vec<_>>();
+        let outputs = postage_strategy.reveal_outputs(self.len(), postage, recipient_script_pubkey);
+
+        let mut reveal_tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: Vec::with_capacity(self.len()),
+            output: Vec::with_capacity(outputs.len()),
+        };
+        let mut outputs = outputs.into_iter();
+        let mut previous_fee = Amount::ZERO;
+        let mut marginal_fees = Vec::with_capacity(self.len());
+        for position in &self.inputs {
+            reveal_tx.input.push(TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: witnesses[*position].clone(),
+            });
+            if reveal_tx.output.len() < postage_strategy.output_count(reveal_tx.input.len()) {
+                reveal_tx.output.extend(outputs.next());
+            }
+
+            let fee = fee_for_vbytes(fee_rate, reveal_tx.vsize() as u64)?;
+            marginal_fees.push(fee - previous_fee);
+            previous_fee = fee;
+        }
+
+        Ok(BatchRevealFees {
+            total: previous_fee,
+            marginal_fees,
+            weight: reveal_tx.weight().to_wu(),
+        })
+    }
+}
+
+/// Fees of a batch reveal transaction, as returned by
+/// [`BatchRevealScripts::estimate_reveal_fees`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchRevealFees {
+    /// Fee of the whole reveal transaction
+    pub total: Amount,
+    /// Marginal fee of each inscription, in the order of the inputs, summing up to the total
+    pub marginal_fees: Vec<Amount>,
+    /// Estimated weight of the reveal transaction
+    #[serde(default)]
+    pub weight: u64,
+}
+
+impl BatchRevealFees {
+    /// Returns the fee of adding one more inscription to the batch, i.e. the marginal fee of
+    /// the last one, or the total fee if the batch has a single inscription.
+    pub fn marginal_fee(&self) -> Amount {
+        self.marginal_fees.last().copied().unwrap_or(Amount::ZERO)
+    }
+}
+
+/// Arguments for creating a commit transaction of a batch of inscriptions, revealed together by
+/// a single reveal transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct CreateBatchCommitTransactionArgs<T>
+where
+    T: Inscription,
+{
+    /// UTXOs to be used as inputs of the transaction
+    pub inputs: Vec<Utxo>,
+    /// Inscriptions to write, in the order of the reveal inputs
+    pub inscriptions: Vec<T>,
+    /// Address to send the leftovers BTC of the transaction
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
+    pub leftovers_recipient: Address,
+    /// Script pubkey of the inputs
+    pub txin_script_pubkey: ScriptBuf,
+    /// Current fee rate on the network
+    pub fee_rate: FeeRate,
+    /// Derivation path for the keypair
+    pub derivation_path: Option<DerivationPath>,
+    /// How the postage of the inscriptions is allocated to the reveal outputs
+    #[serde(default)]
+    pub postage_strategy: PostageStrategy,
+    /// Value given to each inscription, [`POSTAGE`] if not set
+    #[serde(default)]
+    pub postage: Option<Amount>,
+}
+
+/// Commit transaction of a batch of inscriptions, returned by
+/// [`OrdTransactionBuilder::build_batch_commit_transaction`].
+///
+/// The commit transaction has one P2TR output per inscription, in order, followed by the change
+/// output. Every commit output holds the postage of its inscription, except for the last one
+/// which also holds the whole reveal fee, so the first sat of each reveal input lands at the
+/// location of its inscription given by [`PostageStrategy::location`], without pointers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchCommitTransaction {
+    /// The unsigned commit transaction
+    #[serde(with = "crate::utils::serde_helpers::transaction")]
+    pub unsigned_tx: Transaction,
+    /// The redeem scripts of the inscriptions, in the order of the commit outputs
+    pub redeem_scripts: Vec<ScriptBuf>,
+    /// Commit transaction fee
+    pub commit_fee: Amount,
+    /// Fees of the reveal transaction
+    pub reveal_fees: BatchRevealFees,
+    /// Leftover amount to be sent to the leftovers recipient
+    pub leftover_amount: Amount,
+    /// Value given to each inscription
+    pub postage: Amount,
+    /// How the postage of the inscriptions is allocated to the reveal outputs
+    pub postage_strategy: PostageStrategy,
+}
+
+impl BatchCommitTransaction {
+    /// Returns the commit outputs spent by the reveal transaction.
+    pub fn commit_outputs(&self) -> &[TxOut] {
+        &self.unsigned_tx.output[..self.redeem_scripts.len()]
+    }
+
+    /// Returns the IDs of the inscriptions revealed by the given reveal transaction.
+    pub fn inscription_ids(&self, reveal_tx: &Transaction) -> Vec<InscriptionId> {
+        let txid = reveal_tx.txid();
+        (0..self.redeem_scripts.len() as u32)
+            .map(|index| InscriptionId { txid, index })
+            .collect()
+    }
+}
+
+/// Arguments for creating the reveal transaction of a batch of inscriptions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchRevealTransactionArgs {
+    /// The commit transaction returned by
+    /// [`OrdTransactionBuilder::build_batch_commit_transaction`], whose txid is unchanged by
+    /// signing
+    pub commit: BatchCommitTransaction,
+    /// Recipient of the inscriptions
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
+    pub recipient_address: Address,
+    /// Derivation path for the keypair, as given to the commit
+    pub derivation_path: Option<DerivationPath>,
+}
+
+impl OrdTransactionBuilder {
+    /// Creates the commit transaction of a batch of inscriptions, e.g. many BRC-20 mints, so
+    /// they're inscribed with a single commit and a single reveal transaction.
+    ///
+    /// The commit transaction is signed with [`Self::sign_commit_transaction`] and the reveal
+    /// transaction is built with [`Self::build_batch_reveal_transaction`]. Identical
+    /// inscriptions share the same commit script.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidScriptType`] if the builder doesn't use P2TR,
+    /// [`OrdError::InvalidInputs`] if there are no inscriptions,
+    /// [`OrdError::TransactionTooHeavy`] if the estimated reveal transaction exceeds the
+    /// [`max_standard_tx_weight`](crate::constants::max_standard_tx_weight) of the network,
+    /// so the commit outputs could never be spent by a relayed transaction,
+    /// [`OrdError::DustOutput`] if the postage is below the dust limit of the commit outputs,
+    /// or [`OrdError::InsufficientBalance`] if the inputs don't cover the postage and the fees.
+    pub async fn build_batch_commit_transaction<T>(
+        &mut self,
+        network: Network,
+        recipient_address: Address,
+        args: CreateBatchCommitTransactionArgs<T>,
+    ) -> OrdResult<BatchCommitTransaction>
+    where
+        T: Inscription,
+    {
+        if self.script_type != ScriptType::P2TR {
+            return Err(OrdError::InvalidScriptType);
+        }
+        if args.inscriptions.is_empty() {
+            return Err(OrdError::InvalidInputs);
+        }
+
+        let secp_ctx = secp256k1::Secp256k1::new();
+        let pubkey = self
+            .signer
+            .schnorr_public_key(&args.derivation_path.unwrap_or_default())
+            .await?;
+        let postage = args.postage.unwrap_or(POSTAGE);
+
+        let mut scripts = BatchRevealScripts::new();
+        for inscription in &args.inscriptions {
+            let redeem_script = self.generate_redeem_script(
+                inscription,
+                super::RedeemScriptPubkey::XPublickey(pubkey),
+            )?;
+            scripts.push(redeem_script);
+        }
+        let reveal_fees = scripts.estimate_reveal_fees(
+            &recipient_address.script_pubkey(),
+            ScriptType::P2TR,
+            args.postage_strategy,
+            postage,
+            args.fee_rate,
+        )?;
+        check_standard_tx_weight(network, reveal_fees.weight)?;
+
+        let mut tx_out = Vec::with_capacity(scripts.len() + 1);
+        for (index, redeem_script) in scripts.iter().enumerate() {
+            let value = if index + 1 == scripts.len() {
+                postage + reveal_fees.total
+            } else {
+                postage
+            };
+            let payload =
+                TaprootPayload::build(&secp_ctx, pubkey, &redeem_script.into(), value, network)?;
+            let dust_limit = dust_limit(&payload.prevouts.script_pubkey);
+            if postage < dust_limit {
+                return Err(OrdError::DustOutput {
+                    value: postage,
+                    dust_limit,
+                });
+            }
+            tx_out.push(payload.prevouts);
+        }
+        tx_out.push(TxOut {
+            value: Amount::ZERO, // placeholder for leftover amount, which is calculated later
+            script_pubkey: args.txin_script_pubkey.clone(),
+        });
+
+        let tx_in = args
+            .inputs
+            .iter()
+            .map(|input| TxIn {
+                previous_output: OutPoint {
+                    txid: input.id,
+                    vout: input.index,
+                },
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::from_consensus(0xffffffff),
+                witness: Witness::new(),
+            })
+            .collect::<Vec<_>>();
+        let mut unsigned_tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: tx_in,
+            output: tx_out,
+        };
+        let commit_fee =
+            estimate_commit_fee(unsigned_tx.clone(), self.script_type, args.fee_rate, &None)?;
+
+        let input_amount = args.inputs.iter().map(|input| input.amount).sum::<Amount>();
+        let required = postage * scripts.len() as u64 + reveal_fees.total + commit_fee;
+        let leftover_amount =
+            input_amount
+                .checked_sub(required)
+                .ok_or(OrdError::InsufficientBalance {
+                    available: input_amount,
+                    required,
+                })?;
+        debug!("batch leftover_amount: {leftover_amount}");
+        if let Some(change) = unsigned_tx.output.last_mut() {
+            change.value = leftover_amount;
+        }
+
+        Ok(BatchCommitTransaction {
+            unsigned_tx,
+            redeem_scripts: scripts.iter().map(ScriptBuf::from).collect(),
+            commit_fee,
+            reveal_fees,
+            leftover_amount,
+            postage,
+            postage_strategy: args.postage_strategy,
+        })
+    }
+
+    /// Creates and signs the reveal transaction of a batch of inscriptions, spending all the
+    /// commit outputs of the [`BatchCommitTransaction`].
+    ///
+    /// Input `i` reveals the inscription `i`, whose ID is returned by
+    /// [`BatchCommitTransaction::inscription_ids`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidScriptType`] if the builder doesn't use P2TR,
+    /// [`OrdError::TransactionTooHeavy`] if the reveal transaction exceeds the
+    /// [`max_standard_tx_weight`](crate::constants::max_standard_tx_weight) of the network of
+    /// the recipient, so it wouldn't be relayed, or any error occurring while signing the
+    /// inputs.
+    pub async fn build_batch_reveal_transaction(
+        &mut self,
+        args: BatchRevealTransactionArgs,
+    ) -> OrdResult<Transaction> {
+        if self.script_type != ScriptType::P2TR {
+            return Err(OrdError::InvalidScriptType);
+        }
+
+        let secp_ctx = secp256k1::Secp256k1::new();
+        let derivation_path = args.derivation_path.unwrap_or_default();
+        let pubkey = self.signer.schnorr_public_key(&derivation_path).await?;
+
+        let commit = &args.commit;
+        let commit_txid = commit.unsigned_tx.txid();
+        let prevouts = commit.commit_outputs().to_vec();
+        let control_blocks = commit
+            .redeem_scripts
+            .iter()
+            .zip(&prevouts)
+            .map(|(redeem_script, prevout)| {
+                TaprootPayload::build(
+                    &secp_ctx,
+                    pubkey,
+                    redeem_script,
+                    prevout.value,
+                    Network::Bitcoin,
+                )
+                .map(|payload| payload.control_block)
+            })
+            .collect::<OrdResult<Vec<_>>>()?;
+
+        let unsigned_tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: (0..prevouts.len() as u32)
+                .map(|vout| TxIn {
+                    previous_output: OutPoint {
+                        txid: commit_txid,
+                        vout,
+                    },
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::from_consensus(0xffffffff),
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: commit.postage_strategy.reveal_outputs(
+                prevouts.len(),
+                commit.postage,
+                &args.recipient_address.script_pubkey(),
+            ),
+        };
+
+        let reveal_tx = self
+            .signer
+            .sign_reveal_inputs_script_path(
+                &self.public_key,
+                control_blocks
+                    .iter()
+                    .zip(&commit.redeem_scripts)
+                    .enumerate()
+                    .map(|(index, (control_block, redeem_script))| {
+                        (index, control_block, redeem_script, None)
+                    }),
+                &prevouts,
+                unsigned_tx,
+                &derivation_path,
+            )
+            .await?;
+
+        check_standard_tx_weight(
+            *args.recipient_address.network(),
+            reveal_tx.weight().to_wu(),
+        )?;
+
+        Ok(reveal_tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::Secp256k1;
+
+    use super::*;
+    use crate::utils::constants::MAX_STANDARD_TX_WEIGHT;
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::wallet::builder::{verify_transaction, SignCommitTransactionArgs};
+    use crate::wallet::{LocalSigner, Wallet};
+    use crate::{Brc20, Nft, OrdParser};
+
+    #[test]
+    fn test_should_allocate_postage() {
+        let postage = Amount::from_sat(333);
+        let script_pubkey = ScriptBuf::new();
+
+        let outputs = PostageStrategy::SeparateOutputs.reveal_outputs(3, postage, &script_pubkey);
+        assert_eq!(outputs.len(), 3);
+        assert!(outputs.iter().all(|output| output.value == postage));
+        assert_eq!(
+            PostageStrategy::SeparateOutputs.location(2, postage),
+            (2, 0)
+        );
+
+        let outputs = PostageStrategy::SharedOutput.reveal_outputs(3, postage, &script_pubkey);
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].value, postage * 3);
+        assert_eq!(PostageStrategy::SharedOutput.location(2, postage), (0, 666));
+
+        for strategy in [
+            PostageStrategy::SeparateOutputs,
+            PostageStrategy::SharedOutput,
+        ] {
+            assert_eq!(strategy.pointer(2, postage), 666);
+            assert!(strategy
+                .reveal_outputs(0, postage, &script_pubkey)
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn test_should_share_identical_redeem_scripts() {
+        let mint = ScriptBuf::from_bytes(vec![0x51; 100]);
+        let other = ScriptBuf::from_bytes(vec![0x52; 120]);
+
+        let mut scripts = BatchRevealScripts::new();
+        for _ in 0..3 {
+            scripts.push(mint.clone());
+        }
+        assert_eq!(scripts.push(other.clone()), 3);
+        assert_eq!(scripts.len(), 4);
+        assert_eq!(scripts.unique_count(), 2);
+        assert_eq!(scripts.get(1), Some(mint.as_script()));
+        assert_eq!(scripts.get(3), Some(other.as_script()));
+        assert_eq!(scripts.get(4), None);
+        assert_eq!(
+            scripts
+                .iter()
+                .filter(|script| *script == mint.as_script())
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_should_estimate_marginal_batch_reveal_fees() {
+        let postage = Amount::from_sat(333);
+        let fee_rate = FeeRate::from_sat_per_vb(10).unwrap();
+        let recipient =
+            ScriptBuf::new_p2tr_tweaked(bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+                    .parse()
+                    .unwrap(),
+            ));
+        let mint = ScriptBuf::from_bytes(vec![0x51; 100]);
+
+        let mut scripts = BatchRevealScripts::new();
+        for _ in 0..10 {
+            scripts.push(mint.clone());
+        }
+
+        for strategy in [
+            PostageStrategy::SeparateOutputs,
+            PostageStrategy::SharedOutput,
+        ] {
+            let fees = scripts
+                .estimate_reveal_fees(&recipient, ScriptType::P2TR, strategy, postage, fee_rate)
+                .unwrap();
+            assert_eq!(fees.marginal_fees.len(), 10);
+            assert_eq!(
+                fees.marginal_fees.iter().copied().sum::<Amount>(),
+                fees.total
+            );
+            // the overhead is paid once
+            assert!(fees.marginal_fees[0] > fees.marginal_fee());
+
+            let single = crate::utils::fees::estimate_reveal_fee_for_script(
+                vec![OutPoint::null()],
+                &recipient,
+                mint.clone(),
+                ScriptType::P2TR,
+                fee_rate,
+                &None,
+            )
+            .unwrap();
+            assert_eq!(fees.marginal_fees[0], single);
+            assert!(fees.total < single * 10);
+        }
+
+        let separate = scripts
+            .estimate_reveal_fees(
+                &recipient,
+                ScriptType::P2TR,
+                PostageStrategy::SeparateOutputs,
+                postage,
+                fee_rate,
+            )
+            .unwrap();
+        let shared = scripts
+            .estimate_reveal_fees(
+                &recipient,
+                ScriptType::P2TR,
+                PostageStrategy::SharedOutput,
+                postage,
+                fee_rate,
+            )
+            .unwrap();
+        assert!(shared.marginal_fee() < separate.marginal_fee());
+    }
+
+    #[tokio::test]
+    async fn test_should_build_batch_commit_and_reveal_transactions() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let wallet = Wallet::new_with_signer(LocalSigner::new(private_key))
+            .with_signature_verification(true);
+        let mut builder = OrdTransactionBuilder::new(public_key, ScriptType::P2TR, wallet);
+
+        let inputs = vec![test_utxo(1, Amount::from_sat(50_000))];
+        let inscriptions = vec![
+            Brc20::mint("ordi", 1_000),
+            Brc20::mint("ordi", 1_000),
+            Brc20::transfer("ordi", 100),
+        ];
+
+        for strategy in [
+            PostageStrategy::SeparateOutputs,
+            PostageStrategy::SharedOutput,
+        ] {
+            let commit = builder
+                .build_batch_commit_transaction(
+                    Network::Testnet,
+                    address.clone(),
+                    CreateBatchCommitTransactionArgs {
+                        inputs: inputs.clone(),
+                        inscriptions: inscriptions.clone(),
+                        leftovers_recipient: address.clone(),
+                        txin_script_pubkey: address.script_pubkey(),
+                        fee_rate: FeeRate::from_sat_per_vb(2).unwrap(),
+                        derivation_path: None,
+                        postage_strategy: strategy,
+                        postage: None,
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(commit.unsigned_tx.output.len(), 4);
+            assert_eq!(commit.redeem_scripts.len(), 3);
+            assert_eq!(
+                commit
+                    .unsigned_tx
+                    .output
+                    .iter()
+                    .map(|output| output.value)
+                    .sum::<Amount>()
+                    + commit.commit_fee,
+                inputs[0].amount
+            );
+
+            let commit_tx = builder
+                .sign_commit_transaction(
+                    commit.unsigned_tx.clone(),
+                    SignCommitTransactionArgs {
+                        inputs: inputs.clone(),
+                        txin_script_pubkey: address.script_pubkey(),
+                        derivation_path: None,
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(commit_tx.txid(), commit.unsigned_tx.txid());
+
+            let reveal_tx = builder
+                .build_batch_reveal_transaction(BatchRevealTransactionArgs {
+                    commit: commit.clone(),
+                    recipient_address: address.clone(),
+                    derivation_path: None,
+                })
+                .await
+                .unwrap();
+            assert_eq!(reveal_tx.input.len(), 3);
+            assert_eq!(
+                reveal_tx.output.len(),
+                strategy.output_count(inscriptions.len())
+            );
+            verify_transaction(&reveal_tx, commit.commit_outputs()).unwrap();
+
+            let reveal_fee = commit
+                .commit_outputs()
+                .iter()
+                .map(|output| output.value)
+                .sum::<Amount>()
+                - reveal_tx
+                    .output
+                    .iter()
+                    .map(|output| output.value)
+                    .sum::<Amount>();
+            assert_eq!(reveal_fee, commit.reveal_fees.total);
+
+            let parsed = OrdParser::parse_all(&reveal_tx).unwrap();
+            assert_eq!(parsed.len(), 3);
+            for (index, (id, parsed)) in parsed.iter().enumerate() {
+                assert_eq!(*id, commit.inscription_ids(&reveal_tx)[index]);
+                assert!(
+                    matches!(parsed, OrdParser::Brc20 { inscription, .. } if *inscription == inscriptions[index]),
+                    "inscription {index}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_not_build_non_standard_batch_reveal_transaction() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let wallet = Wallet::new_with_signer(LocalSigner::new(private_key));
+        let mut builder = OrdTransactionBuilder::new(public_key, ScriptType::P2TR, wallet);
+
+        let inputs = vec![test_utxo(1, Amount::from_sat(1_000_000))];
+        // 5 inscriptions of 90 kB weigh more than the 400 kWU relayed by standard nodes
+        let inscriptions =
+            vec![Nft::new(Some(b"text/plain".to_vec()), Some(vec![b'a'; 90_000])); 5];
+
+        let args = |address: &Address| CreateBatchCommitTransactionArgs {
+            inputs: inputs.clone(),
+            inscriptions: inscriptions.clone(),
+            leftovers_recipient: address.clone(),
+            txin_script_pubkey: address.script_pubkey(),
+            fee_rate: FeeRate::from_sat_per_vb(1).unwrap(),
+            derivation_path: None,
+            postage_strategy: PostageStrategy::SeparateOutputs,
+            postage: None,
+        };
+
+        // the commit is rejected before the funds are locked in outputs which can't be revealed
+        let mainnet = Address::p2wpkh(&public_key, Network::Bitcoin).unwrap();
+        let result = builder
+            .build_batch_commit_transaction(Network::Bitcoin, mainnet.clone(), args(&mainnet))
+            .await;
+        assert!(matches!(
+            result,
+            Err(OrdError::TransactionTooHeavy { weight, max: MAX_STANDARD_TX_WEIGHT })
+                if weight > MAX_STANDARD_TX_WEIGHT
+        ));
+
+        let regtest = Address::p2wpkh(&public_key, Network::Regtest).unwrap();
+        let commit = builder
+            .build_batch_commit_transaction(Network::Regtest, regtest.clone(), args(&regtest))
+            .await
+            .unwrap();
+        assert!(commit.reveal_fees.weight > MAX_STANDARD_TX_WEIGHT);
+
+        for (recipient_address, standard) in [(mainnet, false), (regtest, true)] {
+            let result = builder
+                .build_batch_reveal_transaction(BatchRevealTransactionArgs {
+                    commit: commit.clone(),
+                    recipient_address,
+                    derivation_path: None,
+                })
+                .await;
+            if standard {
+                let reveal = result.unwrap();
+                assert!(reveal.weight().to_wu() > MAX_STANDARD_TX_WEIGHT);
+                assert!(reveal.weight().to_wu() <= commit.reveal_fees.weight);
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(OrdError::TransactionTooHeavy { weight, max: MAX_STANDARD_TX_WEIGHT })
+                        if weight > MAX_STANDARD_TX_WEIGHT
+                ));
+            }
+        }
+    }
+}
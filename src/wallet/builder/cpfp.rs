@@ -0,0 +1,269 @@
+use bitcoin::absolute::LockTime;
+use bitcoin::bip32::DerivationPath;
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Address, Amount, FeeRate, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+
+use super::{OrdTransactionBuilder, TaprootKeySpend, TxInputInfo};
+use crate::utils::constants::{dust_limit, ANCHOR_SCRIPT_PUBKEY};
+use crate::utils::fees::{estimate_transaction_fees_for_prevouts, fee_for_vbytes};
+use crate::{OrdError, OrdResult};
+
+/// Arguments of [`OrdTransactionBuilder::build_cpfp_transaction`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpfpArgs {
+    /// Fee paid by the parent transaction, e.g. [`super::CreateCommitTransaction::reveal_fee`]
+    /// for a reveal transaction, or zero for an anchored one
+    pub parent_fee: Amount,
+    /// Index of the output of the parent spent by the child, e.g. 0 for the postage output of a
+    /// reveal transaction, or the index of its pay-to-anchor output
+    pub vout: u32,
+    /// Derivation path of the key locking the spent output, ignored for pay-to-anchor outputs
+    #[serde(default)]
+    pub derivation_path: DerivationPath,
+    /// Key signing the spent output if it's a P2TR output spent through the key path
+    #[serde(default)]
+    pub key_spend: TaprootKeySpend,
+    /// Additional inputs funding the child, e.g. when the postage doesn't cover its fee
+    #[serde(default)]
+    pub inputs: Vec<TxInputInfo>,
+    /// Recipient of the single output of the child, i.e. the owner of the inscription when
+    /// spending the postage output
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
+    pub recipient: Address,
+}
+
+/// Child transaction returned by [`OrdTransactionBuilder::build_cpfp_transaction`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpfpTransaction {
+    /// Signed child transaction
+    pub transaction: Transaction,
+    /// Fee paid by the child transaction
+    pub fee: Amount,
+    /// Fee rate of the package of the parent and the child, i.e. their total fee over their
+    /// total size
+    pub package_fee_rate: FeeRate,
+}
+
+impl OrdTransactionBuilder {
+    /// Builds and signs a child-pays-for-parent transaction bumping the fee of a stuck
+    /// transaction, e.g. a reveal transaction whose commit transaction is confirmed.
+    ///
+    /// The child spends an output of the parent, either its postage output or its
+    /// pay-to-anchor output, see [`super::RevealTransactionArgs::anchor`], followed by the
+    /// additional inputs, and sends everything but its fee to the recipient. The postage is
+    /// spent by the first input, so the inscription is moved to the first sat of the output.
+    ///
+    /// The child pays for the parent the fee missing for the package of both transactions to
+    /// reach the given fee rate, and at least its own size at the minimum relay fee rate.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::OutputNotFound`] if the parent has no output at `vout`, or
+    /// [`OrdError::InsufficientBalance`] if the inputs don't cover the fee and a non-dust
+    /// output.
+    pub async fn build_cpfp_transaction(
+        &self,
+        parent: &Transaction,
+        fee_rate: FeeRate,
+        args: CpfpArgs,
+    ) -> OrdResult<CpfpTransaction> {
+        let tx_out = parent
+            .output
+            .get(args.vout as usize)
+            .ok_or(OrdError::OutputNotFound(args.vout as usize))?
+            .clone();
+        let is_anchor = tx_out.script_pubkey.as_bytes() == ANCHOR_SCRIPT_PUBKEY;
+        let parent_input = TxInputInfo {
+            outpoint: OutPoint::new(parent.txid(), args.vout),
+            tx_out,
+            derivation_path: args.derivation_path,
+            key_spend: args.key_spend,
+            rune_balances: None,
+        };
+        let inputs = std::iter::once(parent_input)
+            .chain(args.inputs)
+            .collect::<Vec<_>>();
+        let prevouts = inputs
+            .iter()
+            .map(|input| input.tx_out.clone())
+            .collect::<Vec<_>>();
+        let input_amount = prevouts.iter().map(|prevout| prevout.value).sum::<Amount>();
+
+        let recipient = args.recipient.script_pubkey();
+        let outputs = vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: recipient.clone(),
+        }];
+        let estimate =
+            |fee_rate| estimate_transaction_fees_for_prevouts(&prevouts, fee_rate, outputs.clone());
+        let package_fee = estimate(fee_rate)? + fee_for_vbytes(fee_rate, parent.vsize() as u64)?;
+        let fee = package_fee
+            .checked_sub(args.parent_fee)
+            .unwrap_or_default()
+            .max(estimate(FeeRate::BROADCAST_MIN)?);
+        debug!("paying {fee} for the child of {}", parent.txid());
+
+        let dust_limit = dust_limit(&recipient);
+        let value = input_amount
+            .checked_sub(fee)
+            .filter(|value| *value >= dust_limit)
+            .ok_or(OrdError::InsufficientBalance {
+                required: fee + dust_limit,
+                available: input_amount,
+            })?;
+
+        let unsigned_tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: inputs
+                .iter()
+                .map(|input| TxIn {
+                    previous_output: input.outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: vec![TxOut {
+                value,
+                script_pubkey: recipient,
+            }],
+        };
+
+        // pay-to-anchor outputs are spent with an empty witness
+        let signed_inputs = inputs.iter().enumerate().skip(usize::from(is_anchor));
+        let transaction = self
+            .signer
+            .sign_inputs(&unsigned_tx, &prevouts, signed_inputs)
+            .await?;
+
+        let package_vsize = (parent.vsize() + transaction.vsize()) as u64;
+        let package_fee_rate =
+            FeeRate::from_sat_per_kwu((args.parent_fee + fee).to_sat() * 250 / package_vsize);
+
+        Ok(CpfpTransaction {
+            transaction,
+            fee,
+            package_fee_rate,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::Network;
+
+    use super::*;
+    use crate::utils::constants::POSTAGE;
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::wallet::{
+        verify_transaction, CreateCommitTransactionArgsV2, RevealTransactionArgs, Utxo,
+    };
+    use crate::Brc20;
+
+    #[tokio::test]
+    async fn test_should_build_cpfp_transaction() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let commit = builder
+            .build_commit_transaction_with_fixed_fees(
+                Network::Testnet,
+                CreateCommitTransactionArgsV2 {
+                    inputs: vec![test_utxo(1, Amount::from_sat(20_000))],
+                    txin_script_pubkey: address.script_pubkey(),
+                    inscription: Brc20::transfer("mona".to_string(), 100),
+                    leftovers_recipient: address.clone(),
+                    commit_fee: Amount::from_sat(2_500),
+                    // leaves a postage of 10,000 sats and a reveal fee of 300 sats
+                    reveal_fee: Amount::from_sat(10_300) - POSTAGE,
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+        let reveal = builder
+            .build_reveal_transaction(RevealTransactionArgs {
+                postage: Some(Amount::from_sat(10_000)),
+                anchor: true,
+                ..RevealTransactionArgs::new(
+                    Utxo {
+                        id: commit.unsigned_tx.txid(),
+                        index: 0,
+                        amount: commit.reveal_balance,
+                    },
+                    address.clone(),
+                    commit.redeem_script.clone(),
+                )
+            })
+            .await
+            .unwrap();
+        // the anchored reveal pays no fee, its postage keeps the 300 sats reserved for it
+        assert_eq!(reveal.output[0].value, Amount::from_sat(10_300));
+        assert_eq!(reveal.output[1].value, Amount::ZERO);
+        let args = CpfpArgs {
+            parent_fee: Amount::ZERO,
+            vout: 0,
+            derivation_path: DerivationPath::default(),
+            key_spend: TaprootKeySpend::default(),
+            inputs: vec![],
+            recipient: address.clone(),
+        };
+
+        // spending the postage output
+        let fee_rate = FeeRate::from_sat_per_vb(20).unwrap();
+        let child = builder
+            .build_cpfp_transaction(&reveal, fee_rate, args.clone())
+            .await
+            .unwrap();
+        verify_transaction(&child.transaction, &reveal.output[..1]).unwrap();
+        assert_eq!(child.transaction.input[0].previous_output.vout, 0);
+        assert_eq!(
+            child.transaction.output[0].value,
+            reveal.output[0].value - child.fee
+        );
+        assert!(child.package_fee_rate >= fee_rate);
+        let package_vsize = (reveal.vsize() + child.transaction.vsize()) as u64;
+        assert!(args.parent_fee + child.fee >= fee_rate.fee_vb(package_vsize).unwrap());
+
+        // spending the anchor output, funded by another input
+        let funding = TxInputInfo {
+            outpoint: OutPoint::new(commit.unsigned_tx.txid(), 1),
+            tx_out: commit.unsigned_tx.output[1].clone(),
+            derivation_path: DerivationPath::default(),
+            key_spend: TaprootKeySpend::default(),
+            rune_balances: None,
+        };
+        let child = builder
+            .build_cpfp_transaction(
+                &reveal,
+                fee_rate,
+                CpfpArgs {
+                    vout: 1,
+                    inputs: vec![funding.clone()],
+                    ..args.clone()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(child.transaction.input[0].witness.is_empty());
+        verify_transaction(
+            &child.transaction,
+            &[reveal.output[1].clone(), funding.tx_out.clone()],
+        )
+        .unwrap();
+        assert!(child.package_fee_rate >= fee_rate);
+
+        assert!(matches!(
+            builder
+                .build_cpfp_transaction(&reveal, FeeRate::from_sat_per_vb(500).unwrap(), args)
+                .await,
+            Err(OrdError::InsufficientBalance { .. })
+        ));
+    }
+}
@@ -0,0 +1,332 @@
+use bitcoin::script::Instruction;
+use bitcoin::{Amount, FeeRate, OutPoint, Script, ScriptBuf, Transaction, TxOut};
+
+use super::{SatPoint, ScriptType, TxInputInfo};
+use crate::utils::constants::PARENT_TAG;
+use crate::utils::fees::{estimate_child_reveal_fee, MultisigConfig};
+use crate::wallet::ParsedEnvelope;
+use crate::{InscriptionId, OrdError, OrdResult};
+
+/// Parent of a child inscription, spent by the reveal transaction to prove its ownership.
+///
+/// Following the provenance rules of `ord`, the reveal transaction spends the output holding
+/// the parent inscription in its second input, and returns it to the same script pubkey in its
+/// second output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParentInfo {
+    /// ID of the parent inscription, which must be declared by the child inscription, e.g.
+    /// with [`crate::Nft::with_parent`]
+    pub inscription_id: InscriptionId,
+    /// Output holding the parent inscription, signed as by
+    /// [`super::OrdTransactionBuilder::sign_transaction`]
+    pub input: TxInputInfo,
+    /// Offset of the sat of the parent inscription in the output, i.e. the offset of its
+    /// [`SatPoint`], `0` if it's on the first sat
+    #[serde(default)]
+    pub offset: u64,
+}
+
+impl ParentInfo {
+    /// Returns the output returning the parent inscription to its owner.
+    pub fn return_output(&self) -> TxOut {
+        self.input.tx_out.clone()
+    }
+
+    /// Checks that the redeem script declares the parent inscription in a parent field.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::MissingParentTag`] if the parent is not declared, as the child
+    /// inscription would then not be recognized as a child by indexers.
+    pub(crate) fn check_declared_by(&self, redeem_script: &Script) -> OrdResult<()> {
+        let parent = self.inscription_id.get_raw();
+        let pushes = redeem_script
+            .instructions()
+            .filter_map(|instruction| match instruction {
+                Ok(Instruction::PushBytes(push)) => Some(push.as_bytes()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if pushes
+            .windows(2)
+            .any(|window| window[0] == PARENT_TAG && window[1] == parent.as_slice())
+        {
+            Ok(())
+        } else {
+            Err(OrdError::MissingParentTag(self.inscription_id))
+        }
+    }
+
+    /// Estimates the fee of the reveal transaction of the child inscription.
+    pub(crate) fn estimate_reveal_fee(
+        &self,
+        recipient_script_pubkey: &Script,
+        redeem_script: ScriptBuf,
+        script_type: ScriptType,
+        fee_rate: FeeRate,
+        multisig_config: &Option<MultisigConfig>,
+    ) -> OrdResult<Amount> {
+        estimate_child_reveal_fee(
+            recipient_script_pubkey,
+            redeem_script,
+            script_type,
+            fee_rate,
+            multisig_config,
+            self.return_output(),
+        )
+    }
+
+    /// Checks that the parent inscription is returned to its owner by a reveal transaction
+    /// paying the given fee.
+    ///
+    /// The sats of the commit output left over by the postage, i.e. the reveal fee, precede
+    /// the parent sats in the parent return output, so the parent inscription ends up at its
+    /// offset plus the reveal fee in it, and would be spent as fee if that exceeded the value
+    /// of the parent output.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::ParentValueTooLow`] if the parent inscription would be spent as fee.
+    pub(crate) fn check_returned(&self, reveal_fee: Amount) -> OrdResult<()> {
+        let value = self.input.tx_out.value;
+        let returned = self
+            .offset
+            .checked_add(reveal_fee.to_sat())
+            .is_some_and(|offset| offset < value.to_sat());
+        if !returned {
+            return Err(OrdError::ParentValueTooLow {
+                value,
+                fee: reveal_fee,
+                offset: self.offset,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Reveal transaction of child inscriptions, with the outputs spent by its inputs, to be audited
+/// by [`audit_child_reveals`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChildReveal {
+    /// The reveal transaction, signed or not
+    #[serde(with = "crate::utils::serde_helpers::transaction")]
+    pub transaction: Transaction,
+    /// Outputs spent by the inputs of the transaction, in order
+    pub prevouts: Vec<TxOut>,
+}
+
+/// Linkage of a child inscription to its parent, as reported by [`audit_child_reveals`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChildReport {
+    /// ID of the child inscription
+    pub inscription_id: InscriptionId,
+    /// Whether the envelope of the child has a parent field with the parent inscription
+    pub declares_parent: bool,
+    /// Whether the reveal transaction spends the output holding the parent inscription
+    pub spends_parent: bool,
+    /// Location of the parent inscription after the reveal transaction, or `None` if it's
+    /// spent as fee, which makes it unusable for the following children
+    pub parent_location: Option<SatPoint>,
+}
+
+impl ChildReport {
+    /// Returns whether indexers will recognize the inscription as a child of the parent, and
+    /// the parent is still owned after its reveal.
+    pub fn is_valid(&self) -> bool {
+        self.declares_parent && self.spends_parent && self.parent_location.is_some()
+    }
+}
+
+/// Audits the parent linkage of the children revealed by the transactions, e.g. before
+/// broadcasting the reveals of a collection, reporting for each child whether it declares the
+/// parent in a parent field and whether its reveal spends the parent, as the provenance rules of
+/// `ord` require.
+///
+/// The reveals are expected in broadcast order: the parent is followed from `parent_location`
+/// through the reveals spending it, each of which moves it to the output receiving its sat.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidInputs`] if the prevouts of a reveal don't match its inputs.
+pub fn audit_child_reveals(
+    parent: InscriptionId,
+    parent_location: SatPoint,
+    reveals: &[ChildReveal],
+) -> OrdResult<Vec<ChildReport>> {
+    let mut location = Some(parent_location);
+    let mut reports = Vec::new();
+    for reveal in reveals {
+        let tx = &reveal.transaction;
+        if tx.input.len() != reveal.prevouts.len() {
+            return Err(OrdError::InvalidInputs);
+        }
+
+        let spent = location.and_then(|location| {
+            tx.input
+                .iter()
+                .position(|input| input.previous_output == location.outpoint)
+                .map(|index| (index, location.offset))
+        });
+        if let Some((index, offset)) = spent {
+            let sat = reveal.prevouts[..index]
+                .iter()
+                .map(|prevout| prevout.value.to_sat())
+                .sum::<u64>()
+                + offset;
+            location = locate_output_sat(tx, sat);
+        }
+
+        let txid = tx.txid();
+        reports.extend(
+            ParsedEnvelope::from_transaction(tx)
+                .into_iter()
+                .enumerate()
+                .map(|(index, envelope)| ChildReport {
+                    inscription_id: InscriptionId {
+                        txid,
+                        index: index as u32,
+                    },
+                    declares_parent: envelope.payload.parents().contains(&parent),
+                    spends_parent: spent.is_some(),
+                    parent_location: location,
+                }),
+        );
+    }
+
+    Ok(reports)
+}
+
+/// Returns the location of the sat at the given offset into the inputs of the transaction, or
+/// `None` if it's spent as fee.
+fn locate_output_sat(tx: &Transaction, mut sat: u64) -> Option<SatPoint> {
+    let txid = tx.txid();
+    for (vout, output) in tx.output.iter().enumerate() {
+        let value = output.value.to_sat();
+        if sat < value {
+            return Some(SatPoint {
+                outpoint: OutPoint::new(txid, vout as u32),
+                offset: sat,
+            });
+        }
+        sat -= value;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::absolute::LockTime;
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Sequence, TxIn, Txid, Witness};
+
+    use super::*;
+    use crate::utils::test_utils::test_txid;
+    use crate::{Inscription as _, Nft};
+
+    fn reveal(inputs: &[(OutPoint, Option<Nft>, u64)], outputs: &[u64]) -> ChildReveal {
+        let input = inputs
+            .iter()
+            .map(|(previous_output, child, _)| {
+                let witness = match child {
+                    Some(child) => Witness::from_slice(&[
+                        vec![0; 64],
+                        child.fields().unwrap().to_script().unwrap().into_bytes(),
+                        vec![0xc0; 33],
+                    ]),
+                    None => Witness::from_slice(&[vec![0; 64]]),
+                };
+                TxIn {
+                    previous_output: *previous_output,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness,
+                }
+            })
+            .collect();
+        let output = outputs
+            .iter()
+            .map(|value| TxOut {
+                value: Amount::from_sat(*value),
+                script_pubkey: ScriptBuf::new(),
+            })
+            .collect();
+
+        ChildReveal {
+            transaction: Transaction {
+                version: Version::TWO,
+                lock_time: LockTime::ZERO,
+                input,
+                output,
+            },
+            prevouts: inputs
+                .iter()
+                .map(|(_, _, value)| TxOut {
+                    value: Amount::from_sat(*value),
+                    script_pubkey: ScriptBuf::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_should_audit_child_reveals() {
+        let parent = InscriptionId {
+            txid: test_txid(),
+            index: 0,
+        };
+        let parent_location = SatPoint {
+            outpoint: OutPoint::new(parent.txid, 0),
+            offset: 0,
+        };
+        let child = Nft::new(Some(b"text/plain".to_vec()), Some(b"child".to_vec()));
+        let commit = |vout| OutPoint::new(Txid::all_zeros(), vout);
+
+        // the first child returns the parent in its second output, after the reveal fee
+        let first = reveal(
+            &[
+                (commit(0), Some(child.clone().with_parent(parent)), 1_000),
+                (parent_location.outpoint, None, 10_000),
+            ],
+            &[546, 10_000],
+        );
+        let first_txid = first.transaction.txid();
+        // the second child doesn't declare the parent, and burns it in the fee
+        let second = reveal(
+            &[
+                (commit(1), Some(child.clone()), 20_000),
+                (OutPoint::new(first_txid, 1), None, 10_000),
+            ],
+            &[546],
+        );
+        // the parent is gone for the third child
+        let third = reveal(
+            &[(commit(2), Some(child.with_parent(parent)), 1_000)],
+            &[546],
+        );
+
+        let reports =
+            audit_child_reveals(parent, parent_location, &[first, second, third]).unwrap();
+        assert_eq!(reports.len(), 3);
+
+        assert!(reports[0].is_valid());
+        assert_eq!(
+            reports[0].parent_location,
+            Some(SatPoint {
+                outpoint: OutPoint::new(first_txid, 1),
+                offset: 454,
+            })
+        );
+
+        assert!(!reports[1].declares_parent);
+        assert!(reports[1].spends_parent);
+        assert_eq!(reports[1].parent_location, None);
+
+        assert!(reports[2].declares_parent);
+        assert!(!reports[2].spends_parent);
+        assert!(!reports[2].is_valid());
+    }
+}
@@ -0,0 +1,216 @@
+use bitcoin::bip32::DerivationPath;
+use bitcoin::psbt::Psbt;
+use bitcoin::{ScriptBuf, Transaction, TxOut};
+
+use super::{OrdTransactionBuilder, Utxo};
+use crate::{OrdError, OrdResult};
+
+/// Input of a commit transaction which may be co-funded by inputs signed elsewhere,
+/// e.g. by a service sponsoring the fees of the inscription.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommitInput {
+    /// Input signed by the builder, locked by the `txin_script_pubkey` of the sign arguments
+    Own(Utxo),
+    /// Input signed by another party
+    Foreign {
+        /// The spent output
+        utxo: Utxo,
+        /// Script pubkey of the spent output
+        script_pubkey: ScriptBuf,
+    },
+}
+
+impl CommitInput {
+    /// Returns the output spent by the input.
+    pub fn utxo(&self) -> &Utxo {
+        match self {
+            Self::Own(utxo) => utxo,
+            Self::Foreign { utxo, .. } => utxo,
+        }
+    }
+
+    /// Returns whether the input is signed by another party.
+    pub fn is_foreign(&self) -> bool {
+        matches!(self, Self::Foreign { .. })
+    }
+}
+
+/// Arguments for signing the own inputs of a commit transaction co-funded by foreign inputs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignPartialCommitTransactionArgs {
+    /// Inputs of the transaction, in the same order
+    pub inputs: Vec<CommitInput>,
+    /// Script pubkey of the own inputs
+    pub txin_script_pubkey: ScriptBuf,
+    /// Derivation path of the key of the own inputs
+    pub derivation_path: Option<DerivationPath>,
+}
+
+impl OrdTransactionBuilder {
+    /// Signs the own inputs of a commit transaction co-funded by foreign inputs, returning a PSBT
+    /// to be completed by the owners of the foreign inputs.
+    ///
+    /// The commit transaction is built as usual, passing both own and foreign inputs to
+    /// [`Self::build_commit_transaction`]. Every input of the PSBT has its witness UTXO set,
+    /// while only the own inputs are finalized.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the inputs don't match the transaction ones,
+    /// or any error occurring while signing the own inputs.
+    pub async fn sign_commit_transaction_partial(
+        &mut self,
+        unsigned_tx: Transaction,
+        args: SignPartialCommitTransactionArgs,
+    ) -> OrdResult<Psbt> {
+        let matches_tx = unsigned_tx.input.len() == args.inputs.len()
+            && unsigned_tx
+                .input
+                .iter()
+                .zip(&args.inputs)
+                .all(|(txin, input)| {
+                    txin.previous_output.txid == input.utxo().id
+                        && txin.previous_output.vout == input.utxo().index
+                });
+        if !matches_tx {
+            return Err(OrdError::InvalidInputs);
+        }
+
+        let mut psbt = Psbt::from_unsigned_tx(unsigned_tx.clone())?;
+
+        let own_inputs = args
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| !input.is_foreign())
+            .map(|(index, input)| (index, input.utxo().clone()))
+            .collect::<Vec<_>>();
+        let signed_tx = self
+            .signer
+            .sign_commit_transaction_inputs(
+                &self.public_key,
+                &own_inputs,
+                unsigned_tx,
+                &args.txin_script_pubkey,
+                &args.derivation_path.unwrap_or_default(),
+            )
+            .await?;
+
+        for (index, input) in args.inputs.into_iter().enumerate() {
+            let psbt_input = &mut psbt.inputs[index];
+            match input {
+                CommitInput::Own(utxo) => {
+                    psbt_input.witness_utxo = Some(TxOut {
+                        value: utxo.amount,
+                        script_pubkey: args.txin_script_pubkey.clone(),
+                    });
+                    psbt_input.final_script_witness = Some(signed_tx.input[index].witness.clone());
+                }
+                CommitInput::Foreign {
+                    utxo,
+                    script_pubkey,
+                } => {
+                    psbt_input.witness_utxo = Some(TxOut {
+                        value: utxo.amount,
+                        script_pubkey,
+                    });
+                }
+            }
+        }
+
+        Ok(psbt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::{Address, Amount, FeeRate, Network, Txid};
+
+    use super::*;
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::wallet::CreateCommitTransactionArgs;
+    use crate::Brc20;
+
+    #[tokio::test]
+    async fn test_should_sign_own_commit_inputs_only() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let sponsor_script_pubkey = Address::from_str("tb1qax89amll2uas5k92tmuc8rdccmqddqw94vrr86")
+            .unwrap()
+            .assume_checked()
+            .script_pubkey();
+
+        let own = test_utxo(1, Amount::from_sat(1_000));
+        let foreign = Utxo {
+            id: Txid::from_str("9283b3f9d4da5ba5fc5ee8e6e3a9b5da4e6e6e7f5c8d7c3b3a1f2e8d9c0b1a2f")
+                .unwrap(),
+            index: 0,
+            amount: Amount::from_sat(10_000),
+        };
+
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let commit = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    vec![own.clone(), foreign.clone()],
+                    Brc20::transfer("mona", 100),
+                    address.clone(),
+                    address.script_pubkey(),
+                    FeeRate::from_sat_per_vb(1).unwrap(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        let psbt = builder
+            .sign_commit_transaction_partial(
+                commit.unsigned_tx.clone(),
+                SignPartialCommitTransactionArgs {
+                    inputs: vec![
+                        CommitInput::Own(own.clone()),
+                        CommitInput::Foreign {
+                            utxo: foreign.clone(),
+                            script_pubkey: sponsor_script_pubkey.clone(),
+                        },
+                    ],
+                    txin_script_pubkey: address.script_pubkey(),
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(psbt.unsigned_tx, commit.unsigned_tx);
+        assert_eq!(
+            psbt.inputs[0].final_script_witness.as_ref().unwrap().len(),
+            2
+        );
+        assert!(psbt.inputs[1].final_script_witness.is_none());
+        assert_eq!(
+            psbt.inputs[1].witness_utxo,
+            Some(TxOut {
+                value: foreign.amount,
+                script_pubkey: sponsor_script_pubkey,
+            })
+        );
+
+        // inputs must match the transaction ones
+        let result = builder
+            .sign_commit_transaction_partial(
+                commit.unsigned_tx,
+                SignPartialCommitTransactionArgs {
+                    inputs: vec![CommitInput::Own(own)],
+                    txin_script_pubkey: address.script_pubkey(),
+                    derivation_path: None,
+                },
+            )
+            .await;
+        assert!(matches!(result, Err(OrdError::InvalidInputs)));
+    }
+}
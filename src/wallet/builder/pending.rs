@@ -0,0 +1,514 @@
+use bitcoin::{Address, Amount, FeeRate, OutPoint, TxOut};
+
+use super::{ChangePolicy, CreateCommitTransaction, OrdTransactionBuilder, ScriptType};
+use crate::utils::constants::{dust_limit, POSTAGE};
+use crate::utils::fees::{
+    estimate_reveal_fee, estimate_transaction_fees_for_prevouts, MultisigConfig,
+};
+use crate::{OrdError, OrdResult};
+
+/// A commit transaction whose reveal has not been broadcast yet.
+///
+/// The reveal fee is fixed when the commit transaction is built, since it is
+/// locked into the value of the commit output. If the network fee rate rises
+/// before the pair is broadcast, the reveal would be stuck forever, so the pending
+/// inscription keeps track of the fee environment it was built for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingInscription {
+    /// The commit transaction, as returned by the builder.
+    pub commit: CreateCommitTransaction,
+    /// Outputs spent by the inputs of the commit transaction, used to estimate its fee.
+    pub prevouts: Vec<TxOut>,
+    /// How the leftovers of the commit transaction are handled when it's rebuilt, as given to
+    /// the builder.
+    #[serde(default)]
+    pub change_policy: ChangePolicy,
+    /// Recipient of the reveal transaction.
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
+    pub reveal_recipient: Address,
+    /// Fee rate used to estimate the commit and reveal fees.
+    pub fee_rate: FeeRate,
+    /// Multisig configuration used to estimate the reveal fee, if applicable.
+    pub multisig_config: Option<MultisigConfig>,
+    /// Unix timestamp (in seconds) of when the commit transaction was built.
+    pub created_at: u64,
+    /// Unix timestamp (in seconds) after which the pending inscription must be rebuilt.
+    pub deadline: Option<u64>,
+}
+
+/// Advice returned by [`PendingInscription::rebuild_advice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebuildAdvice {
+    /// The pending inscription can still be broadcast as it is.
+    Keep,
+    /// The fee rate on the network is higher than the one used for the reveal fee.
+    UnderFee {
+        /// Current fee rate on the network
+        current_fee_rate: FeeRate,
+    },
+    /// The deadline of the pending inscription has passed.
+    Expired,
+}
+
+impl PendingInscription {
+    /// Creates a new pending inscription without a deadline, with the default change policy.
+    pub fn new(
+        commit: CreateCommitTransaction,
+        prevouts: Vec<TxOut>,
+        reveal_recipient: Address,
+        fee_rate: FeeRate,
+        created_at: u64,
+    ) -> Self {
+        Self {
+            commit,
+            prevouts,
+            change_policy: ChangePolicy::default(),
+            reveal_recipient,
+            fee_rate,
+            multisig_config: None,
+            created_at,
+            deadline: None,
+        }
+    }
+
+    /// Sets the change policy applied to the leftovers when the commit transaction is rebuilt.
+    pub fn with_change_policy(mut self, change_policy: ChangePolicy) -> Self {
+        self.change_policy = change_policy;
+        self
+    }
+
+    /// Sets the multisig configuration used to estimate the reveal fee.
+    pub fn with_multisig_config(mut self, multisig_config: MultisigConfig) -> Self {
+        self.multisig_config = Some(multisig_config);
+        self
+    }
+
+    /// Sets a deadline `ttl` seconds after the creation time.
+    pub fn with_ttl(mut self, ttl: u64) -> Self {
+        self.deadline = Some(self.created_at.saturating_add(ttl));
+        self
+    }
+
+    /// Returns the age of the pending inscription in seconds.
+    pub fn age(&self, now: u64) -> u64 {
+        now.saturating_sub(self.created_at)
+    }
+
+    /// Returns whether the deadline of the pending inscription has passed.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.deadline
+            .map(|deadline| now >= deadline)
+            .unwrap_or(false)
+    }
+
+    /// Returns whether the pending inscription should be rebuilt,
+    /// given the current fee rate and unix timestamp.
+    pub fn rebuild_advice(&self, current_fee_rate: FeeRate, now: u64) -> RebuildAdvice {
+        if self.is_expired(now) {
+            RebuildAdvice::Expired
+        } else if should_rebuild(self, current_fee_rate) {
+            RebuildAdvice::UnderFee { current_fee_rate }
+        } else {
+            RebuildAdvice::Keep
+        }
+    }
+
+    /// Total amount of the commit transaction inputs.
+    fn input_amount(&self) -> Amount {
+        self.commit.reveal_balance + self.commit.commit_fee + self.commit.leftover_amount
+    }
+}
+
+/// Returns whether the reveal fee of the pending inscription has been
+/// estimated with a fee rate lower than the current one.
+pub fn should_rebuild(pending: &PendingInscription, current_fee_rate: FeeRate) -> bool {
+    current_fee_rate > pending.fee_rate
+}
+
+impl OrdTransactionBuilder {
+    /// Rebuilds an unbroadcast commit transaction with the fees estimated at the given fee rate.
+    ///
+    /// The commit inputs and the redeem script are preserved, only the reveal balance and the
+    /// leftover amount are updated. The commit fee is estimated from the
+    /// [`PendingInscription::prevouts`], and the leftovers are handled with the
+    /// [`PendingInscription::change_policy`], as when the commit transaction was built, so the
+    /// postage of a commit whose leftovers were merged into it is recomputed from [`POSTAGE`].
+    /// If the builder holds the taproot payload of the commit transaction, its prevouts are
+    /// updated as well, so the reveal can be signed right away.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::OutputNotFound`] if the commit transaction has no reveal output,
+    /// [`OrdError::InsufficientBalance`] if the commit inputs don't cover the new fees,
+    /// [`OrdError::UnexpectedLeftovers`] if the leftovers of a commit without change exceed the
+    /// tolerance of the change policy, or [`OrdError::DustOutput`] if the change policy keeps
+    /// a change output below its dust limit.
+    pub fn rebuild_pending_inscription(
+        &mut self,
+        pending: &PendingInscription,
+        fee_rate: FeeRate,
+        now: u64,
+    ) -> OrdResult<PendingInscription> {
+        let reveal_fee = match &pending.commit.parent {
+            Some(parent) => {
+                let reveal_fee = parent.estimate_reveal_fee(
+                    &pending.reveal_recipient.script_pubkey(),
+                    pending.commit.redeem_script.clone(),
+                    self.script_type,
+                    fee_rate,
+                    &pending.multisig_config,
+                )?;
+                parent.check_returned(reveal_fee)?;
+                reveal_fee
+            }
+            None => estimate_reveal_fee(
+                vec![OutPoint::null()],
+                pending.reveal_recipient.clone(),
+                pending.commit.redeem_script.clone(),
+                self.script_type,
+                fee_rate,
+                &pending.multisig_config,
+            )?,
+        };
+        // the postage of a merged commit already holds its leftovers, which are recomputed from the
+        // inputs at the new fee rate
+        let mut postage = match pending.change_policy {
+            ChangePolicy::MergeIntoPostage if pending.commit.unsigned_tx.output.len() < 2 => {
+                POSTAGE
+            }
+            _ => pending.commit.postage,
+        };
+        let reveal_balance = postage + reveal_fee;
+
+        let mut unsigned_tx = pending.commit.unsigned_tx.clone();
+        if unsigned_tx.output.is_empty() {
+            return Err(OrdError::OutputNotFound(0));
+        }
+
+        let estimate_commit_fee = |outputs: &[TxOut]| {
+            estimate_transaction_fees_for_prevouts(&pending.prevouts, fee_rate, outputs.to_vec())
+        };
+        let estimated_commit_fee = estimate_commit_fee(&unsigned_tx.output)?;
+
+        let input_amount = pending.input_amount();
+        let leftover_amount = input_amount
+            .checked_sub(reveal_balance)
+            .and_then(|v| v.checked_sub(estimated_commit_fee))
+            .ok_or(OrdError::InsufficientBalance {
+                required: reveal_balance + estimated_commit_fee,
+                available: input_amount,
+            })?;
+        debug!("rebuilt leftover_amount: {leftover_amount}");
+
+        let mut reveal_balance = reveal_balance;
+        let change_script_pubkey = unsigned_tx
+            .output
+            .get(1)
+            .map(|change| change.script_pubkey.clone());
+        let (commit_fee, leftover_amount) = match (pending.change_policy, change_script_pubkey) {
+            (ChangePolicy::Omit { tolerance }, _) if leftover_amount > tolerance => {
+                return Err(OrdError::UnexpectedLeftovers {
+                    leftover: leftover_amount,
+                    tolerance,
+                });
+            }
+            (ChangePolicy::MergeIntoPostage, None) => {
+                debug!("merging leftover_amount into the postage");
+                postage += leftover_amount;
+                reveal_balance += leftover_amount;
+                (estimated_commit_fee, Amount::ZERO)
+            }
+            // changeless commit transactions keep sweeping the leftovers into the fee
+            (_, None) => (estimated_commit_fee + leftover_amount, Amount::ZERO),
+            (policy, Some(_)) if policy.should_sweep(leftover_amount) => {
+                debug!("sweeping leftover_amount into the commit fee");
+                unsigned_tx.output.truncate(1);
+                (estimated_commit_fee + leftover_amount, Amount::ZERO)
+            }
+            (policy, Some(change_script_pubkey))
+                if policy.should_merge_into_postage(leftover_amount, &change_script_pubkey) =>
+            {
+                debug!("merging leftover_amount into the postage");
+                // the change output is dropped, so it's no longer paid by the commit fee
+                unsigned_tx.output.truncate(1);
+                let commit_fee = estimate_commit_fee(&unsigned_tx.output)?;
+                let merged = input_amount - reveal_balance - commit_fee;
+                postage += merged;
+                reveal_balance += merged;
+                (commit_fee, Amount::ZERO)
+            }
+            (_, Some(change_script_pubkey)) => {
+                let dust_limit = dust_limit(&change_script_pubkey);
+                if leftover_amount < dust_limit {
+                    return Err(OrdError::DustOutput {
+                        value: leftover_amount,
+                        dust_limit,
+                    });
+                }
+                unsigned_tx.output[1].value = leftover_amount;
+                (estimated_commit_fee, leftover_amount)
+            }
+        };
+        unsigned_tx.output[0].value = reveal_balance;
+
+        if self.script_type == ScriptType::P2TR {
+            if let Some(taproot_payload) = self.taproot_payload.as_mut() {
+                if taproot_payload.address.script_pubkey() == unsigned_tx.output[0].script_pubkey {
+                    taproot_payload.prevouts.value = reveal_balance;
+                }
+            }
+        }
+
+        let deadline = pending
+            .deadline
+            .map(|deadline| now.saturating_add(deadline.saturating_sub(pending.created_at)));
+
+        Ok(PendingInscription {
+            commit: CreateCommitTransaction {
+                unsigned_tx,
+                redeem_script: pending.commit.redeem_script.clone(),
+                reveal_balance,
+                commit_fee,
+                reveal_fee,
+                leftover_amount,
+                postage,
+                parent: pending.commit.parent.clone(),
+                tapleaf_hash: pending.commit.tapleaf_hash,
+                merkle_root: pending.commit.merkle_root,
+            },
+            prevouts: pending.prevouts.clone(),
+            change_policy: pending.change_policy,
+            reveal_recipient: pending.reveal_recipient.clone(),
+            fee_rate,
+            multisig_config: pending.multisig_config.clone(),
+            created_at: now,
+            deadline,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::Network;
+
+    use super::*;
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::wallet::CreateCommitTransactionArgs;
+    use crate::Brc20;
+
+    async fn pending_inscription(
+        builder: &mut OrdTransactionBuilder,
+        fee_rate: FeeRate,
+    ) -> PendingInscription {
+        pending_inscription_with_amount(builder, fee_rate, Amount::from_sat(10_000)).await
+    }
+
+    async fn pending_inscription_with_amount(
+        builder: &mut OrdTransactionBuilder,
+        fee_rate: FeeRate,
+        amount: Amount,
+    ) -> PendingInscription {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+
+        let commit = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    vec![test_utxo(1, amount)],
+                    Brc20::transfer("mona".to_string(), 100),
+                    address.clone(),
+                    address.script_pubkey(),
+                    fee_rate,
+                ),
+            )
+            .await
+            .unwrap();
+
+        let prevouts = vec![TxOut {
+            value: amount,
+            script_pubkey: address.script_pubkey(),
+        }];
+        PendingInscription::new(commit, prevouts, address, fee_rate, 1_000)
+    }
+
+    #[tokio::test]
+    async fn test_should_advise_rebuild() {
+        let mut builder = OrdTransactionBuilder::p2tr(test_private_key());
+        let fee_rate = FeeRate::from_sat_per_vb(2).unwrap();
+        let pending = pending_inscription(&mut builder, fee_rate)
+            .await
+            .with_ttl(600);
+
+        assert!(!should_rebuild(&pending, fee_rate));
+        assert!(should_rebuild(
+            &pending,
+            FeeRate::from_sat_per_vb(3).unwrap()
+        ));
+
+        assert_eq!(pending.rebuild_advice(fee_rate, 1_100), RebuildAdvice::Keep);
+        assert_eq!(
+            pending.rebuild_advice(FeeRate::from_sat_per_vb(5).unwrap(), 1_100),
+            RebuildAdvice::UnderFee {
+                current_fee_rate: FeeRate::from_sat_per_vb(5).unwrap()
+            }
+        );
+        assert_eq!(
+            pending.rebuild_advice(fee_rate, 1_600),
+            RebuildAdvice::Expired
+        );
+        assert_eq!(pending.age(1_600), 600);
+    }
+
+    #[tokio::test]
+    async fn test_should_rebuild_pending_inscription_with_higher_fee() {
+        let mut builder = OrdTransactionBuilder::p2tr(test_private_key());
+        let pending = pending_inscription(&mut builder, FeeRate::from_sat_per_vb(2).unwrap()).await;
+
+        let new_fee_rate = FeeRate::from_sat_per_vb(4).unwrap();
+        let rebuilt = builder
+            .rebuild_pending_inscription(&pending, new_fee_rate, 2_000)
+            .unwrap();
+
+        assert_eq!(rebuilt.fee_rate, new_fee_rate);
+        assert_eq!(rebuilt.created_at, 2_000);
+        assert_eq!(rebuilt.commit.reveal_fee, pending.commit.reveal_fee * 2);
+        // the commit fee is estimated from the P2WPKH prevouts
+        assert_eq!(
+            rebuilt.commit.commit_fee,
+            estimate_transaction_fees_for_prevouts(
+                &pending.prevouts,
+                new_fee_rate,
+                pending.commit.unsigned_tx.output.clone()
+            )
+            .unwrap()
+        );
+        assert_eq!(rebuilt.input_amount(), pending.input_amount());
+        assert_eq!(
+            rebuilt.commit.unsigned_tx.output[0].value,
+            rebuilt.commit.reveal_balance
+        );
+        assert_eq!(
+            rebuilt.commit.unsigned_tx.output[1].value,
+            rebuilt.commit.leftover_amount
+        );
+        assert_eq!(
+            builder.taproot_payload.as_ref().unwrap().prevouts.value,
+            rebuilt.commit.reveal_balance
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_not_rebuild_with_insufficient_balance() {
+        let mut builder = OrdTransactionBuilder::p2tr(test_private_key());
+        let pending = pending_inscription(&mut builder, FeeRate::from_sat_per_vb(2).unwrap()).await;
+
+        let result = builder.rebuild_pending_inscription(
+            &pending,
+            FeeRate::from_sat_per_vb(100).unwrap(),
+            2_000,
+        );
+        assert!(matches!(result, Err(OrdError::InsufficientBalance { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_should_apply_change_policy_when_rebuilding() {
+        let mut builder = OrdTransactionBuilder::p2tr(test_private_key());
+        let fee_rate = FeeRate::from_sat_per_vb(2).unwrap();
+        let pending =
+            pending_inscription_with_amount(&mut builder, fee_rate, Amount::from_sat(1_950)).await;
+        assert_eq!(pending.commit.unsigned_tx.output.len(), 2);
+
+        // the leftovers at the new fee rate are below the dust limit of the change output
+        let new_fee_rate = FeeRate::from_sat_per_vb(5).unwrap();
+        let result = builder.rebuild_pending_inscription(&pending, new_fee_rate, 2_000);
+        assert!(matches!(result, Err(OrdError::DustOutput { .. })));
+
+        let swept = builder
+            .rebuild_pending_inscription(
+                &pending.clone().with_change_policy(ChangePolicy::Sweep {
+                    threshold: Amount::from_sat(1_000),
+                }),
+                new_fee_rate,
+                2_000,
+            )
+            .unwrap();
+        assert_eq!(swept.commit.unsigned_tx.output.len(), 1);
+        assert_eq!(swept.commit.leftover_amount, Amount::ZERO);
+        assert_eq!(swept.input_amount(), pending.input_amount());
+
+        let merged = builder
+            .rebuild_pending_inscription(
+                &pending
+                    .clone()
+                    .with_change_policy(ChangePolicy::MergeIntoPostage),
+                new_fee_rate,
+                2_000,
+            )
+            .unwrap();
+        assert_eq!(merged.commit.unsigned_tx.output.len(), 1);
+        assert_eq!(merged.commit.leftover_amount, Amount::ZERO);
+        assert!(merged.commit.postage > pending.commit.postage);
+        assert_eq!(
+            merged.commit.reveal_balance,
+            merged.commit.postage + merged.commit.reveal_fee
+        );
+        assert_eq!(
+            merged.commit.unsigned_tx.output[0].value,
+            merged.commit.reveal_balance
+        );
+        assert_eq!(merged.input_amount(), pending.input_amount());
+        // the commit fee doesn't pay for the dropped change output
+        assert!(merged.commit.commit_fee < swept.commit.commit_fee);
+    }
+
+    #[tokio::test]
+    async fn test_should_rebuild_merged_pending_inscription() {
+        let mut builder = OrdTransactionBuilder::p2tr(test_private_key());
+        let fee_rate = FeeRate::from_sat_per_vb(2).unwrap();
+        let pending =
+            pending_inscription_with_amount(&mut builder, fee_rate, Amount::from_sat(1_950))
+                .await
+                .with_change_policy(ChangePolicy::MergeIntoPostage);
+        let merged = builder
+            .rebuild_pending_inscription(&pending, FeeRate::from_sat_per_vb(5).unwrap(), 2_000)
+            .unwrap();
+        assert!(merged.commit.postage > POSTAGE);
+
+        // the merged leftovers pay for the higher fees of the new rebuild
+        let rebuilt = builder
+            .rebuild_pending_inscription(&merged, FeeRate::from_sat_per_vb(6).unwrap(), 3_000)
+            .unwrap();
+        assert_eq!(rebuilt.commit.unsigned_tx.output.len(), 1);
+        assert_eq!(rebuilt.commit.leftover_amount, Amount::ZERO);
+        assert!(rebuilt.commit.postage >= POSTAGE);
+        assert!(rebuilt.commit.postage < merged.commit.postage);
+        assert_eq!(
+            rebuilt.commit.reveal_balance,
+            rebuilt.commit.postage + rebuilt.commit.reveal_fee
+        );
+        assert_eq!(
+            rebuilt.commit.unsigned_tx.output[0].value,
+            rebuilt.commit.reveal_balance
+        );
+        assert_eq!(rebuilt.input_amount(), pending.input_amount());
+    }
+
+    #[tokio::test]
+    async fn test_should_not_rebuild_without_reveal_output() {
+        let mut builder = OrdTransactionBuilder::p2tr(test_private_key());
+        let mut pending =
+            pending_inscription(&mut builder, FeeRate::from_sat_per_vb(2).unwrap()).await;
+        pending.commit.unsigned_tx.output.clear();
+
+        let result = builder.rebuild_pending_inscription(
+            &pending,
+            FeeRate::from_sat_per_vb(4).unwrap(),
+            2_000,
+        );
+        assert!(matches!(result, Err(OrdError::OutputNotFound(0))));
+    }
+}
@@ -0,0 +1,283 @@
+use bitcoin::{Amount, FeeRate, ScriptBuf, Sequence, Transaction, TxOut, Witness};
+
+use super::{OrdTransactionBuilder, TxInputInfo};
+use crate::utils::constants::dust_limit;
+use crate::utils::fees::estimate_transaction_fees_for_prevouts;
+use crate::{OrdError, OrdResult};
+
+/// Arguments of [`OrdTransactionBuilder::bump_fee`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BumpFeeArgs {
+    /// Outputs spent by the inputs of the transaction, in order, signed as by
+    /// [`OrdTransactionBuilder::sign_transaction`]
+    pub inputs: Vec<TxInputInfo>,
+    /// Index of the change output paying for the fee bump, e.g. 1 for a commit transaction
+    pub change_index: usize,
+}
+
+impl OrdTransactionBuilder {
+    /// Rebuilds a stuck transaction, e.g. a commit or edict transaction created at a too low fee
+    /// rate, paying the fee at the new fee rate out of its change output, and re-signs it.
+    ///
+    /// Following the BIP 125 replacement rules, the replacement pays at least the fee of the
+    /// original transaction plus its size at the minimum relay fee rate, and all its inputs
+    /// signal replaceability. The other outputs are kept as is, so the reveal transaction of a
+    /// bumped commit only needs to be rebuilt with the new txid. The change output is dropped
+    /// if it falls below its dust limit, provided it's the last output and the transaction has
+    /// no `OP_RETURN` output, so no output referenced by index, e.g. by a runestone, is moved.
+    ///
+    /// Transactions whose inputs didn't signal replaceability can only be replaced by nodes
+    /// relaying full RBF replacements, the default since Bitcoin Core 28.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the inputs don't match the transaction,
+    /// [`OrdError::OutputNotFound`] if there's no change output at the given index, or
+    /// [`OrdError::InsufficientBalance`] if the change doesn't cover the new fee, or the new fee
+    /// and its dust limit when it can't be dropped.
+    pub async fn bump_fee(
+        &self,
+        tx: &Transaction,
+        new_fee_rate: FeeRate,
+        args: BumpFeeArgs,
+    ) -> OrdResult<Transaction> {
+        if tx.input.len() != args.inputs.len()
+            || tx
+                .input
+                .iter()
+                .zip(&args.inputs)
+                .any(|(txin, input)| txin.previous_output != input.outpoint)
+        {
+            return Err(OrdError::InvalidInputs);
+        }
+        let change = tx
+            .output
+            .get(args.change_index)
+            .ok_or(OrdError::OutputNotFound(args.change_index))?
+            .clone();
+
+        let prevouts = args
+            .inputs
+            .iter()
+            .map(|input| input.tx_out.clone())
+            .collect::<Vec<_>>();
+        let input_amount = prevouts.iter().map(|prevout| prevout.value).sum::<Amount>();
+        let output_amount = tx.output.iter().map(|output| output.value).sum::<Amount>();
+        let fee = input_amount
+            .checked_sub(output_amount)
+            .ok_or(OrdError::InvalidInputs)?;
+
+        let estimate = |fee_rate| {
+            estimate_transaction_fees_for_prevouts(&prevouts, fee_rate, tx.output.clone())
+        };
+        let new_fee = estimate(new_fee_rate)?.max(fee + estimate(FeeRate::BROADCAST_MIN)?);
+        debug!("bumping the fee of {} from {fee} to {new_fee}", tx.txid());
+
+        let available = change.value + fee;
+        let change_value = available
+            .checked_sub(new_fee)
+            .ok_or(OrdError::InsufficientBalance {
+                required: new_fee,
+                available,
+            })?;
+
+        let mut unsigned_tx = tx.clone();
+        let dust_limit = dust_limit(&change.script_pubkey);
+        if change_value < dust_limit {
+            // removing an output shifts the outputs after it, which are referenced by index,
+            // e.g. by the edicts and pointer of a runestone
+            let is_last = args.change_index + 1 == tx.output.len();
+            let has_op_return = tx
+                .output
+                .iter()
+                .any(|output| output.script_pubkey.is_op_return());
+            if !is_last || has_op_return {
+                return Err(OrdError::InsufficientBalance {
+                    required: new_fee + dust_limit,
+                    available,
+                });
+            }
+            debug!("dropping the change output of {change_value} to the fee");
+            unsigned_tx.output.remove(args.change_index);
+        } else {
+            unsigned_tx.output[args.change_index] = TxOut {
+                value: change_value,
+                script_pubkey: change.script_pubkey,
+            };
+        }
+        for txin in &mut unsigned_tx.input {
+            if !txin.sequence.is_rbf() {
+                txin.sequence = Sequence::ENABLE_RBF_NO_LOCKTIME;
+            }
+            txin.script_sig = ScriptBuf::new();
+            txin.witness = Witness::new();
+        }
+
+        self.sign_transaction(&unsigned_tx, &args.inputs).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::bip32::DerivationPath;
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::{Address, Network};
+
+    use super::*;
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::wallet::{verify_transaction, CreateCommitTransactionArgsV2};
+    use crate::Brc20;
+
+    #[tokio::test]
+    async fn test_should_bump_commit_fee() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let utxo = test_utxo(1, Amount::from_sat(8_000));
+        let commit = builder
+            .build_commit_transaction_with_fixed_fees(
+                Network::Testnet,
+                CreateCommitTransactionArgsV2 {
+                    inputs: vec![utxo.clone()],
+                    txin_script_pubkey: address.script_pubkey(),
+                    inscription: Brc20::transfer("mona".to_string(), 100),
+                    leftovers_recipient: address.clone(),
+                    commit_fee: Amount::from_sat(150),
+                    reveal_fee: Amount::from_sat(4_700),
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+        let inputs = vec![TxInputInfo {
+            outpoint: bitcoin::OutPoint::new(utxo.id, utxo.index),
+            tx_out: TxOut {
+                value: utxo.amount,
+                script_pubkey: address.script_pubkey(),
+            },
+            derivation_path: DerivationPath::default(),
+            key_spend: Default::default(),
+            rune_balances: None,
+        }];
+        let signed = builder
+            .sign_transaction(&commit.unsigned_tx, &inputs)
+            .await
+            .unwrap();
+        let args = BumpFeeArgs {
+            inputs: inputs.clone(),
+            change_index: 1,
+        };
+
+        let fee_rate = FeeRate::from_sat_per_vb(10).unwrap();
+        let bumped = builder
+            .bump_fee(&signed, fee_rate, args.clone())
+            .await
+            .unwrap();
+        verify_transaction(&bumped, &[inputs[0].tx_out.clone()]).unwrap();
+        assert_ne!(bumped.txid(), signed.txid());
+        assert!(bumped.input.iter().all(|txin| txin.sequence.is_rbf()));
+        // the commit output is kept, so the reveal stays valid with the new txid
+        assert_eq!(bumped.output[0], signed.output[0]);
+
+        let fee = utxo.amount
+            - bumped
+                .output
+                .iter()
+                .map(|output| output.value)
+                .sum::<Amount>();
+        assert!(fee >= fee_rate.fee_vb(bumped.vsize() as u64).unwrap());
+        assert!(fee > commit.commit_fee);
+
+        // the change is dropped once below its dust limit
+        let dropped = builder
+            .bump_fee(&signed, FeeRate::from_sat_per_vb(18).unwrap(), args.clone())
+            .await
+            .unwrap();
+        assert_eq!(dropped.output.len(), 1);
+
+        assert!(matches!(
+            builder
+                .bump_fee(&signed, FeeRate::from_sat_per_vb(100).unwrap(), args)
+                .await,
+            Err(OrdError::InsufficientBalance { .. })
+        ));
+    }
+
+    #[cfg(feature = "rune")]
+    #[tokio::test]
+    async fn test_should_keep_edict_outputs_when_bumping() {
+        use ordinals::RuneId;
+
+        use crate::utils::test_utils::test_txid;
+        use crate::wallet::{CreateEdictTxArgs, RUNE_POSTAGE};
+
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let inputs = (0..2)
+            .map(|vout| TxInputInfo {
+                outpoint: bitcoin::OutPoint::new(test_txid(), vout),
+                tx_out: TxOut {
+                    value: Amount::from_sat(12_000),
+                    script_pubkey: address.script_pubkey(),
+                },
+                derivation_path: DerivationPath::default(),
+                key_spend: Default::default(),
+                rune_balances: None,
+            })
+            .collect::<Vec<_>>();
+        let unsigned_tx = builder
+            .create_edict_transaction(&CreateEdictTxArgs {
+                runes: vec![(RuneId::new(219, 1).unwrap(), 1_000)],
+                inputs: inputs.clone(),
+                destination: address.clone(),
+                change_address: address.clone(),
+                rune_change_address: address.clone(),
+                fee_rate: FeeRate::from_sat_per_vb(1).unwrap(),
+            })
+            .unwrap();
+        let signed = builder
+            .sign_transaction(&unsigned_tx, &inputs)
+            .await
+            .unwrap();
+        let args = BumpFeeArgs {
+            inputs: inputs.clone(),
+            change_index: 3,
+        };
+
+        let bumped = builder
+            .bump_fee(&signed, FeeRate::from_sat_per_vb(10).unwrap(), args.clone())
+            .await
+            .unwrap();
+        assert_eq!(bumped.output.len(), 4);
+        assert_eq!(bumped.output[..3], signed.output[..3]);
+        assert!(bumped.output[3].value < signed.output[3].value);
+
+        // the change is not dropped from a transaction with a runestone
+        let prevouts = inputs
+            .iter()
+            .map(|input| input.tx_out.clone())
+            .collect::<Vec<_>>();
+        let fee_rate = (1..)
+            .map(|sat_per_vb| FeeRate::from_sat_per_vb(sat_per_vb).unwrap())
+            .find(|fee_rate| {
+                let fee = estimate_transaction_fees_for_prevouts(
+                    &prevouts,
+                    *fee_rate,
+                    signed.output.clone(),
+                )
+                .unwrap();
+                Amount::from_sat(24_000) - RUNE_POSTAGE * 2 - fee
+                    < dust_limit(&address.script_pubkey())
+            })
+            .unwrap();
+        assert!(matches!(
+            builder.bump_fee(&signed, fee_rate, args).await,
+            Err(OrdError::InsufficientBalance { .. })
+        ));
+    }
+}
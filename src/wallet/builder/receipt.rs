@@ -0,0 +1,150 @@
+use bitcoin::{Amount, Transaction, Txid};
+
+use super::{CreateCommitTransaction, Utxo};
+use crate::{InscriptionId, OrdError, OrdResult};
+
+/// All the artifacts of a completed commit and reveal flow.
+///
+/// It can be stored by applications to later audit the inscription.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InscriptionReceipt {
+    /// ID of the commit transaction
+    pub commit_txid: Txid,
+    /// ID of the reveal transaction
+    pub reveal_txid: Txid,
+    /// ID of the revealed inscription
+    pub inscription_id: InscriptionId,
+    /// Total fees paid by the commit and reveal transactions
+    pub fees_paid: Amount,
+    /// Output of the reveal transaction holding the inscription
+    pub postage_utxo: Utxo,
+}
+
+impl InscriptionReceipt {
+    /// Creates the receipt of an inscription from the commit transaction returned by the builder
+    /// and the signed commit and reveal transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reveal transaction doesn't spend the commit output,
+    /// or if it has no outputs.
+    pub fn new(
+        commit: &CreateCommitTransaction,
+        signed_commit_tx: &Transaction,
+        reveal_tx: &Transaction,
+    ) -> OrdResult<Self> {
+        let commit_txid = signed_commit_tx.txid();
+        let reveal_txid = reveal_tx.txid();
+
+        let reveal_input = reveal_tx.input.first().ok_or(OrdError::NoInputs)?;
+        if reveal_input.previous_output.txid != commit_txid
+            || reveal_input.previous_output.vout != 0
+        {
+            return Err(OrdError::InvalidInputs);
+        }
+
+        let postage = reveal_tx
+            .output
+            .first()
+            .ok_or_else(|| OrdError::Custom("reveal transaction has no outputs".to_string()))?;
+        let reveal_outputs_value = reveal_tx
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<Amount>();
+        let reveal_fee = commit
+            .reveal_balance
+            .checked_sub(reveal_outputs_value)
+            .unwrap_or(Amount::ZERO);
+
+        Ok(Self {
+            commit_txid,
+            reveal_txid,
+            inscription_id: commit.inscription_id(reveal_txid),
+            fees_paid: commit.commit_fee + reveal_fee,
+            postage_utxo: Utxo {
+                id: reveal_txid,
+                index: 0,
+                amount: postage.value,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::{Address, FeeRate, Network};
+
+    use super::*;
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::wallet::{
+        CreateCommitTransactionArgs, OrdTransactionBuilder, RevealTransactionArgs,
+        SignCommitTransactionArgs,
+    };
+    use crate::Brc20;
+
+    #[tokio::test]
+    async fn test_should_create_inscription_receipt() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
+
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let commit = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    inputs.clone(),
+                    Brc20::transfer("mona", 100),
+                    address.clone(),
+                    address.script_pubkey(),
+                    FeeRate::from_sat_per_vb(1).unwrap(),
+                ),
+            )
+            .await
+            .unwrap();
+        let signed_commit_tx = builder
+            .sign_commit_transaction(
+                commit.unsigned_tx.clone(),
+                SignCommitTransactionArgs {
+                    inputs,
+                    txin_script_pubkey: address.script_pubkey(),
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+        let reveal_tx = builder
+            .build_reveal_transaction(RevealTransactionArgs::new(
+                Utxo {
+                    id: signed_commit_tx.txid(),
+                    index: 0,
+                    amount: commit.reveal_balance,
+                },
+                address,
+                commit.redeem_script.clone(),
+            ))
+            .await
+            .unwrap();
+
+        let receipt = InscriptionReceipt::new(&commit, &signed_commit_tx, &reveal_tx).unwrap();
+        assert_eq!(receipt.commit_txid, signed_commit_tx.txid());
+        assert_eq!(receipt.inscription_id.txid, reveal_tx.txid());
+        assert_eq!(receipt.fees_paid, commit.commit_fee + commit.reveal_fee);
+        assert_eq!(receipt.postage_utxo.amount, reveal_tx.output[0].value);
+
+        let json = serde_json::to_string(&receipt).unwrap();
+        assert_eq!(
+            serde_json::from_str::<InscriptionReceipt>(&json).unwrap(),
+            receipt
+        );
+
+        assert!(matches!(
+            InscriptionReceipt::new(&commit, &reveal_tx, &reveal_tx),
+            Err(OrdError::InvalidInputs)
+        ));
+    }
+}
@@ -1,15 +1,20 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
 use bitcoin::absolute::LockTime;
 use bitcoin::bip32::DerivationPath;
 use bitcoin::transaction::Version;
 use bitcoin::{
-    Address, Amount, FeeRate, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+    Address, Amount, FeeRate, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+    Witness,
 };
-use ordinals::{Edict, Etching, RuneId, Runestone as OrdRunestone};
+use ordinals::{Artifact, Edict, Etching, Rune, RuneId, Runestone as OrdRunestone, SpacedRune};
 
 use super::Utxo;
-use crate::constants::POSTAGE;
+use crate::constants::{dust_limit, first_rune_height, POSTAGE};
 use crate::fees::estimate_transaction_fees;
-use crate::wallet::builder::TxInputInfo;
+use crate::provider::{ChainProvider, ConfirmationStatus, ConfirmationWatch};
+use crate::wallet::builder::{RuneBalance, TxInputInfo};
 use crate::wallet::ScriptType;
 use crate::{OrdError, OrdResult, OrdTransactionBuilder};
 
@@ -20,7 +25,12 @@ pub const RUNE_POSTAGE: Amount = Amount::from_sat(10_000);
 
 #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
 /// Runestone wrapper; implemented because FOR SOME REASONS, the `Runestone` of `ordinals` doesn't implement Clone...
-#[derive(Debug, Default, Clone)]
+///
+/// It's (de)serialized with the serde representation of the `ordinals` types, e.g. rune IDs as
+/// `block:tx` strings, and missing fields default to empty, so decoded runestones can be returned
+/// by JSON APIs as they are. Its [`Display`](fmt::Display) implementation is meant for logs.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Runestone {
     pub edicts: Vec<Edict>,
     pub etching: Option<Etching>,
@@ -40,6 +50,211 @@ impl From<Runestone> for OrdRunestone {
     }
 }
 
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+impl From<OrdRunestone> for Runestone {
+    fn from(runestone: OrdRunestone) -> Self {
+        Runestone {
+            edicts: runestone.edicts,
+            etching: runestone.etching,
+            mint: runestone.mint,
+            pointer: runestone.pointer,
+        }
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+impl From<&OrdRunestone> for Runestone {
+    fn from(runestone: &OrdRunestone) -> Self {
+        Runestone {
+            edicts: runestone.edicts.clone(),
+            etching: runestone.etching,
+            mint: runestone.mint,
+            pointer: runestone.pointer,
+        }
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+impl fmt::Display for Runestone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(etching) = &self.etching {
+            parts.push(format!("etching {}", DisplayEtching(etching)));
+        }
+        if let Some(mint) = self.mint {
+            parts.push(format!("mint {mint}"));
+        }
+        for edict in &self.edicts {
+            parts.push(format!(
+                "edict {} of {} to output {}",
+                edict.amount, edict.id, edict.output
+            ));
+        }
+        if let Some(pointer) = self.pointer {
+            parts.push(format!("pointer {pointer}"));
+        }
+
+        if parts.is_empty() {
+            write!(f, "empty runestone")
+        } else {
+            write!(f, "runestone: {}", parts.join(", "))
+        }
+    }
+}
+
+/// Displays an etching as its spaced rune followed by its non-default fields.
+struct DisplayEtching<'a>(&'a Etching);
+
+impl fmt::Display for DisplayEtching<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let etching = self.0;
+        match etching.rune {
+            Some(rune) => write!(
+                f,
+                "{}",
+                SpacedRune::new(rune, etching.spacers.unwrap_or_default())
+            )?,
+            None => write!(f, "reserved rune")?,
+        }
+
+        let mut fields = Vec::new();
+        if let Some(symbol) = etching.symbol {
+            fields.push(format!("symbol {symbol}"));
+        }
+        if let Some(divisibility) = etching.divisibility {
+            fields.push(format!("divisibility {divisibility}"));
+        }
+        if let Some(premine) = etching.premine {
+            fields.push(format!("premine {premine}"));
+        }
+        if let Some(terms) = etching.terms {
+            if let Some(amount) = terms.amount {
+                fields.push(format!("amount {amount}"));
+            }
+            if let Some(cap) = terms.cap {
+                fields.push(format!("cap {cap}"));
+            }
+            for (name, (start, end)) in [("height", terms.height), ("offset", terms.offset)] {
+                if start.is_some() || end.is_some() {
+                    let bound =
+                        |bound: Option<u64>| bound.map(|b| b.to_string()).unwrap_or_default();
+                    fields.push(format!("{name} {}..{}", bound(start), bound(end)));
+                }
+            }
+        }
+        if etching.turbo {
+            fields.push("turbo".to_string());
+        }
+
+        if !fields.is_empty() {
+            write!(f, " ({})", fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+impl Runestone {
+    /// Deciphers the runestone of the transaction.
+    ///
+    /// Returns `None` if the transaction has no runestone, or a cenotaph if the runestone is
+    /// malformed, in which case all the runes of the transaction inputs are burned.
+    pub fn decipher(transaction: &Transaction) -> Option<Artifact> {
+        let encoded = bitcoin::consensus::serialize(transaction);
+        // both versions share the consensus encoding, so a valid transaction can always be decoded
+        let transaction: bitcoin030::Transaction =
+            bitcoin030::consensus::deserialize(&encoded).expect("transaction decoding cannot fail");
+
+        OrdRunestone::decipher(&transaction)
+    }
+
+    /// Returns the index of the output receiving the runes left unallocated by the edicts of the
+    /// runestone, as defined by the protocol: the output set by the pointer, or else the first
+    /// output which is not an `OP_RETURN`.
+    ///
+    /// Returns `None` if no output can receive the runes, in which case they're burned.
+    pub fn default_output(&self, transaction: &Transaction) -> Option<u32> {
+        let output_count = u32::try_from(transaction.output.len()).ok()?;
+        match self.pointer {
+            Some(pointer) => (pointer < output_count).then_some(pointer),
+            None => transaction
+                .output
+                .iter()
+                .position(|txout| !txout.script_pubkey.is_op_return())
+                .and_then(|index| u32::try_from(index).ok()),
+        }
+    }
+
+    /// Validates the outputs of the edicts of the runestone, for a transaction with
+    /// `output_count` outputs, including the `OP_RETURN` output of the runestone.
+    ///
+    /// An edict whose output is the number of outputs of the transaction is valid: its runes are
+    /// split across all the outputs which are not an `OP_RETURN`, see [`Self::edict_outputs`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidEdict`] if the output of an edict is greater than the number
+    /// of outputs, which would make the whole runestone a cenotaph and burn all the runes of the
+    /// transaction inputs.
+    pub fn validate_edicts(&self, output_count: usize) -> OrdResult<()> {
+        for (index, edict) in self.edicts.iter().enumerate() {
+            if edict.output as usize > output_count {
+                return Err(OrdError::InvalidEdict(format!(
+                    "output {} of edict {index} exceeds the {output_count} outputs of the \
+                     transaction",
+                    edict.output
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the indexes of the outputs receiving the runes of the edict, as defined by the
+    /// protocol:
+    ///
+    /// - the output of the edict, if it's an output of the transaction;
+    /// - all the outputs which are not an `OP_RETURN`, if the output of the edict is the number
+    ///   of outputs of the transaction. Each of them receives the amount of the edict while the
+    ///   balance lasts, or, if the amount is zero, the whole balance is divided evenly between
+    ///   them, with the remainder going to the first ones;
+    /// - none if the output is out of bounds, in which case the runestone is a cenotaph.
+    pub fn edict_outputs(&self, edict: &Edict, transaction: &Transaction) -> Vec<u32> {
+        let output = edict.output as usize;
+        match output.cmp(&transaction.output.len()) {
+            std::cmp::Ordering::Less => vec![edict.output],
+            std::cmp::Ordering::Equal => transaction
+                .output
+                .iter()
+                .enumerate()
+                .filter(|(_, txout)| !txout.script_pubkey.is_op_return())
+                .filter_map(|(index, _)| u32::try_from(index).ok())
+                .collect(),
+            std::cmp::Ordering::Greater => Vec::new(),
+        }
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+impl RuneBalance {
+    /// Creates the balance of the rune with the given ID.
+    pub fn new(id: RuneId, amount: u128) -> Self {
+        Self {
+            block: id.block,
+            tx: id.tx,
+            amount,
+        }
+    }
+
+    /// Returns the ID of the rune.
+    pub fn id(&self) -> RuneId {
+        RuneId {
+            block: self.block,
+            tx: self.tx,
+        }
+    }
+}
+
 /// Arguments for the [`OrdTransactionBuilder::create_edict_transaction`] method.
 #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
 pub struct CreateEdictTxArgs {
@@ -63,15 +278,52 @@ impl CreateEdictTxArgs {
             .iter()
             .fold(Amount::ZERO, |a, b| a + b.tx_out.value)
     }
+
+    /// Checks that the rune balances of the inputs cover the transferred amounts, if the balance
+    /// of any input is known, the inputs with unknown balances being assumed to hold no runes.
+    fn check_rune_balances(&self) -> OrdResult<()> {
+        if self
+            .inputs
+            .iter()
+            .all(|input| input.rune_balances.is_none())
+        {
+            return Ok(());
+        }
+
+        let mut required = BTreeMap::<RuneId, u128>::new();
+        for (rune, amount) in &self.runes {
+            let total = required.entry(*rune).or_default();
+            *total = total.saturating_add(*amount);
+        }
+
+        for (rune, required) in required {
+            let available = self
+                .inputs
+                .iter()
+                .flat_map(|input| input.rune_balances.iter().flatten())
+                .filter(|balance| balance.id() == rune)
+                .fold(0u128, |total, balance| total.saturating_add(balance.amount));
+            if available < required {
+                return Err(OrdError::InsufficientRuneBalance {
+                    rune: rune.to_string(),
+                    required,
+                    available,
+                });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Arguments for creating a etching reveal transaction
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
 pub struct EtchingTransactionArgs {
     /// Transaction input (output of commit transaction)
     pub input: Utxo,
     /// Recipient address of the inscription, only support P2PKH
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
     pub recipient_address: Address,
     /// The redeem script returned by `create_commit_transaction`
     pub redeem_script: ScriptBuf,
@@ -79,21 +331,48 @@ pub struct EtchingTransactionArgs {
     pub runestone: Runestone,
     /// The derivation path of the input
     pub derivation_path: Option<DerivationPath>,
+    /// Confirmations the commit transaction must have before the etching is built, checked by
+    /// [`OrdTransactionBuilder::build_etching_transaction_with_provider`].
+    /// [`OrdRunestone::COMMIT_CONFIRMATIONS`] by default, as required by the protocol.
+    #[serde(default)]
+    pub min_commit_confirmations: Option<u32>,
+    /// Additional inputs funding the fees of the etching, e.g. when the reveal balance of the
+    /// commit output no longer covers the fees at broadcast time. They're spent after the commit
+    /// output, and signed with the keys of their derivation paths.
+    #[serde(default)]
+    pub funding_inputs: Vec<TxInputInfo>,
+    /// Output receiving the leftovers of the funding inputs, appended after the runestone.
+    #[serde(default)]
+    pub change_output: Option<TxOut>,
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+impl EtchingTransactionArgs {
+    /// Returns the confirmations the commit transaction must have before the etching is built.
+    pub fn min_commit_confirmations(&self) -> u32 {
+        self.min_commit_confirmations
+            .unwrap_or(OrdRunestone::COMMIT_CONFIRMATIONS.into())
+    }
 }
 
 #[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
 impl OrdTransactionBuilder {
     /// Creates an unsigned rune edict transaction.
     ///
-    /// This method doesn't check the runes balances, so it's the responsibility of the caller to
-    /// check that the inputs have enough of the given rune balance to make the transfer. As per
-    /// runes standard, if the inputs rune balance is less than specified transfer amount, the
-    /// amount will be reduced to the available balance amount.
+    /// The runes balances are only checked if the [`TxInputInfo::rune_balances`] of any input
+    /// are given, otherwise it's the responsibility of the caller to check that the inputs have
+    /// enough of the given rune balance to make the transfer. As per runes standard, if the
+    /// inputs rune balance is less than specified transfer amount, the amount will be reduced to
+    /// the available balance amount.
     ///
     /// # Errors
+    /// * Returns [`OrdError::InsufficientRuneBalance`] if the given rune balances of the inputs
+    ///   don't cover the transferred amount of a rune.
     /// * Returns [`OrdError::InsufficientBalance`] if the inputs BTC amount is not enough
     ///   to cover the outputs and transaction fee.
     pub fn create_edict_transaction(&self, args: &CreateEdictTxArgs) -> OrdResult<Transaction> {
+        args.check_rune_balances()?;
+
         let edicts = args
             .runes
             .iter()
@@ -159,13 +438,13 @@ impl OrdTransactionBuilder {
             args.fee_rate,
             &None,
             unsigned_tx.output.clone(),
-        );
+        )?;
         let change_amount = args
             .input_amount()
             .checked_sub(fee_amount + RUNE_POSTAGE * 2)
             .ok_or(OrdError::InsufficientBalance {
-                required: (fee_amount + RUNE_POSTAGE * 2).to_sat(),
-                available: args.input_amount().to_sat(),
+                required: fee_amount + RUNE_POSTAGE * 2,
+                available: args.input_amount(),
             })?;
 
         unsigned_tx.output[3].value = change_amount;
@@ -174,6 +453,18 @@ impl OrdTransactionBuilder {
     }
 
     /// Create the reveal transaction
+    ///
+    /// The confirmations of the commit transaction are not checked, so the etching is invalid
+    /// if it is broadcast before the commit is mature, see
+    /// [`Self::build_etching_transaction_with_provider`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidEtching`] if the etching of the runestone is invalid, see
+    /// [`validate_etching`], [`OrdError::InvalidEdict`] if an edict would make the runestone a
+    /// cenotaph, see [`Runestone::validate_edicts`], [`OrdError::DustOutput`] if the change output is below its dust
+    /// limit, or [`OrdError::InsufficientBalance`] if the commit output and the funding inputs
+    /// don't cover the outputs.
     pub async fn build_etching_transaction(
         &mut self,
         args: EtchingTransactionArgs,
@@ -184,18 +475,25 @@ impl OrdTransactionBuilder {
             vout: args.input.index,
         };
 
+        if let Some(etching) = &args.runestone.etching {
+            validate_etching(etching, *args.recipient_address.network(), None)?;
+        }
+        // the postage outputs, the runestone and the change
+        args.runestone
+            .validate_edicts(3 + usize::from(args.change_output.is_some()))?;
+
         let runestone = OrdRunestone::from(args.runestone);
         let btc_030_script = runestone.encipher();
         let btc_031_script = ScriptBuf::from_bytes(btc_030_script.to_bytes());
 
         // tx out
-        let tx_out = vec![
+        let mut tx_out = vec![
             TxOut {
-                value: Amount::from_sat(POSTAGE),
+                value: POSTAGE,
                 script_pubkey: args.recipient_address.script_pubkey(),
             },
             TxOut {
-                value: Amount::from_sat(POSTAGE),
+                value: POSTAGE,
                 script_pubkey: args.recipient_address.script_pubkey(),
             },
             TxOut {
@@ -203,13 +501,47 @@ impl OrdTransactionBuilder {
                 script_pubkey: btc_031_script,
             },
         ];
+        if let Some(change_output) = args.change_output {
+            let dust_limit = dust_limit(&change_output.script_pubkey);
+            if change_output.value < dust_limit {
+                return Err(OrdError::DustOutput {
+                    value: change_output.value,
+                    dust_limit,
+                });
+            }
+            tx_out.push(change_output);
+        }
+
+        // the commit output first, then the funding inputs
+        let commit_prevout = match self.taproot_payload.as_ref() {
+            Some(taproot_payload) => taproot_payload.prevouts.clone(),
+            None => TxOut {
+                value: args.input.amount,
+                script_pubkey: ScriptBuf::new_p2wsh(&args.redeem_script.wscript_hash()),
+            },
+        };
+        let prevouts = std::iter::once(commit_prevout)
+            .chain(args.funding_inputs.iter().map(|input| input.tx_out.clone()))
+            .collect::<Vec<_>>();
+        let available = prevouts.iter().map(|prevout| prevout.value).sum::<Amount>();
+        let required = tx_out.iter().map(|output| output.value).sum::<Amount>();
+        if !args.funding_inputs.is_empty() && available < required {
+            return Err(OrdError::InsufficientBalance {
+                required,
+                available,
+            });
+        }
+
         // txin
-        let tx_in = vec![TxIn {
-            previous_output,
-            script_sig: ScriptBuf::new(),
-            sequence: Sequence::from_consensus(0xffffffff),
-            witness: Witness::new(),
-        }];
+        let tx_in = std::iter::once(previous_output)
+            .chain(args.funding_inputs.iter().map(|input| input.outpoint))
+            .map(|previous_output| TxIn {
+                previous_output,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::from_consensus(0xffffffff),
+                witness: Witness::new(),
+            })
+            .collect();
 
         // make transaction and sign it
         let unsigned_tx = Transaction {
@@ -219,15 +551,18 @@ impl OrdTransactionBuilder {
             output: tx_out,
         };
 
+        let derivation_path = args.derivation_path.unwrap_or_default();
         let tx = match self.taproot_payload.as_ref() {
             Some(taproot_payload) => {
                 self.signer
-                    .sign_reveal_transaction_schnorr(
+                    .sign_reveal_input_script_path(
                         &self.public_key,
-                        taproot_payload,
+                        &taproot_payload.control_block,
+                        &prevouts,
                         &args.redeem_script,
+                        None,
                         unsigned_tx,
-                        &args.derivation_path.unwrap_or_default(),
+                        &derivation_path,
                     )
                     .await
             }
@@ -238,13 +573,172 @@ impl OrdTransactionBuilder {
                         &args.input,
                         unsigned_tx,
                         &args.redeem_script,
+                        &derivation_path,
                     )
                     .await
             }
         }?;
+        if args.funding_inputs.is_empty() {
+            return Ok(tx);
+        }
 
-        Ok(tx)
+        self.signer
+            .sign_inputs(
+                &tx,
+                &prevouts,
+                args.funding_inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, input)| (index + 1, input)),
+            )
+            .await
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+impl OrdTransactionBuilder {
+    /// Create the reveal transaction, once the commit transaction has
+    /// [`EtchingTransactionArgs::min_commit_confirmations`] according to the provider.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::ImmatureCommit`] if the commit transaction is not deep enough yet,
+    /// any error of [`ConfirmationWatch::poll`], or of [`Self::build_etching_transaction`].
+    pub async fn build_etching_transaction_with_provider<P>(
+        &mut self,
+        args: EtchingTransactionArgs,
+        provider: &P,
+    ) -> OrdResult<Transaction>
+    where
+        P: ChainProvider + ?Sized,
+    {
+        let required = args.min_commit_confirmations();
+        let confirmations = match ConfirmationWatch::new(args.input.id, required)
+            .poll(provider)
+            .await?
+        {
+            ConfirmationStatus::Confirmed { .. } => None,
+            ConfirmationStatus::Confirming { confirmations } => Some(confirmations),
+            ConfirmationStatus::Unconfirmed => Some(0),
+        };
+        if let Some(confirmations) = confirmations {
+            return Err(OrdError::ImmatureCommit {
+                txid: args.input.id,
+                confirmations,
+                required,
+            });
+        }
+
+        self.build_etching_transaction(args).await
+    }
+}
+
+/// Validates the etching, which would otherwise be silently enciphered into a cenotaph or an
+/// unmintable rune.
+///
+/// Checks that:
+///
+/// - the divisibility is at most [`Etching::MAX_DIVISIBILITY`] and the spacers at most
+///   [`Etching::MAX_SPACERS`];
+/// - the rune is not reserved;
+/// - the supply, i.e. the premine plus the cap times the amount of the terms, doesn't overflow;
+/// - runes are active on the network in the block following `current_height`, if given, see
+///   [`first_rune_height`];
+/// - the start of the height and offset windows of the terms is not after their end;
+/// - the absolute height window of the terms is not already closed at `current_height`, if given.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidEtching`] describing the first failed check.
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+pub fn validate_etching(
+    etching: &Etching,
+    network: Network,
+    current_height: Option<u64>,
+) -> OrdResult<()> {
+    let invalid = |reason: String| Err(OrdError::InvalidEtching(reason));
+
+    if let Some(divisibility) = etching.divisibility {
+        if divisibility > Etching::MAX_DIVISIBILITY {
+            return invalid(format!(
+                "divisibility {divisibility} exceeds {}",
+                Etching::MAX_DIVISIBILITY
+            ));
+        }
+    }
+    if let Some(spacers) = etching.spacers {
+        if spacers > Etching::MAX_SPACERS {
+            return invalid(format!(
+                "spacers {spacers:#x} exceed {:#x}",
+                Etching::MAX_SPACERS
+            ));
+        }
+    }
+    if let Some(rune) = etching.rune {
+        if rune.is_reserved() {
+            return invalid(format!("rune {rune} is reserved"));
+        }
+    }
+    if etching.supply().is_none() {
+        return invalid("supply overflows".to_string());
+    }
+    if let Some(current_height) = current_height {
+        let first_height = first_rune_height(network);
+        if current_height + 1 < u64::from(first_height) {
+            return invalid(format!(
+                "runes are not active on {network} before height {first_height}"
+            ));
+        }
+    }
+
+    let Some(terms) = etching.terms else {
+        return Ok(());
+    };
+    for (window, (start, end)) in [("height", terms.height), ("offset", terms.offset)] {
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return invalid(format!("{window} start {start} is after its end {end}"));
+            }
+        }
+    }
+    if let (Some(end), Some(current_height)) = (terms.height.1, current_height) {
+        // the rune can be minted from the block following the etching one
+        if end <= current_height + 1 {
+            return invalid(format!(
+                "height end {end} is not after the etching height {}",
+                current_height + 1
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Encodes the rune as the commitment of its etching.
+///
+/// The commitment is the little-endian encoding of the rune without trailing zero bytes, and
+/// must be set as the `rune` field (tag 13) of the inscription revealed by the etching
+/// transaction, see [`crate::Nft::with_rune`].
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+pub fn rune_commitment_bytes(rune: Rune) -> Vec<u8> {
+    rune.commitment()
+}
+
+/// Decodes the rune from the commitment of its etching, i.e. the inverse of
+/// [`rune_commitment_bytes`].
+///
+/// # Errors
+///
+/// Returns [`OrdError::RuneCommitment`] if the commitment is longer than 16 bytes.
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+pub fn rune_from_commitment_bytes(commitment: &[u8]) -> OrdResult<Rune> {
+    if commitment.len() > 16 {
+        return Err(OrdError::RuneCommitment(commitment.len()));
     }
+
+    let mut bytes = [0; 16];
+    bytes[..commitment.len()].copy_from_slice(commitment);
+    Ok(Rune(u128::from_le_bytes(bytes)))
 }
 
 #[cfg(test)]
@@ -254,16 +748,19 @@ mod tests {
 
     use bitcoin::bip32::DerivationPath;
     use bitcoin::consensus::Decodable;
+    use bitcoin::hashes::Hash as _;
     use bitcoin::key::Secp256k1;
-    use bitcoin::{Network, OutPoint, PrivateKey, PublicKey, Txid};
+    use bitcoin::{BlockHash, Network, OutPoint, PrivateKey, PublicKey, Txid};
     use hex_literal::hex;
+    use ordinals::Terms;
 
     use super::*;
-    use crate::wallet::{CreateCommitTransactionArgsV2, LocalSigner};
+    use crate::provider::{BlockRef, TxStatus};
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::wallet::{verify_transaction, CreateCommitTransactionArgsV2, LocalSigner};
     use crate::{Nft, SignCommitTransactionArgs, Wallet};
 
     // <https://mempool.space/testnet/address/tb1qzc8dhpkg5e4t6xyn4zmexxljc4nkje59dg3ark>
-    const WIF: &str = "cVkWbHmoCx6jS8AyPNQqvFr8V9r2qzDHJLaxGDQgDJfxT73w6fuU";
 
     #[tokio::test]
     async fn create_edict_transaction() {
@@ -297,6 +794,8 @@ mod tests {
                         .unwrap(),
                     },
                     derivation_path: DerivationPath::default(),
+                    key_spend: Default::default(),
+                    rune_balances: None,
                 },
                 TxInputInfo {
                     outpoint: OutPoint::new(
@@ -314,6 +813,8 @@ mod tests {
                         .unwrap(),
                     },
                     derivation_path: DerivationPath::default(),
+                    key_spend: Default::default(),
+                    rune_balances: None,
                 },
                 TxInputInfo {
                     outpoint: OutPoint::new(
@@ -331,6 +832,8 @@ mod tests {
                         .unwrap(),
                     },
                     derivation_path: DerivationPath::default(),
+                    key_spend: Default::default(),
+                    rune_balances: None,
                 },
             ],
             destination: Address::from_str(
@@ -394,6 +897,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_should_check_input_rune_balances() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2tr(
+            &Secp256k1::new(),
+            public_key.inner.x_only_public_key().0,
+            None,
+            Network::Testnet,
+        );
+        let builder = OrdTransactionBuilder::p2tr(private_key);
+        let rune = RuneId::new(219, 1).unwrap();
+        let other_rune = RuneId::new(220, 3).unwrap();
+        let input = |vout, rune_balances| TxInputInfo {
+            outpoint: OutPoint::new(Txid::all_zeros(), vout),
+            tx_out: TxOut {
+                value: Amount::from_sat(50_000),
+                script_pubkey: address.script_pubkey(),
+            },
+            derivation_path: DerivationPath::default(),
+            key_spend: Default::default(),
+            rune_balances,
+        };
+        let args = |runes, inputs| CreateEdictTxArgs {
+            runes,
+            inputs,
+            destination: address.clone(),
+            change_address: address.clone(),
+            rune_change_address: address.clone(),
+            fee_rate: FeeRate::from_sat_per_vb(1).unwrap(),
+        };
+
+        // unknown balances are not checked
+        assert!(builder
+            .create_edict_transaction(&args(vec![(rune, 1_000)], vec![input(0, None)]))
+            .is_ok());
+        // balances are summed across inputs
+        let inputs = vec![
+            input(0, Some(vec![RuneBalance::new(rune, 600)])),
+            input(1, Some(vec![RuneBalance::new(rune, 400)])),
+            input(2, None),
+        ];
+        assert!(builder
+            .create_edict_transaction(&args(vec![(rune, 1_000)], inputs.clone()))
+            .is_ok());
+
+        for (runes, expected_rune, required, available) in [
+            (vec![(rune, 1_001)], rune, 1_001, 1_000),
+            (vec![(rune, 500), (rune, 501)], rune, 1_001, 1_000),
+            (vec![(rune, 1), (other_rune, 1)], other_rune, 1, 0),
+        ] {
+            let result = builder.create_edict_transaction(&args(runes, inputs.clone()));
+            assert!(
+                matches!(
+                    &result,
+                    Err(OrdError::InsufficientRuneBalance { rune, required: r, available: a })
+                        if *rune == expected_rune.to_string() && *r == required && *a == available
+                ),
+                "{result:?}"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_should_append_runestone() {
         // this test refers to these testnet transactions, commit and reveal:
@@ -401,19 +967,13 @@ mod tests {
         // <https://mempool.space/testnet/tx/a35802655b63f1c99c1fd3ff8fdf3415f3abb735d647d402c0af5e9a73cbe4c6>
         // made by address tb1qzc8dhpkg5e4t6xyn4zmexxljc4nkje59dg3ark
 
-        use ordinals::{Etching, Rune, Terms};
-        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let private_key = test_private_key();
         let public_key = private_key.public_key(&Secp256k1::new());
         let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
 
         let mut builder = OrdTransactionBuilder::p2tr(private_key);
 
-        let inputs = vec![Utxo {
-            id: Txid::from_str("791b415dc6946d864d368a0e5ec5c09ee2ad39cf298bc6e3f9aec293732cfda7")
-                .unwrap(), // the transaction that funded our wallet
-            index: 1,
-            amount: Amount::from_sat(8_000),
-        }];
+        let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
         let commit_transaction_args = CreateCommitTransactionArgsV2 {
             inputs: inputs.clone(),
             txin_script_pubkey: address.script_pubkey(),
@@ -507,6 +1067,9 @@ mod tests {
                     pointer: None,
                 },
                 derivation_path: None,
+                min_commit_confirmations: None,
+                funding_inputs: vec![],
+                change_output: None,
             })
             .await
             .unwrap();
@@ -514,4 +1077,486 @@ mod tests {
         assert_eq!(reveal_transaction.output.len(), 3);
         assert_eq!(reveal_transaction.output[2].script_pubkey, expected_script);
     }
+
+    #[tokio::test]
+    async fn test_should_fund_etching_with_additional_inputs() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let commit = builder
+            .build_commit_transaction_with_fixed_fees(
+                Network::Testnet,
+                CreateCommitTransactionArgsV2 {
+                    inputs: vec![Utxo {
+                        id: Txid::all_zeros(),
+                        index: 0,
+                        amount: Amount::from_sat(30_000),
+                    }],
+                    txin_script_pubkey: address.script_pubkey(),
+                    inscription: Nft::new(None, Some(b"SUPERMAXRUNENAME".to_vec())),
+                    leftovers_recipient: address.clone(),
+                    commit_fee: Amount::from_sat(2_500),
+                    reveal_fee: Amount::from_sat(1_000),
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+        let funding_input = TxInputInfo {
+            outpoint: OutPoint::new(Txid::all_zeros(), 1),
+            tx_out: TxOut {
+                value: Amount::from_sat(20_000),
+                script_pubkey: address.script_pubkey(),
+            },
+            derivation_path: DerivationPath::default(),
+            key_spend: Default::default(),
+            rune_balances: None,
+        };
+        let change_output = TxOut {
+            value: Amount::from_sat(15_000),
+            script_pubkey: address.script_pubkey(),
+        };
+        let args = EtchingTransactionArgs {
+            input: commit.reveal_input(Txid::all_zeros()),
+            recipient_address: address.clone(),
+            redeem_script: commit.redeem_script.clone(),
+            runestone: Runestone::default(),
+            derivation_path: None,
+            min_commit_confirmations: None,
+            funding_inputs: vec![funding_input.clone()],
+            change_output: Some(change_output.clone()),
+        };
+
+        let etching = builder
+            .build_etching_transaction(args.clone())
+            .await
+            .unwrap();
+        assert_eq!(etching.input.len(), 2);
+        assert_eq!(etching.input[1].previous_output, funding_input.outpoint);
+        assert_eq!(etching.output.len(), 4);
+        assert_eq!(etching.output[3], change_output);
+        let prevouts = [
+            builder.taproot_payload().unwrap().prevouts.clone(),
+            funding_input.tx_out.clone(),
+        ];
+        verify_transaction(&etching, &prevouts).unwrap();
+
+        // the change can't be funded by the commit output alone
+        let result = builder
+            .build_etching_transaction(EtchingTransactionArgs {
+                change_output: Some(TxOut {
+                    value: Amount::from_sat(40_000),
+                    ..change_output
+                }),
+                ..args
+            })
+            .await;
+        assert!(
+            matches!(result, Err(OrdError::InsufficientBalance { .. })),
+            "{result:?}"
+        );
+    }
+
+    /// Provider seeing the commit transaction confirmed at the given height, if any, below a tip
+    /// at height 100.
+    struct CommitProvider(Option<u64>);
+
+    #[async_trait::async_trait]
+    impl ChainProvider for CommitProvider {
+        async fn tip(&self) -> OrdResult<BlockRef> {
+            Ok(block(100))
+        }
+
+        async fn transaction_status(&self, _txid: &Txid) -> OrdResult<TxStatus> {
+            Ok(self.0.map_or(TxStatus::Mempool, |height| {
+                TxStatus::Confirmed(block(height))
+            }))
+        }
+
+        async fn wait_for_block(&self) -> OrdResult<()> {
+            Ok(())
+        }
+    }
+
+    fn block(height: u64) -> BlockRef {
+        BlockRef {
+            height,
+            hash: BlockHash::all_zeros(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_refuse_etching_immature_commit() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let args = |min_commit_confirmations| EtchingTransactionArgs {
+            input: Utxo {
+                id: Txid::all_zeros(),
+                index: 0,
+                amount: Amount::from_sat(10_000),
+            },
+            recipient_address: address.clone(),
+            redeem_script: ScriptBuf::new(),
+            runestone: Runestone::default(),
+            derivation_path: None,
+            min_commit_confirmations,
+            funding_inputs: vec![],
+            change_output: None,
+        };
+
+        for (provider, min_commit_confirmations, confirmations, required) in [
+            (CommitProvider(None), None, 0, 6),
+            (CommitProvider(Some(99)), None, 2, 6),
+            (CommitProvider(Some(99)), Some(3), 2, 3),
+        ] {
+            let result = builder
+                .build_etching_transaction_with_provider(args(min_commit_confirmations), &provider)
+                .await;
+            assert!(
+                matches!(
+                    result,
+                    Err(OrdError::ImmatureCommit { confirmations: c, required: r, .. })
+                        if c == confirmations && r == required
+                ),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_should_display_and_serialize_runestone() {
+        let rune_id = RuneId {
+            block: 840_000,
+            tx: 1,
+        };
+        let runestone = Runestone {
+            edicts: vec![Edict {
+                id: rune_id,
+                amount: 1_000,
+                output: 2,
+            }],
+            etching: Some(Etching {
+                rune: Some(Rune::from_str("UNCOMMONGOODS").unwrap()),
+                spacers: Some(0b10000000),
+                divisibility: Some(2),
+                premine: None,
+                symbol: Some('⧉'),
+                terms: Some(Terms {
+                    amount: Some(1),
+                    cap: Some(100),
+                    height: (Some(840_000), None),
+                    offset: (None, None),
+                }),
+                turbo: true,
+            }),
+            mint: Some(rune_id),
+            pointer: Some(1),
+        };
+
+        assert_eq!(
+            runestone.to_string(),
+            "runestone: etching UNCOMMON•GOODS (symbol ⧉, divisibility 2, amount 1, cap 100, \
+             height 840000.., turbo), mint 840000:1, edict 1000 of 840000:1 to output 2, \
+             pointer 1"
+        );
+        assert_eq!(Runestone::default().to_string(), "empty runestone");
+
+        let json = serde_json::to_value(&runestone).unwrap();
+        assert_eq!(json["mint"], "840000:1");
+        assert_eq!(json["edicts"][0]["id"], "840000:1");
+        assert_eq!(
+            serde_json::from_value::<Runestone>(json).unwrap(),
+            runestone
+        );
+        assert_eq!(
+            serde_json::from_str::<Runestone>(r#"{"pointer":1}"#).unwrap(),
+            Runestone {
+                pointer: Some(1),
+                ..Default::default()
+            }
+        );
+
+        let ord_runestone = OrdRunestone::from(runestone.clone());
+        assert_eq!(Runestone::from(&ord_runestone), runestone);
+    }
+
+    #[test]
+    fn test_should_validate_edict_outputs() {
+        let rune_id = RuneId {
+            block: 840_000,
+            tx: 1,
+        };
+        let runestone = |output| Runestone {
+            edicts: vec![Edict {
+                id: rune_id,
+                amount: 0,
+                output,
+            }],
+            ..Default::default()
+        };
+        let script = OrdRunestone::from(runestone(0)).encipher();
+        let output = |script_pubkey| TxOut {
+            value: RUNE_POSTAGE,
+            script_pubkey,
+        };
+        let transaction = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![
+                output(ScriptBuf::new_p2wsh(&bitcoin::WScriptHash::all_zeros())),
+                output(ScriptBuf::from_bytes(script.to_bytes())),
+                output(ScriptBuf::new_p2wsh(&bitcoin::WScriptHash::all_zeros())),
+            ],
+        };
+
+        assert!(runestone(2).validate_edicts(3).is_ok());
+        assert_eq!(
+            runestone(2).edict_outputs(&runestone(2).edicts[0], &transaction),
+            vec![2]
+        );
+        // splits across the outputs which are not an OP_RETURN
+        assert!(runestone(3).validate_edicts(3).is_ok());
+        assert_eq!(
+            runestone(3).edict_outputs(&runestone(3).edicts[0], &transaction),
+            vec![0, 2]
+        );
+        // cenotaph
+        assert!(matches!(
+            runestone(4).validate_edicts(3),
+            Err(OrdError::InvalidEdict(_))
+        ));
+        assert!(runestone(4)
+            .edict_outputs(&runestone(4).edicts[0], &transaction)
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_should_refuse_etching_cenotaph_edicts() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+
+        let result = builder
+            .build_etching_transaction(EtchingTransactionArgs {
+                input: Utxo {
+                    id: Txid::all_zeros(),
+                    index: 0,
+                    amount: Amount::from_sat(10_000),
+                },
+                recipient_address: address,
+                redeem_script: ScriptBuf::new(),
+                runestone: Runestone {
+                    edicts: vec![Edict {
+                        id: RuneId { block: 1, tx: 0 },
+                        amount: 1,
+                        output: 4,
+                    }],
+                    ..Default::default()
+                },
+                derivation_path: None,
+                min_commit_confirmations: None,
+                funding_inputs: vec![],
+                change_output: None,
+            })
+            .await;
+        assert!(
+            matches!(result, Err(OrdError::InvalidEdict(_))),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn test_should_encode_rune_commitment() {
+        for (rune, bytes) in [
+            (0, vec![]),
+            (1, vec![1]),
+            (255, vec![255]),
+            (256, vec![0, 1]),
+            (u128::MAX, vec![255; 16]),
+        ] {
+            assert_eq!(rune_commitment_bytes(Rune(rune)), bytes);
+            assert_eq!(rune_from_commitment_bytes(&bytes).unwrap(), Rune(rune));
+        }
+
+        let rune = Rune::from_str("SUPERMAXRUNENAME").unwrap();
+        assert_eq!(
+            rune_from_commitment_bytes(&rune_commitment_bytes(rune)).unwrap(),
+            rune
+        );
+        assert!(matches!(
+            rune_from_commitment_bytes(&[1; 17]),
+            Err(OrdError::RuneCommitment(17))
+        ));
+    }
+
+    #[test]
+    fn test_should_decipher_runestone_default_output() {
+        let rune_id = RuneId {
+            block: 840_000,
+            tx: 1,
+        };
+        let recipient =
+            ScriptBuf::new_p2tr_tweaked(bitcoin::key::TweakedPublicKey::dangerous_assume_tweaked(
+                bitcoin::XOnlyPublicKey::from_str(
+                    "ddf99a3af83d2f741c955394345df2abd67a33d4e9b27d6256b65cfb24b64236",
+                )
+                .unwrap(),
+            ));
+        let transaction = |runestone: Runestone| {
+            let script = OrdRunestone::from(runestone).encipher();
+            Transaction {
+                version: Version::TWO,
+                lock_time: LockTime::ZERO,
+                input: vec![],
+                output: vec![
+                    TxOut {
+                        value: Amount::ZERO,
+                        script_pubkey: ScriptBuf::from_bytes(script.to_bytes()),
+                    },
+                    TxOut {
+                        value: RUNE_POSTAGE,
+                        script_pubkey: recipient.clone(),
+                    },
+                    TxOut {
+                        value: RUNE_POSTAGE,
+                        script_pubkey: recipient.clone(),
+                    },
+                ],
+            }
+        };
+
+        // without pointer, the first non OP_RETURN output gets the unallocated runes
+        let tx = transaction(Runestone {
+            edicts: vec![Edict {
+                id: rune_id,
+                amount: 100,
+                output: 2,
+            }],
+            ..Default::default()
+        });
+        let Some(Artifact::Runestone(runestone)) = Runestone::decipher(&tx) else {
+            panic!("expected a runestone");
+        };
+        let runestone = Runestone::from(runestone);
+        assert_eq!(runestone.edicts.len(), 1);
+        assert_eq!(runestone.default_output(&tx), Some(1));
+
+        let tx = transaction(Runestone {
+            pointer: Some(2),
+            ..Default::default()
+        });
+        let Some(Artifact::Runestone(runestone)) = Runestone::decipher(&tx) else {
+            panic!("expected a runestone");
+        };
+        assert_eq!(Runestone::from(runestone).default_output(&tx), Some(2));
+
+        // all outputs are OP_RETURN
+        let mut tx = transaction(Runestone::default());
+        tx.output.truncate(1);
+        let Some(Artifact::Runestone(runestone)) = Runestone::decipher(&tx) else {
+            panic!("expected a runestone");
+        };
+        assert_eq!(Runestone::from(runestone).default_output(&tx), None);
+
+        // a pointer out of the outputs makes a cenotaph
+        let tx = transaction(Runestone {
+            pointer: Some(3),
+            ..Default::default()
+        });
+        assert!(matches!(
+            Runestone::decipher(&tx),
+            Some(Artifact::Cenotaph(_))
+        ));
+
+        let mut tx = transaction(Runestone::default());
+        tx.output.remove(0);
+        assert!(Runestone::decipher(&tx).is_none());
+    }
+
+    #[test]
+    fn test_should_validate_etching() {
+        let etching = Etching {
+            rune: Some(Rune::from_str("SUPERMAXRUNENAME").unwrap()),
+            divisibility: Some(2),
+            premine: Some(10_000),
+            spacers: None,
+            symbol: Some('$'),
+            terms: Some(Terms {
+                amount: Some(2000),
+                cap: Some(500),
+                height: (Some(840_000), Some(850_000)),
+                offset: (None, Some(1_000)),
+            }),
+            turbo: true,
+        };
+        assert!(validate_etching(&etching, Network::Bitcoin, None).is_ok());
+        assert!(validate_etching(&etching, Network::Bitcoin, Some(840_000)).is_ok());
+
+        let terms = etching.terms.unwrap();
+        for invalid in [
+            Etching {
+                divisibility: Some(Etching::MAX_DIVISIBILITY + 1),
+                ..etching
+            },
+            Etching {
+                spacers: Some(Etching::MAX_SPACERS + 1),
+                ..etching
+            },
+            Etching {
+                rune: Some(Rune::reserved(840_000, 1)),
+                ..etching
+            },
+            Etching {
+                premine: Some(u128::MAX),
+                ..etching
+            },
+            Etching {
+                terms: Some(Terms {
+                    cap: Some(u128::MAX),
+                    ..terms
+                }),
+                ..etching
+            },
+            Etching {
+                terms: Some(Terms {
+                    height: (Some(850_000), Some(840_000)),
+                    ..terms
+                }),
+                ..etching
+            },
+            Etching {
+                terms: Some(Terms {
+                    offset: (Some(10), Some(1)),
+                    ..terms
+                }),
+                ..etching
+            },
+        ] {
+            assert!(
+                matches!(
+                    validate_etching(&invalid, Network::Bitcoin, None),
+                    Err(OrdError::InvalidEtching(_))
+                ),
+                "{invalid:?} should be invalid"
+            );
+        }
+
+        // the mint window is closed
+        assert!(validate_etching(&etching, Network::Bitcoin, Some(849_999)).is_err());
+
+        // runes are not active yet
+        assert!(matches!(
+            validate_etching(&etching, Network::Bitcoin, Some(839_998)),
+            Err(OrdError::InvalidEtching(_))
+        ));
+        assert!(validate_etching(&etching, Network::Bitcoin, Some(839_999)).is_ok());
+        assert!(validate_etching(&etching, Network::Testnet, Some(840_000)).is_err());
+        assert!(validate_etching(&etching, Network::Regtest, Some(0)).is_ok());
+    }
 }
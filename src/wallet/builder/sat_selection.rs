@@ -0,0 +1,210 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bitcoin::{Address, Network, OutPoint};
+
+use super::{CreateCommitTransaction, CreateCommitTransactionArgs, OrdTransactionBuilder, Utxo};
+use crate::utils::constants::POSTAGE;
+use crate::{Nft, OrdError, OrdResult};
+
+/// Location of a sat: the output holding it and its offset within the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SatPoint {
+    /// Output holding the sat
+    pub outpoint: OutPoint,
+    /// Offset of the sat within the output
+    pub offset: u64,
+}
+
+impl fmt::Display for SatPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.outpoint, self.offset)
+    }
+}
+
+impl FromStr for SatPoint {
+    type Err = OrdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || OrdError::Custom(format!("invalid satpoint: {s}"));
+
+        let (outpoint, offset) = s.rsplit_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            outpoint: outpoint.parse().map_err(|_| invalid())?,
+            offset: offset.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Moves the input holding the sat at `satpoint` to the front of `inputs`, so that the sat
+/// ends up in the inscription output of the reveal transaction.
+///
+/// Returns the offset of the sat within the inscription output, which must be used as the
+/// inscription pointer if it's not zero.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidSatPoint`] if no input holds the sat, or if the sat offset isn't
+/// covered by the postage of the inscription output.
+pub fn arrange_inputs_for_sat(inputs: &mut [Utxo], satpoint: SatPoint) -> OrdResult<u64> {
+    let position = inputs
+        .iter()
+        .position(|input| {
+            input.id == satpoint.outpoint.txid && input.index == satpoint.outpoint.vout
+        })
+        .ok_or(OrdError::InvalidSatPoint {
+            satpoint,
+            reason: "the output is not among the inputs",
+        })?;
+
+    if satpoint.offset >= inputs[position].amount.to_sat() {
+        return Err(OrdError::InvalidSatPoint {
+            satpoint,
+            reason: "the offset exceeds the output amount",
+        });
+    }
+    if satpoint.offset >= POSTAGE.to_sat() {
+        return Err(OrdError::InvalidSatPoint {
+            satpoint,
+            reason: "the offset is not covered by the inscription postage",
+        });
+    }
+
+    inputs[..=position].rotate_right(1);
+
+    Ok(satpoint.offset)
+}
+
+impl OrdTransactionBuilder {
+    /// Creates the commit transaction for an inscription on the sat at `satpoint`,
+    /// similar to the `--sat` option of `ord`.
+    ///
+    /// The input holding the sat is spent first, so the sat is sent to the commit output and
+    /// then to the inscription output of the reveal transaction. The inscription pointer is set
+    /// to the offset of the sat within the inscription output.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidSatPoint`] if the sat can't be inscribed with the given inputs.
+    pub async fn build_commit_transaction_on_sat(
+        &mut self,
+        network: Network,
+        recipient_address: Address,
+        mut args: CreateCommitTransactionArgs<Nft>,
+        satpoint: SatPoint,
+    ) -> OrdResult<CreateCommitTransaction> {
+        let offset = arrange_inputs_for_sat(&mut args.inputs, satpoint)?;
+        if offset > 0 {
+            args.inscription = args.inscription.with_pointer(offset);
+        }
+
+        self.build_commit_transaction(network, recipient_address, args)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::{Amount, FeeRate, Txid};
+
+    use super::*;
+    use crate::inscription::nft::create_nft;
+    use crate::utils::test_utils::{test_private_key, test_txid, test_utxo};
+
+    fn utxo(txid: &str, index: u32, amount: u64) -> Utxo {
+        Utxo {
+            id: Txid::from_str(txid).unwrap(),
+            index,
+            amount: Amount::from_sat(amount),
+        }
+    }
+
+    fn inputs() -> Vec<Utxo> {
+        vec![
+            test_utxo(1, Amount::from_sat(8_000)),
+            utxo(
+                "4472899344bce1a6c83c6ec45859f79ab622b55b3faf67e555e3e03cee5139e6",
+                0,
+                1_000,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_should_parse_satpoint() {
+        let satpoint = SatPoint::from_str(&format!("{}:1:42", test_txid())).unwrap();
+        assert_eq!(satpoint.outpoint.txid, test_txid());
+        assert_eq!(satpoint.outpoint.vout, 1);
+        assert_eq!(satpoint.offset, 42);
+        assert_eq!(SatPoint::from_str(&satpoint.to_string()).unwrap(), satpoint);
+
+        assert!(SatPoint::from_str("791b415d:1").is_err());
+    }
+
+    #[test]
+    fn test_should_arrange_inputs_for_sat() {
+        let mut inputs = inputs();
+        let satpoint = SatPoint {
+            outpoint: OutPoint {
+                txid: inputs[1].id,
+                vout: 0,
+            },
+            offset: 42,
+        };
+
+        assert_eq!(arrange_inputs_for_sat(&mut inputs, satpoint).unwrap(), 42);
+        assert_eq!(inputs[0].id, satpoint.outpoint.txid);
+        assert_eq!(inputs[1].amount, Amount::from_sat(8_000));
+
+        for offset in [POSTAGE.to_sat(), 1_000] {
+            let result = arrange_inputs_for_sat(&mut inputs, SatPoint { offset, ..satpoint });
+            assert!(matches!(result, Err(OrdError::InvalidSatPoint { .. })));
+        }
+
+        let result = arrange_inputs_for_sat(
+            &mut inputs,
+            SatPoint {
+                outpoint: OutPoint::null(),
+                offset: 0,
+            },
+        );
+        assert!(matches!(result, Err(OrdError::InvalidSatPoint { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_should_build_commit_transaction_on_sat() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let inputs = inputs();
+        let satpoint = SatPoint {
+            outpoint: OutPoint {
+                txid: inputs[1].id,
+                vout: inputs[1].index,
+            },
+            offset: 100,
+        };
+
+        let commit = OrdTransactionBuilder::p2tr(private_key)
+            .build_commit_transaction_on_sat(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    inputs,
+                    create_nft("text/plain", "Hello, world!"),
+                    address.clone(),
+                    address.script_pubkey(),
+                    FeeRate::from_sat_per_vb(1).unwrap(),
+                ),
+                satpoint,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            commit.unsigned_tx.input[0].previous_output,
+            satpoint.outpoint
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use bitcoin::secp256k1::{All, Message, Secp256k1};
+use bitcoin::sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+use bitcoin::{Script, ScriptBuf, TapLeafHash, Transaction, TxOut, Witness};
+
+use super::verify::{taproot_script_spend_sighash, verify_input};
+use crate::{OrdError, OrdResult};
+
+/// Signing session of a transaction, e.g. a large consolidating commit or a batch reveal.
+///
+/// The segwit sighashes of all the inputs of a transaction commit to the same hashes of its
+/// prevouts, amounts, sequences and outputs. The session computes them once for the messages
+/// signed for all the inputs and the verification of their signatures, so the cost of signing a
+/// transaction grows linearly with its number of inputs rather than quadratically.
+///
+/// Signatures don't commit to the witnesses and script sigs, so witnesses can be set while the
+/// session is open. Script sigs must be set before opening it, as they're verified along with
+/// the witnesses.
+#[derive(Debug)]
+pub struct SigningSession {
+    cache: SighashCache<Transaction>,
+    prevouts: Vec<TxOut>,
+}
+
+impl SigningSession {
+    /// Opens a signing session of the transaction, given the outputs spent by all its inputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the number of prevouts doesn't match the inputs.
+    pub fn new(transaction: Transaction, prevouts: Vec<TxOut>) -> OrdResult<Self> {
+        if transaction.input.len() != prevouts.len() {
+            return Err(OrdError::InvalidInputs);
+        }
+
+        Ok(Self {
+            cache: SighashCache::new(transaction),
+            prevouts,
+        })
+    }
+
+    /// Returns the transaction, with the witnesses set so far.
+    pub fn transaction(&self) -> &Transaction {
+        self.cache.transaction()
+    }
+
+    /// Returns the outputs spent by the inputs of the transaction.
+    pub fn prevouts(&self) -> &[TxOut] {
+        &self.prevouts
+    }
+
+    /// Returns the `SIGHASH_ALL` message of the P2WPKH spend of the input at `index`, for the
+    /// given program, i.e. the script pubkey of the prevout or the redeem script of a nested
+    /// segwit output.
+    pub fn p2wpkh_sighash(&mut self, index: usize, program: &Script) -> OrdResult<Message> {
+        let value = self.prevout(index)?.value;
+        Ok(Message::from(self.cache.p2wpkh_signature_hash(
+            index,
+            program,
+            value,
+            EcdsaSighashType::All,
+        )?))
+    }
+
+    /// Returns the `SIGHASH_ALL` message of the P2WSH spend of the input at `index` through the
+    /// witness script.
+    pub fn p2wsh_sighash(&mut self, index: usize, witness_script: &Script) -> OrdResult<Message> {
+        let value = self.prevout(index)?.value;
+        Ok(Message::from(self.cache.p2wsh_signature_hash(
+            index,
+            witness_script,
+            value,
+            EcdsaSighashType::All,
+        )?))
+    }
+
+    /// Returns the `SIGHASH_DEFAULT` message of the taproot key path spend of the input at
+    /// `index`.
+    pub fn key_spend_sighash(&mut self, index: usize) -> OrdResult<Message> {
+        Ok(Message::from(self.cache.taproot_key_spend_signature_hash(
+            index,
+            &Prevouts::All(&self.prevouts),
+            TapSighashType::Default,
+        )?))
+    }
+
+    /// Returns the `SIGHASH_DEFAULT` message of the taproot script path spend of the leaf by the
+    /// input at `index`, committing to the annex of its witness, if any.
+    pub fn script_spend_sighash(
+        &mut self,
+        index: usize,
+        leaf_hash: TapLeafHash,
+        annex: Option<&[u8]>,
+    ) -> OrdResult<Message> {
+        Ok(Message::from(taproot_script_spend_sighash(
+            &mut self.cache,
+            index,
+            &Prevouts::All(&self.prevouts),
+            leaf_hash,
+            annex,
+            TapSighashType::Default,
+        )?))
+    }
+
+    /// Sets the witness of the input at `index`.
+    pub fn set_witness(&mut self, index: usize, witness: Witness) -> OrdResult<()> {
+        *self
+            .cache
+            .witness_mut(index)
+            .ok_or(OrdError::InputNotFound(index))? = witness;
+        Ok(())
+    }
+
+    /// Pushes an element to the witness of the input at `index`, e.g. the annex after the
+    /// signature, script and control block of a script path spend.
+    pub fn push_witness(&mut self, index: usize, element: impl AsRef<[u8]>) -> OrdResult<()> {
+        self.cache
+            .witness_mut(index)
+            .ok_or(OrdError::InputNotFound(index))?
+            .push(element);
+        Ok(())
+    }
+
+    /// Verifies the witness of the input at `index`, as [`super::verify_transaction`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidWitness`] if the witness is invalid or can't be verified.
+    pub fn verify_input(&mut self, secp: &Secp256k1<All>, index: usize) -> OrdResult<()> {
+        verify_input(secp, &mut self.cache, index, &Prevouts::All(&self.prevouts))
+    }
+
+    /// Closes the session, returning the transaction with its witnesses.
+    pub fn into_transaction(self) -> Transaction {
+        self.cache.into_transaction()
+    }
+
+    fn prevout(&self, index: usize) -> OrdResult<&TxOut> {
+        self.prevouts
+            .get(index)
+            .ok_or(OrdError::InputNotFound(index))
+    }
+}
+
+/// Sets the script sig of the input at `index` pushing the redeem script of a nested segwit
+/// output, to be done before opening the [`SigningSession`] of the transaction.
+pub(crate) fn set_redeem_script(
+    transaction: &mut Transaction,
+    index: usize,
+    redeem_script: &Script,
+) -> OrdResult<()> {
+    let push = bitcoin::script::PushBytesBuf::try_from(redeem_script.to_bytes())?;
+    transaction
+        .input
+        .get_mut(index)
+        .ok_or(OrdError::InputNotFound(index))?
+        .script_sig = ScriptBuf::builder().push_slice(push).into_script();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::absolute::LockTime;
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::key::{Keypair, TapTweak as _};
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, OutPoint, Sequence, TxIn, Txid};
+
+    use super::*;
+    use crate::utils::test_utils::test_private_key;
+    use crate::wallet::verify_transaction;
+
+    #[test]
+    fn test_should_sign_consolidation_in_session() {
+        let secp = Secp256k1::new();
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&secp);
+        let keypair = Keypair::from_secret_key(&secp, &private_key.inner);
+        let (x_public_key, _) = keypair.x_only_public_key();
+
+        let p2wpkh = ScriptBuf::new_p2wpkh(&public_key.wpubkey_hash().unwrap());
+        let p2tr = ScriptBuf::new_p2tr(&secp, x_public_key, None);
+        let prevouts = (0..8u64)
+            .map(|i| TxOut {
+                value: Amount::from_sat(10_000 + i),
+                script_pubkey: if i % 2 == 0 {
+                    p2wpkh.clone()
+                } else {
+                    p2tr.clone()
+                },
+            })
+            .collect::<Vec<_>>();
+        let transaction = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: (0..prevouts.len() as u32)
+                .map(|vout| TxIn {
+                    previous_output: OutPoint::new(Txid::all_zeros(), vout),
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: vec![TxOut {
+                value: Amount::from_sat(70_000),
+                script_pubkey: p2tr.clone(),
+            }],
+        };
+
+        assert!(matches!(
+            SigningSession::new(transaction.clone(), prevouts[1..].to_vec()),
+            Err(OrdError::InvalidInputs)
+        ));
+
+        let mut session = SigningSession::new(transaction.clone(), prevouts.clone()).unwrap();
+        for (index, prevout) in prevouts.iter().enumerate() {
+            // the messages match the ones of a fresh sighash cache
+            let mut cache = SighashCache::new(&transaction);
+            let witness = if prevout.script_pubkey.is_p2wpkh() {
+                let message = session.p2wpkh_sighash(index, &p2wpkh).unwrap();
+                let expected = cache
+                    .p2wpkh_signature_hash(index, &p2wpkh, prevout.value, EcdsaSighashType::All)
+                    .unwrap();
+                assert_eq!(message, Message::from(expected));
+
+                let signature = secp.sign_ecdsa(&message, &private_key.inner);
+                Witness::p2wpkh(
+                    &bitcoin::ecdsa::Signature::sighash_all(signature),
+                    &public_key.inner,
+                )
+            } else {
+                let message = session.key_spend_sighash(index).unwrap();
+                let expected = cache
+                    .taproot_key_spend_signature_hash(
+                        index,
+                        &Prevouts::All(&prevouts),
+                        TapSighashType::Default,
+                    )
+                    .unwrap();
+                assert_eq!(message, Message::from(expected));
+
+                let tweaked = keypair.tap_tweak(&secp, None).to_inner();
+                let signature = secp.sign_schnorr_no_aux_rand(&message, &tweaked);
+                Witness::from_slice(&[signature.as_ref().as_slice()])
+            };
+            session.set_witness(index, witness).unwrap();
+            session.verify_input(&secp, index).unwrap();
+        }
+
+        let signed = session.into_transaction();
+        verify_transaction(&signed, &prevouts).unwrap();
+    }
+}
@@ -1,21 +1,31 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
 use bitcoin::bip32::{ChainCode, DerivationPath, Xpriv};
-use bitcoin::hashes::Hash as _;
-use bitcoin::key::Secp256k1;
+use bitcoin::key::{Secp256k1, TapTweak as _};
 use bitcoin::secp256k1::ecdsa::Signature;
 use bitcoin::secp256k1::{self, All, Error, Message};
 use bitcoin::sighash::{Prevouts, SighashCache};
-use bitcoin::taproot::{ControlBlock, LeafVersion};
+use bitcoin::taproot::ControlBlock;
 use bitcoin::{
-    Network, PrivateKey, PublicKey, ScriptBuf, TapLeafHash, TapSighashType, Transaction, TxOut,
-    Witness, XOnlyPublicKey,
+    Network, PrivateKey, PublicKey, ScriptBuf, TapLeafHash, TapNodeHash, TapSighashType,
+    Transaction, TxOut, Witness, XOnlyPublicKey,
 };
+use futures_util::stream::{self, StreamExt as _, TryStreamExt as _};
 
 use super::super::builder::Utxo;
+use super::session::{set_redeem_script, SigningSession};
 use super::taproot::TaprootPayload;
-use crate::wallet::builder::TxInputInfo;
+use super::verify::verify_input;
+use crate::wallet::builder::{TaprootKeySpend, TxInputInfo};
 use crate::{OrdError, OrdResult};
 
 /// An abstraction over a transaction signer.
+///
+/// Keys are always derived by the signer, and the builder never needs randomness, so it can be
+/// used in environments without a source of entropy, such as IC canisters, by implementing this
+/// trait on top of the platform signing API.
 #[async_trait::async_trait]
 pub trait BtcTxSigner {
     /// Retrieves the ECDSA public key at the given derivation path.
@@ -40,8 +50,25 @@ pub trait BtcTxSigner {
         message: Message,
         derivation_path: &DerivationPath,
     ) -> Result<secp256k1::schnorr::Signature, secp256k1::Error>;
+
+    /// Signs a message with the Schnorr key tweaked with the given merkle root as defined by
+    /// BIP-341, as required to spend a taproot output through the key path, e.g. a BIP-86
+    /// output with no merkle root.
+    ///
+    /// Signers which can't tweak their keys should return
+    /// [`OrdError::TweakedSigningUnsupported`].
+    async fn sign_with_schnorr_tweaked(
+        &self,
+        message: Message,
+        derivation_path: &DerivationPath,
+        merkle_root: Option<TapNodeHash>,
+    ) -> OrdResult<secp256k1::schnorr::Signature>;
 }
 
+/// Signer holding a private key in memory.
+///
+/// Keys are derived deterministically from the private key, and schnorr signatures are produced
+/// without auxiliary randomness, so signing the same message always yields the same signature.
 pub struct LocalSigner {
     master_key: Xpriv,
     secp: Secp256k1<All>,
@@ -52,6 +79,15 @@ impl LocalSigner {
         ChainCode::from([0; 32])
     }
 
+    /// Creates a signer deriving the keys from the given extended private key, e.g. the master
+    /// key of a BIP-32 wallet.
+    pub fn from_xpriv(master_key: Xpriv) -> Self {
+        Self {
+            master_key,
+            secp: Secp256k1::new(),
+        }
+    }
+
     pub fn new(private_key: PrivateKey) -> Self {
         // Network is only used for encoding and decoding the private key and is not important for
         // signing. So we can use any value here.
@@ -113,12 +149,34 @@ impl BtcTxSigner for LocalSigner {
         let signature = self.secp.sign_schnorr_no_aux_rand(&message, &keypair);
         Ok(signature)
     }
+
+    async fn sign_with_schnorr_tweaked(
+        &self,
+        message: Message,
+        derivation_path: &DerivationPath,
+        merkle_root: Option<TapNodeHash>,
+    ) -> OrdResult<secp256k1::schnorr::Signature> {
+        let keypair = self
+            .derived(derivation_path)
+            .to_keypair(&self.secp)
+            .tap_tweak(&self.secp, merkle_root)
+            .to_inner();
+        Ok(self.secp.sign_schnorr_no_aux_rand(&message, &keypair))
+    }
 }
 
 /// An Ordinal-aware Bitcoin wallet.
+///
+/// The public keys returned by the signer are cached by derivation path, saving round-trips to
+/// remote signers. The cache must be cleared with [`Self::clear_public_key_cache`] if the keys
+/// of the signer change, e.g. when replacing it.
 pub struct Wallet {
     pub signer: Box<dyn BtcTxSigner>,
     secp: Secp256k1<All>,
+    signing_parallelism: usize,
+    verify_signatures: bool,
+    ecdsa_public_keys: Mutex<HashMap<DerivationPath, PublicKey>>,
+    schnorr_public_keys: Mutex<HashMap<DerivationPath, XOnlyPublicKey>>,
 }
 
 impl Wallet {
@@ -126,7 +184,67 @@ impl Wallet {
         Self {
             signer: Box::new(signer),
             secp: Secp256k1::new(),
+            signing_parallelism: 1,
+            verify_signatures: false,
+            ecdsa_public_keys: Mutex::default(),
+            schnorr_public_keys: Mutex::default(),
+        }
+    }
+
+    /// Returns the ECDSA public key at the given derivation path, fetching it from the signer
+    /// only the first time.
+    pub async fn ecdsa_public_key(&self, derivation_path: &DerivationPath) -> OrdResult<PublicKey> {
+        if let Some(public_key) = lock(&self.ecdsa_public_keys).get(derivation_path) {
+            return Ok(*public_key);
         }
+
+        let public_key = self.signer.ecdsa_public_key(derivation_path).await?;
+        lock(&self.ecdsa_public_keys).insert(derivation_path.clone(), public_key);
+        Ok(public_key)
+    }
+
+    /// Returns the schnorr public key at the given derivation path, fetching it from the signer
+    /// only the first time.
+    pub async fn schnorr_public_key(
+        &self,
+        derivation_path: &DerivationPath,
+    ) -> OrdResult<XOnlyPublicKey> {
+        if let Some(public_key) = lock(&self.schnorr_public_keys).get(derivation_path) {
+            return Ok(*public_key);
+        }
+
+        let public_key = self.signer.schnorr_public_key(derivation_path).await?;
+        lock(&self.schnorr_public_keys).insert(derivation_path.clone(), public_key);
+        Ok(public_key)
+    }
+
+    /// Clears the cached public keys, so they're fetched again from the signer.
+    pub fn clear_public_key_cache(&self) {
+        lock(&self.ecdsa_public_keys).clear();
+        lock(&self.schnorr_public_keys).clear();
+    }
+
+    /// Sets the maximum number of inputs of a generic transaction signed concurrently by
+    /// [`Self::sign_transaction`], e.g. to hide the latency of a remote signer.
+    ///
+    /// Inputs are signed sequentially by default. A parallelism of 0 is treated as 1.
+    pub fn with_signing_parallelism(mut self, parallelism: usize) -> Self {
+        self.signing_parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Returns the maximum number of inputs signed concurrently.
+    pub fn signing_parallelism(&self) -> usize {
+        self.signing_parallelism
+    }
+
+    /// Enables the verification of the witnesses of the signed inputs with
+    /// [`verify_transaction`](super::verify_transaction), so signing fails instead of returning
+    /// a transaction which would be rejected once broadcast, e.g. if a remote signer used the
+    /// wrong key.
+    pub fn with_signature_verification(mut self, verify: bool) -> Self {
+        self.verify_signatures = verify;
+        self
     }
 
     pub async fn sign_commit_transaction(
@@ -139,7 +257,28 @@ impl Wallet {
     ) -> OrdResult<Transaction> {
         self.sign_ecdsa(
             own_pubkey,
-            inputs,
+            inputs.iter().enumerate(),
+            transaction,
+            txin_script,
+            TransactionType::Commit,
+            derivation_path,
+        )
+        .await
+    }
+
+    /// Signs only the given inputs of the commit transaction, identified by their index,
+    /// leaving the other ones to be signed by another party.
+    pub async fn sign_commit_transaction_inputs(
+        &mut self,
+        own_pubkey: &PublicKey,
+        inputs: &[(usize, Utxo)],
+        transaction: Transaction,
+        txin_script: &ScriptBuf,
+        derivation_path: &DerivationPath,
+    ) -> OrdResult<Transaction> {
+        self.sign_ecdsa(
+            own_pubkey,
+            inputs.iter().map(|(index, utxo)| (*index, utxo)),
             transaction,
             txin_script,
             TransactionType::Commit,
@@ -148,24 +287,29 @@ impl Wallet {
         .await
     }
 
+    /// Signs the spend of the P2WSH or P2SH-P2WSH commit output by the reveal transaction,
+    /// with the ECDSA key at the given derivation path.
     pub async fn sign_reveal_transaction_ecdsa(
         &mut self,
         own_pubkey: &PublicKey,
         input: &Utxo,
         transaction: Transaction,
         redeem_script: &bitcoin::ScriptBuf,
+        derivation_path: &DerivationPath,
     ) -> OrdResult<Transaction> {
         self.sign_ecdsa(
             own_pubkey,
-            &[input.clone()],
+            std::iter::once((0, input)),
             transaction,
             redeem_script,
             TransactionType::Reveal,
-            &DerivationPath::default(),
+            derivation_path,
         )
         .await
     }
 
+    /// Signs the script path spend of the P2TR commit output by the reveal transaction, with the
+    /// schnorr key at the given derivation path.
     pub async fn sign_reveal_transaction_schnorr(
         &mut self,
         own_pubkey: &PublicKey,
@@ -174,69 +318,119 @@ impl Wallet {
         transaction: Transaction,
         derivation_path: &DerivationPath,
     ) -> OrdResult<Transaction> {
-        let prevouts_array = vec![taproot.prevouts.clone()];
-        let prevouts = Prevouts::All(&prevouts_array);
-
-        let mut sighash_cache = SighashCache::new(transaction.clone());
-        let sighash_sig = sighash_cache.taproot_script_spend_signature_hash(
-            0,
-            &prevouts,
-            TapLeafHash::from_script(redeem_script, LeafVersion::TapScript),
-            TapSighashType::Default,
-        )?;
-
-        let msg = secp256k1::Message::from_digest(sighash_sig.to_byte_array());
-        let sig = self.signer.sign_with_schnorr(msg, derivation_path).await?;
-
-        // verify
-        self.secp.verify_schnorr(&sig, &msg, &taproot.pubkey)?;
-
-        // append witness
-        let signature = bitcoin::taproot::Signature {
-            sig,
-            hash_ty: TapSighashType::Default,
-        }
-        .into();
-        self.append_witness_to_input(
-            &mut sighash_cache,
-            signature,
-            0,
-            &own_pubkey.inner,
-            Some(redeem_script),
-            Some(&taproot.control_block),
-        )?;
+        self.sign_reveal_transaction_script_path(
+            own_pubkey,
+            &taproot.control_block,
+            &taproot.prevouts,
+            redeem_script,
+            transaction,
+            derivation_path,
+        )
+        .await
+    }
 
-        Ok(sighash_cache.into_transaction())
+    /// Signs the script path spend of the commit output by the reveal transaction,
+    /// given the control block of the redeem script leaf and the commit output.
+    pub async fn sign_reveal_transaction_script_path(
+        &mut self,
+        own_pubkey: &PublicKey,
+        control_block: &ControlBlock,
+        prevout: &TxOut,
+        redeem_script: &ScriptBuf,
+        transaction: Transaction,
+        derivation_path: &DerivationPath,
+    ) -> OrdResult<Transaction> {
+        self.sign_reveal_input_script_path(
+            own_pubkey,
+            control_block,
+            std::slice::from_ref(prevout),
+            redeem_script,
+            None,
+            transaction,
+            derivation_path,
+        )
+        .await
     }
 
-    async fn sign_tr(
-        &self,
-        prev_outs: &[&TxOut],
-        index: usize,
-        sighash_cache: &mut SighashCache<Transaction>,
+    /// Signs the script path spend of the commit output by the first input of a reveal
+    /// transaction with additional inputs, given the outputs spent by all of its inputs, and
+    /// appends the taproot annex, if any, to its witness.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn sign_reveal_input_script_path(
+        &mut self,
+        own_pubkey: &PublicKey,
+        control_block: &ControlBlock,
+        prevouts: &[TxOut],
+        redeem_script: &ScriptBuf,
+        annex: Option<&[u8]>,
+        transaction: Transaction,
         derivation_path: &DerivationPath,
-    ) -> OrdResult<()> {
-        let prevouts = Prevouts::All(prev_outs);
-        let sighash = sighash_cache.taproot_key_spend_signature_hash(
-            index,
-            &prevouts,
-            TapSighashType::Default,
-        )?;
+    ) -> OrdResult<Transaction> {
+        self.sign_reveal_inputs_script_path(
+            own_pubkey,
+            std::iter::once((0, control_block, redeem_script, annex)),
+            prevouts,
+            transaction,
+            derivation_path,
+        )
+        .await
+    }
+
+    /// Signs the script path spends of commit outputs by the given inputs of a reveal
+    /// transaction, identified by their index along with the control block and the redeem
+    /// script of the spent leaf, and the taproot annex of their witness, if any, e.g. the inputs
+    /// of a batch reveal.
+    ///
+    /// `prevouts` are the outputs spent by all the inputs of the transaction.
+    pub(crate) async fn sign_reveal_inputs_script_path<'a>(
+        &mut self,
+        own_pubkey: &PublicKey,
+        inputs: impl IntoIterator<Item = (usize, &'a ControlBlock, &'a ScriptBuf, Option<&'a [u8]>)>,
+        prevouts: &[TxOut],
+        transaction: Transaction,
+        derivation_path: &DerivationPath,
+    ) -> OrdResult<Transaction> {
+        let txid = transaction.txid();
+        let mut session = SigningSession::new(transaction, prevouts.to_vec())?;
+        let mut signed = Vec::new();
+        for (index, control_block, redeem_script, annex) in inputs {
+            let sign_input = async {
+                let leaf_hash = TapLeafHash::from_script(redeem_script, control_block.leaf_version);
+                let msg = session.script_spend_sighash(index, leaf_hash, annex)?;
+                let sig = self.signer.sign_with_schnorr(msg, derivation_path).await?;
 
-        let msg = Message::from(sighash);
-        let signature = self.signer.sign_with_schnorr(msg, derivation_path).await?;
+                // verify against the key of the leaf, which may differ from the internal key
+                self.secp
+                    .verify_schnorr(&sig, &msg, &XOnlyPublicKey::from(own_pubkey.inner))?;
 
-        let signature = bitcoin::taproot::Signature {
-            sig: signature,
-            hash_ty: TapSighashType::Default,
-        };
+                // append witness
+                let signature = bitcoin::taproot::Signature {
+                    sig,
+                    hash_ty: TapSighashType::Default,
+                }
+                .into();
+                let mut witness = Self::input_witness(
+                    signature,
+                    &own_pubkey.inner,
+                    Some(redeem_script),
+                    Some(control_block),
+                )?;
+                if let Some(annex) = annex {
+                    witness.push(annex);
+                }
+                session.set_witness(index, witness)?;
 
-        let mut witness = Witness::new();
-        witness.push(signature.to_vec());
+                OrdResult::Ok(())
+            };
+            sign_input.await.map_err(|err| err.for_input(txid, index))?;
+            signed.push(index);
+        }
 
-        *sighash_cache.witness_mut(index).unwrap() = witness;
+        for index in signed {
+            self.verify_session_input(&mut session, index)?;
+        }
 
-        Ok(())
+        Ok(session.into_transaction())
     }
 
     /// Sign a generic transaction.
@@ -244,6 +438,10 @@ impl Wallet {
     /// The given transaction must have the same inputs as the ones given in the `prev_outs` argument.
     /// The signature is checked against the given `own_pubkey` public key before being accepted
     /// as valid and returned.
+    ///
+    /// Up to [`Self::signing_parallelism`] inputs are signed concurrently, which reduces the latency
+    /// of remote signers. The witnesses are placed at the index of their input regardless of the
+    /// order the signatures are returned in.
     pub async fn sign_transaction(
         &self,
         transaction: &Transaction,
@@ -253,120 +451,269 @@ impl Wallet {
             return Err(OrdError::InvalidInputs);
         }
 
-        let mut cache = SighashCache::new(transaction.clone());
-        for (index, input) in prev_outs.iter().enumerate() {
-            match &input.tx_out.script_pubkey {
-                s if s.is_p2wpkh() || s.is_p2wsh() => {
-                    let sighash = cache.p2wpkh_signature_hash(
-                        index,
-                        s,
-                        input.tx_out.value,
-                        bitcoin::EcdsaSighashType::All,
-                    )?;
-                    let message = Message::from(sighash);
-
-                    let signature = self
-                        .signer
-                        .sign_with_ecdsa(message, &input.derivation_path)
-                        .await?;
-                    let public_key = self.signer.ecdsa_public_key(&input.derivation_path).await?;
-                    let ord_signature = bitcoin::ecdsa::Signature::sighash_all(signature).into();
-
-                    self.append_witness_to_input(
-                        &mut cache,
-                        ord_signature,
-                        index,
-                        &public_key.inner,
-                        None,
-                        None,
-                    )?;
-                }
-                s if s.is_p2tr() => {
-                    self.sign_tr(
-                        &prev_outs.iter().map(|v| &v.tx_out).collect::<Vec<_>>(),
-                        index,
-                        &mut cache,
-                        &input.derivation_path,
-                    )
-                    .await?
-                }
-                _ => return Err(OrdError::InvalidScriptType),
+        let prevouts = prev_outs
+            .iter()
+            .map(|input| input.tx_out.clone())
+            .collect::<Vec<_>>();
+        self.sign_inputs(transaction, &prevouts, prev_outs.iter().enumerate())
+            .await
+    }
+
+    /// Signs the given inputs of a transaction, whose other inputs are signed separately, e.g.
+    /// the funding inputs of a reveal transaction.
+    ///
+    /// `prevouts` are the outputs spent by all the inputs of the transaction, which are
+    /// committed to by taproot signatures.
+    pub(crate) async fn sign_inputs<'a>(
+        &self,
+        transaction: &Transaction,
+        prevouts: &[TxOut],
+        inputs: impl IntoIterator<Item = (usize, &'a TxInputInfo)>,
+    ) -> OrdResult<Transaction> {
+        let txid = transaction.txid();
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+
+        // nested segwit inputs are signed for the P2WPKH program of the signer's key
+        let mut redeem_scripts = Vec::with_capacity(inputs.len());
+        for (index, input) in &inputs {
+            let redeem_script = if input.tx_out.script_pubkey.is_p2sh() {
+                let redeem_script = self
+                    .nested_p2wpkh_redeem_script(input)
+                    .await
+                    .map_err(|err| err.for_input(txid, *index))?;
+                Some(redeem_script)
+            } else {
+                None
+            };
+            redeem_scripts.push(redeem_script);
+        }
+
+        // script sigs are verified along with the witnesses, so they're set before opening the
+        // signing session
+        let mut transaction = transaction.clone();
+        for ((index, _), redeem_script) in inputs.iter().zip(&redeem_scripts) {
+            if let Some(redeem_script) = redeem_script {
+                set_redeem_script(&mut transaction, *index, redeem_script)?;
             }
         }
 
-        Ok(cache.into_transaction())
+        let mut session = SigningSession::new(transaction, prevouts.to_vec())?;
+        let requests = inputs
+            .iter()
+            .zip(&redeem_scripts)
+            .map(|((index, input), redeem_script)| {
+                Self::input_signature_request(&mut session, *index, input, redeem_script.as_ref())
+                    .map_err(|err| err.for_input(txid, *index))
+            })
+            .collect::<OrdResult<Vec<_>>>()?;
+
+        let witnesses = stream::iter(requests)
+            .map(|request| async move {
+                let index = request.index;
+                self.sign_input(request)
+                    .await
+                    .map(|witness| (index, witness))
+                    .map_err(|err| err.for_input(txid, index))
+            })
+            .buffered(self.signing_parallelism)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for (index, witness) in witnesses {
+            session.set_witness(index, witness)?;
+        }
+        for (index, _) in inputs {
+            self.verify_session_input(&mut session, index)?;
+        }
+
+        Ok(session.into_transaction())
+    }
+
+    /// Returns the P2WPKH redeem script of the P2SH output spent by the input, checking that
+    /// it's locked by the key of the signer.
+    async fn nested_p2wpkh_redeem_script(&self, input: &TxInputInfo) -> OrdResult<ScriptBuf> {
+        let public_key = self.ecdsa_public_key(&input.derivation_path).await?;
+        let redeem_script = ScriptBuf::new_p2wpkh(
+            &public_key
+                .wpubkey_hash()
+                .ok_or(OrdError::InvalidScriptType)?,
+        );
+        if ScriptBuf::new_p2sh(&redeem_script.script_hash()) != input.tx_out.script_pubkey {
+            return Err(OrdError::InvalidScriptType);
+        }
+
+        Ok(redeem_script)
+    }
+
+    /// Computes the message to sign for the input at `index` of a generic transaction.
+    ///
+    /// P2SH inputs are signed for the given P2WPKH redeem script.
+    fn input_signature_request<'a>(
+        session: &mut SigningSession,
+        index: usize,
+        input: &'a TxInputInfo,
+        redeem_script: Option<&ScriptBuf>,
+    ) -> OrdResult<InputSignatureRequest<'a>> {
+        let script_code = redeem_script.unwrap_or(&input.tx_out.script_pubkey);
+        let (message, key_spend) = match script_code {
+            s if s.is_p2wpkh() || s.is_p2wsh() => (session.p2wpkh_sighash(index, s)?, None),
+            s if s.is_p2tr() => (session.key_spend_sighash(index)?, Some(input.key_spend)),
+            _ => return Err(OrdError::InvalidScriptType),
+        };
+
+        Ok(InputSignatureRequest {
+            index,
+            message,
+            derivation_path: &input.derivation_path,
+            key_spend,
+        })
+    }
+
+    /// Signs an input of a generic transaction, returning its witness.
+    async fn sign_input(&self, request: InputSignatureRequest<'_>) -> OrdResult<Witness> {
+        if let Some(key_spend) = request.key_spend {
+            let signature = match key_spend {
+                TaprootKeySpend::Untweaked => {
+                    self.signer
+                        .sign_with_schnorr(request.message, request.derivation_path)
+                        .await?
+                }
+                TaprootKeySpend::Bip86 => {
+                    self.signer
+                        .sign_with_schnorr_tweaked(request.message, request.derivation_path, None)
+                        .await?
+                }
+            };
+            let signature = bitcoin::taproot::Signature {
+                sig: signature,
+                hash_ty: TapSighashType::Default,
+            };
+
+            let mut witness = Witness::new();
+            witness.push(signature.to_vec());
+            Ok(witness)
+        } else {
+            let signature = self
+                .signer
+                .sign_with_ecdsa(request.message, request.derivation_path)
+                .await?;
+            let public_key = self.ecdsa_public_key(request.derivation_path).await?;
+
+            Ok(Witness::p2wpkh(
+                &bitcoin::ecdsa::Signature::sighash_all(signature),
+                &public_key.inner,
+            ))
+        }
     }
 
-    async fn sign_ecdsa(
+    async fn sign_ecdsa<'a>(
         &mut self,
         own_pubkey: &PublicKey,
-        utxos: &[Utxo],
+        utxos: impl IntoIterator<Item = (usize, &'a Utxo)>,
         transaction: Transaction,
         script: &ScriptBuf,
         transaction_type: TransactionType,
         derivation_path: &DerivationPath,
     ) -> OrdResult<Transaction> {
+        let txid = transaction.txid();
         let mut hash = SighashCache::new(transaction.clone());
-        for (index, input) in utxos.iter().enumerate() {
-            let sighash = match transaction_type {
-                TransactionType::Commit => hash.p2wpkh_signature_hash(
-                    index,
-                    script,
-                    input.amount,
-                    bitcoin::EcdsaSighashType::All,
-                )?,
-                TransactionType::Reveal => hash.p2wsh_signature_hash(
-                    index,
-                    script,
-                    input.amount,
-                    bitcoin::EcdsaSighashType::All,
-                )?,
+        let mut prevouts = Vec::new();
+        for (index, input) in utxos {
+            let sign_input = async {
+                let sighash = match transaction_type {
+                    TransactionType::Commit => hash.p2wpkh_signature_hash(
+                        index,
+                        script,
+                        input.amount,
+                        bitcoin::EcdsaSighashType::All,
+                    )?,
+                    TransactionType::Reveal => hash.p2wsh_signature_hash(
+                        index,
+                        script,
+                        input.amount,
+                        bitcoin::EcdsaSighashType::All,
+                    )?,
+                };
+
+                let message = Message::from(sighash);
+                let signature = self
+                    .signer
+                    .sign_with_ecdsa(message, derivation_path)
+                    .await?;
+
+                // append witness
+                let signature = bitcoin::ecdsa::Signature::sighash_all(signature).into();
+                let redeem_script = match transaction_type {
+                    TransactionType::Commit => None,
+                    TransactionType::Reveal => Some(script),
+                };
+                *hash
+                    .witness_mut(index)
+                    .ok_or(OrdError::InputNotFound(index))? =
+                    Self::input_witness(signature, &own_pubkey.inner, redeem_script, None)?;
+
+                OrdResult::Ok(())
             };
+            sign_input.await.map_err(|err| err.for_input(txid, index))?;
 
-            let message = Message::from(sighash);
-            let signature = self
-                .signer
-                .sign_with_ecdsa(message, derivation_path)
-                .await?;
+            let script_pubkey = match transaction_type {
+                TransactionType::Commit => script.clone(),
+                TransactionType::Reveal => ScriptBuf::new_p2wsh(&script.wscript_hash()),
+            };
+            prevouts.push((
+                index,
+                TxOut {
+                    value: input.amount,
+                    script_pubkey,
+                },
+            ));
+        }
 
-            // append witness
-            let signature = bitcoin::ecdsa::Signature::sighash_all(signature).into();
-            match transaction_type {
-                TransactionType::Commit => {
-                    self.append_witness_to_input(
-                        &mut hash,
-                        signature,
-                        index,
-                        &own_pubkey.inner,
-                        None,
-                        None,
-                    )?;
-                }
-                TransactionType::Reveal => {
-                    self.append_witness_to_input(
-                        &mut hash,
-                        signature,
-                        index,
-                        &own_pubkey.inner,
-                        Some(script),
-                        None,
-                    )?;
-                }
-            }
+        let transaction = hash.into_transaction();
+        for (index, prevout) in prevouts {
+            self.verify_input(&transaction, index, &Prevouts::One(index, prevout))?;
         }
 
-        Ok(hash.into_transaction())
+        Ok(transaction)
     }
 
-    fn append_witness_to_input(
+    /// Verifies the witness of a signed input, if enabled with
+    /// [`Self::with_signature_verification`].
+    fn verify_input<T: Borrow<TxOut>>(
         &self,
-        sighasher: &mut SighashCache<Transaction>,
-        signature: OrdSignature,
+        transaction: &Transaction,
         index: usize,
+        prevouts: &Prevouts<T>,
+    ) -> OrdResult<()> {
+        if !self.verify_signatures {
+            return Ok(());
+        }
+
+        let mut cache = SighashCache::new(transaction);
+        verify_input(&self.secp, &mut cache, index, prevouts)
+            .map_err(|err| err.for_input(transaction.txid(), index))
+    }
+
+    /// Verifies the witness of an input signed in a session, if enabled with
+    /// [`Self::with_signature_verification`].
+    fn verify_session_input(&self, session: &mut SigningSession, index: usize) -> OrdResult<()> {
+        if !self.verify_signatures {
+            return Ok(());
+        }
+
+        let txid = session.transaction().txid();
+        session
+            .verify_input(&self.secp, index)
+            .map_err(|err| err.for_input(txid, index))
+    }
+
+    /// Builds the witness of a signed input.
+    fn input_witness(
+        signature: OrdSignature,
         pubkey: &secp256k1::PublicKey,
         redeem_script: Option<&ScriptBuf>,
         control_block: Option<&ControlBlock>,
-    ) -> OrdResult<()> {
+    ) -> OrdResult<Witness> {
         // push redeem script if necessary
         let witness = if let Some(redeem_script) = redeem_script {
             let mut witness = Witness::new();
@@ -388,15 +735,24 @@ impl Wallet {
         };
         debug!("witness: {witness:?}");
 
-        // append witness
-        *sighasher
-            .witness_mut(index)
-            .ok_or(OrdError::InputNotFound(index))? = witness;
-
-        Ok(())
+        Ok(witness)
     }
 }
 
+/// Locks the public key cache, which can't be left inconsistent by a panicking thread.
+fn lock<T>(cache: &Mutex<T>) -> MutexGuard<'_, T> {
+    cache.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Message to sign for an input of a generic transaction.
+struct InputSignatureRequest<'a> {
+    index: usize,
+    message: Message,
+    derivation_path: &'a DerivationPath,
+    /// Key signing a taproot key path spend, `None` for an ECDSA signature
+    key_spend: Option<TaprootKeySpend>,
+}
+
 /// Type of the transaction to sign
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum TransactionType {
@@ -420,3 +776,300 @@ impl From<bitcoin::ecdsa::Signature> for OrdSignature {
         Self::Ecdsa(sig)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use bitcoin::absolute::LockTime;
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Address, Amount, OutPoint, TxIn, Txid};
+
+    use super::*;
+    use crate::utils::test_utils::test_private_key;
+
+    /// Signer taking a variable time to sign, tracking the signatures in flight and the public
+    /// keys requested.
+    #[derive(Default)]
+    struct SlowSigner {
+        signer: Option<LocalSigner>,
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+        key_requests: Arc<AtomicUsize>,
+    }
+
+    impl SlowSigner {
+        fn signer(&self) -> &LocalSigner {
+            self.signer.as_ref().unwrap()
+        }
+
+        async fn delay(&self, message: &Message) {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            let millis = u64::from(message.as_ref()[0] % 10);
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl BtcTxSigner for SlowSigner {
+        async fn ecdsa_public_key(&self, derivation_path: &DerivationPath) -> OrdResult<PublicKey> {
+            self.key_requests.fetch_add(1, Ordering::SeqCst);
+            self.signer().ecdsa_public_key(derivation_path).await
+        }
+
+        async fn sign_with_ecdsa(
+            &self,
+            message: Message,
+            derivation_path: &DerivationPath,
+        ) -> Result<Signature, secp256k1::Error> {
+            self.delay(&message).await;
+            self.signer()
+                .sign_with_ecdsa(message, derivation_path)
+                .await
+        }
+
+        async fn schnorr_public_key(
+            &self,
+            derivation_path: &DerivationPath,
+        ) -> OrdResult<XOnlyPublicKey> {
+            self.key_requests.fetch_add(1, Ordering::SeqCst);
+            self.signer().schnorr_public_key(derivation_path).await
+        }
+
+        async fn sign_with_schnorr(
+            &self,
+            message: Message,
+            derivation_path: &DerivationPath,
+        ) -> Result<secp256k1::schnorr::Signature, secp256k1::Error> {
+            self.delay(&message).await;
+            self.signer()
+                .sign_with_schnorr(message, derivation_path)
+                .await
+        }
+
+        async fn sign_with_schnorr_tweaked(
+            &self,
+            message: Message,
+            derivation_path: &DerivationPath,
+            merkle_root: Option<TapNodeHash>,
+        ) -> OrdResult<secp256k1::schnorr::Signature> {
+            self.delay(&message).await;
+            self.signer()
+                .sign_with_schnorr_tweaked(message, derivation_path, merkle_root)
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_sign_bip86_key_spends() {
+        // <https://github.com/bitcoin/bips/blob/master/bip-0086.mediawiki#test-vectors>
+        let mnemonic = bip39::Mnemonic::from_str(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let master_key = Xpriv::new_master(Network::Bitcoin, &mnemonic.to_seed("")).unwrap();
+        let wallet = Wallet::new_with_signer(LocalSigner::from_xpriv(master_key))
+            .with_signature_verification(true);
+        let derivation_path = DerivationPath::from_str("m/86'/0'/0'/0/0").unwrap();
+
+        let internal_key = wallet.schnorr_public_key(&derivation_path).await.unwrap();
+        assert_eq!(
+            internal_key.to_string(),
+            "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115"
+        );
+        let script_pubkey = ScriptBuf::from_hex(
+            "5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c",
+        )
+        .unwrap();
+        assert_eq!(
+            Address::p2tr(&Secp256k1::new(), internal_key, None, Network::Bitcoin).script_pubkey(),
+            script_pubkey
+        );
+
+        let transaction = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: Amount::from_sat(9_000),
+                script_pubkey: script_pubkey.clone(),
+            }],
+        };
+        let mut input = TxInputInfo {
+            outpoint: OutPoint::null(),
+            tx_out: TxOut {
+                value: Amount::from_sat(10_000),
+                script_pubkey,
+            },
+            derivation_path,
+            key_spend: TaprootKeySpend::Bip86,
+            rune_balances: None,
+        };
+
+        // the signature is checked against the tweaked output key
+        let signed = wallet
+            .sign_transaction(&transaction, &[input.clone()])
+            .await
+            .unwrap();
+        assert_eq!(signed.input[0].witness.len(), 1);
+
+        input.key_spend = TaprootKeySpend::Untweaked;
+        assert!(wallet
+            .sign_transaction(&transaction, &[input])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_should_sign_inputs_concurrently_in_order() {
+        let private_key = test_private_key();
+        let secp = Secp256k1::new();
+        let public_key = private_key.public_key(&secp);
+        let p2wpkh = Address::p2wpkh(&public_key, Network::Testnet)
+            .unwrap()
+            .script_pubkey();
+        let p2tr = Address::p2tr(
+            &secp,
+            public_key.inner.x_only_public_key().0,
+            None,
+            Network::Testnet,
+        )
+        .script_pubkey();
+
+        let prev_outs = (0..12)
+            .map(|index| TxInputInfo {
+                outpoint: OutPoint::new(Txid::all_zeros(), index),
+                tx_out: TxOut {
+                    value: Amount::from_sat(10_000),
+                    script_pubkey: if index % 2 == 0 {
+                        p2wpkh.clone()
+                    } else {
+                        p2tr.clone()
+                    },
+                },
+                derivation_path: DerivationPath::default(),
+                key_spend: TaprootKeySpend::Bip86,
+                rune_balances: None,
+            })
+            .collect::<Vec<_>>();
+        let transaction = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: prev_outs
+                .iter()
+                .map(|input| TxIn {
+                    previous_output: input.outpoint,
+                    ..Default::default()
+                })
+                .collect(),
+            output: vec![TxOut {
+                value: Amount::from_sat(100_000),
+                script_pubkey: p2tr.clone(),
+            }],
+        };
+
+        let wallet = |parallelism| {
+            let max_in_flight = Arc::new(AtomicUsize::new(0));
+            let wallet = Wallet::new_with_signer(SlowSigner {
+                signer: Some(LocalSigner::new(private_key)),
+                max_in_flight: max_in_flight.clone(),
+                ..Default::default()
+            })
+            .with_signing_parallelism(parallelism);
+            (wallet, max_in_flight)
+        };
+
+        let (sequential, max_in_flight) = wallet(1);
+        let expected = sequential
+            .sign_transaction(&transaction, &prev_outs)
+            .await
+            .unwrap();
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+
+        let (concurrent, max_in_flight) = wallet(4);
+        let signed = concurrent
+            .sign_transaction(&transaction, &prev_outs)
+            .await
+            .unwrap();
+        assert_eq!(signed, expected);
+        assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 4);
+    }
+
+    #[tokio::test]
+    async fn test_local_signer_should_be_deterministic() {
+        let private_key = test_private_key();
+        let derivation_path = DerivationPath::default();
+        let message = Message::from_digest([0x42; 32]);
+
+        let signer = LocalSigner::new(private_key);
+        let other_signer = LocalSigner::new(private_key);
+
+        assert_eq!(
+            signer.schnorr_public_key(&derivation_path).await.unwrap(),
+            other_signer
+                .schnorr_public_key(&derivation_path)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            signer
+                .sign_with_schnorr(message, &derivation_path)
+                .await
+                .unwrap(),
+            other_signer
+                .sign_with_schnorr(message, &derivation_path)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            signer
+                .sign_with_ecdsa(message, &derivation_path)
+                .await
+                .unwrap(),
+            other_signer
+                .sign_with_ecdsa(message, &derivation_path)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_cache_public_keys() {
+        let private_key = test_private_key();
+        let key_requests = Arc::new(AtomicUsize::new(0));
+        let wallet = Wallet::new_with_signer(SlowSigner {
+            signer: Some(LocalSigner::new(private_key)),
+            key_requests: key_requests.clone(),
+            ..Default::default()
+        });
+        let derivation_path = DerivationPath::default();
+        let other_path = DerivationPath::from_str("m/86'/1'/0'").unwrap();
+
+        let public_key = wallet.ecdsa_public_key(&derivation_path).await.unwrap();
+        assert_eq!(
+            wallet.ecdsa_public_key(&derivation_path).await.unwrap(),
+            public_key
+        );
+        assert_eq!(key_requests.load(Ordering::SeqCst), 1);
+
+        let x_public_key = wallet.schnorr_public_key(&derivation_path).await.unwrap();
+        assert_eq!(x_public_key, public_key.inner.x_only_public_key().0);
+        assert_ne!(
+            wallet.schnorr_public_key(&other_path).await.unwrap(),
+            x_public_key
+        );
+        wallet.schnorr_public_key(&derivation_path).await.unwrap();
+        assert_eq!(key_requests.load(Ordering::SeqCst), 3);
+
+        wallet.clear_public_key_cache();
+        wallet.ecdsa_public_key(&derivation_path).await.unwrap();
+        assert_eq!(key_requests.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,271 @@
+use bitcoin::bip32::DerivationPath;
+use bitcoin::{Address, Amount, FeeRate, Network, ScriptBuf, Transaction};
+
+use super::{
+    CreateCommitTransaction, CreateCommitTransactionArgs, OrdTransactionBuilder,
+    RevealTransactionArgs, Utxo,
+};
+use crate::inscription::Inscription;
+use crate::{OrdError, OrdResult};
+
+/// Funding offered by a sponsor paying the fees of an inscription on behalf of a user.
+///
+/// This is the first message of the sponsored inscription flow, sent by the sponsor to the user:
+///
+/// 1. the sponsor sends a [`SponsorOffer`] to the user;
+/// 2. the user builds the commit transaction with [`OrdTransactionBuilder::build_sponsored_commit`],
+///    keeps the returned [`SponsoredCommit`] and sends its [`SponsorshipRequest`] to the sponsor;
+/// 3. the sponsor signs the commit transaction with
+///    [`OrdTransactionBuilder::sign_sponsorship_request`] and sends it back to the user;
+/// 4. the user broadcasts the commit transaction and builds the reveal transaction with the
+///    arguments returned by [`SponsoredCommit::reveal_args`].
+///
+/// The inscription is locked by the user key, so only the user can reveal it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SponsorOffer {
+    /// UTXOs of the sponsor funding the commit transaction
+    pub inputs: Vec<Utxo>,
+    /// Script pubkey of the sponsor UTXOs
+    pub txin_script_pubkey: ScriptBuf,
+    /// Address of the sponsor receiving the leftovers of the commit transaction
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
+    pub change_address: Address,
+    /// Fee rate of the commit and reveal transactions
+    pub fee_rate: FeeRate,
+    /// Maximum amount the sponsor is willing to spend, including fees and postage
+    pub max_amount: Amount,
+}
+
+/// Unsigned commit transaction sent by the user to the sponsor for signing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SponsorshipRequest {
+    /// The unsigned commit transaction, spending the sponsor UTXOs
+    pub unsigned_tx: Transaction,
+}
+
+/// Commit transaction funded by a sponsor, kept by the user to reveal the inscription.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SponsoredCommit {
+    /// The commit transaction built from the sponsor offer
+    pub commit: CreateCommitTransaction,
+    /// Recipient address of the inscription
+    #[serde(deserialize_with = "crate::utils::serde_helpers::address::deserialize")]
+    pub recipient_address: Address,
+}
+
+impl SponsoredCommit {
+    /// Returns the request to send to the sponsor.
+    pub fn request(&self) -> SponsorshipRequest {
+        SponsorshipRequest {
+            unsigned_tx: self.commit.unsigned_tx.clone(),
+        }
+    }
+
+    /// Returns the arguments to build the reveal transaction once the sponsor has signed the
+    /// commit transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the signed transaction is not the commit transaction.
+    pub fn reveal_args(
+        &self,
+        signed_commit_tx: &Transaction,
+        derivation_path: Option<DerivationPath>,
+    ) -> OrdResult<RevealTransactionArgs> {
+        let txid = self.commit.unsigned_tx.txid();
+        if signed_commit_tx.txid() != txid {
+            return Err(OrdError::InvalidInputs);
+        }
+
+        Ok(RevealTransactionArgs {
+            input: Utxo {
+                id: txid,
+                index: 0,
+                amount: self.commit.reveal_balance,
+            },
+            recipient_address: self.recipient_address.clone(),
+            redeem_script: self.commit.redeem_script.clone(),
+            derivation_path,
+            control_block: None,
+            timelock: None,
+            postage: Some(self.commit.postage),
+            anchor: false,
+            parent: self.commit.parent.clone(),
+            annex: None,
+        })
+    }
+}
+
+impl OrdTransactionBuilder {
+    /// Builds the commit transaction of an inscription funded by a sponsor.
+    ///
+    /// The leftovers of the commit transaction are sent back to the sponsor.
+    pub async fn build_sponsored_commit<T>(
+        &mut self,
+        network: Network,
+        offer: SponsorOffer,
+        inscription: T,
+        recipient_address: Address,
+        derivation_path: Option<DerivationPath>,
+    ) -> OrdResult<SponsoredCommit>
+    where
+        T: Inscription,
+    {
+        let commit = self
+            .build_commit_transaction(
+                network,
+                recipient_address.clone(),
+                CreateCommitTransactionArgs {
+                    derivation_path,
+                    ..CreateCommitTransactionArgs::new(
+                        offer.inputs,
+                        inscription,
+                        offer.change_address,
+                        offer.txin_script_pubkey,
+                        offer.fee_rate,
+                    )
+                },
+            )
+            .await?;
+
+        Ok(SponsoredCommit {
+            commit,
+            recipient_address,
+        })
+    }
+
+    /// Signs the commit transaction of a sponsorship request, as the sponsor.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the transaction doesn't spend exactly the offered
+    /// UTXOs, or [`OrdError::SponsorshipExceeded`] if it spends more than the offered amount.
+    pub async fn sign_sponsorship_request(
+        &mut self,
+        offer: &SponsorOffer,
+        request: SponsorshipRequest,
+        derivation_path: Option<DerivationPath>,
+    ) -> OrdResult<Transaction> {
+        let spent_amount = sponsored_amount(offer, &request.unsigned_tx)?;
+        if spent_amount > offer.max_amount {
+            return Err(OrdError::SponsorshipExceeded {
+                amount: spent_amount,
+                max_amount: offer.max_amount,
+            });
+        }
+
+        self.signer
+            .sign_commit_transaction(
+                &self.public_key,
+                &offer.inputs,
+                request.unsigned_tx,
+                &offer.txin_script_pubkey,
+                &derivation_path.unwrap_or_default(),
+            )
+            .await
+    }
+}
+
+/// Returns the amount spent by the sponsor, i.e. the offered UTXOs minus the change returned
+/// to the sponsor.
+fn sponsored_amount(offer: &SponsorOffer, unsigned_tx: &Transaction) -> OrdResult<Amount> {
+    let spends_offered_inputs = unsigned_tx.input.len() == offer.inputs.len()
+        && unsigned_tx
+            .input
+            .iter()
+            .zip(&offer.inputs)
+            .all(|(txin, utxo)| {
+                txin.previous_output.txid == utxo.id && txin.previous_output.vout == utxo.index
+            });
+    if !spends_offered_inputs {
+        return Err(OrdError::InvalidInputs);
+    }
+
+    let input_amount = offer.inputs.iter().map(|utxo| utxo.amount).sum::<Amount>();
+    let change_script_pubkey = offer.change_address.script_pubkey();
+    let change_amount = unsigned_tx
+        .output
+        .iter()
+        .filter(|txout| txout.script_pubkey == change_script_pubkey)
+        .map(|txout| txout.value)
+        .sum::<Amount>();
+
+    input_amount
+        .checked_sub(change_amount)
+        .ok_or(OrdError::InvalidInputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use bitcoin::PrivateKey;
+
+    use super::*;
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::Nft;
+
+    #[tokio::test]
+    async fn test_should_inscribe_with_sponsored_fees() {
+        let secp = Secp256k1::new();
+        let user_key = test_private_key();
+        let user_address = Address::p2wpkh(&user_key.public_key(&secp), Network::Testnet).unwrap();
+        let sponsor_key = PrivateKey::new(
+            SecretKey::from_slice(&[0x02; 32]).unwrap(),
+            Network::Testnet,
+        );
+        let sponsor_address =
+            Address::p2wpkh(&sponsor_key.public_key(&secp), Network::Testnet).unwrap();
+
+        let offer = SponsorOffer {
+            inputs: vec![test_utxo(1, Amount::from_sat(10_000))],
+            txin_script_pubkey: sponsor_address.script_pubkey(),
+            change_address: sponsor_address.clone(),
+            fee_rate: FeeRate::from_sat_per_vb(1).unwrap(),
+            max_amount: Amount::from_sat(1_000),
+        };
+
+        let mut user = OrdTransactionBuilder::p2tr(user_key);
+        let sponsored = user
+            .build_sponsored_commit(
+                Network::Testnet,
+                offer.clone(),
+                Nft::new(
+                    Some("text/plain;charset=utf-8".as_bytes().to_vec()),
+                    Some(b"sponsored".to_vec()),
+                ),
+                user_address.clone(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            sponsored.commit.unsigned_tx.output[1].script_pubkey,
+            sponsor_address.script_pubkey()
+        );
+
+        let mut sponsor = OrdTransactionBuilder::p2wsh(sponsor_key);
+        let signed_commit_tx = sponsor
+            .sign_sponsorship_request(&offer, sponsored.request(), None)
+            .await
+            .unwrap();
+        assert_eq!(signed_commit_tx.txid(), sponsored.commit.unsigned_tx.txid());
+        assert!(!signed_commit_tx.input[0].witness.is_empty());
+
+        let reveal_args = sponsored.reveal_args(&signed_commit_tx, None).unwrap();
+        let reveal_tx = user.build_reveal_transaction(reveal_args).await.unwrap();
+        assert_eq!(
+            reveal_tx.output[0].script_pubkey,
+            user_address.script_pubkey()
+        );
+
+        // the sponsor doesn't spend more than offered
+        let stingy_offer = SponsorOffer {
+            max_amount: Amount::from_sat(100),
+            ..offer
+        };
+        let result = sponsor
+            .sign_sponsorship_request(&stingy_offer, sponsored.request(), None)
+            .await;
+        assert!(matches!(result, Err(OrdError::SponsorshipExceeded { .. })));
+    }
+}
@@ -1,7 +1,10 @@
-use bitcoin::secp256k1::{All, Secp256k1};
-use bitcoin::taproot::{ControlBlock, LeafVersion, TaprootBuilder};
-use bitcoin::{Address, Amount, Network, ScriptBuf, TxOut, XOnlyPublicKey};
+use bitcoin::bip32::{ChildNumber, DerivationPath};
+use bitcoin::key::TapTweak as _;
+use bitcoin::secp256k1::{All, Secp256k1, Verification};
+use bitcoin::taproot::{ControlBlock, LeafVersion, TapNodeHash, TaprootBuilder};
+use bitcoin::{Address, Amount, Network, Script, ScriptBuf, TapLeafHash, TxOut, XOnlyPublicKey};
 
+use super::signer::BtcTxSigner;
 use crate::{OrdError, OrdResult};
 
 #[derive(Debug, Clone)]
@@ -18,11 +21,34 @@ impl TaprootPayload {
         secp: &Secp256k1<All>,
         x_public_key: XOnlyPublicKey,
         redeem_script: &ScriptBuf,
-        reveal_balance: u64,
+        reveal_balance: Amount,
         network: Network,
+    ) -> OrdResult<Self> {
+        Self::build_with_leaf_version(
+            secp,
+            x_public_key,
+            redeem_script,
+            reveal_balance,
+            network,
+            LeafVersion::TapScript,
+        )
+    }
+
+    /// Build a taproot payload committing to the redeem script as a leaf of the given version.
+    ///
+    /// The leaf version is carried by the control block, which is used to compute the sighash
+    /// when signing the reveal transaction, so leaves of other versions than
+    /// [`LeafVersion::TapScript`] can be spent, e.g. to experiment with future leaf versions.
+    pub fn build_with_leaf_version(
+        secp: &Secp256k1<All>,
+        x_public_key: XOnlyPublicKey,
+        redeem_script: &ScriptBuf,
+        reveal_balance: Amount,
+        network: Network,
+        leaf_version: LeafVersion,
     ) -> OrdResult<Self> {
         let taproot_spend_info = TaprootBuilder::new()
-            .add_leaf(0, redeem_script.clone())
+            .add_leaf_with_ver(0, redeem_script.clone(), leaf_version)
             .expect("adding leaf should work")
             .finalize(secp, x_public_key)
             .ok()
@@ -33,14 +59,346 @@ impl TaprootPayload {
 
         Ok(Self {
             control_block: taproot_spend_info
-                .control_block(&(redeem_script.clone(), LeafVersion::TapScript))
+                .control_block(&(redeem_script.clone(), leaf_version))
                 .ok_or(OrdError::TaprootCompute)?,
             prevouts: TxOut {
-                value: Amount::from_sat(reveal_balance),
+                value: reveal_balance,
                 script_pubkey: address.script_pubkey(),
             },
             address,
             pubkey: x_public_key,
         })
     }
+
+    /// Returns the hash of the redeem script leaf and the merkle root of the script tree, which
+    /// the commit output commits to.
+    ///
+    /// Along with the internal key, they're enough to verify the commitment with
+    /// [`verify_p2tr_commitment`], without disclosing the redeem script before the reveal.
+    pub fn commitment(&self, redeem_script: &Script) -> (TapLeafHash, TapNodeHash) {
+        let leaf_hash = TapLeafHash::from_script(redeem_script, self.control_block.leaf_version);
+        let merkle_root = self
+            .control_block
+            .merkle_branch
+            .as_inner()
+            .iter()
+            .fold(TapNodeHash::from(leaf_hash), |node, sibling| {
+                TapNodeHash::from_node_hashes(node, *sibling)
+            });
+
+        (leaf_hash, merkle_root)
+    }
+}
+
+/// Returns whether the P2TR script pubkey commits to the script tree with the given merkle root,
+/// tweaking the internal key.
+///
+/// Third parties can check with it that a commit output commits to the inscription claimed by
+/// its [`super::CreateCommitTransaction::merkle_root`], e.g. the single leaf whose hash is its
+/// [`super::CreateCommitTransaction::tapleaf_hash`].
+pub fn verify_p2tr_commitment(
+    script_pubkey: &Script,
+    internal_key: XOnlyPublicKey,
+    merkle_root: TapNodeHash,
+) -> bool {
+    let secp = Secp256k1::verification_only();
+    ScriptBuf::new_p2tr(&secp, internal_key, Some(merkle_root)).as_script() == script_pubkey
+}
+
+/// Builds a P2TR commit output with the redeem script as its only leaf, tweaking the given
+/// internal key.
+///
+/// Returns the script pubkey of the output, the control block required to spend it through the
+/// redeem script and the merkle root of the script tree, so the inscription leaf can be
+/// integrated in the taproot derivation scheme of an external wallet.
+pub fn build_p2tr_commit_output(
+    redeem_script: &ScriptBuf,
+    internal_key: XOnlyPublicKey,
+) -> OrdResult<(ScriptBuf, ControlBlock, TapNodeHash)> {
+    build_p2tr_commit_output_with_leaf_version(redeem_script, internal_key, LeafVersion::TapScript)
+}
+
+/// Builds a P2TR commit output as [`build_p2tr_commit_output`] does, with the redeem script as
+/// a leaf of the given version.
+pub fn build_p2tr_commit_output_with_leaf_version(
+    redeem_script: &ScriptBuf,
+    internal_key: XOnlyPublicKey,
+    leaf_version: LeafVersion,
+) -> OrdResult<(ScriptBuf, ControlBlock, TapNodeHash)> {
+    let secp = Secp256k1::verification_only();
+    let taproot_spend_info = TaprootBuilder::new()
+        .add_leaf_with_ver(0, redeem_script.clone(), leaf_version)
+        .map_err(|_| OrdError::TaprootCompute)?
+        .finalize(&secp, internal_key)
+        .map_err(|_| OrdError::TaprootCompute)?;
+
+    let control_block = taproot_spend_info
+        .control_block(&(redeem_script.clone(), leaf_version))
+        .ok_or(OrdError::TaprootCompute)?;
+    let merkle_root = taproot_spend_info
+        .merkle_root()
+        .ok_or(OrdError::TaprootCompute)?;
+
+    Ok((
+        ScriptBuf::new_p2tr_tweaked(taproot_spend_info.output_key()),
+        control_block,
+        merkle_root,
+    ))
+}
+
+/// Derives the key path P2TR address of the signer's schnorr public key at the given derivation
+/// path, e.g. to send inscriptions to a taproot address controlled by the signer.
+///
+/// The key is tweaked without a script tree, as defined by BIP 86.
+pub async fn derive_reveal_destination(
+    signer: &dyn BtcTxSigner,
+    derivation_path: &DerivationPath,
+    network: Network,
+) -> OrdResult<Address> {
+    p2tr_bip86_address(signer, derivation_path, network).await
+}
+
+/// Derives the [BIP-86] P2TR address of the signer's schnorr public key at the given derivation
+/// path, as generated by standard wallets, e.g. Sparrow or the `tr()` descriptors of Bitcoin
+/// Core, given a path built with [`bip86_derivation_path`].
+///
+/// Outputs sent to the address are spent through the key path with
+/// [`TaprootKeySpend::Bip86`](super::TaprootKeySpend::Bip86).
+///
+/// [BIP-86]: https://github.com/bitcoin/bips/blob/master/bip-0086.mediawiki
+pub async fn p2tr_bip86_address(
+    signer: &dyn BtcTxSigner,
+    derivation_path: &DerivationPath,
+    network: Network,
+) -> OrdResult<Address> {
+    let x_public_key = signer.schnorr_public_key(derivation_path).await?;
+
+    Ok(Address::p2tr(
+        &Secp256k1::verification_only(),
+        x_public_key,
+        None,
+        network,
+    ))
+}
+
+/// Returns the BIP-86 derivation path `m/86'/coin_type'/account'/change/index`, with the coin
+/// type of the network, i.e. 0 for mainnet and 1 for the test networks.
+///
+/// # Errors
+///
+/// Returns [`OrdError::Bip32`] if the account or the index are not valid child numbers.
+pub fn bip86_derivation_path(
+    network: Network,
+    account: u32,
+    change: bool,
+    index: u32,
+) -> OrdResult<DerivationPath> {
+    let coin_type = match network {
+        Network::Bitcoin => 0,
+        _ => 1,
+    };
+
+    Ok(DerivationPath::from(vec![
+        ChildNumber::from_hardened_idx(86)?,
+        ChildNumber::from_hardened_idx(coin_type)?,
+        ChildNumber::from_hardened_idx(account)?,
+        ChildNumber::from_normal_idx(u32::from(change))?,
+        ChildNumber::from_normal_idx(index)?,
+    ]))
+}
+
+/// Computes the script pubkey of the taproot output committing to `script`,
+/// given the control block of its leaf.
+pub fn script_pubkey_from_control_block<C: Verification>(
+    secp: &Secp256k1<C>,
+    control_block: &ControlBlock,
+    script: &Script,
+) -> ScriptBuf {
+    let leaf_hash = TapLeafHash::from_script(script, control_block.leaf_version);
+    let merkle_root = control_block
+        .merkle_branch
+        .as_inner()
+        .iter()
+        .fold(TapNodeHash::from(leaf_hash), |node, sibling| {
+            TapNodeHash::from_node_hashes(node, *sibling)
+        });
+    let (output_key, _) = control_block
+        .internal_key
+        .tap_tweak(secp, Some(merkle_root));
+
+    ScriptBuf::new_p2tr_tweaked(output_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::bip32::Xpriv;
+
+    use super::super::signer::LocalSigner;
+    use super::*;
+    use crate::utils::test_utils::test_private_key;
+
+    #[test]
+    fn test_should_compute_script_pubkey_from_control_block() {
+        let secp = Secp256k1::new();
+        let private_key = test_private_key();
+        let x_public_key = private_key.public_key(&secp).inner.x_only_public_key().0;
+        let redeem_script = ScriptBuf::from_bytes(vec![0x51]);
+
+        let payload = TaprootPayload::build(
+            &secp,
+            x_public_key,
+            &redeem_script,
+            Amount::from_sat(1_000),
+            Network::Testnet,
+        )
+        .unwrap();
+
+        assert_eq!(
+            script_pubkey_from_control_block(&secp, &payload.control_block, &redeem_script),
+            payload.address.script_pubkey()
+        );
+    }
+
+    #[test]
+    fn test_should_build_p2tr_commit_output() {
+        let secp = Secp256k1::new();
+        let private_key = test_private_key();
+        let x_public_key = private_key.public_key(&secp).inner.x_only_public_key().0;
+        let redeem_script = ScriptBuf::from_bytes(vec![0x51]);
+
+        let (script_pubkey, control_block, merkle_root) =
+            build_p2tr_commit_output(&redeem_script, x_public_key).unwrap();
+        let payload = TaprootPayload::build(
+            &secp,
+            x_public_key,
+            &redeem_script,
+            Amount::from_sat(1_000),
+            Network::Testnet,
+        )
+        .unwrap();
+
+        assert_eq!(script_pubkey, payload.address.script_pubkey());
+        assert_eq!(control_block, payload.control_block);
+        assert_eq!(
+            merkle_root,
+            TapNodeHash::from(TapLeafHash::from_script(
+                &redeem_script,
+                LeafVersion::TapScript
+            ))
+        );
+        assert!(control_block.verify_taproot_commitment(
+            &secp,
+            script_pubkey_output_key(&script_pubkey),
+            &redeem_script
+        ));
+    }
+
+    #[test]
+    fn test_should_build_payload_with_leaf_version() {
+        let secp = Secp256k1::new();
+        let private_key = test_private_key();
+        let x_public_key = private_key.public_key(&secp).inner.x_only_public_key().0;
+        let redeem_script = ScriptBuf::from_bytes(vec![0x51]);
+        let leaf_version = LeafVersion::from_consensus(0xc2).unwrap();
+
+        let payload = TaprootPayload::build_with_leaf_version(
+            &secp,
+            x_public_key,
+            &redeem_script,
+            Amount::from_sat(1_000),
+            Network::Testnet,
+            leaf_version,
+        )
+        .unwrap();
+        let tapscript = TaprootPayload::build(
+            &secp,
+            x_public_key,
+            &redeem_script,
+            Amount::from_sat(1_000),
+            Network::Testnet,
+        )
+        .unwrap();
+
+        assert_eq!(payload.control_block.leaf_version, leaf_version);
+        assert_ne!(payload.address, tapscript.address);
+        assert_eq!(
+            script_pubkey_from_control_block(&secp, &payload.control_block, &redeem_script),
+            payload.address.script_pubkey()
+        );
+
+        let (script_pubkey, control_block, _) =
+            build_p2tr_commit_output_with_leaf_version(&redeem_script, x_public_key, leaf_version)
+                .unwrap();
+        assert_eq!(script_pubkey, payload.address.script_pubkey());
+        assert_eq!(control_block, payload.control_block);
+    }
+
+    fn script_pubkey_output_key(script_pubkey: &ScriptBuf) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_should_derive_reveal_destination() {
+        let secp = Secp256k1::new();
+        let private_key = test_private_key();
+        let signer = LocalSigner::new(private_key);
+        let derivation_path = DerivationPath::default();
+
+        let address = derive_reveal_destination(&signer, &derivation_path, Network::Testnet)
+            .await
+            .unwrap();
+
+        let x_public_key = signer.schnorr_public_key(&derivation_path).await.unwrap();
+        assert_eq!(
+            address,
+            Address::p2tr(&secp, x_public_key, None, Network::Testnet)
+        );
+        assert!(address.to_string().starts_with("tb1p"));
+    }
+
+    #[tokio::test]
+    async fn test_should_derive_bip86_addresses() {
+        // <https://github.com/bitcoin/bips/blob/master/bip-0086.mediawiki#test-vectors>
+        let mnemonic = bip39::Mnemonic::from_str(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let master_key = Xpriv::new_master(Network::Bitcoin, &mnemonic.to_seed("")).unwrap();
+        let signer = LocalSigner::from_xpriv(master_key);
+
+        for (change, index, address) in [
+            (
+                false,
+                0,
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            ),
+            (
+                false,
+                1,
+                "bc1p4qhjn9zdvkux4e44uhx8tc55attvtyu358kutcqkudyccelu0was9fqzwh",
+            ),
+            (
+                true,
+                0,
+                "bc1p3qkhfews2uk44qtvauqyr2ttdsw7svhkl9nkm9s9c3x4ax5h60wqwruhk7",
+            ),
+        ] {
+            let derivation_path =
+                bip86_derivation_path(Network::Bitcoin, 0, change, index).unwrap();
+            let derived = p2tr_bip86_address(&signer, &derivation_path, Network::Bitcoin)
+                .await
+                .unwrap();
+            assert_eq!(derived.to_string(), address);
+        }
+
+        assert_eq!(
+            bip86_derivation_path(Network::Testnet, 1, false, 7)
+                .unwrap()
+                .to_string(),
+            "m/86'/1'/1'/0/7"
+        );
+        assert!(bip86_derivation_path(Network::Bitcoin, 1 << 31, false, 0).is_err());
+    }
 }
@@ -0,0 +1,239 @@
+use bitcoin::absolute::LockTime;
+use bitcoin::opcodes::all::{OP_CLTV, OP_CSV, OP_PUSHNUM_1, OP_PUSHNUM_16};
+use bitcoin::script::{read_scriptint, Instruction};
+use bitcoin::{Script, Sequence};
+
+use crate::{OrdError, OrdResult};
+
+/// Mask of the bits of a sequence number used by relative lock times (BIP 68).
+const SEQUENCE_LOCK_MASK: u32 = 0x0040_ffff;
+
+/// Lock time of the reveal transaction and sequence of its input.
+///
+/// Redeem scripts containing `OP_CHECKLOCKTIMEVERIFY` or `OP_CHECKSEQUENCEVERIFY` can only be
+/// spent by reveal transactions satisfying them, e.g. to schedule the release of an inscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevealTimelock {
+    /// Lock time of the reveal transaction
+    pub lock_time: LockTime,
+    /// Sequence of the reveal transaction input
+    pub sequence: Sequence,
+}
+
+impl Default for RevealTimelock {
+    fn default() -> Self {
+        Self {
+            lock_time: LockTime::ZERO,
+            sequence: Sequence::MAX,
+        }
+    }
+}
+
+impl RevealTimelock {
+    /// Returns the lowest lock time and sequence satisfying the timelocks of the redeem script.
+    ///
+    /// Scripts without timelocks get a zero lock time and a final sequence.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::Timelock`] if the timelocks of the script can't be satisfied together,
+    /// e.g. when mixing block height and time based lock times.
+    pub fn for_script(script: &Script) -> OrdResult<Self> {
+        let mut timelock = Self::default();
+
+        for (operand, op) in script_timelocks(script)? {
+            if op == OP_CLTV {
+                let lock_time = LockTime::from_consensus(operand);
+                if !lock_time.is_implied_by(timelock.lock_time) {
+                    timelock.lock_time = lock_time;
+                }
+                if !timelock.sequence.enables_absolute_lock_time() {
+                    timelock.sequence = Sequence::ENABLE_LOCKTIME_NO_RBF;
+                }
+            } else {
+                let Some(required) = Sequence::from_consensus(operand).to_relative_lock_time()
+                else {
+                    continue;
+                };
+                let satisfied = timelock
+                    .sequence
+                    .to_relative_lock_time()
+                    .is_some_and(|current| required.is_implied_by(current));
+                if !satisfied {
+                    timelock.sequence = Sequence::from_consensus(operand & SEQUENCE_LOCK_MASK);
+                }
+            }
+        }
+
+        timelock.verify(script)?;
+
+        Ok(timelock)
+    }
+
+    /// Verifies that the lock time and sequence satisfy the timelocks of the redeem script.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::Timelock`] describing the first timelock which is not satisfied.
+    pub fn verify(&self, script: &Script) -> OrdResult<()> {
+        for (operand, op) in script_timelocks(script)? {
+            if op == OP_CLTV {
+                let required = LockTime::from_consensus(operand);
+                if !self.sequence.enables_absolute_lock_time() {
+                    return Err(OrdError::Timelock(
+                        "a final input sequence disables the lock time".to_string(),
+                    ));
+                }
+                if !required.is_implied_by(self.lock_time) {
+                    return Err(OrdError::Timelock(format!(
+                        "lock time {} doesn't satisfy OP_CHECKLOCKTIMEVERIFY {required}",
+                        self.lock_time
+                    )));
+                }
+            } else {
+                let sequence = Sequence::from_consensus(operand);
+                let Some(required) = sequence.to_relative_lock_time() else {
+                    // the disable flag makes OP_CHECKSEQUENCEVERIFY behave as a NOP
+                    continue;
+                };
+                let satisfied = self
+                    .sequence
+                    .to_relative_lock_time()
+                    .is_some_and(|lock_time| required.is_implied_by(lock_time));
+                if !satisfied {
+                    return Err(OrdError::Timelock(format!(
+                        "sequence {} doesn't satisfy OP_CHECKSEQUENCEVERIFY {required}",
+                        self.sequence
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects the operands of the `OP_CHECKLOCKTIMEVERIFY` and `OP_CHECKSEQUENCEVERIFY` opcodes
+/// of the script.
+fn script_timelocks(script: &Script) -> OrdResult<Vec<(u32, bitcoin::opcodes::Opcode)>> {
+    let mut timelocks = Vec::new();
+    let mut operand = None;
+
+    for instruction in script.instructions() {
+        match instruction? {
+            Instruction::Op(op) if op == OP_CLTV || op == OP_CSV => {
+                let operand = operand
+                    .ok_or_else(|| OrdError::Timelock(format!("missing operand of {op}")))?;
+                let operand = u32::try_from(operand).map_err(|_| {
+                    OrdError::Timelock(format!("invalid operand {operand} of {op}"))
+                })?;
+                timelocks.push((operand, op));
+            }
+            Instruction::Op(op)
+                if (OP_PUSHNUM_1.to_u8()..=OP_PUSHNUM_16.to_u8()).contains(&op.to_u8()) =>
+            {
+                operand = Some(i64::from(op.to_u8() - OP_PUSHNUM_1.to_u8() + 1));
+            }
+            Instruction::PushBytes(bytes) => {
+                operand = read_scriptint(bytes.as_bytes()).ok();
+            }
+            Instruction::Op(_) => operand = None,
+        }
+    }
+
+    Ok(timelocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::opcodes::all::{OP_CHECKSIG, OP_DROP};
+    use bitcoin::script::Builder as ScriptBuilder;
+
+    use super::*;
+
+    fn timelocked_script(operand: i64, op: bitcoin::opcodes::Opcode) -> bitcoin::ScriptBuf {
+        ScriptBuilder::new()
+            .push_int(operand)
+            .push_opcode(op)
+            .push_opcode(OP_DROP)
+            .push_slice([0x02; 33])
+            .push_opcode(OP_CHECKSIG)
+            .into_script()
+    }
+
+    #[test]
+    fn test_should_not_lock_script_without_timelocks() {
+        let script = ScriptBuilder::new()
+            .push_slice([0x02; 33])
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+
+        let timelock = RevealTimelock::for_script(&script).unwrap();
+        assert_eq!(timelock, RevealTimelock::default());
+        assert!(timelock.verify(&script).is_ok());
+    }
+
+    #[test]
+    fn test_should_satisfy_cltv() {
+        let script = timelocked_script(840_000, OP_CLTV);
+
+        let timelock = RevealTimelock::for_script(&script).unwrap();
+        assert_eq!(timelock.lock_time, LockTime::from_consensus(840_000));
+        assert!(timelock.sequence.enables_absolute_lock_time());
+
+        let too_early = RevealTimelock {
+            lock_time: LockTime::from_consensus(839_999),
+            ..timelock
+        };
+        assert!(matches!(
+            too_early.verify(&script),
+            Err(OrdError::Timelock(_))
+        ));
+
+        let final_sequence = RevealTimelock {
+            sequence: Sequence::MAX,
+            ..timelock
+        };
+        assert!(final_sequence.verify(&script).is_err());
+
+        // a timestamp doesn't satisfy a block height
+        let timestamp = RevealTimelock {
+            lock_time: LockTime::from_consensus(1_700_000_000),
+            ..timelock
+        };
+        assert!(timestamp.verify(&script).is_err());
+    }
+
+    #[test]
+    fn test_should_satisfy_csv() {
+        let script = timelocked_script(6, OP_CSV);
+
+        let timelock = RevealTimelock::for_script(&script).unwrap();
+        assert_eq!(timelock.lock_time, LockTime::ZERO);
+        assert_eq!(timelock.sequence, Sequence::from_height(6));
+
+        let too_early = RevealTimelock {
+            sequence: Sequence::from_height(5),
+            ..timelock
+        };
+        assert!(too_early.verify(&script).is_err());
+        assert!(RevealTimelock::default().verify(&script).is_err());
+    }
+
+    #[test]
+    fn test_should_reject_mixed_lock_time_units() {
+        let script = ScriptBuilder::new()
+            .push_int(840_000)
+            .push_opcode(OP_CLTV)
+            .push_opcode(OP_DROP)
+            .push_int(1_700_000_000)
+            .push_opcode(OP_CLTV)
+            .push_opcode(OP_DROP)
+            .into_script();
+
+        assert!(matches!(
+            RevealTimelock::for_script(&script),
+            Err(OrdError::Timelock(_))
+        ));
+    }
+}
@@ -0,0 +1,411 @@
+use std::borrow::Borrow;
+
+use bitcoin::opcodes::all::{OP_CHECKSIG, OP_CLTV, OP_CSV, OP_DROP};
+use bitcoin::script::Instruction;
+use bitcoin::secp256k1::{All, Message, Secp256k1};
+use bitcoin::sighash::{Annex, Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{ControlBlock, TAPROOT_ANNEX_PREFIX};
+use bitcoin::{
+    PublicKey, Script, ScriptBuf, TapLeafHash, TapSighash, Transaction, TxOut, XOnlyPublicKey,
+};
+
+use crate::utils::constants::ANCHOR_SCRIPT_PUBKEY;
+use crate::{OrdError, OrdResult};
+
+/// Code separator position committed to by script spends without `OP_CODESEPARATOR`.
+const NO_CODE_SEPARATOR: u32 = u32::MAX;
+
+/// Verifies the witnesses of all the inputs of a signed transaction, given the outputs they
+/// spend, so invalid signatures, e.g. made with the wrong key or sighash, are caught before
+/// the transaction is broadcast.
+///
+/// Only the spends produced by this crate can be verified: P2WPKH, P2SH-P2WPKH, P2TR key path,
+/// and P2WSH, P2SH-P2WSH or P2TR script path spends of scripts starting with
+/// `<pubkey> OP_CHECKSIG`, such as the inscription redeem scripts, optionally after
+/// `<n> OP_CHECKLOCKTIMEVERIFY OP_DROP` or `<n> OP_CHECKSEQUENCEVERIFY OP_DROP` timelocks, and
+/// pay-to-anchor spends with an empty witness.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidInputs`] if the number of prevouts doesn't match the inputs, or
+/// [`OrdError::InvalidWitness`] if the witness of an input is invalid or can't be verified.
+pub fn verify_transaction(transaction: &Transaction, prevouts: &[TxOut]) -> OrdResult<()> {
+    if transaction.input.len() != prevouts.len() {
+        return Err(OrdError::InvalidInputs);
+    }
+
+    let secp = Secp256k1::new();
+    let mut cache = SighashCache::new(transaction);
+    (0..prevouts.len())
+        .try_for_each(|index| verify_input(&secp, &mut cache, index, &Prevouts::All(prevouts)))
+}
+
+/// Verifies the witness of the input at `index`.
+///
+/// Taproot spends require all the prevouts unless signed with `SIGHASH_ANYONECANPAY`.
+pub(crate) fn verify_input<T: Borrow<TxOut>, R: Borrow<Transaction>>(
+    secp: &Secp256k1<All>,
+    cache: &mut SighashCache<R>,
+    index: usize,
+    prevouts: &Prevouts<T>,
+) -> OrdResult<()> {
+    let invalid = |reason| OrdError::InvalidWitness { index, reason };
+
+    let prevout = match prevouts {
+        Prevouts::All(prevouts) => prevouts.get(index).map(Borrow::borrow),
+        Prevouts::One(i, prevout) if *i == index => Some(prevout.borrow()),
+        Prevouts::One(..) => None,
+    }
+    .ok_or(OrdError::InputNotFound(index))?;
+    let txin = cache
+        .transaction()
+        .input
+        .get(index)
+        .ok_or(OrdError::InputNotFound(index))?;
+    let witness = txin.witness.to_vec();
+    let script_sig = txin.script_sig.clone();
+    let script_pubkey = &prevout.script_pubkey;
+
+    if script_pubkey.is_p2wpkh() {
+        verify_p2wpkh(secp, cache, index, &witness, script_pubkey, prevout)
+    } else if script_pubkey.is_p2sh() {
+        let mut instructions = script_sig.instructions();
+        let redeem_script = match (instructions.next(), instructions.next()) {
+            (Some(Ok(Instruction::PushBytes(redeem_script))), None) => {
+                ScriptBuf::from_bytes(redeem_script.as_bytes().to_vec())
+            }
+            _ => return Err(invalid("P2SH script sig must push the redeem script")),
+        };
+        if &ScriptBuf::new_p2sh(&redeem_script.script_hash()) != script_pubkey {
+            return Err(invalid("redeem script doesn't match the script pubkey"));
+        }
+        if redeem_script.is_p2wpkh() {
+            verify_p2wpkh(secp, cache, index, &witness, &redeem_script, prevout)
+        } else if redeem_script.is_p2wsh() {
+            verify_p2wsh(secp, cache, index, &witness, &redeem_script, prevout)
+        } else {
+            Err(invalid("unsupported redeem script"))
+        }
+    } else if script_pubkey.is_p2wsh() {
+        verify_p2wsh(secp, cache, index, &witness, script_pubkey, prevout)
+    } else if script_pubkey.is_p2tr() {
+        let output_key = XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..])?;
+
+        // the last element of a taproot witness with at least two elements is the annex if it
+        // starts with 0x50, see BIP 341
+        let (witness, annex) = match witness.split_last() {
+            Some((annex, rest))
+                if !rest.is_empty() && annex.first() == Some(&TAPROOT_ANNEX_PREFIX) =>
+            {
+                (rest, Some(annex.as_slice()))
+            }
+            _ => (witness.as_slice(), None),
+        };
+
+        match witness {
+            [signature] => {
+                let signature = bitcoin::taproot::Signature::from_slice(signature)
+                    .map_err(|_| invalid("malformed schnorr signature"))?;
+                let sighash = cache.taproot_signature_hash(
+                    index,
+                    prevouts,
+                    annex.map(parse_annex).transpose()?,
+                    None,
+                    signature.hash_ty,
+                )?;
+                secp.verify_schnorr(&signature.sig, &Message::from(sighash), &output_key)
+                    .map_err(|_| invalid("invalid signature"))
+            }
+            [signature, leaf_script, control_block] => {
+                let leaf_script = Script::from_bytes(leaf_script);
+                let control_block = ControlBlock::decode(control_block)
+                    .map_err(|_| invalid("malformed control block"))?;
+                if !control_block.verify_taproot_commitment(secp, output_key, leaf_script) {
+                    return Err(invalid("control block doesn't commit to the leaf script"));
+                }
+                let public_key = checksig_key(leaf_script)
+                    .and_then(|key| XOnlyPublicKey::from_slice(key).ok())
+                    .ok_or(invalid("unsupported leaf script"))?;
+
+                let signature = bitcoin::taproot::Signature::from_slice(signature)
+                    .map_err(|_| invalid("malformed schnorr signature"))?;
+                let sighash = taproot_script_spend_sighash(
+                    cache,
+                    index,
+                    prevouts,
+                    TapLeafHash::from_script(leaf_script, control_block.leaf_version),
+                    annex,
+                    signature.hash_ty,
+                )?;
+                secp.verify_schnorr(&signature.sig, &Message::from(sighash), &public_key)
+                    .map_err(|_| invalid("invalid signature"))
+            }
+            _ => Err(invalid("unsupported taproot witness")),
+        }
+    } else if script_pubkey.as_bytes() == ANCHOR_SCRIPT_PUBKEY {
+        if witness.is_empty() && script_sig.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid("pay-to-anchor spends must have an empty witness"))
+        }
+    } else {
+        Err(invalid("unsupported script pubkey"))
+    }
+}
+
+/// Parses a taproot annex.
+///
+/// # Errors
+///
+/// Returns [`OrdError::InvalidAnnex`] if the annex doesn't start with `0x50`.
+pub(crate) fn parse_annex(annex: &[u8]) -> OrdResult<Annex<'_>> {
+    Annex::new(annex).map_err(|_| OrdError::InvalidAnnex("the annex must start with 0x50"))
+}
+
+/// Returns the sighash of the script path spend of the leaf by the input at `index`, committing
+/// to the annex of its witness, if any.
+pub(crate) fn taproot_script_spend_sighash<T: Borrow<TxOut>, R: Borrow<Transaction>>(
+    cache: &mut SighashCache<R>,
+    index: usize,
+    prevouts: &Prevouts<T>,
+    leaf_hash: TapLeafHash,
+    annex: Option<&[u8]>,
+    sighash_type: TapSighashType,
+) -> OrdResult<TapSighash> {
+    let annex = annex.map(parse_annex).transpose()?;
+    Ok(cache.taproot_signature_hash(
+        index,
+        prevouts,
+        annex,
+        Some((leaf_hash, NO_CODE_SEPARATOR)),
+        sighash_type,
+    )?)
+}
+
+/// Verifies the witness of a P2WPKH spend of the given program, either the script pubkey of the
+/// prevout or the redeem script of a nested segwit output.
+fn verify_p2wpkh<R: Borrow<Transaction>>(
+    secp: &Secp256k1<All>,
+    cache: &mut SighashCache<R>,
+    index: usize,
+    witness: &[Vec<u8>],
+    program: &Script,
+    prevout: &TxOut,
+) -> OrdResult<()> {
+    let invalid = |reason| OrdError::InvalidWitness { index, reason };
+
+    let [signature, public_key] = witness else {
+        return Err(invalid(
+            "P2WPKH witness must hold a signature and a public key",
+        ));
+    };
+    let public_key = PublicKey::from_slice(public_key)?;
+    if public_key
+        .wpubkey_hash()
+        .map(|hash| ScriptBuf::new_p2wpkh(&hash))
+        .as_deref()
+        != Some(program)
+    {
+        return Err(invalid("public key doesn't match the script pubkey"));
+    }
+
+    let signature = bitcoin::ecdsa::Signature::from_slice(signature)
+        .map_err(|_| invalid("malformed ECDSA signature"))?;
+    let sighash = cache.p2wpkh_signature_hash(index, program, prevout.value, signature.hash_ty)?;
+    secp.verify_ecdsa(&Message::from(sighash), &signature.sig, &public_key.inner)
+        .map_err(|_| invalid("invalid signature"))
+}
+
+/// Verifies the witness of a P2WSH spend of the given program, either the script pubkey of the
+/// prevout or the redeem script of a nested segwit output.
+fn verify_p2wsh<R: Borrow<Transaction>>(
+    secp: &Secp256k1<All>,
+    cache: &mut SighashCache<R>,
+    index: usize,
+    witness: &[Vec<u8>],
+    program: &Script,
+    prevout: &TxOut,
+) -> OrdResult<()> {
+    let invalid = |reason| OrdError::InvalidWitness { index, reason };
+
+    let [signature, witness_script] = witness else {
+        return Err(invalid(
+            "P2WSH witness must hold a signature and the witness script",
+        ));
+    };
+    let witness_script = Script::from_bytes(witness_script);
+    if ScriptBuf::new_p2wsh(&witness_script.wscript_hash()).as_script() != program {
+        return Err(invalid("witness script doesn't match the script pubkey"));
+    }
+    let public_key = checksig_key(witness_script)
+        .and_then(|key| PublicKey::from_slice(key).ok())
+        .ok_or(invalid("unsupported witness script"))?;
+
+    let signature = bitcoin::ecdsa::Signature::from_slice(signature)
+        .map_err(|_| invalid("malformed ECDSA signature"))?;
+    let sighash =
+        cache.p2wsh_signature_hash(index, witness_script, prevout.value, signature.hash_ty)?;
+    secp.verify_ecdsa(&Message::from(sighash), &signature.sig, &public_key.inner)
+        .map_err(|_| invalid("invalid signature"))
+}
+
+/// Returns the public key checked by a script starting with `<pubkey> OP_CHECKSIG`, after any
+/// `<n> OP_CHECKLOCKTIMEVERIFY OP_DROP` or `<n> OP_CHECKSEQUENCEVERIFY OP_DROP` timelocks.
+fn checksig_key(script: &Script) -> Option<&[u8]> {
+    let mut instructions = script.instructions();
+    loop {
+        match (instructions.next()?.ok()?, instructions.next()?.ok()?) {
+            (Instruction::PushBytes(key), Instruction::Op(OP_CHECKSIG)) => {
+                return Some(key.as_bytes())
+            }
+            (operand, Instruction::Op(op))
+                if (op == OP_CLTV || op == OP_CSV) && operand.script_num().is_some() =>
+            {
+                if instructions.next()?.ok()? != Instruction::Op(OP_DROP) {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::absolute::LockTime;
+    use bitcoin::bip32::DerivationPath;
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::key::TweakedPublicKey;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Address, Amount, Network, OutPoint, PrivateKey, Sequence, TxIn, Txid, Witness};
+
+    use super::*;
+    use crate::wallet::builder::signer::{LocalSigner, Wallet};
+    use crate::wallet::builder::TxInputInfo;
+
+    const WIF: &str = "L5EZftvrYaSudiozVRzTqLcHLNDoVn7H5HSfM9BAN6tMJX8oTWz6";
+
+    fn unsigned_transaction(inputs: usize) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: (0..inputs)
+                .map(|vout| TxIn {
+                    previous_output: OutPoint::new(Txid::all_zeros(), vout as u32),
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::MAX,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: vec![TxOut {
+                value: Amount::from_sat(1_000),
+                script_pubkey: ScriptBuf::new_op_return([0]),
+            }],
+        }
+    }
+
+    async fn signed_transaction() -> (Transaction, Vec<TxOut>) {
+        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let wallet = Wallet::new_with_signer(LocalSigner::new(private_key));
+        let derivation_path = DerivationPath::default();
+        let public_key = wallet.ecdsa_public_key(&derivation_path).await.unwrap();
+        let x_public_key = wallet.schnorr_public_key(&derivation_path).await.unwrap();
+
+        let prevouts = vec![
+            TxOut {
+                value: Amount::from_sat(2_000),
+                script_pubkey: Address::p2wpkh(&public_key, Network::Bitcoin)
+                    .unwrap()
+                    .script_pubkey(),
+            },
+            TxOut {
+                value: Amount::from_sat(3_000),
+                // the signer signs key path spends with the key as given
+                script_pubkey: ScriptBuf::new_p2tr_tweaked(
+                    TweakedPublicKey::dangerous_assume_tweaked(x_public_key),
+                ),
+            },
+        ];
+        let inputs = prevouts
+            .iter()
+            .map(|tx_out| TxInputInfo {
+                outpoint: OutPoint::null(),
+                tx_out: tx_out.clone(),
+                derivation_path: derivation_path.clone(),
+                key_spend: Default::default(),
+                rune_balances: None,
+            })
+            .collect::<Vec<_>>();
+
+        let transaction = wallet
+            .sign_transaction(&unsigned_transaction(2), &inputs)
+            .await
+            .unwrap();
+        (transaction, prevouts)
+    }
+
+    #[tokio::test]
+    async fn test_should_verify_signed_transaction() {
+        let (transaction, prevouts) = signed_transaction().await;
+        verify_transaction(&transaction, &prevouts).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_should_reject_invalid_signatures() {
+        let (transaction, mut prevouts) = signed_transaction().await;
+
+        // the signatures commit to the spent amounts
+        prevouts[0].value = Amount::from_sat(2_001);
+        assert!(matches!(
+            verify_transaction(&transaction, &prevouts),
+            Err(OrdError::InvalidWitness { index: 0, .. })
+        ));
+        prevouts[0].value = Amount::from_sat(2_000);
+
+        let mut tampered = transaction.clone();
+        tampered.output[0].value = Amount::from_sat(999);
+        assert!(matches!(
+            verify_transaction(&tampered, &prevouts),
+            Err(OrdError::InvalidWitness { index: 0, .. })
+        ));
+
+        let mut unsigned = transaction;
+        unsigned.input[1].witness = Witness::new();
+        assert!(matches!(
+            verify_transaction(&unsigned, &prevouts),
+            Err(OrdError::InvalidWitness { index: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_should_reject_signatures_of_the_wrong_key() {
+        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let other_key = PrivateKey::from_slice(&[1; 32], Network::Bitcoin).unwrap();
+        let secp = Secp256k1::new();
+
+        let inputs = [TxInputInfo {
+            outpoint: OutPoint::null(),
+            tx_out: TxOut {
+                value: Amount::from_sat(2_000),
+                script_pubkey: Address::p2wpkh(&other_key.public_key(&secp), Network::Bitcoin)
+                    .unwrap()
+                    .script_pubkey(),
+            },
+            derivation_path: DerivationPath::default(),
+            key_spend: Default::default(),
+            rune_balances: None,
+        }];
+        let transaction = unsigned_transaction(1);
+
+        let wallet = Wallet::new_with_signer(LocalSigner::new(private_key));
+        assert!(wallet.sign_transaction(&transaction, &inputs).await.is_ok());
+
+        let wallet = wallet.with_signature_verification(true);
+        let result = wallet.sign_transaction(&transaction, &inputs).await;
+        assert!(matches!(
+            result,
+            Err(OrdError::InputSigning(crate::InputSigningError { index: 0, source, .. }))
+                if matches!(*source, OrdError::InvalidWitness { index: 0, .. })
+        ));
+    }
+}
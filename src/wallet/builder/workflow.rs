@@ -0,0 +1,474 @@
+use std::fmt;
+
+use bitcoin::{Transaction, Txid};
+
+use super::{CreateCommitTransaction, InscriptionReceipt};
+use crate::provider::{BlockRef, ChainProvider, ConfirmationStatus, ConfirmationWatch};
+use crate::{OrdError, OrdResult};
+
+/// Stage of the lifecycle of an inscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InscriptionStage {
+    /// The commit transaction is built
+    Created,
+    /// The commit transaction is signed
+    CommitSigned,
+    /// The commit transaction is broadcast, waiting for confirmations
+    CommitBroadcast,
+    /// The commit transaction has the required confirmations
+    CommitConfirmed,
+    /// The reveal transaction is signed
+    RevealSigned,
+    /// The reveal transaction is broadcast
+    RevealBroadcast,
+    /// The inscription is revealed
+    Complete,
+    /// The workflow failed, see [`InscriptionWorkflow::failure`]
+    Failed,
+}
+
+impl fmt::Display for InscriptionStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stage = match self {
+            Self::Created => "created",
+            Self::CommitSigned => "commit signed",
+            Self::CommitBroadcast => "commit broadcast",
+            Self::CommitConfirmed => "commit confirmed",
+            Self::RevealSigned => "reveal signed",
+            Self::RevealBroadcast => "reveal broadcast",
+            Self::Complete => "complete",
+            Self::Failed => "failed",
+        };
+        f.write_str(stage)
+    }
+}
+
+/// Failure of an inscription workflow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowFailure {
+    /// Stage the workflow failed at, which is resumed by [`InscriptionWorkflow::retry`]
+    pub stage: InscriptionStage,
+    /// Reason of the failure
+    pub reason: String,
+}
+
+/// State machine of the commit and reveal lifecycle of an inscription.
+///
+/// Each transition checks the current stage and records the artifacts of the new one, so the
+/// workflow can be serialized as a snapshot after every step and resumed from it, e.g. after a
+/// service restart or in the next call to a canister.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InscriptionWorkflow {
+    /// Current stage
+    pub stage: InscriptionStage,
+    /// The commit transaction, as returned by the builder
+    pub commit: CreateCommitTransaction,
+    /// The signed commit transaction
+    pub signed_commit_tx: Option<Transaction>,
+    /// Confirmations of the broadcast commit transaction
+    pub commit_confirmation: Option<ConfirmationWatch>,
+    /// The signed reveal transaction
+    pub reveal_tx: Option<Transaction>,
+    /// Receipt of the completed inscription
+    pub receipt: Option<InscriptionReceipt>,
+    /// Failure of the workflow, if it's failed
+    pub failure: Option<WorkflowFailure>,
+}
+
+impl InscriptionWorkflow {
+    /// Starts the workflow of a built commit transaction.
+    pub fn new(commit: CreateCommitTransaction) -> Self {
+        Self {
+            stage: InscriptionStage::Created,
+            commit,
+            signed_commit_tx: None,
+            commit_confirmation: None,
+            reveal_tx: None,
+            receipt: None,
+            failure: None,
+        }
+    }
+
+    /// Records the signed commit transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the transaction is not the built commit transaction.
+    pub fn commit_signed(&mut self, signed_commit_tx: Transaction) -> OrdResult<()> {
+        self.transition(InscriptionStage::Created, InscriptionStage::CommitSigned)?;
+        self.commit.txid_after_signing(&signed_commit_tx)?;
+
+        self.signed_commit_tx = Some(signed_commit_tx);
+        self.stage = InscriptionStage::CommitSigned;
+        Ok(())
+    }
+
+    /// Records the broadcast of the commit transaction, which must get the given number of
+    /// confirmations before revealing.
+    pub fn commit_broadcast(&mut self, required_confirmations: u32) -> OrdResult<()> {
+        self.transition(
+            InscriptionStage::CommitSigned,
+            InscriptionStage::CommitBroadcast,
+        )?;
+
+        self.commit_confirmation = Some(ConfirmationWatch::new(
+            self.commit_txid(),
+            required_confirmations,
+        ));
+        self.stage = InscriptionStage::CommitBroadcast;
+        Ok(())
+    }
+
+    /// Polls the confirmations of the commit transaction, moving to
+    /// [`InscriptionStage::CommitConfirmed`] once it has the required ones.
+    ///
+    /// Reorgs are tracked by the [`ConfirmationWatch`] of the commit transaction.
+    pub async fn poll_commit<P>(&mut self, provider: &P) -> OrdResult<ConfirmationStatus>
+    where
+        P: ChainProvider + ?Sized,
+    {
+        self.transition(
+            InscriptionStage::CommitBroadcast,
+            InscriptionStage::CommitConfirmed,
+        )?;
+        let watch = self
+            .commit_confirmation
+            .as_mut()
+            .ok_or_else(|| missing_artifact("commit confirmation watch"))?;
+
+        let status = watch.poll(provider).await?;
+        if watch.required == 0 || matches!(status, ConfirmationStatus::Confirmed { .. }) {
+            self.stage = InscriptionStage::CommitConfirmed;
+        }
+        Ok(status)
+    }
+
+    /// Returns the block including the commit transaction, if it's confirmed.
+    pub fn commit_block(&self) -> Option<BlockRef> {
+        self.commit_confirmation.as_ref()?.confirmed_in
+    }
+
+    /// Records the signed reveal transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the transaction doesn't spend the commit output.
+    pub fn reveal_signed(&mut self, reveal_tx: Transaction) -> OrdResult<()> {
+        self.transition(
+            InscriptionStage::CommitConfirmed,
+            InscriptionStage::RevealSigned,
+        )?;
+        self.record_reveal(reveal_tx)
+    }
+
+    /// Records the reveal transaction signed before the commit transaction is broadcast, so the
+    /// pair can be broadcast together, e.g. with [`crate::provider::submit_package`].
+    ///
+    /// The workflow moves on to [`InscriptionStage::RevealSigned`] without waiting for the
+    /// confirmations of the commit transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidInputs`] if the transaction doesn't spend the commit output.
+    pub fn package_signed(&mut self, reveal_tx: Transaction) -> OrdResult<()> {
+        self.transition(
+            InscriptionStage::CommitSigned,
+            InscriptionStage::RevealSigned,
+        )?;
+        self.record_reveal(reveal_tx)
+    }
+
+    /// Returns the txid of the commit transaction, taken from the signed commit transaction
+    /// once recorded, since signing P2SH wrapped inputs changes it.
+    pub fn commit_txid(&self) -> Txid {
+        match &self.signed_commit_tx {
+            Some(signed_commit_tx) => signed_commit_tx.txid(),
+            None => self.commit.txid(),
+        }
+    }
+
+    /// Records the broadcast of the reveal transaction.
+    pub fn reveal_broadcast(&mut self) -> OrdResult<()> {
+        self.transition(
+            InscriptionStage::RevealSigned,
+            InscriptionStage::RevealBroadcast,
+        )?;
+
+        self.stage = InscriptionStage::RevealBroadcast;
+        Ok(())
+    }
+
+    /// Completes the workflow, returning the receipt of the inscription.
+    pub fn complete(&mut self) -> OrdResult<&InscriptionReceipt> {
+        self.transition(
+            InscriptionStage::RevealBroadcast,
+            InscriptionStage::Complete,
+        )?;
+        let signed_commit_tx = self
+            .signed_commit_tx
+            .as_ref()
+            .ok_or_else(|| missing_artifact("signed commit transaction"))?;
+        let reveal_tx = self
+            .reveal_tx
+            .as_ref()
+            .ok_or_else(|| missing_artifact("reveal transaction"))?;
+
+        let receipt = InscriptionReceipt::new(&self.commit, signed_commit_tx, reveal_tx)?;
+        self.stage = InscriptionStage::Complete;
+        Ok(self.receipt.insert(receipt))
+    }
+
+    /// Marks the workflow as failed at the current stage.
+    pub fn fail(&mut self, reason: impl Into<String>) -> OrdResult<()> {
+        if matches!(
+            self.stage,
+            InscriptionStage::Complete | InscriptionStage::Failed
+        ) {
+            return Err(OrdError::InvalidTransition {
+                from: self.stage,
+                to: InscriptionStage::Failed,
+            });
+        }
+
+        self.failure = Some(WorkflowFailure {
+            stage: self.stage,
+            reason: reason.into(),
+        });
+        self.stage = InscriptionStage::Failed;
+        Ok(())
+    }
+
+    /// Resumes a failed workflow at the stage it failed at, so the failed step can be retried.
+    pub fn retry(&mut self) -> OrdResult<()> {
+        let Some(failure) = self.failure.take() else {
+            return Err(OrdError::InvalidTransition {
+                from: self.stage,
+                to: self.stage,
+            });
+        };
+
+        self.stage = failure.stage;
+        Ok(())
+    }
+
+    /// Returns whether the workflow is complete or failed.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.stage,
+            InscriptionStage::Complete | InscriptionStage::Failed
+        )
+    }
+
+    fn record_reveal(&mut self, reveal_tx: Transaction) -> OrdResult<()> {
+        let commit_txid = self.commit_txid();
+        let spends_commit = reveal_tx.input.first().is_some_and(|input| {
+            input.previous_output.txid == commit_txid && input.previous_output.vout == 0
+        });
+        if !spends_commit {
+            return Err(OrdError::InvalidInputs);
+        }
+
+        self.reveal_tx = Some(reveal_tx);
+        self.stage = InscriptionStage::RevealSigned;
+        Ok(())
+    }
+
+    fn transition(&self, from: InscriptionStage, to: InscriptionStage) -> OrdResult<()> {
+        if self.stage != from {
+            return Err(OrdError::InvalidTransition {
+                from: self.stage,
+                to,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Error of a snapshot missing an artifact of its stage, e.g. if it was edited.
+fn missing_artifact(artifact: &str) -> OrdError {
+    OrdError::Custom(format!("inscription workflow is missing the {artifact}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::{Address, Amount, BlockHash, FeeRate, Network, Txid};
+
+    use super::*;
+    use crate::provider::TxStatus;
+    use crate::utils::test_utils::{test_private_key, test_utxo};
+    use crate::wallet::{
+        CreateCommitTransactionArgs, OrdTransactionBuilder, RevealTransactionArgs,
+        SignCommitTransactionArgs, Utxo,
+    };
+    use crate::Brc20;
+
+    struct ConfirmedProvider;
+
+    #[async_trait::async_trait]
+    impl ChainProvider for ConfirmedProvider {
+        async fn tip(&self) -> OrdResult<BlockRef> {
+            Ok(block(102))
+        }
+
+        async fn transaction_status(&self, _txid: &Txid) -> OrdResult<TxStatus> {
+            Ok(TxStatus::Confirmed(block(101)))
+        }
+
+        async fn wait_for_block(&self) -> OrdResult<()> {
+            Ok(())
+        }
+    }
+
+    fn block(height: u64) -> BlockRef {
+        BlockRef {
+            height,
+            hash: BlockHash::all_zeros(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_should_run_inscription_workflow() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
+
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let commit = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    inputs.clone(),
+                    Brc20::transfer("mona", 100),
+                    address.clone(),
+                    address.script_pubkey(),
+                    FeeRate::from_sat_per_vb(1).unwrap(),
+                ),
+            )
+            .await
+            .unwrap();
+        let mut workflow = InscriptionWorkflow::new(commit.clone());
+
+        // transitions must follow the lifecycle
+        assert!(matches!(
+            workflow.reveal_broadcast(),
+            Err(OrdError::InvalidTransition {
+                from: InscriptionStage::Created,
+                to: InscriptionStage::RevealBroadcast,
+            })
+        ));
+
+        let signed_commit_tx = builder
+            .sign_commit_transaction(
+                commit.unsigned_tx.clone(),
+                SignCommitTransactionArgs {
+                    inputs,
+                    txin_script_pubkey: address.script_pubkey(),
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+        workflow.commit_signed(signed_commit_tx.clone()).unwrap();
+        workflow.commit_broadcast(2).unwrap();
+
+        // polling the commit fails, then it's retried from the snapshot
+        workflow.fail("node unreachable").unwrap();
+        assert!(workflow.is_finished());
+        let mut workflow: InscriptionWorkflow =
+            serde_json::from_str(&serde_json::to_string(&workflow).unwrap()).unwrap();
+        workflow.retry().unwrap();
+        assert_eq!(workflow.stage, InscriptionStage::CommitBroadcast);
+
+        workflow.poll_commit(&ConfirmedProvider).await.unwrap();
+        assert_eq!(workflow.stage, InscriptionStage::CommitConfirmed);
+        assert_eq!(workflow.commit_block(), Some(block(101)));
+
+        let reveal_tx = builder
+            .build_reveal_transaction(RevealTransactionArgs::new(
+                Utxo {
+                    id: signed_commit_tx.txid(),
+                    index: 0,
+                    amount: commit.reveal_balance,
+                },
+                address,
+                commit.redeem_script,
+            ))
+            .await
+            .unwrap();
+        workflow.reveal_signed(reveal_tx.clone()).unwrap();
+        workflow.reveal_broadcast().unwrap();
+
+        let receipt = workflow.complete().unwrap();
+        assert_eq!(receipt.commit_txid, signed_commit_tx.txid());
+        assert_eq!(receipt.reveal_txid, reveal_tx.txid());
+        assert_eq!(workflow.stage, InscriptionStage::Complete);
+        assert!(workflow.fail("too late").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_should_prepare_commit_and_reveal_offline() {
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let inputs = vec![test_utxo(1, Amount::from_sat(8_000))];
+
+        let mut builder = OrdTransactionBuilder::p2tr(private_key);
+        let commit = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    inputs.clone(),
+                    Brc20::transfer("mona", 100),
+                    address.clone(),
+                    address.script_pubkey(),
+                    FeeRate::from_sat_per_vb(1).unwrap(),
+                ),
+            )
+            .await
+            .unwrap();
+
+        // the reveal is built on top of the precomputed commit txid
+        let reveal_tx = builder
+            .build_reveal_transaction(RevealTransactionArgs::new(
+                commit.reveal_input(commit.txid()),
+                address.clone(),
+                commit.redeem_script.clone(),
+            ))
+            .await
+            .unwrap();
+
+        let signed_commit_tx = builder
+            .sign_commit_transaction(
+                commit.unsigned_tx.clone(),
+                SignCommitTransactionArgs {
+                    inputs,
+                    txin_script_pubkey: address.script_pubkey(),
+                    derivation_path: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            commit.txid_after_signing(&signed_commit_tx).unwrap(),
+            commit.txid()
+        );
+        assert!(matches!(
+            commit.txid_after_signing(&reveal_tx),
+            Err(OrdError::InvalidInputs)
+        ));
+
+        let mut workflow = InscriptionWorkflow::new(commit);
+        workflow.commit_signed(signed_commit_tx.clone()).unwrap();
+        workflow.package_signed(reveal_tx.clone()).unwrap();
+        workflow.reveal_broadcast().unwrap();
+
+        let receipt = workflow.complete().unwrap();
+        assert_eq!(receipt.commit_txid, signed_commit_tx.txid());
+        assert_eq!(receipt.reveal_txid, reveal_tx.txid());
+    }
+}
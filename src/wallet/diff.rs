@@ -0,0 +1,508 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use bitcoin::absolute::LockTime;
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, OutPoint, ScriptBuf, Sequence, SignedAmount, Transaction, TxOut};
+
+use crate::wallet::ParsedEnvelope;
+use crate::Nft;
+
+/// Semantic differences between two transactions, as returned by [`diff_transactions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionDiff {
+    pub differences: Vec<TransactionDifference>,
+    /// Change of the fee paid by the actual transaction, positive if it pays more than the
+    /// expected one. Only known if both transactions spend the same outputs.
+    pub fee_delta: Option<SignedAmount>,
+}
+
+/// A single difference between the expected and the actual transaction.
+///
+/// Signatures are not compared, so that an unsigned transaction can be checked against its
+/// signed version; the inscriptions of the witnesses are compared instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionDifference {
+    Version {
+        expected: Version,
+        actual: Version,
+    },
+    LockTime {
+        expected: LockTime,
+        actual: LockTime,
+    },
+    /// The actual transaction has an input the expected one doesn't have.
+    InputAdded {
+        index: usize,
+        outpoint: OutPoint,
+    },
+    /// The actual transaction lacks an input of the expected one.
+    InputRemoved {
+        index: usize,
+        outpoint: OutPoint,
+    },
+    /// The input spends a different output.
+    InputChanged {
+        index: usize,
+        expected: OutPoint,
+        actual: OutPoint,
+    },
+    SequenceChanged {
+        index: usize,
+        expected: Sequence,
+        actual: Sequence,
+    },
+    /// The actual transaction has an output the expected one doesn't have.
+    OutputAdded {
+        index: usize,
+        output: TxOut,
+    },
+    /// The actual transaction lacks an output of the expected one.
+    OutputRemoved {
+        index: usize,
+        output: TxOut,
+    },
+    /// The output is sent to a different script pubkey.
+    RecipientChanged {
+        index: usize,
+        expected: ScriptBuf,
+        actual: ScriptBuf,
+    },
+    ValueChanged {
+        index: usize,
+        expected: Amount,
+        actual: Amount,
+    },
+    /// The witness of the input reveals an inscription the expected one doesn't reveal.
+    InscriptionAdded {
+        input: u32,
+        offset: u32,
+        inscription: Box<Nft>,
+    },
+    /// The witness of the input lacks an inscription revealed by the expected one.
+    InscriptionRemoved {
+        input: u32,
+        offset: u32,
+        inscription: Box<Nft>,
+    },
+    /// The inscription revealed by the input has different fields or a different body.
+    InscriptionChanged {
+        input: u32,
+        offset: u32,
+        expected: Box<Nft>,
+        actual: Box<Nft>,
+    },
+}
+
+impl TransactionDiff {
+    /// Returns whether the transactions are semantically equal.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for TransactionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            write!(f, "no differences")?;
+        }
+        for (index, difference) in self.differences.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{difference}")?;
+        }
+        if let Some(fee_delta) = self.fee_delta.filter(|delta| *delta != SignedAmount::ZERO) {
+            write!(f, "\nfee delta: {:+} sat", fee_delta.to_sat())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for TransactionDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Version { expected, actual } => {
+                write!(f, "version changed from {} to {}", expected.0, actual.0)
+            }
+            Self::LockTime { expected, actual } => {
+                write!(f, "lock time changed from {expected} to {actual}")
+            }
+            Self::InputAdded { index, outpoint } => write!(f, "input #{index} {outpoint} added"),
+            Self::InputRemoved { index, outpoint } => {
+                write!(f, "input #{index} {outpoint} removed")
+            }
+            Self::InputChanged {
+                index,
+                expected,
+                actual,
+            } => write!(f, "input #{index} spends {actual} instead of {expected}"),
+            Self::SequenceChanged {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "input #{index} sequence changed from {expected} to {actual}"
+            ),
+            Self::OutputAdded { index, output } => write!(
+                f,
+                "output #{index} of {} sat to {} added",
+                output.value.to_sat(),
+                output.script_pubkey
+            ),
+            Self::OutputRemoved { index, output } => write!(
+                f,
+                "output #{index} of {} sat to {} removed",
+                output.value.to_sat(),
+                output.script_pubkey
+            ),
+            Self::RecipientChanged {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "output #{index} recipient changed from {expected} to {actual}"
+            ),
+            Self::ValueChanged {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "output #{index} value changed from {} to {} sat",
+                expected.to_sat(),
+                actual.to_sat()
+            ),
+            Self::InscriptionAdded {
+                input,
+                offset,
+                inscription,
+            } => write!(
+                f,
+                "inscription {offset} of input #{input} added: {}",
+                describe(inscription)
+            ),
+            Self::InscriptionRemoved {
+                input,
+                offset,
+                inscription,
+            } => write!(
+                f,
+                "inscription {offset} of input #{input} removed: {}",
+                describe(inscription)
+            ),
+            Self::InscriptionChanged {
+                input,
+                offset,
+                expected,
+                actual,
+            } => {
+                write!(f, "inscription {offset} of input #{input} ")?;
+                if expected.body != actual.body {
+                    write!(f, "body altered")?;
+                } else {
+                    write!(f, "fields altered")?;
+                }
+                write!(f, ": {} -> {}", describe(expected), describe(actual))
+            }
+        }
+    }
+}
+
+/// Compares two transactions, e.g. a transaction agreed with a counterparty and the one it
+/// returned, and reports their semantic differences: changed inputs, recipients and values,
+/// added or removed outputs, altered inscriptions and the change of the fee.
+///
+/// Inputs and outputs are compared by index, so reordering them is reported as changes. The
+/// fee delta is only computed if both transactions spend the same outputs, in any order,
+/// since the values of the spent outputs are not known.
+pub fn diff_transactions(expected: &Transaction, actual: &Transaction) -> TransactionDiff {
+    let mut differences = Vec::new();
+
+    if expected.version != actual.version {
+        differences.push(TransactionDifference::Version {
+            expected: expected.version,
+            actual: actual.version,
+        });
+    }
+    if expected.lock_time != actual.lock_time {
+        differences.push(TransactionDifference::LockTime {
+            expected: expected.lock_time,
+            actual: actual.lock_time,
+        });
+    }
+
+    for index in 0..expected.input.len().max(actual.input.len()) {
+        match (expected.input.get(index), actual.input.get(index)) {
+            (Some(expected), Some(actual)) => {
+                if expected.previous_output != actual.previous_output {
+                    differences.push(TransactionDifference::InputChanged {
+                        index,
+                        expected: expected.previous_output,
+                        actual: actual.previous_output,
+                    });
+                }
+                if expected.sequence != actual.sequence {
+                    differences.push(TransactionDifference::SequenceChanged {
+                        index,
+                        expected: expected.sequence,
+                        actual: actual.sequence,
+                    });
+                }
+            }
+            (Some(expected), None) => differences.push(TransactionDifference::InputRemoved {
+                index,
+                outpoint: expected.previous_output,
+            }),
+            (None, Some(actual)) => differences.push(TransactionDifference::InputAdded {
+                index,
+                outpoint: actual.previous_output,
+            }),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    for index in 0..expected.output.len().max(actual.output.len()) {
+        match (expected.output.get(index), actual.output.get(index)) {
+            (Some(expected), Some(actual)) => {
+                if expected.script_pubkey != actual.script_pubkey {
+                    differences.push(TransactionDifference::RecipientChanged {
+                        index,
+                        expected: expected.script_pubkey.clone(),
+                        actual: actual.script_pubkey.clone(),
+                    });
+                }
+                if expected.value != actual.value {
+                    differences.push(TransactionDifference::ValueChanged {
+                        index,
+                        expected: expected.value,
+                        actual: actual.value,
+                    });
+                }
+            }
+            (Some(expected), None) => differences.push(TransactionDifference::OutputRemoved {
+                index,
+                output: expected.clone(),
+            }),
+            (None, Some(actual)) => differences.push(TransactionDifference::OutputAdded {
+                index,
+                output: actual.clone(),
+            }),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    let mut expected_inscriptions = inscriptions(expected);
+    for (key, actual) in inscriptions(actual) {
+        let (input, offset) = key;
+        match expected_inscriptions.remove(&key) {
+            Some(expected) if expected == actual => {}
+            Some(expected) => differences.push(TransactionDifference::InscriptionChanged {
+                input,
+                offset,
+                expected: Box::new(expected),
+                actual: Box::new(actual),
+            }),
+            None => differences.push(TransactionDifference::InscriptionAdded {
+                input,
+                offset,
+                inscription: Box::new(actual),
+            }),
+        }
+    }
+    differences.extend(
+        expected_inscriptions
+            .into_iter()
+            .map(
+                |((input, offset), inscription)| TransactionDifference::InscriptionRemoved {
+                    input,
+                    offset,
+                    inscription: Box::new(inscription),
+                },
+            ),
+    );
+
+    TransactionDiff {
+        differences,
+        fee_delta: fee_delta(expected, actual),
+    }
+}
+
+/// Returns the inscriptions of the transaction, keyed by input and offset.
+fn inscriptions(tx: &Transaction) -> BTreeMap<(u32, u32), Nft> {
+    ParsedEnvelope::from_transaction(tx)
+        .into_iter()
+        .map(|envelope| ((envelope.input, envelope.offset), envelope.payload))
+        .collect()
+}
+
+fn fee_delta(expected: &Transaction, actual: &Transaction) -> Option<SignedAmount> {
+    let mut expected_inputs = expected
+        .input
+        .iter()
+        .map(|input| input.previous_output)
+        .collect::<Vec<_>>();
+    let mut actual_inputs = actual
+        .input
+        .iter()
+        .map(|input| input.previous_output)
+        .collect::<Vec<_>>();
+    expected_inputs.sort();
+    actual_inputs.sort();
+    if expected_inputs != actual_inputs {
+        return None;
+    }
+
+    let output_amount = |tx: &Transaction| {
+        tx.output
+            .iter()
+            .map(|output| output.value)
+            .sum::<Amount>()
+            .to_signed()
+            .ok()
+    };
+
+    output_amount(expected)?.checked_sub(output_amount(actual)?)
+}
+
+fn describe(inscription: &Nft) -> String {
+    format!(
+        "{} ({} bytes)",
+        inscription.content_type().unwrap_or("unknown content type"),
+        inscription.body.as_ref().map(Vec::len).unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::{Address, Network, Txid, Witness};
+
+    use super::*;
+    use crate::utils::test_utils::TestWallet;
+    use crate::wallet::ScriptType;
+    use crate::Brc20;
+
+    fn recipient() -> ScriptBuf {
+        Address::from_str("bcrt1pu8kl0t74qn89ljqs6ez558uyjvht3d93hsa2ha3u7654hgqjmadqlm20ps")
+            .unwrap()
+            .assume_checked()
+            .script_pubkey()
+    }
+
+    fn transaction(inscription: &Brc20) -> Transaction {
+        let redeem_script = TestWallet::new(Network::Regtest, 1)
+            .redeem_script(inscription, ScriptType::P2TR)
+            .unwrap();
+
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![bitcoin::TxIn {
+                previous_output: OutPoint::new(
+                    Txid::from_str(
+                        "9100acad2da80d2198b257acc5d98a6265fda510bc8f1252334876dad4c289f4",
+                    )
+                    .unwrap(),
+                    0,
+                ),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::from_slice(&[vec![1; 64], redeem_script.to_bytes(), vec![0xc0]]),
+            }],
+            output: vec![
+                TxOut {
+                    value: Amount::from_sat(546),
+                    script_pubkey: recipient(),
+                },
+                TxOut {
+                    value: Amount::from_sat(10_000),
+                    script_pubkey: recipient(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_should_not_report_signature_differences() {
+        let expected = transaction(&Brc20::mint("ordi", 1_000));
+        let mut actual = expected.clone();
+        let mut witness = actual.input[0].witness.to_vec();
+        witness[0] = vec![2; 64];
+        actual.input[0].witness = Witness::from_slice(&witness);
+
+        let diff = diff_transactions(&expected, &actual);
+        assert!(diff.is_empty());
+        assert_eq!(diff.fee_delta, Some(SignedAmount::ZERO));
+        assert_eq!(diff.to_string(), "no differences");
+    }
+
+    #[test]
+    fn test_should_report_mutated_transaction() {
+        let expected = transaction(&Brc20::mint("ordi", 1_000));
+        let mut actual = transaction(&Brc20::mint("ordi", 100_000));
+        actual.output[0].script_pubkey = ScriptBuf::new_op_return([1, 2, 3]);
+        actual.output[1].value = Amount::from_sat(9_000);
+        actual.output.push(TxOut {
+            value: Amount::from_sat(546),
+            script_pubkey: recipient(),
+        });
+
+        let diff = diff_transactions(&expected, &actual);
+        assert_eq!(diff.differences.len(), 4);
+        assert!(matches!(
+            diff.differences[0],
+            TransactionDifference::RecipientChanged { index: 0, .. }
+        ));
+        assert_eq!(
+            diff.differences[1],
+            TransactionDifference::ValueChanged {
+                index: 1,
+                expected: Amount::from_sat(10_000),
+                actual: Amount::from_sat(9_000),
+            }
+        );
+        assert!(matches!(
+            diff.differences[2],
+            TransactionDifference::OutputAdded { index: 2, .. }
+        ));
+        assert!(matches!(
+            &diff.differences[3],
+            TransactionDifference::InscriptionChanged { input: 0, offset: 0, expected, actual }
+                if expected.body != actual.body
+        ));
+        assert_eq!(diff.fee_delta, Some(SignedAmount::from_sat(1_000 - 546)));
+
+        let text = diff.to_string();
+        assert!(text.contains("inscription 0 of input #0 body altered"));
+        assert!(text.ends_with("fee delta: +454 sat"));
+    }
+
+    #[test]
+    fn test_should_report_input_changes_without_fee_delta() {
+        let expected = transaction(&Brc20::mint("ordi", 1_000));
+        let mut actual = expected.clone();
+        actual.input[0].previous_output.vout = 1;
+        actual.input[0].witness = Witness::new();
+
+        let diff = diff_transactions(&expected, &actual);
+        assert_eq!(diff.differences.len(), 2);
+        assert!(matches!(
+            diff.differences[0],
+            TransactionDifference::InputChanged { index: 0, .. }
+        ));
+        assert!(matches!(
+            diff.differences[1],
+            TransactionDifference::InscriptionRemoved {
+                input: 0,
+                offset: 0,
+                ..
+            }
+        ));
+        assert_eq!(diff.fee_delta, None);
+    }
+}
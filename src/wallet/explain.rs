@@ -0,0 +1,379 @@
+use std::fmt;
+
+use bitcoin::bip32::DerivationPath;
+use bitcoin::opcodes::all::{OP_PUSHNUM_13, OP_RETURN};
+use bitcoin::script::Instruction;
+use bitcoin::{Address, Amount, FeeRate, Network, Script, Transaction};
+
+use crate::utils::constants::{dust_limit, POSTAGE};
+use crate::wallet::{OrdParser, TxInputInfo};
+use crate::InscriptionId;
+
+/// Human-readable report of a transaction, as returned by [`explain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionReport {
+    pub txid: bitcoin::Txid,
+    pub inputs: Vec<InputReport>,
+    pub outputs: Vec<OutputReport>,
+    /// Inscriptions found in the transaction inputs.
+    pub inscriptions: Vec<(InscriptionId, OrdParser)>,
+    /// Transaction fee, if all the prevouts are known.
+    pub fee: Option<Amount>,
+    /// Transaction fee rate, if all the prevouts are known.
+    pub fee_rate: Option<FeeRate>,
+    pub vsize: usize,
+}
+
+/// Report of a single transaction input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputReport {
+    pub outpoint: bitcoin::OutPoint,
+    /// Script type of the spent output.
+    pub kind: ScriptKind,
+    /// Value of the spent output, if known.
+    pub value: Option<Amount>,
+    /// Derivation path used to sign the input, if known.
+    pub derivation_path: Option<DerivationPath>,
+    /// Whether the input has a witness.
+    pub signed: bool,
+}
+
+/// Report of a single transaction output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputReport {
+    pub role: OutputRole,
+    pub kind: ScriptKind,
+    pub address: Option<Address>,
+    pub value: Amount,
+    /// Whether the output value is below the dust limit of its script type.
+    pub dust: bool,
+}
+
+/// Best-effort guess of the role of a transaction output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputRole {
+    /// An OP_RETURN output carrying a runestone.
+    Runestone,
+    /// Any other OP_RETURN output.
+    OpReturn,
+    /// An output holding exactly the inscription postage.
+    Postage,
+    /// Any other output (payments, change, commit outputs).
+    Payment,
+}
+
+/// Script type of an output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    OpReturn,
+    Unknown,
+}
+
+impl ScriptKind {
+    /// Classifies a script pubkey.
+    pub fn from_script(script: &Script) -> Self {
+        if script.is_p2pkh() {
+            Self::P2pkh
+        } else if script.is_p2sh() {
+            Self::P2sh
+        } else if script.is_p2wpkh() {
+            Self::P2wpkh
+        } else if script.is_p2wsh() {
+            Self::P2wsh
+        } else if script.is_p2tr() {
+            Self::P2tr
+        } else if script.is_op_return() {
+            Self::OpReturn
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+impl fmt::Display for ScriptKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::P2pkh => "p2pkh",
+            Self::P2sh => "p2sh",
+            Self::P2wpkh => "p2wpkh",
+            Self::P2wsh => "p2wsh",
+            Self::P2tr => "p2tr",
+            Self::OpReturn => "op_return",
+            Self::Unknown => "unknown",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+impl fmt::Display for OutputRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let role = match self {
+            Self::Runestone => "runestone",
+            Self::OpReturn => "op_return",
+            Self::Postage => "postage",
+            Self::Payment => "payment",
+        };
+        write!(f, "{role}")
+    }
+}
+
+impl TransactionReport {
+    /// Builds the report of a transaction.
+    ///
+    /// `prevouts` must be in the same order as the transaction inputs; inputs without
+    /// a matching prevout are reported with an unknown value and no fee is computed.
+    pub fn new(tx: &Transaction, prevouts: &[TxInputInfo], network: Network) -> Self {
+        let inputs = tx
+            .input
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let prevout = prevouts
+                    .get(index)
+                    .filter(|prevout| prevout.outpoint == input.previous_output);
+                InputReport {
+                    outpoint: input.previous_output,
+                    kind: prevout
+                        .map(|prevout| ScriptKind::from_script(&prevout.tx_out.script_pubkey))
+                        .unwrap_or(ScriptKind::Unknown),
+                    value: prevout.map(|prevout| prevout.tx_out.value),
+                    derivation_path: prevout.map(|prevout| prevout.derivation_path.clone()),
+                    signed: !input.witness.is_empty() || !input.script_sig.is_empty(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let outputs = tx
+            .output
+            .iter()
+            .map(|output| OutputReport {
+                role: output_role(&output.script_pubkey, output.value),
+                kind: ScriptKind::from_script(&output.script_pubkey),
+                address: Address::from_script(&output.script_pubkey, network).ok(),
+                value: output.value,
+                dust: output.value < dust_limit(&output.script_pubkey),
+            })
+            .collect::<Vec<_>>();
+
+        let input_amount = inputs
+            .iter()
+            .map(|input| input.value)
+            .sum::<Option<Amount>>();
+        let output_amount = outputs.iter().map(|output| output.value).sum::<Amount>();
+        let fee = input_amount.and_then(|amount| amount.checked_sub(output_amount));
+        let fee_rate = fee.map(|fee| {
+            FeeRate::from_sat_per_kwu(
+                fee.to_sat().saturating_mul(1000) / tx.weight().to_wu().max(1),
+            )
+        });
+
+        Self {
+            txid: tx.txid(),
+            inputs,
+            outputs,
+            inscriptions: OrdParser::parse_all(tx).unwrap_or_default(),
+            fee,
+            fee_rate,
+            vsize: tx.vsize(),
+        }
+    }
+}
+
+impl fmt::Display for TransactionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "transaction {} ({} vB)", self.txid, self.vsize)?;
+
+        writeln!(f, "inputs:")?;
+        for (index, input) in self.inputs.iter().enumerate() {
+            write!(f, "  #{index} {} {}", input.outpoint, input.kind)?;
+            match input.value {
+                Some(value) => write!(f, " {} sat", value.to_sat())?,
+                None => write!(f, " (unknown value)")?,
+            }
+            if let Some(path) = &input.derivation_path {
+                write!(f, " path={path}")?;
+            }
+            writeln!(f, "{}", if input.signed { "" } else { " [unsigned]" })?;
+        }
+
+        writeln!(f, "outputs:")?;
+        for (index, output) in self.outputs.iter().enumerate() {
+            write!(f, "  #{index} {} {}", output.role, output.kind)?;
+            if let Some(address) = &output.address {
+                write!(f, " {address}")?;
+            }
+            writeln!(
+                f,
+                " {} sat{}",
+                output.value.to_sat(),
+                if output.dust { " [dust]" } else { "" }
+            )?;
+        }
+
+        if !self.inscriptions.is_empty() {
+            writeln!(f, "inscriptions:")?;
+            for (id, inscription) in &self.inscriptions {
+                match inscription {
+                    OrdParser::Brc20 { inscription, .. } => {
+                        writeln!(f, "  {id} brc20 {inscription:?}")?
+                    }
+                    OrdParser::Ordinal(nft) => writeln!(
+                        f,
+                        "  {id} {} ({} bytes)",
+                        nft.content_type().unwrap_or("unknown content type"),
+                        nft.body.as_ref().map(Vec::len).unwrap_or_default()
+                    )?,
+                }
+            }
+        }
+
+        match (self.fee, self.fee_rate) {
+            (Some(fee), Some(fee_rate)) => write!(
+                f,
+                "fee: {} sat ({} sat/vB)",
+                fee.to_sat(),
+                fee_rate.to_sat_per_vb_floor()
+            ),
+            _ => write!(f, "fee: unknown"),
+        }
+    }
+}
+
+/// Returns a human-readable description of the transaction, showing its inputs, outputs,
+/// inscriptions and fee.
+///
+/// This is meant to be used while developing, in order to spot mistakes such as a wrong
+/// leftover amount or a missing postage output.
+pub fn explain(tx: &Transaction, prevouts: &[TxInputInfo], network: Network) -> String {
+    TransactionReport::new(tx, prevouts, network).to_string()
+}
+
+fn output_role(script: &Script, value: Amount) -> OutputRole {
+    let mut instructions = script.instructions();
+    if instructions.next() == Some(Ok(Instruction::Op(OP_RETURN))) {
+        if instructions.next() == Some(Ok(Instruction::Op(OP_PUSHNUM_13))) {
+            OutputRole::Runestone
+        } else {
+            OutputRole::OpReturn
+        }
+    } else if value == POSTAGE {
+        OutputRole::Postage
+    } else {
+        OutputRole::Payment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{OutPoint, ScriptBuf, Sequence, TxIn, TxOut, Txid, Witness};
+
+    use super::*;
+
+    fn p2tr_address() -> Address {
+        Address::from_str("bcrt1pu8kl0t74qn89ljqs6ez558uyjvht3d93hsa2ha3u7654hgqjmadqlm20ps")
+            .unwrap()
+            .assume_checked()
+    }
+
+    fn transaction() -> (Transaction, Vec<TxInputInfo>) {
+        let outpoint = OutPoint::new(
+            Txid::from_str("9100acad2da80d2198b257acc5d98a6265fda510bc8f1252334876dad4c289f4")
+                .unwrap(),
+            1,
+        );
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![
+                TxOut {
+                    value: POSTAGE,
+                    script_pubkey: p2tr_address().script_pubkey(),
+                },
+                TxOut {
+                    value: Amount::from_sat(8_000),
+                    script_pubkey: p2tr_address().script_pubkey(),
+                },
+                TxOut {
+                    value: Amount::ZERO,
+                    script_pubkey: ScriptBuf::new_op_return([1, 2, 3]),
+                },
+            ],
+        };
+        let prevouts = vec![TxInputInfo {
+            outpoint,
+            tx_out: TxOut {
+                value: Amount::from_sat(10_000),
+                script_pubkey: p2tr_address().script_pubkey(),
+            },
+            derivation_path: DerivationPath::from_str("m/0/1").unwrap(),
+            key_spend: Default::default(),
+            rune_balances: None,
+        }];
+
+        (tx, prevouts)
+    }
+
+    #[test]
+    fn test_should_report_transaction() {
+        let (tx, prevouts) = transaction();
+        let report = TransactionReport::new(&tx, &prevouts, Network::Regtest);
+
+        assert_eq!(report.inputs.len(), 1);
+        assert_eq!(report.inputs[0].kind, ScriptKind::P2tr);
+        assert_eq!(report.inputs[0].value, Some(Amount::from_sat(10_000)));
+        assert!(!report.inputs[0].signed);
+
+        assert_eq!(report.outputs[0].role, OutputRole::Postage);
+        assert_eq!(report.outputs[0].address, Some(p2tr_address()));
+        assert_eq!(report.outputs[1].role, OutputRole::Payment);
+        assert_eq!(report.outputs[2].role, OutputRole::OpReturn);
+        assert_eq!(report.outputs[2].kind, ScriptKind::OpReturn);
+        assert!(report.outputs.iter().all(|output| !output.dust));
+
+        assert_eq!(report.fee, Some(Amount::from_sat(10_000 - 8_000) - POSTAGE));
+        assert!(report.fee_rate.is_some());
+        assert!(report.inscriptions.is_empty());
+
+        let text = explain(&tx, &prevouts, Network::Regtest);
+        assert!(text.contains("path=m/0/1"));
+        assert!(text.contains("postage p2tr"));
+        assert!(text.contains(&format!("fee: {} sat", 10_000 - 8_000 - POSTAGE.to_sat())));
+    }
+
+    #[test]
+    fn test_should_report_unknown_fee_without_prevouts() {
+        let (tx, _) = transaction();
+        let report = TransactionReport::new(&tx, &[], Network::Regtest);
+
+        assert_eq!(report.inputs[0].kind, ScriptKind::Unknown);
+        assert_eq!(report.inputs[0].value, None);
+        assert_eq!(report.fee, None);
+        assert!(report.to_string().ends_with("fee: unknown"));
+    }
+
+    #[test]
+    fn test_should_detect_runestone_output() {
+        let script = ScriptBuf::builder()
+            .push_opcode(OP_RETURN)
+            .push_opcode(OP_PUSHNUM_13)
+            .push_slice([0, 1])
+            .into_script();
+
+        assert_eq!(output_role(&script, Amount::ZERO), OutputRole::Runestone);
+    }
+}
@@ -1,12 +1,23 @@
+mod curse;
 mod envelope;
+mod metrics;
+#[cfg(feature = "rune")]
+mod runestone;
 
-use bitcoin::script::{Builder as ScriptBuilder, PushBytesBuf};
-use bitcoin::Transaction;
+use bitcoin::script::PushBytesBuf;
+use bitcoin::{Transaction, Txid};
 use serde::{Deserialize, Serialize};
 
-use self::envelope::ParsedEnvelope;
-use crate::wallet::RedeemScriptPubkey;
-use crate::{Brc20, Inscription, InscriptionId, InscriptionParseError, Nft, OrdError, OrdResult};
+pub use self::curse::{Curse, ParseMode, ParseOptions, ParsedInscription};
+pub use self::envelope::{Envelope, ParsedEnvelope, RawEnvelope};
+use self::metrics::NoMetrics;
+pub use self::metrics::{ParserCounters, ParserCountersSnapshot, ParserMetrics};
+#[cfg(feature = "rune")]
+pub use self::runestone::{Artifact, Cenotaph, RunestoneParser};
+use crate::{
+    Brc20, Brc20ContentType, EnvelopeBuilder, Inscription, InscriptionId, InscriptionParseError,
+    Nft, OrdError, OrdResult,
+};
 
 /// Encapsulates inscription parsing logic for both Ordinals and BRC20s.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -14,15 +25,41 @@ pub enum OrdParser {
     /// Denotes a parsed [Nft] inscription.
     Ordinal(Nft),
     /// Denotes a parsed [Brc20] inscription.
-    Brc20(Brc20),
+    Brc20 {
+        /// The parsed BRC-20 operation
+        inscription: Brc20,
+        /// The inscription body, as inscribed, since re-serializing the operation may not
+        /// produce the same bytes
+        raw_body: Vec<u8>,
+    },
 }
 
 impl OrdParser {
+    /// Returns the body of the inscription exactly as it was inscribed, e.g. to check it
+    /// byte-for-byte against an indexer.
+    pub fn raw_body(&self) -> Option<&[u8]> {
+        match self {
+            Self::Brc20 { raw_body, .. } => Some(raw_body),
+            Self::Ordinal(nft) => nft.body.as_deref(),
+        }
+    }
+
+    /// Returns the inscription the parsed inscription delegates its content to, if any.
+    pub fn delegate(&self) -> Option<InscriptionId> {
+        match self {
+            Self::Ordinal(nft) => nft.delegate(),
+            Self::Brc20 { .. } => None,
+        }
+    }
+
     /// Parses all inscriptions from a given transaction and categorizes them as either `Self::Brc20` or `Self::Ordinal`.
     ///
     /// This function extracts all inscription data from the transaction, attempts to parse each inscription,
     /// and returns a vector of categorized inscriptions with their corresponding IDs.
     ///
+    /// As in ord, inscriptions are identified by their position among the envelopes of the
+    /// transaction, across all its inputs, i.e. the `N` of `<txid>iN`.
+    ///
     /// # Errors
     ///
     /// Will return an error if any inscription data cannot be parsed correctly,
@@ -32,23 +69,112 @@ impl OrdParser {
 
         ParsedEnvelope::from_transaction(tx)
             .into_iter()
-            .map(|envelope| {
-                let inscription_id = InscriptionId {
-                    txid,
-                    index: envelope.input,
-                };
-
-                let raw_body = envelope.payload.body.as_ref().ok_or_else(|| {
-                    OrdError::InscriptionParser(InscriptionParseError::ParsedEnvelope(
-                        "Empty payload body in envelope".to_string(),
-                    ))
-                })?;
-
-                if let Some(brc20) = Self::parse_brc20(raw_body) {
-                    Ok((inscription_id, Self::Brc20(brc20)))
-                } else {
-                    Ok((inscription_id, Self::Ordinal(envelope.payload)))
+            .enumerate()
+            .map(|(index, envelope)| {
+                Self::parse_envelope(inscription_id(txid, index), envelope, ParseMode::Lenient)
+            })
+            .collect::<Result<Vec<(InscriptionId, Self)>, OrdError>>()
+    }
+
+    /// Parses all inscriptions from a given transaction as [`Self::parse_all`] does, reporting
+    /// the curses of their envelopes, e.g. duplicate or unrecognized even fields.
+    ///
+    /// With [`ParseMode::Strict`], cursed envelopes and BRC-20 operations with invalid tickers
+    /// or content types are never classified as `Self::Brc20`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if any inscription data cannot be parsed correctly.
+    pub fn parse_all_with_mode(
+        tx: &Transaction,
+        mode: ParseMode,
+    ) -> OrdResult<Vec<ParsedInscription>> {
+        Self::parse_all_with_options(
+            tx,
+            &ParseOptions {
+                mode,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Parses all inscriptions from a given transaction as [`Self::parse_all_with_mode`] does,
+    /// only considering the envelopes with the protocol ID of the options.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if any inscription data cannot be parsed correctly.
+    pub fn parse_all_with_options(
+        tx: &Transaction,
+        options: &ParseOptions,
+    ) -> OrdResult<Vec<ParsedInscription>> {
+        Self::parse_all_with_metrics(tx, options, &NoMetrics)
+    }
+
+    /// Parses all inscriptions from a given transaction as [`Self::parse_all_with_options`]
+    /// does, reporting the envelopes seen, the bodies decoded, the invalid JSON bodies and the
+    /// cursed envelopes to the metrics.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if any inscription data cannot be parsed correctly.
+    pub fn parse_all_with_metrics(
+        tx: &Transaction,
+        options: &ParseOptions,
+        metrics: &(impl ParserMetrics + ?Sized),
+    ) -> OrdResult<Vec<ParsedInscription>> {
+        let txid = tx.txid();
+
+        ParsedEnvelope::from_transaction_with_protocol_id(tx, &options.protocol_id)
+            .into_iter()
+            .enumerate()
+            .map(|(index, envelope)| {
+                metrics.envelope_seen();
+                let curses = curse::envelope_curses(&envelope);
+                if !curses.is_empty() {
+                    metrics.cursed(&curses);
+                }
+                if envelope
+                    .payload
+                    .body
+                    .as_deref()
+                    .is_some_and(metrics::is_invalid_json)
+                {
+                    metrics.invalid_json();
                 }
+
+                let (id, inscription) =
+                    Self::parse_envelope(inscription_id(txid, index), envelope, options.mode)?;
+                metrics.body_decoded();
+
+                Ok(ParsedInscription {
+                    id,
+                    inscription,
+                    curses,
+                })
+            })
+            .collect()
+    }
+
+    /// Parses all the inscriptions revealed by the transaction input at the specified index,
+    /// e.g. the inscriptions of a batch mint.
+    ///
+    /// As in [`Self::parse_all`], the inscriptions are identified by their position among the
+    /// envelopes of the whole transaction, so they're numbered after the ones of the previous
+    /// inputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any inscription data at the specified index cannot be parsed correctly.
+    pub fn parse_input(tx: &Transaction, index: usize) -> OrdResult<Vec<(InscriptionId, Self)>> {
+        let txid = tx.txid();
+
+        ParsedEnvelope::from_transaction(tx)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, envelope)| envelope.input as usize == index)
+            .map(|(position, envelope)| {
+                Self::parse_envelope(inscription_id(txid, position), envelope, ParseMode::Lenient)
             })
             .collect::<Result<Vec<(InscriptionId, Self)>, OrdError>>()
     }
@@ -63,28 +189,68 @@ impl OrdParser {
     ///
     /// Returns an error if the inscription data at the specified index cannot be parsed,
     /// if there is no data at the specified index, or if the data at the index does not contain a valid payload.
+    #[deprecated(
+        since = "0.3.1",
+        note = "only the first inscription of the input is returned, use `parse_input` instead"
+    )]
     pub fn parse_one(tx: &Transaction, index: usize) -> OrdResult<(InscriptionId, Self)> {
-        let envelope = ParsedEnvelope::from_transaction_input(tx, index).ok_or_else(|| {
-            OrdError::InscriptionParser(InscriptionParseError::ParsedEnvelope(
-                "No data found in envelope at specified index".to_string(),
-            ))
-        })?;
-
-        let raw_body = envelope.payload.body.as_ref().ok_or_else(|| {
-            OrdError::InscriptionParser(InscriptionParseError::ParsedEnvelope(
-                "Empty payload body in envelope".to_string(),
-            ))
-        })?;
+        let txid = tx.txid();
+        let (position, envelope) = ParsedEnvelope::from_transaction(tx)
+            .into_iter()
+            .enumerate()
+            .find(|(_, envelope)| envelope.input as usize == index)
+            .ok_or_else(|| {
+                OrdError::InscriptionParser(InscriptionParseError::ParsedEnvelope(format!(
+                    "No data found in envelope at input {index} of transaction {txid}"
+                )))
+            })?;
+
+        Self::parse_envelope(inscription_id(txid, position), envelope, ParseMode::Lenient)
+    }
 
-        let inscription_id = InscriptionId {
-            txid: tx.txid(),
-            index: envelope.input,
+    /// Categorizes the inscription of a parsed envelope.
+    ///
+    /// With [`ParseMode::Strict`], the inscription is classified as `Self::Brc20` only if the
+    /// envelope is not cursed, the ticker is valid and the content type is a
+    /// [`Brc20ContentType`]. Both content types are accepted alike.
+    fn parse_envelope(
+        inscription_id: InscriptionId,
+        envelope: ParsedEnvelope,
+        mode: ParseMode,
+    ) -> OrdResult<(InscriptionId, Self)> {
+        let Some(raw_body) = envelope.payload.body.as_deref() else {
+            // a delegate inscription takes its body from the delegate
+            if envelope.payload.delegate().is_some() {
+                return Ok((inscription_id, Self::Ordinal(envelope.payload)));
+            }
+
+            return Err(OrdError::InscriptionParser(
+                InscriptionParseError::ParsedEnvelope(format!(
+                    "Empty payload body in envelope of inscription {inscription_id}"
+                )),
+            ));
         };
 
-        if let Some(brc20) = Self::parse_brc20(raw_body) {
-            Ok((inscription_id, Self::Brc20(brc20)))
-        } else {
-            Ok((inscription_id, Self::Ordinal(envelope.payload)))
+        let brc20 = Self::parse_brc20(raw_body).filter(|brc20| {
+            mode == ParseMode::Lenient
+                || (curse::envelope_curses(&envelope).is_empty()
+                    && brc20.validate_ticker().is_ok()
+                    && envelope
+                        .payload
+                        .media_type()
+                        .as_ref()
+                        .and_then(Brc20ContentType::from_media_type)
+                        .is_some())
+        });
+        match brc20 {
+            Some(brc20) => Ok((
+                inscription_id,
+                Self::Brc20 {
+                    inscription: brc20,
+                    raw_body: raw_body.to_vec(),
+                },
+            )),
+            None => Ok((inscription_id, Self::Ordinal(envelope.payload))),
         }
     }
 
@@ -97,7 +263,11 @@ impl OrdParser {
 
 impl From<Brc20> for OrdParser {
     fn from(inscription: Brc20) -> Self {
-        Self::Brc20(inscription)
+        let raw_body = serde_json::to_vec(&inscription).unwrap_or_default();
+        Self::Brc20 {
+            inscription,
+            raw_body,
+        }
     }
 }
 
@@ -138,7 +308,7 @@ impl TryFrom<OrdParser> for Brc20 {
 
     fn try_from(parser: OrdParser) -> Result<Self, Self::Error> {
         match parser {
-            OrdParser::Brc20(brc20) => Ok(brc20),
+            OrdParser::Brc20 { inscription, .. } => Ok(inscription),
             _ => Err(OrdError::InscriptionParser(InscriptionParseError::NotBrc20)),
         }
     }
@@ -149,7 +319,7 @@ impl TryFrom<&OrdParser> for Brc20 {
 
     fn try_from(parser: &OrdParser) -> Result<Self, Self::Error> {
         match parser {
-            OrdParser::Brc20(brc20) => Ok(brc20.clone()),
+            OrdParser::Brc20 { inscription, .. } => Ok(inscription.clone()),
             _ => Err(OrdError::InscriptionParser(InscriptionParseError::NotBrc20)),
         }
     }
@@ -158,30 +328,35 @@ impl TryFrom<&OrdParser> for Brc20 {
 impl Inscription for OrdParser {
     fn content_type(&self) -> String {
         match self {
-            Self::Brc20(inscription) => inscription.content_type(),
+            Self::Brc20 { inscription, .. } => inscription.content_type(),
             Self::Ordinal(inscription) => Inscription::content_type(inscription),
         }
     }
 
     fn data(&self) -> OrdResult<PushBytesBuf> {
         match self {
-            Self::Brc20(inscription) => inscription.data(),
+            Self::Brc20 { inscription, .. } => inscription.data(),
             Self::Ordinal(inscription) => inscription.data(),
         }
     }
 
-    fn generate_redeem_script(
-        &self,
-        builder: ScriptBuilder,
-        pubkey: RedeemScriptPubkey,
-    ) -> OrdResult<ScriptBuilder> {
+    fn fields(&self) -> OrdResult<EnvelopeBuilder> {
         match self {
-            Self::Brc20(inscription) => inscription.generate_redeem_script(builder, pubkey),
-            Self::Ordinal(inscription) => inscription.generate_redeem_script(builder, pubkey),
+            Self::Brc20 { inscription, .. } => inscription.fields(),
+            Self::Ordinal(inscription) => inscription.fields(),
         }
     }
 }
 
+/// Returns the ID of the inscription at the given position among the envelopes of the
+/// transaction.
+fn inscription_id(txid: Txid, index: usize) -> InscriptionId {
+    InscriptionId {
+        txid,
+        index: index as u32,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bitcoin::absolute::LockTime;
@@ -190,7 +365,8 @@ mod tests {
     use bitcoin::{opcodes, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, Witness};
 
     use super::*;
-    use crate::utils::test_utils::get_transaction_by_id;
+    use crate::utils::test_utils::{get_transaction_by_id, test_private_key, test_utxo};
+    use crate::wallet::RedeemScriptPubkey;
 
     #[tokio::test]
     async fn ord_parser_should_parse_one() {
@@ -201,6 +377,7 @@ mod tests {
         .await
         .unwrap();
 
+        #[allow(deprecated)]
         let (inscription_id, parsed_inscription) = OrdParser::parse_one(&transaction, 0).unwrap();
 
         assert_eq!(inscription_id.index, 0);
@@ -311,6 +488,205 @@ mod tests {
             brc20,
             Brc20::deploy("kobp", 1000, Some(10), Some(8), Some(true))
         );
+
+        let parsed = OrdParser::parse_all_with_mode(&transaction, ParseMode::Strict).unwrap();
+        assert!(!parsed[0].is_cursed());
+        assert_eq!(Brc20::try_from(&parsed[0].inscription).unwrap(), brc20);
+    }
+
+    #[test]
+    fn ord_parser_should_report_curses_of_envelopes() {
+        let brc20 = br#"{"p":"brc-20","op":"transfer","tick":"kobp","amt":"10"}"#;
+
+        let script = ScriptBuilder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(opcodes::all::OP_IF)
+            .push_slice(b"ord")
+            .push_slice([1])
+            .push_slice(b"text/plain;charset=utf-8")
+            .push_slice([1])
+            .push_slice(b"text/plain;charset=utf-8")
+            .push_slice([])
+            .push_slice::<&PushBytes>(brc20.as_slice().try_into().unwrap())
+            .push_opcode(opcodes::all::OP_ENDIF)
+            .into_script();
+
+        let transaction = Transaction {
+            version: Version::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[script.into_bytes(), Vec::new()]),
+            }],
+            output: Vec::new(),
+        };
+
+        let parsed = OrdParser::parse_all_with_mode(&transaction, ParseMode::Lenient).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].curses, vec![Curse::DuplicateField]);
+        assert!(matches!(parsed[0].inscription, OrdParser::Brc20 { .. }));
+        assert_eq!(parsed[0].inscription.raw_body(), Some(brc20.as_slice()));
+
+        let parsed = OrdParser::parse_all_with_mode(&transaction, ParseMode::Strict).unwrap();
+        assert!(parsed[0].is_cursed());
+        let nft = Nft::try_from(&parsed[0].inscription).unwrap();
+        assert!(nft.duplicate_field);
+        assert_eq!(nft.body.as_deref(), Some(brc20.as_slice()));
+    }
+
+    #[test]
+    fn ord_parser_should_accept_both_brc20_content_types() {
+        let transaction_with = |content_type: &str| {
+            let brc20 = Brc20::mint("ordi", 10).to_string();
+            let script = ScriptBuilder::new()
+                .push_opcode(opcodes::OP_FALSE)
+                .push_opcode(opcodes::all::OP_IF)
+                .push_slice(b"ord")
+                .push_slice([1])
+                .push_slice::<&PushBytes>(content_type.as_bytes().try_into().unwrap())
+                .push_slice([])
+                .push_slice::<&PushBytes>(brc20.as_bytes().try_into().unwrap())
+                .push_opcode(opcodes::all::OP_ENDIF)
+                .into_script();
+            Transaction {
+                version: Version::ONE,
+                lock_time: LockTime::ZERO,
+                input: vec![TxIn {
+                    previous_output: OutPoint::null(),
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::from_slice(&[script.into_bytes(), Vec::new()]),
+                }],
+                output: Vec::new(),
+            }
+        };
+
+        for content_type in [
+            Brc20ContentType::TextPlain.as_str(),
+            Brc20ContentType::ApplicationJson.as_str(),
+            "text/plain",
+            "application/json;charset=utf-8",
+        ] {
+            let parsed =
+                OrdParser::parse_all_with_mode(&transaction_with(content_type), ParseMode::Strict)
+                    .unwrap();
+            assert_eq!(
+                parsed[0].inscription,
+                OrdParser::from(Brc20::mint("ordi", 10)),
+                "{content_type}"
+            );
+        }
+
+        let transaction = transaction_with("image/png");
+        let parsed = OrdParser::parse_all_with_mode(&transaction, ParseMode::Strict).unwrap();
+        assert!(matches!(parsed[0].inscription, OrdParser::Ordinal(_)));
+        let parsed = OrdParser::parse_all_with_mode(&transaction, ParseMode::Lenient).unwrap();
+        assert!(matches!(parsed[0].inscription, OrdParser::Brc20 { .. }));
+    }
+
+    #[test]
+    fn ord_parser_should_inscribe_brc20_as_json() {
+        let inscription =
+            Brc20::transfer("ordi", 100).with_content_type(Brc20ContentType::ApplicationJson);
+        let script = inscription
+            .generate_redeem_script(
+                ScriptBuilder::new(),
+                RedeemScriptPubkey::XPublickey(
+                    bitcoin::XOnlyPublicKey::from_slice(&[0x02; 32]).unwrap(),
+                ),
+            )
+            .unwrap()
+            .into_script();
+        let transaction = Transaction {
+            version: Version::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[script.into_bytes(), Vec::new()]),
+            }],
+            output: Vec::new(),
+        };
+
+        let envelopes = ParsedEnvelope::from_transaction(&transaction);
+        assert_eq!(
+            envelopes[0].payload.content_type(),
+            Some("application/json")
+        );
+        let parsed = OrdParser::parse_all_with_mode(&transaction, ParseMode::Strict).unwrap();
+        assert_eq!(
+            Brc20::try_from(&parsed[0].inscription).unwrap(),
+            inscription.operation
+        );
+    }
+
+    #[test]
+    fn ord_parser_should_not_classify_invalid_tickers_in_strict_mode() {
+        let script = Brc20::mint("ord", 10)
+            .generate_redeem_script(
+                ScriptBuilder::new(),
+                RedeemScriptPubkey::XPublickey(
+                    bitcoin::XOnlyPublicKey::from_slice(&[0x02; 32]).unwrap(),
+                ),
+            )
+            .unwrap()
+            .into_script();
+        let transaction = Transaction {
+            version: Version::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[script.into_bytes(), Vec::new()]),
+            }],
+            output: Vec::new(),
+        };
+
+        let parsed = OrdParser::parse_all_with_mode(&transaction, ParseMode::Lenient).unwrap();
+        assert_eq!(
+            parsed[0].inscription,
+            OrdParser::from(Brc20::mint("ord", 10))
+        );
+
+        let parsed = OrdParser::parse_all_with_mode(&transaction, ParseMode::Strict).unwrap();
+        assert!(!parsed[0].is_cursed());
+        assert!(matches!(parsed[0].inscription, OrdParser::Ordinal(_)));
+    }
+
+    #[test]
+    fn ord_parser_should_expose_delegate_as_inscription_id() {
+        let delegate = InscriptionId::parse_from_str(
+            "6fb976ab49dcec017f1e201e84395983204ae1a7c2abf7ced0a85d692e442799i0",
+        )
+        .unwrap();
+        let script = Nft::new(None, None)
+            .with_delegate(delegate)
+            .reveal_script_as_scriptbuf(ScriptBuilder::new())
+            .unwrap();
+
+        let transaction = Transaction {
+            version: Version::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[script.into_bytes(), Vec::new()]),
+            }],
+            output: Vec::new(),
+        };
+
+        let (_, parsed) = OrdParser::parse_all(&transaction).unwrap().remove(0);
+        assert_eq!(parsed.delegate(), Some(delegate));
+        assert_eq!(
+            Nft::try_from(parsed).unwrap().delegate,
+            Some(delegate.get_raw()),
+        );
+        assert_eq!(delegate.get_raw().len(), 32, "the zero index is trimmed");
     }
 
     #[test]
@@ -374,11 +750,32 @@ mod tests {
 
         let (parsed_nft, nft_iid) = (&parsed_data[1].1, parsed_data[1].0);
         assert_eq!(nft_iid.txid, transaction.txid());
-        assert_eq!(nft_iid.index, 0);
+        assert_eq!(nft_iid.index, 1);
+        assert_eq!(brc20_iid.to_string(), format!("{}i0", transaction.txid()));
+        assert_eq!(nft_iid.to_string(), format!("{}i1", transaction.txid()));
 
         let nft = Nft::try_from(parsed_nft).unwrap();
         assert_eq!(nft.content_type().unwrap(), "text/plain;charset=utf-8");
         assert_eq!(nft.body().unwrap(), "Hello, world!");
+
+        assert_eq!(
+            OrdParser::parse_input(&transaction, 0).unwrap(),
+            parsed_data
+        );
+        assert!(OrdParser::parse_input(&transaction, 1).unwrap().is_empty());
+
+        // the inscriptions of the next input are numbered after the ones of the first input
+        let mut transaction = transaction;
+        transaction.input.push(transaction.input[0].clone());
+        let ids = OrdParser::parse_input(&transaction, 1)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id.index)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [2, 3]);
+        #[allow(deprecated)]
+        let (id, _) = OrdParser::parse_one(&transaction, 1).unwrap();
+        assert_eq!(id.index, 2);
     }
 
     #[test]
@@ -508,4 +905,53 @@ mod tests {
     struct MempoolApiVin {
         witness: Vec<String>,
     }
+
+    #[tokio::test]
+    async fn ord_parser_should_parse_p2wsh_reveal() {
+        use bitcoin::secp256k1::Secp256k1;
+        use bitcoin::{Address, Amount, FeeRate};
+
+        use crate::wallet::{
+            CreateCommitTransactionArgs, OrdTransactionBuilder, RevealTransactionArgs, Utxo,
+        };
+
+        let private_key = test_private_key();
+        let public_key = private_key.public_key(&Secp256k1::new());
+        let address = Address::p2wpkh(&public_key, Network::Testnet).unwrap();
+        let inscription = Brc20::transfer("mona".to_string(), 100);
+
+        let mut builder = OrdTransactionBuilder::p2wsh(private_key);
+        let commit = builder
+            .build_commit_transaction(
+                Network::Testnet,
+                address.clone(),
+                CreateCommitTransactionArgs::new(
+                    vec![test_utxo(1, Amount::from_sat(8_000))],
+                    inscription.clone(),
+                    address.clone(),
+                    address.script_pubkey(),
+                    FeeRate::from_sat_per_vb(1).unwrap(),
+                ),
+            )
+            .await
+            .unwrap();
+        let reveal = builder
+            .build_reveal_transaction(RevealTransactionArgs::new(
+                Utxo {
+                    id: commit.unsigned_tx.txid(),
+                    index: 0,
+                    amount: commit.reveal_balance,
+                },
+                address,
+                commit.redeem_script,
+            ))
+            .await
+            .unwrap();
+
+        let parsed = OrdParser::parse_input(&reveal, 0).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].0.txid, reveal.txid());
+        assert_eq!(parsed[0].1, OrdParser::from(inscription));
+        assert_eq!(OrdParser::parse_all(&reveal).unwrap().len(), 1);
+    }
 }
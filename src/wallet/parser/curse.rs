@@ -0,0 +1,106 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::envelope::ParsedEnvelope;
+use super::OrdParser;
+use crate::constants::PROTOCOL_ID;
+use crate::InscriptionId;
+
+/// Anomaly of an inscription envelope which makes the inscription cursed.
+///
+/// [Reference](https://docs.ordinals.com/inscriptions.html#fields)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Curse {
+    /// A field is set more than once.
+    DuplicateField,
+    /// A field tag is not followed by a value.
+    IncompleteField,
+    /// An even field, reserved for future use, is not recognized.
+    UnrecognizedEvenField,
+    /// The envelope contains `OP_PUSHNUM` opcodes.
+    Pushnum,
+    /// The envelope is preceded by a stuttering `OP_FALSE`.
+    Stutter,
+}
+
+impl fmt::Display for Curse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let curse = match self {
+            Self::DuplicateField => "duplicate field",
+            Self::IncompleteField => "incomplete field",
+            Self::UnrecognizedEvenField => "unrecognized even field",
+            Self::Pushnum => "pushnum",
+            Self::Stutter => "stutter",
+        };
+        f.write_str(curse)
+    }
+}
+
+/// Defines how cursed envelopes are classified by [`OrdParser::parse_all_with_mode`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParseMode {
+    /// Cursed envelopes are classified as clean ones, reporting their curses.
+    #[default]
+    Lenient,
+    /// Cursed envelopes, operations with invalid tickers and operations inscribed with a
+    /// content type other than a [`crate::Brc20ContentType`] are never classified as BRC-20
+    /// inscriptions, since indexers don't process them.
+    Strict,
+}
+
+/// Options of [`OrdParser::parse_all_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseOptions {
+    /// How cursed envelopes are classified
+    pub mode: ParseMode,
+    /// Protocol ID pushed after `OP_IF` by the envelopes to parse, [`PROTOCOL_ID`] by default.
+    ///
+    /// Envelopes with another protocol ID are ignored, so research protocols and testnets can
+    /// use their own namespace, e.g. with [`crate::EnvelopeBuilder::protocol_id`].
+    pub protocol_id: Vec<u8>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            mode: ParseMode::default(),
+            protocol_id: PROTOCOL_ID.to_vec(),
+        }
+    }
+}
+
+/// An inscription parsed from a transaction, along with the curses of its envelope.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ParsedInscription {
+    /// ID of the inscription
+    pub id: InscriptionId,
+    /// The parsed inscription
+    pub inscription: OrdParser,
+    /// Curses of the inscription envelope, empty for a clean inscription
+    pub curses: Vec<Curse>,
+}
+
+impl ParsedInscription {
+    /// Returns whether the inscription envelope has any curse.
+    pub fn is_cursed(&self) -> bool {
+        !self.curses.is_empty()
+    }
+}
+
+/// Returns the curses of the envelope.
+pub(super) fn envelope_curses(envelope: &ParsedEnvelope) -> Vec<Curse> {
+    [
+        (envelope.payload.duplicate_field, Curse::DuplicateField),
+        (envelope.payload.incomplete_field, Curse::IncompleteField),
+        (
+            envelope.payload.unrecognized_even_field,
+            Curse::UnrecognizedEvenField,
+        ),
+        (envelope.pushnum, Curse::Pushnum),
+        (envelope.stutter, Curse::Stutter),
+    ]
+    .into_iter()
+    .filter_map(|(cursed, curse)| cursed.then_some(curse))
+    .collect()
+}
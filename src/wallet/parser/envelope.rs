@@ -4,8 +4,10 @@
 use std::collections::BTreeMap;
 use std::iter::Peekable;
 
-use bitcoin::script::{Error as ScriptError, Instruction, Instructions};
-use bitcoin::{opcodes, Script, Transaction};
+use bitcoin::opcodes::Opcode;
+use bitcoin::script::{Error as ScriptError, Instruction, Instructions, PushBytes};
+use bitcoin::taproot::TAPROOT_ANNEX_PREFIX;
+use bitcoin::{opcodes, Script, Transaction, Witness};
 
 use crate::constants::{
     CONTENT_ENCODING_TAG, CONTENT_TYPE_TAG, DELEGATE_TAG, METADATA_TAG, METAPROTOCOL_TAG,
@@ -14,15 +16,33 @@ use crate::constants::{
 use crate::Nft;
 
 type ParseResult<T> = std::result::Result<T, ScriptError>;
-type RawEnvelope = Envelope<Vec<Vec<u8>>>;
-pub(crate) type ParsedEnvelope = Envelope<Nft>;
 
-/// Representation of parsed transaction witness data,
-/// generic over `T` to handle various types.
+/// Envelope with the data pushes between `OP_IF <protocol id>` and `OP_ENDIF`, as they appear
+/// in the script, before tags and body are interpreted.
+///
+/// Fields are the pairs of pushes preceding the first empty push at an even position, and the
+/// body chunks are the pushes following it.
+pub type RawEnvelope = Envelope<Vec<Vec<u8>>>;
+
+/// Envelope with its fields and body interpreted as an [`Nft`], as ord indexes it.
+pub type ParsedEnvelope = Envelope<Nft>;
+
+/// Inscription envelope found in a transaction input witness, generic over its payload: the
+/// raw pushes of a [`RawEnvelope`], or the interpreted fields of a [`ParsedEnvelope`].
+///
+/// # Stability
+///
+/// The envelope API follows semver like the rest of the crate: the fields, the type aliases
+/// and the public functions are only changed or removed in breaking releases. The struct is
+/// `#[non_exhaustive]`, so that new anomalies tracked by ord can be added as fields in minor
+/// releases. Envelopes are parsed as the referenced ord version does, and fixes aligning the
+/// parser with ord are not considered breaking.
 #[derive(Debug, Default, PartialEq, Clone)]
+#[non_exhaustive]
 pub struct Envelope<T> {
-    /// Reference to an item's index in the witness.
+    /// Index of the transaction input whose witness contains the envelope.
     pub input: u32,
+    /// Index of the envelope among the envelopes of the same input.
     pub offset: u32,
     /// Main content of the envelope, generic over `T` to handle various types.
     pub payload: T,
@@ -35,42 +55,104 @@ pub struct Envelope<T> {
 }
 
 impl ParsedEnvelope {
-    pub(crate) fn from_transaction(transaction: &Transaction) -> Vec<Self> {
-        RawEnvelope::from_transaction(transaction)
+    /// Parses the envelopes of all the inputs of the transaction.
+    pub fn from_transaction(transaction: &Transaction) -> Vec<Self> {
+        Self::from_transaction_with_protocol_id(transaction, &PROTOCOL_ID)
+    }
+
+    /// Parses the envelopes pushing the given protocol ID, instead of [`PROTOCOL_ID`].
+    pub fn from_transaction_with_protocol_id(
+        transaction: &Transaction,
+        protocol_id: &[u8],
+    ) -> Vec<Self> {
+        RawEnvelope::from_transaction_with_protocol_id(transaction, protocol_id)
             .into_iter()
             .map(|envelope| envelope.into())
             .collect()
     }
 
     /// Fetch a single parsed envelope from a specific transaction input if it exists.
-    pub(crate) fn from_transaction_input(transaction: &Transaction, index: usize) -> Option<Self> {
+    pub fn from_transaction_input(transaction: &Transaction, index: usize) -> Option<Self> {
         transaction.input.get(index).and_then(|input| {
-            input.witness.tapscript().and_then(|tapscript| {
-                RawEnvelope::from_tapscript(tapscript, index)
-                    .ok()
-                    .and_then(|envelopes| envelopes.into_iter().next())
-                    .map(|raw_envelope| raw_envelope.into())
-            })
+            RawEnvelope::from_witness(&input.witness, index, &PROTOCOL_ID)
+                .into_iter()
+                .next()
+                .map(|raw_envelope| raw_envelope.into())
         })
     }
 }
 
 impl RawEnvelope {
-    fn from_transaction(transaction: &Transaction) -> Vec<Self> {
+    /// Extracts the envelopes of all the inputs of the transaction, without interpreting them.
+    pub fn from_transaction(transaction: &Transaction) -> Vec<Self> {
+        Self::from_transaction_with_protocol_id(transaction, &PROTOCOL_ID)
+    }
+
+    /// Extracts the envelopes pushing the given protocol ID, instead of [`PROTOCOL_ID`].
+    pub fn from_transaction_with_protocol_id(
+        transaction: &Transaction,
+        protocol_id: &[u8],
+    ) -> Vec<Self> {
         let mut envelopes = Vec::new();
 
         for (i, input) in transaction.input.iter().enumerate() {
-            if let Some(tapscript) = input.witness.tapscript() {
-                if let Ok(input_envelopes) = Self::from_tapscript(tapscript, i) {
-                    envelopes.extend(input_envelopes);
-                }
-            }
+            envelopes.extend(Self::from_witness(&input.witness, i, protocol_id));
         }
 
         envelopes
     }
 
-    fn from_tapscript(tapscript: &Script, input: usize) -> ParseResult<Vec<Self>> {
+    /// Returns the tag and value pairs preceding the body, in script order, including
+    /// duplicate and unrecognized tags. A trailing tag without value is omitted.
+    pub fn fields(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.payload[..self.body_separator().unwrap_or(self.payload.len())]
+            .chunks_exact(2)
+            .map(|pair| (pair[0].as_slice(), pair[1].as_slice()))
+    }
+
+    /// Returns the pushes of the body, preserving the chunk boundaries of the script, or
+    /// `None` if the envelope has no body.
+    pub fn body_chunks(&self) -> Option<&[Vec<u8>]> {
+        self.body_separator().map(|i| &self.payload[i + 1..])
+    }
+
+    /// Returns the position of the empty push separating the fields from the body.
+    fn body_separator(&self) -> Option<usize> {
+        self.payload
+            .iter()
+            .enumerate()
+            .position(|(i, push)| i % 2 == 0 && push.is_empty())
+    }
+
+    /// Extracts the envelopes from the tapscript of a P2TR script-path spend or,
+    /// failing that, from the witness script of a P2WSH spend.
+    fn from_witness(witness: &Witness, input: usize, protocol_id: &[u8]) -> Vec<Self> {
+        // as ord does, the leaf script is parsed whatever the leaf version of the control block,
+        // and leaves which aren't valid scripts hold no envelopes
+        let tapscript_envelopes = witness
+            .tapscript()
+            .and_then(|tapscript| Self::from_tapscript(tapscript, input, protocol_id).ok())
+            .filter(|envelopes| !envelopes.is_empty());
+        if let Some(envelopes) = tapscript_envelopes {
+            return envelopes;
+        }
+
+        // the witness script is the last element of a P2WSH witness, while the last element of a
+        // taproot witness is its control block or its annex, which are never parsed
+        match witness.last() {
+            Some(witness_script) if witness.len() > 1 && is_witness_script(witness_script) => {
+                Self::from_tapscript(Script::from_bytes(witness_script), input, protocol_id)
+                    .unwrap_or_default()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn from_tapscript(
+        tapscript: &Script,
+        input: usize,
+        protocol_id: &[u8],
+    ) -> ParseResult<Vec<Self>> {
         let mut envelopes = Vec::new();
 
         let mut instructions = tapscript.instructions().peekable();
@@ -78,8 +160,13 @@ impl RawEnvelope {
         let mut stuttered = false;
         while let Some(instruction) = instructions.next().transpose()? {
             if instruction == Instruction::PushBytes((&[]).into()) {
-                let (stutter, envelope) =
-                    Self::from_instructions(&mut instructions, input, envelopes.len(), stuttered)?;
+                let (stutter, envelope) = Self::from_instructions(
+                    &mut instructions,
+                    input,
+                    envelopes.len(),
+                    stuttered,
+                    protocol_id,
+                )?;
                 if let Some(envelope) = envelope {
                     envelopes.push(envelope);
                 } else {
@@ -108,13 +195,17 @@ impl RawEnvelope {
         input: usize,
         offset: usize,
         stutter: bool,
+        protocol_id: &[u8],
     ) -> ParseResult<(bool, Option<Self>)> {
         if !Self::accept(instructions, Instruction::Op(opcodes::all::OP_IF))? {
             let stutter = instructions.peek() == Some(&Ok(Instruction::PushBytes((&[]).into())));
             return Ok((stutter, None));
         }
 
-        if !Self::accept(instructions, Instruction::PushBytes((&PROTOCOL_ID).into()))? {
+        let Ok(protocol_id) = <&PushBytes>::try_from(protocol_id) else {
+            return Ok((false, None));
+        };
+        if !Self::accept(instructions, Instruction::PushBytes(protocol_id))? {
             let stutter = instructions.peek() == Some(&Ok(Instruction::PushBytes((&[]).into())));
             return Ok((stutter, None));
         }
@@ -138,78 +229,16 @@ impl RawEnvelope {
                         }),
                     ));
                 }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_NEG1)) => {
-                    pushnum = true;
-                    payload.push(vec![0x81]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_1)) => {
-                    pushnum = true;
-                    payload.push(vec![1]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_2)) => {
-                    pushnum = true;
-                    payload.push(vec![2]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_3)) => {
-                    pushnum = true;
-                    payload.push(vec![3]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_4)) => {
-                    pushnum = true;
-                    payload.push(vec![4]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_5)) => {
-                    pushnum = true;
-                    payload.push(vec![5]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_6)) => {
-                    pushnum = true;
-                    payload.push(vec![6]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_7)) => {
-                    pushnum = true;
-                    payload.push(vec![7]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_8)) => {
+                Some(Instruction::Op(opcode)) => {
+                    let Some(value) = pushnum_value(opcode) else {
+                        return Ok((false, None));
+                    };
                     pushnum = true;
-                    payload.push(vec![8]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_9)) => {
-                    pushnum = true;
-                    payload.push(vec![9]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_10)) => {
-                    pushnum = true;
-                    payload.push(vec![10]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_11)) => {
-                    pushnum = true;
-                    payload.push(vec![11]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_12)) => {
-                    pushnum = true;
-                    payload.push(vec![12]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_13)) => {
-                    pushnum = true;
-                    payload.push(vec![13]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_14)) => {
-                    pushnum = true;
-                    payload.push(vec![14]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_15)) => {
-                    pushnum = true;
-                    payload.push(vec![15]);
-                }
-                Some(Instruction::Op(opcodes::all::OP_PUSHNUM_16)) => {
-                    pushnum = true;
-                    payload.push(vec![16]);
+                    payload.push(value);
                 }
                 Some(Instruction::PushBytes(data)) => {
                     payload.push(data.as_bytes().to_vec());
                 }
-                Some(_) => return Ok((false, None)),
             }
         }
     }
@@ -217,11 +246,7 @@ impl RawEnvelope {
 
 impl From<RawEnvelope> for ParsedEnvelope {
     fn from(envelope: RawEnvelope) -> Self {
-        let body = envelope
-            .payload
-            .iter()
-            .enumerate()
-            .position(|(i, push)| i % 2 == 0 && push.is_empty());
+        let body = envelope.body_separator();
 
         let mut fields: BTreeMap<&[u8], Vec<&[u8]>> = BTreeMap::new();
 
@@ -278,6 +303,30 @@ impl From<RawEnvelope> for ParsedEnvelope {
     }
 }
 
+/// Returns whether the last element of a witness may be the witness script of a P2WSH spend.
+///
+/// The annex of a taproot witness starts with `0x50`, i.e. `OP_RESERVED`, and the control block
+/// of a tapscript leaf with `0xc0` or `0xc1`, which are not opcodes executable by segwit v0
+/// scripts, so neither can be the first byte of a valid witness script.
+fn is_witness_script(element: &[u8]) -> bool {
+    element.first().is_some_and(|first| {
+        *first != TAPROOT_ANNEX_PREFIX && *first <= opcodes::all::OP_NOP10.to_u8()
+    })
+}
+
+/// Returns the value pushed by an `OP_PUSHNUM` opcode, decoded as ord does, so tags and values
+/// pushed with these opcodes are indexed like their single byte push equivalents.
+fn pushnum_value(opcode: Opcode) -> Option<Vec<u8>> {
+    let first = opcodes::all::OP_PUSHNUM_1.to_u8();
+    let last = opcodes::all::OP_PUSHNUM_16.to_u8();
+
+    match opcode.to_u8() {
+        _ if opcode == opcodes::all::OP_PUSHNUM_NEG1 => Some(vec![0x81]),
+        code if (first..=last).contains(&code) => Some(vec![code - first + 1]),
+        _ => None,
+    }
+}
+
 fn remove_field(fields: &mut BTreeMap<&[u8], Vec<&[u8]>>, field: &[u8]) -> Option<Vec<u8>> {
     let values = fields.get_mut(field)?;
 
@@ -360,6 +409,85 @@ mod tests {
         })
     }
 
+    #[test]
+    fn envelope_should_expose_raw_fields_and_body_chunks() {
+        let witness = witness_from_script(&[
+            b"ord",
+            &[1],
+            b"text/plain",
+            &[1],
+            b"image/png",
+            &[],
+            b"he",
+            b"llo",
+        ]);
+        let transaction = Transaction {
+            version: Version::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness,
+            }],
+            output: Vec::new(),
+        };
+
+        let envelopes = RawEnvelope::from_transaction(&transaction);
+        assert_eq!(envelopes.len(), 1);
+        let envelope = &envelopes[0];
+        assert_eq!(
+            envelope.fields().collect::<Vec<_>>(),
+            vec![
+                ([1].as_slice(), b"text/plain".as_slice()),
+                ([1].as_slice(), b"image/png".as_slice()),
+            ]
+        );
+        assert_eq!(
+            envelope.body_chunks(),
+            Some([b"he".to_vec(), b"llo".to_vec()].as_slice())
+        );
+
+        let parsed = ParsedEnvelope::from(envelope.clone());
+        assert_eq!(parsed.payload.body.as_deref(), Some(b"hello".as_slice()));
+        assert!(parsed.payload.duplicate_field);
+    }
+
+    #[test]
+    fn envelope_should_parse_leaves_of_any_version() {
+        let script = ScriptBuilder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(opcodes::all::OP_IF)
+            .push_slice(b"ord")
+            .push_slice([1])
+            .push_slice(b"text/plain")
+            .push_slice([])
+            .push_slice(b"hello")
+            .push_opcode(opcodes::all::OP_ENDIF)
+            .into_script();
+        let mut control_block = vec![0xc2];
+        control_block.extend_from_slice(&[2; 32]);
+
+        let envelopes = parse_envelope(&[Witness::from_slice(&[
+            vec![1; 64],
+            script.into_bytes(),
+            control_block.clone(),
+        ])]);
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(
+            envelopes[0].payload.body.as_deref(),
+            Some(b"hello".as_slice())
+        );
+
+        // a leaf of an unknown version which isn't a valid script
+        let envelopes = parse_envelope(&[Witness::from_slice(&[
+            vec![1; 64],
+            vec![0x4c, 0xff],
+            control_block,
+        ])]);
+        assert!(envelopes.is_empty());
+    }
+
     #[test]
     fn envelope_should_parse_a_valid_brc20() {
         let brc20_data = br#"{
@@ -638,4 +766,138 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn envelope_should_parse_a_p2wsh_witness_script() {
+        let script = ScriptBuilder::new()
+            .push_slice([0x02; 33])
+            .push_opcode(opcodes::all::OP_CHECKSIG)
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(opcodes::all::OP_IF)
+            .push_slice(PROTOCOL_ID)
+            .push_slice([])
+            .push_slice(b"ord")
+            .push_opcode(opcodes::all::OP_ENDIF)
+            .into_script();
+        // a DER signature followed by the witness script
+        let witness = Witness::from_slice(&[vec![0x30; 71], script.into_bytes()]);
+
+        let envelopes = parse_envelope(&[witness]);
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(
+            envelopes[0].payload.body.as_deref(),
+            Some(b"ord".as_slice())
+        );
+    }
+
+    #[test]
+    fn envelope_should_not_parse_taproot_annex_nor_control_block() {
+        let envelope = ScriptBuilder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(opcodes::all::OP_IF)
+            .push_slice(PROTOCOL_ID)
+            .push_slice([])
+            .push_slice(b"ord")
+            .push_opcode(opcodes::all::OP_ENDIF)
+            .into_script();
+        let leaf = ScriptBuilder::new()
+            .push_slice([0x02; 32])
+            .push_opcode(opcodes::all::OP_CHECKSIG)
+            .into_script();
+        // an annex whose bytes following the prefix hold an envelope
+        let mut annex = vec![TAPROOT_ANNEX_PREFIX];
+        annex.extend_from_slice(envelope.as_bytes());
+        // a control block whose bytes following the leaf version hold an envelope
+        let mut control_block = vec![0xc0];
+        control_block.extend_from_slice(envelope.as_bytes());
+
+        for witness in [
+            // key path spends
+            Witness::from_slice(&[vec![1; 64], annex.clone()]),
+            // script path spends of a leaf without envelope
+            Witness::from_slice(&[vec![1; 64], leaf.to_bytes(), control_block.clone()]),
+            Witness::from_slice(&[
+                vec![1; 64],
+                leaf.to_bytes(),
+                control_block.clone(),
+                annex.clone(),
+            ]),
+        ] {
+            assert!(parse_envelope(&[witness]).is_empty());
+        }
+
+        // the same envelope is parsed from the witness script of a P2WSH spend
+        let witness = Witness::from_slice(&[vec![0x30; 71], envelope.into_bytes()]);
+        assert_eq!(parse_envelope(&[witness]).len(), 1);
+    }
+
+    #[test]
+    fn envelope_should_map_pushnum_tags_and_values() {
+        // fields and body pushed with pushnum opcodes, as in cursed mainnet inscriptions
+        let script = ScriptBuilder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(opcodes::all::OP_IF)
+            .push_slice(PROTOCOL_ID)
+            .push_opcode(opcodes::all::OP_PUSHNUM_1)
+            .push_slice(b"text/plain;charset=utf-8")
+            .push_opcode(opcodes::all::OP_PUSHNUM_2)
+            .push_opcode(opcodes::all::OP_PUSHNUM_1)
+            .push_opcode(opcodes::all::OP_PUSHNUM_NEG1)
+            .push_slice(b"odd")
+            .push_slice([])
+            .push_opcode(opcodes::all::OP_PUSHNUM_16)
+            .push_opcode(opcodes::all::OP_PUSHNUM_NEG1)
+            .push_opcode(opcodes::all::OP_ENDIF)
+            .into_script();
+
+        let envelopes = parse_envelope(&[Witness::from_slice(&[script.into_bytes(), Vec::new()])]);
+        assert_eq!(envelopes.len(), 1);
+        let envelope = &envelopes[0];
+        assert!(envelope.pushnum);
+        assert_eq!(
+            envelope.payload.content_type(),
+            Some("text/plain;charset=utf-8")
+        );
+        assert_eq!(envelope.payload.pointer, Some(vec![1]));
+        assert_eq!(envelope.payload.pointer(), Some(1));
+        // the odd 0x81 tag is ignored
+        assert!(!envelope.payload.unrecognized_even_field);
+        assert!(!envelope.payload.duplicate_field);
+        assert_eq!(envelope.payload.body, Some(vec![16, 0x81]));
+    }
+
+    #[test]
+    fn envelope_should_decode_every_pushnum_opcode() {
+        let mut builder = ScriptBuilder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(opcodes::all::OP_IF)
+            .push_slice(PROTOCOL_ID)
+            .push_slice([]);
+        for n in 1..=16 {
+            builder = builder.push_int(n);
+        }
+        let script = builder.push_opcode(opcodes::all::OP_ENDIF).into_script();
+
+        let envelopes = parse_envelope(&[Witness::from_slice(&[script.into_bytes(), Vec::new()])]);
+        assert!(envelopes[0].pushnum);
+        assert_eq!(
+            envelopes[0].payload.body,
+            Some((1..=16).collect::<Vec<u8>>())
+        );
+    }
+
+    #[test]
+    fn envelope_should_stop_at_non_push_opcodes() {
+        let script = ScriptBuilder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(opcodes::all::OP_IF)
+            .push_slice(PROTOCOL_ID)
+            .push_opcode(opcodes::all::OP_RESERVED)
+            .push_slice([])
+            .push_opcode(opcodes::all::OP_ENDIF)
+            .into_script();
+
+        let envelopes = parse_envelope(&[Witness::from_slice(&[script.into_bytes(), Vec::new()])]);
+        assert!(envelopes.is_empty());
+    }
 }
@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::Curse;
+
+/// Receives the events of [`super::OrdParser::parse_all_with_metrics`], e.g. to export them as
+/// Prometheus counters from a long-running indexer.
+///
+/// All methods do nothing by default, so implementors only override the events they track.
+pub trait ParserMetrics {
+    /// An envelope with the expected protocol ID was found.
+    fn envelope_seen(&self) {}
+
+    /// The body of an envelope was decoded into an inscription.
+    fn body_decoded(&self) {}
+
+    /// The body of an envelope looks like a JSON object, but isn't valid JSON, e.g. a
+    /// malformed BRC-20 operation.
+    fn invalid_json(&self) {}
+
+    /// An envelope has the given curses, which are never empty.
+    fn cursed(&self, curses: &[Curse]) {
+        let _ = curses;
+    }
+}
+
+/// Metrics which ignore every event.
+pub(crate) struct NoMetrics;
+
+impl ParserMetrics for NoMetrics {}
+
+/// Counts the events of the parser, to be read with [`ParserCounters::snapshot`].
+#[derive(Debug, Default)]
+pub struct ParserCounters {
+    envelopes: AtomicU64,
+    bodies: AtomicU64,
+    invalid_json: AtomicU64,
+    cursed: AtomicU64,
+}
+
+/// Values of [`ParserCounters`] at a point in time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParserCountersSnapshot {
+    /// Number of envelopes seen
+    pub envelopes: u64,
+    /// Number of bodies decoded into inscriptions
+    pub bodies: u64,
+    /// Number of bodies looking like JSON objects which aren't valid JSON
+    pub invalid_json: u64,
+    /// Number of cursed envelopes
+    pub cursed: u64,
+}
+
+impl ParserCounters {
+    /// Creates counters starting from zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current values of the counters.
+    pub fn snapshot(&self) -> ParserCountersSnapshot {
+        ParserCountersSnapshot {
+            envelopes: self.envelopes.load(Ordering::Relaxed),
+            bodies: self.bodies.load(Ordering::Relaxed),
+            invalid_json: self.invalid_json.load(Ordering::Relaxed),
+            cursed: self.cursed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl ParserMetrics for ParserCounters {
+    fn envelope_seen(&self) {
+        self.envelopes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn body_decoded(&self) {
+        self.bodies.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn invalid_json(&self) {
+        self.invalid_json.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn cursed(&self, _curses: &[Curse]) {
+        self.cursed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns whether the body starts as a JSON object but isn't valid JSON.
+pub(super) fn is_invalid_json(body: &[u8]) -> bool {
+    body.trim_ascii_start().starts_with(b"{")
+        && serde_json::from_slice::<serde::de::IgnoredAny>(body).is_err()
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{OutPoint, ScriptBuf, Sequence, Transaction, TxIn, Witness};
+
+    use super::*;
+    use crate::constants::CONTENT_TYPE_TAG;
+    use crate::wallet::{OrdParser, ParseOptions};
+    use crate::EnvelopeBuilder;
+
+    fn input(script: ScriptBuf) -> TxIn {
+        TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::from_slice(&[script.into_bytes(), Vec::new()]),
+        }
+    }
+
+    #[test]
+    fn test_should_count_parser_events() {
+        let valid = EnvelopeBuilder::new()
+            .field(CONTENT_TYPE_TAG, b"text/plain".as_slice())
+            .body(br#"{"p":"brc-20","op":"mint","tick":"ordi","amt":"1000"}"#)
+            .to_script()
+            .unwrap();
+        let cursed_invalid_json = EnvelopeBuilder::new()
+            .field(CONTENT_TYPE_TAG, b"text/plain".as_slice())
+            .field(CONTENT_TYPE_TAG, b"text/plain".as_slice())
+            .body(br#"{"p":"brc-20","op":"mint""#)
+            .to_script()
+            .unwrap();
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![input(valid), input(cursed_invalid_json)],
+            output: Vec::new(),
+        };
+
+        let counters = ParserCounters::new();
+        let parsed =
+            OrdParser::parse_all_with_metrics(&tx, &ParseOptions::default(), &counters).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(
+            counters.snapshot(),
+            ParserCountersSnapshot {
+                envelopes: 2,
+                bodies: 2,
+                invalid_json: 1,
+                cursed: 1,
+            }
+        );
+    }
+}
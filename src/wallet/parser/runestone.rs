@@ -0,0 +1,187 @@
+use std::fmt;
+
+use bitcoin::Transaction;
+use ordinals::{Artifact as OrdArtifact, Flaw, Rune, RuneId};
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::Runestone;
+
+/// Parses the runestones of transactions, e.g. for an indexer.
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunestoneParser;
+
+impl RunestoneParser {
+    /// Decodes the runestone of the first output of the transaction carrying one.
+    ///
+    /// Returns `None` if the transaction has no runestone, or [`Artifact::Cenotaph`] if the
+    /// runestone is malformed. A cenotaph is not a transfer: all the runes of the transaction
+    /// inputs are burned.
+    pub fn parse(tx: &Transaction) -> Option<Artifact> {
+        Runestone::decipher(tx).map(Artifact::from)
+    }
+}
+
+/// Runestone decoded from a transaction, mirroring the `Artifact` of `ordinals`.
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Artifact {
+    /// A valid runestone
+    Runestone(Box<Runestone>),
+    /// A malformed runestone, burning the runes of the transaction inputs
+    Cenotaph(Cenotaph),
+}
+
+impl Artifact {
+    /// Returns the rune minted by the transaction, if any.
+    ///
+    /// The mint of a cenotaph still counts towards the cap of the rune, but the minted runes
+    /// are burned.
+    pub fn mint(&self) -> Option<RuneId> {
+        match self {
+            Self::Runestone(runestone) => runestone.mint,
+            Self::Cenotaph(cenotaph) => cenotaph.mint,
+        }
+    }
+
+    /// Returns the runestone, unless it's a cenotaph.
+    pub fn runestone(&self) -> Option<&Runestone> {
+        match self {
+            Self::Runestone(runestone) => Some(runestone),
+            Self::Cenotaph(_) => None,
+        }
+    }
+
+    /// Returns whether the runestone is a cenotaph.
+    pub fn is_cenotaph(&self) -> bool {
+        matches!(self, Self::Cenotaph(_))
+    }
+}
+
+impl From<OrdArtifact> for Artifact {
+    fn from(artifact: OrdArtifact) -> Self {
+        match artifact {
+            OrdArtifact::Runestone(runestone) => Self::Runestone(Box::new(runestone.into())),
+            OrdArtifact::Cenotaph(cenotaph) => Self::Cenotaph(Cenotaph {
+                etching: cenotaph.etching,
+                flaw: cenotaph.flaw,
+                mint: cenotaph.mint,
+            }),
+        }
+    }
+}
+
+/// Malformed runestone.
+#[cfg_attr(docsrs, doc(cfg(feature = "rune")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cenotaph {
+    /// Rune etched by the cenotaph, which can't be minted and has no premine
+    pub etching: Option<Rune>,
+    /// Reason the runestone is malformed, or `None` if its `OP_RETURN` has the cenotaph flag
+    pub flaw: Option<Flaw>,
+    /// Rune minted by the cenotaph, whose runes are burned
+    pub mint: Option<RuneId>,
+}
+
+impl fmt::Display for Cenotaph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.flaw {
+            Some(flaw) => write!(f, "cenotaph: {flaw}"),
+            None => write!(f, "cenotaph"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Amount, ScriptBuf, TxOut};
+    use ordinals::{Edict, Runestone as OrdRunestone};
+
+    use super::*;
+
+    fn transaction(script_pubkey: ScriptBuf) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![
+                TxOut {
+                    value: Amount::ZERO,
+                    script_pubkey,
+                },
+                TxOut {
+                    value: Amount::from_sat(10_000),
+                    script_pubkey: ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_raw_hash(
+                        bitcoin::hashes::Hash::all_zeros(),
+                    )),
+                },
+            ],
+        }
+    }
+
+    fn encipher(runestone: Runestone) -> ScriptBuf {
+        ScriptBuf::from_bytes(OrdRunestone::from(runestone).encipher().to_bytes())
+    }
+
+    #[test]
+    fn test_should_parse_runestones_and_cenotaphs() {
+        let rune_id = RuneId::from_str("840000:1").unwrap();
+        let edict = |output| Edict {
+            id: rune_id,
+            amount: 100,
+            output,
+        };
+
+        let artifact = RunestoneParser::parse(&transaction(encipher(Runestone {
+            edicts: vec![edict(1)],
+            mint: Some(rune_id),
+            ..Default::default()
+        })))
+        .unwrap();
+        assert!(!artifact.is_cenotaph());
+        assert_eq!(artifact.mint(), Some(rune_id));
+        assert_eq!(artifact.runestone().unwrap().edicts, vec![edict(1)]);
+
+        // an edict to an output out of the transaction burns the runes
+        let artifact = RunestoneParser::parse(&transaction(encipher(Runestone {
+            edicts: vec![edict(3)],
+            mint: Some(rune_id),
+            ..Default::default()
+        })))
+        .unwrap();
+        let Artifact::Cenotaph(cenotaph) = &artifact else {
+            panic!("expected a cenotaph");
+        };
+        assert_eq!(cenotaph.flaw, Some(Flaw::EdictOutput));
+        assert_eq!(artifact.mint(), Some(rune_id));
+        assert!(artifact.runestone().is_none());
+        assert_eq!(
+            cenotaph.to_string(),
+            "cenotaph: edict output greater than transaction output count"
+        );
+
+        // a non-pushdata opcode
+        let script = bitcoin::script::Builder::new()
+            .push_opcode(bitcoin::opcodes::all::OP_RETURN)
+            .push_opcode(bitcoin::opcodes::all::OP_PUSHNUM_13)
+            .push_opcode(bitcoin::opcodes::all::OP_VERIFY)
+            .into_script();
+        assert_eq!(
+            RunestoneParser::parse(&transaction(script)),
+            Some(Artifact::Cenotaph(Cenotaph {
+                etching: None,
+                flaw: Some(Flaw::Opcode),
+                mint: None,
+            }))
+        );
+
+        assert_eq!(
+            RunestoneParser::parse(&transaction(ScriptBuf::new_op_return([1, 2, 3]))),
+            None
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! UTXO selection
+//!
+//! Selects the inputs of a transaction among the UTXOs of a wallet, so callers don't have to
+//! pre-select them. UTXOs are compared by their effective value, i.e. their value minus the fee
+//! paid to spend them, and UTXOs which cost more to spend than they bring are never selected.
+
+use std::cmp::Reverse;
+
+use bitcoin::{Amount, FeeRate, Script, SignedAmount, TxOut};
+
+use super::Utxo;
+use crate::utils::constants::dust_limit;
+use crate::utils::fees::{fee_for_vbytes, spend_fee, to_signed};
+use crate::{OrdError, OrdResult};
+
+/// Maximum number of branches explored by [`SelectionStrategy::BranchAndBound`] before falling
+/// back to [`SelectionStrategy::LargestFirst`].
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// Strategy used to select the UTXOs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelectionStrategy {
+    /// Selects the UTXOs with the largest values first, which minimizes the number of inputs.
+    #[default]
+    LargestFirst,
+    /// Looks for a set of UTXOs covering the target without change, within the cost of a change
+    /// output, as Bitcoin Core does, and falls back to [`Self::LargestFirst`] if there's none.
+    BranchAndBound,
+    /// Selects the UTXOs confirmed first, unconfirmed UTXOs last, which consolidates the old
+    /// UTXOs of the wallet over time.
+    OldestFirst,
+}
+
+/// A UTXO which may be selected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UtxoCandidate {
+    pub utxo: Utxo,
+    /// Height of the block confirming the UTXO, `None` if it's unconfirmed
+    pub height: Option<u32>,
+}
+
+impl From<Utxo> for UtxoCandidate {
+    fn from(utxo: Utxo) -> Self {
+        Self { utxo, height: None }
+    }
+}
+
+/// Inputs selected by [`UtxoSelector::select`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UtxoSelection {
+    /// The selected UTXOs, in the order they were selected
+    pub inputs: Vec<Utxo>,
+    /// Value of the change output, `None` if the leftovers don't cover the cost of a change
+    /// output and are left to the fee
+    pub change: Option<Amount>,
+    /// Fee paid to spend the selected UTXOs, and the change output if any
+    pub fee: Amount,
+}
+
+impl UtxoSelection {
+    /// Returns the total value of the selected UTXOs.
+    pub fn input_amount(&self) -> Amount {
+        self.inputs.iter().map(|utxo| utxo.amount).sum()
+    }
+}
+
+/// Selects the inputs of a transaction with a [`SelectionStrategy`].
+///
+/// UTXOs are assumed to be locked by the same script pubkey, as the inputs of
+/// [`CreateCommitTransactionArgs`](super::CreateCommitTransactionArgs), while the change is
+/// sent to the change script pubkey.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoSelector {
+    strategy: SelectionStrategy,
+    input_spend_fee: Amount,
+    change_fee: Amount,
+    change_dust_limit: Amount,
+}
+
+/// A UTXO with its effective value.
+struct Candidate<'a> {
+    utxo: &'a Utxo,
+    height: Option<u32>,
+    effective_value: Amount,
+}
+
+impl UtxoSelector {
+    /// Creates a selector of UTXOs locked by `txin_script_pubkey`, with the change sent to
+    /// `change_script_pubkey`, at the given fee rate.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InvalidScriptType`] if the UTXOs are not P2WPKH, P2TR or P2SH
+    /// outputs, or [`OrdError::FeeOverflow`] if the fees don't fit in an amount of sats.
+    pub fn new(
+        strategy: SelectionStrategy,
+        txin_script_pubkey: &Script,
+        change_script_pubkey: &Script,
+        fee_rate: FeeRate,
+    ) -> OrdResult<Self> {
+        let change_output = TxOut {
+            value: Amount::ZERO,
+            script_pubkey: change_script_pubkey.to_owned(),
+        };
+
+        Ok(Self {
+            strategy,
+            input_spend_fee: spend_fee(txin_script_pubkey, fee_rate)?,
+            change_fee: fee_for_vbytes(fee_rate, change_output.size() as u64)?,
+            change_dust_limit: dust_limit(change_script_pubkey),
+        })
+    }
+
+    /// Returns the cost of a change output, i.e. its fee and its dust limit. Leftovers below
+    /// this cost are left to the fee rather than sent to a change output.
+    pub fn cost_of_change(&self) -> Amount {
+        self.change_fee + self.change_dust_limit
+    }
+
+    /// Selects the UTXOs covering the target, i.e. the value of the outputs and the fee of the
+    /// transaction without its inputs and change output.
+    ///
+    /// The fee paid to spend the selected UTXOs is covered by the UTXOs themselves, on top of
+    /// the target, and the leftovers are returned as change if they cover the cost of a change
+    /// output. UTXOs with a non-positive effective value are never selected.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrdError::InsufficientBalance`] if the candidates don't cover the target.
+    pub fn select(&self, candidates: &[UtxoCandidate], target: Amount) -> OrdResult<UtxoSelection> {
+        let mut candidates = candidates
+            .iter()
+            .filter_map(|candidate| {
+                let effective_value =
+                    to_signed(candidate.utxo.amount) - to_signed(self.input_spend_fee);
+                (effective_value > SignedAmount::ZERO).then(|| Candidate {
+                    utxo: &candidate.utxo,
+                    height: candidate.height,
+                    effective_value: effective_value.to_unsigned().unwrap_or_default(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let available = candidates
+            .iter()
+            .map(|candidate| candidate.effective_value)
+            .sum::<Amount>();
+        if available < target {
+            return Err(OrdError::InsufficientBalance {
+                required: target,
+                available,
+            });
+        }
+
+        match self.strategy {
+            SelectionStrategy::LargestFirst => {
+                candidates.sort_by_key(|candidate| Reverse(candidate.effective_value));
+                Ok(self.accumulate(&candidates, target))
+            }
+            SelectionStrategy::OldestFirst => {
+                candidates.sort_by_key(|candidate| candidate.height.unwrap_or(u32::MAX));
+                Ok(self.accumulate(&candidates, target))
+            }
+            SelectionStrategy::BranchAndBound => {
+                candidates.sort_by_key(|candidate| Reverse(candidate.effective_value));
+                let selection = self
+                    .branch_and_bound(&candidates, target)
+                    .map(|selected| {
+                        self.selection(selected.into_iter().map(|index| &candidates[index]), target)
+                    })
+                    .unwrap_or_else(|| self.accumulate(&candidates, target));
+                Ok(selection)
+            }
+        }
+    }
+
+    /// Selects the candidates in order until they cover the target.
+    fn accumulate(&self, candidates: &[Candidate], target: Amount) -> UtxoSelection {
+        let mut selected_value = Amount::ZERO;
+        let count = candidates
+            .iter()
+            .position(|candidate| {
+                selected_value += candidate.effective_value;
+                selected_value >= target
+            })
+            .map_or(candidates.len(), |index| index + 1);
+
+        self.selection(candidates[..count].iter(), target)
+    }
+
+    /// Looks for a set of candidates, sorted by decreasing effective value, whose effective
+    /// value is within the cost of change above the target, with a depth first search.
+    ///
+    /// Returns the indexes of the set with the least leftovers found within
+    /// [`BNB_MAX_TRIES`] branches.
+    fn branch_and_bound(&self, candidates: &[Candidate], target: Amount) -> Option<Vec<usize>> {
+        let upper_bound = target + self.cost_of_change();
+        // effective value of the candidates from each index to the end
+        let mut remaining = vec![Amount::ZERO; candidates.len() + 1];
+        for index in (0..candidates.len()).rev() {
+            remaining[index] = remaining[index + 1] + candidates[index].effective_value;
+        }
+
+        let mut best: Option<(Amount, Vec<usize>)> = None;
+        let mut selected = Vec::new();
+        let mut selected_value = Amount::ZERO;
+        let mut index = 0;
+        for _ in 0..BNB_MAX_TRIES {
+            let backtrack =
+                if selected_value + remaining[index] < target || selected_value > upper_bound {
+                    true
+                } else if selected_value >= target {
+                    if best
+                        .as_ref()
+                        .is_none_or(|(best_value, _)| selected_value < *best_value)
+                    {
+                        best = Some((selected_value, selected.clone()));
+                    }
+                    if selected_value == target {
+                        break;
+                    }
+                    true
+                } else {
+                    index == candidates.len()
+                };
+
+            if backtrack {
+                // exclude the last selected candidate and explore the next ones
+                let Some(last) = selected.pop() else {
+                    break;
+                };
+                selected_value -= candidates[last].effective_value;
+                index = last + 1;
+            } else {
+                selected.push(index);
+                selected_value += candidates[index].effective_value;
+                index += 1;
+            }
+        }
+
+        best.map(|(_, selected)| selected)
+    }
+
+    fn selection<'a>(
+        &self,
+        selected: impl Iterator<Item = &'a Candidate<'a>>,
+        target: Amount,
+    ) -> UtxoSelection {
+        let mut inputs = Vec::new();
+        let mut selected_value = Amount::ZERO;
+        for candidate in selected {
+            inputs.push(candidate.utxo.clone());
+            selected_value += candidate.effective_value;
+        }
+
+        let inputs_fee = self.input_spend_fee * inputs.len() as u64;
+        let leftovers = selected_value - target;
+        if leftovers >= self.cost_of_change() {
+            UtxoSelection {
+                inputs,
+                change: Some(leftovers - self.change_fee),
+                fee: inputs_fee + self.change_fee,
+            }
+        } else {
+            UtxoSelection {
+                inputs,
+                change: None,
+                fee: inputs_fee + leftovers,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use bitcoin::{Address, Txid};
+
+    use super::*;
+
+    fn script_pubkey() -> bitcoin::ScriptBuf {
+        Address::from_str("bc1pxwww0ct9ue7e8tdnlmug5m2tamfn7q06sahstg39ys4c9f3340qqxrdu9k")
+            .unwrap()
+            .assume_checked()
+            .script_pubkey()
+    }
+
+    fn candidates(amounts: &[(u64, Option<u32>)]) -> Vec<UtxoCandidate> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(index, (amount, height))| UtxoCandidate {
+                utxo: Utxo {
+                    id: Txid::from_str(
+                        "9100acad2da80d2198b257acc5d98a6265fda510bc8f1252334876dad4c289f4",
+                    )
+                    .unwrap(),
+                    index: index as u32,
+                    amount: Amount::from_sat(*amount),
+                },
+                height: *height,
+            })
+            .collect()
+    }
+
+    fn selector(strategy: SelectionStrategy) -> UtxoSelector {
+        // 58 sats to spend a key path input and 43 sats for a P2TR change output
+        UtxoSelector::new(
+            strategy,
+            &script_pubkey(),
+            &script_pubkey(),
+            FeeRate::from_sat_per_vb(1).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn indexes(selection: &UtxoSelection) -> Vec<u32> {
+        selection.inputs.iter().map(|utxo| utxo.index).collect()
+    }
+
+    #[test]
+    fn test_should_select_largest_utxos_first() {
+        let candidates = candidates(&[(5_000, None), (20_000, None), (10_000, None), (40, None)]);
+        let selector = selector(SelectionStrategy::LargestFirst);
+
+        let selection = selector
+            .select(&candidates, Amount::from_sat(25_000))
+            .unwrap();
+        assert_eq!(indexes(&selection), vec![1, 2]);
+        assert_eq!(
+            selection.change,
+            Some(Amount::from_sat(30_000 - 25_000 - 2 * 58 - 43))
+        );
+        assert_eq!(selection.fee, Amount::from_sat(2 * 58 + 43));
+        assert_eq!(
+            selection.input_amount(),
+            Amount::from_sat(25_000) + selection.change.unwrap() + selection.fee
+        );
+
+        // the 40 sats UTXO costs more than it brings
+        assert!(matches!(
+            selector.select(&candidates, Amount::from_sat(34_900)),
+            Err(OrdError::InsufficientBalance { available, .. })
+                if available == Amount::from_sat(35_000 - 3 * 58)
+        ));
+    }
+
+    #[test]
+    fn test_should_select_oldest_utxos_first() {
+        let candidates = candidates(&[
+            (20_000, None),
+            (5_000, Some(840_100)),
+            (10_000, Some(840_000)),
+        ]);
+        let selection = selector(SelectionStrategy::OldestFirst)
+            .select(&candidates, Amount::from_sat(12_000))
+            .unwrap();
+
+        assert_eq!(indexes(&selection), vec![2, 1]);
+        assert!(selection.change.is_some());
+    }
+
+    #[test]
+    fn test_should_select_utxos_without_change() {
+        let candidates =
+            candidates(&[(20_000, None), (7_058, None), (10_000, None), (3_058, None)]);
+        let selector = selector(SelectionStrategy::BranchAndBound);
+
+        let selection = selector
+            .select(&candidates, Amount::from_sat(10_000))
+            .unwrap();
+        assert_eq!(indexes(&selection), vec![1, 3]);
+        assert_eq!(selection.change, None);
+        assert_eq!(selection.fee, Amount::from_sat(2 * 58));
+
+        // leftovers within the cost of change are left to the fee
+        let selection = selector
+            .select(&candidates, Amount::from_sat(9_900))
+            .unwrap();
+        assert_eq!(indexes(&selection), vec![2]);
+        assert_eq!(selection.change, None);
+        assert_eq!(selection.fee, Amount::from_sat(58 + 42));
+
+        // without a match, falls back to the largest UTXOs
+        let selection = selector
+            .select(&candidates, Amount::from_sat(35_000))
+            .unwrap();
+        assert_eq!(indexes(&selection), vec![0, 2, 1]);
+        assert!(selection.change.is_some());
+    }
+}